@@ -0,0 +1,35 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use libbgs::numbers::*;
+
+const BIG_P: u128 = 1_000_000_000_000_000_124_399;
+
+// Each element is converted, used once, and converted back, as when enumerating in Steinitz
+// order.
+fn single_use<B: FpBackend<BIG_P>>() -> u128 {
+    (0..1000)
+        .map(|i| (B::from(i) * B::from(i + 1)).into())
+        .fold(0, u128::wrapping_add)
+}
+
+fn long_chain<B: FpBackend<BIG_P>>() -> u128 {
+    B::from(3).pow(BIG_P - 2).into()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    c.bench_function("single use montgomery", |b| {
+        b.iter(single_use::<FpNum<BIG_P>>)
+    });
+    c.bench_function("single use barrett", |b| {
+        b.iter(single_use::<Barrett<BIG_P>>)
+    });
+    c.bench_function("long chain montgomery", |b| {
+        b.iter(long_chain::<FpNum<BIG_P>>)
+    });
+    c.bench_function("long chain barrett", |b| {
+        b.iter(long_chain::<Barrett<BIG_P>>)
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -2,6 +2,8 @@ use criterion::{criterion_group, criterion_main, Criterion};
 
 use libbgs::markoff::*;
 
+type Hash = Box<dyn Fn(&u64) -> usize + Send + Sync>;
+
 fn query<F>(filter: &BloomFilter<u64, F>)
 where
     F: Fn(&u64) -> usize + Send + Sync,
@@ -12,7 +14,7 @@ where
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
-    let hashes: Vec<Box<dyn Fn(&u64) -> usize + Send + Sync>> = vec![
+    let hashes: Vec<Hash> = vec![
         Box::new(|x| (x % 10_000) as usize),
         Box::new(|x| (((x % 997) * (x % 991)) % 10_000) as usize),
     ];
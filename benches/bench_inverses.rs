@@ -13,8 +13,14 @@ fn find_inverses() {
     }
 }
 
+fn find_batch_inverses() {
+    let mut xs = FpNum::<BIG_P>::from_iter(1..100);
+    FpNum::batch_inverse(&mut xs);
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
-    c.bench_function("find inverses", |b| b.iter(|| find_inverses()));
+    c.bench_function("find inverses", |b| b.iter(find_inverses));
+    c.bench_function("find batch inverses", |b| b.iter(find_batch_inverses));
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -2,18 +2,17 @@ use criterion::{criterion_group, criterion_main, Criterion};
 
 use std::collections::HashSet;
 
-use rayon::iter::*;
-
 use libbgs::markoff::*;
 use libbgs::numbers::*;
+use libbgs::streams::*;
 
 const BIG_P: u128 = 1_000_000_000_000_000_124_399;
 
 #[derive(PartialEq, Eq)]
 struct Phantom {}
 
-impl Factor<Phantom, 7> for FpNum<BIG_P> {
-    const FACTORS: Factorization<7> = Factorization::new([
+impl Factor<Phantom> for FpNum<BIG_P> {
+    const FACTORS: Factorization = Factorization::new(&[
         (2, 1),
         (7, 1),
         (13, 1),
@@ -24,8 +23,8 @@ impl Factor<Phantom, 7> for FpNum<BIG_P> {
     ]);
 }
 
-impl Factor<Phantom, 11> for QuadNum<BIG_P> {
-    const FACTORS: Factorization<11> = Factorization::new([
+impl Factor<Phantom> for QuadNum<BIG_P> {
+    const FACTORS: Factorization = Factorization::new(&[
         (2, 4),
         (3, 1),
         (5, 2),
@@ -51,8 +50,8 @@ fn run_tester<'a>(stream: impl Iterator<Item = &'a u128>) {
 
 fn criterion_benchmark(c: &mut Criterion) {
     const LIMIT: u128 = 50;
-    let fp_decomp = SylowDecomp::new();
-    let fp2_decomp = SylowDecomp::new();
+    let fp_decomp = SylowDecomp::<Phantom, 7, FpNum<BIG_P>>::new();
+    let fp2_decomp = SylowDecomp::<Phantom, 11, QuadNum<BIG_P>>::new();
 
     let mut fp_stream_builder = SylowStreamBuilder::new()
         .add_flag(flags::NO_UPPER_HALF)
@@ -63,23 +62,23 @@ fn criterion_benchmark(c: &mut Criterion) {
         .add_flag(flags::NO_PARABOLIC)
         .add_flag(flags::LEQ);
 
-    for d in FpNum::FACTORS.maximal_divisors(LIMIT) {
-        fp_stream_builder = fp_stream_builder.add_target(d);
+    for d in FpNum::<BIG_P>::FACTORS.maximal_divisors(LIMIT) {
+        fp_stream_builder = fp_stream_builder.add_target(&d);
     }
 
-    for d in QuadNum::FACTORS.maximal_divisors(LIMIT) {
-        fp2_stream_builder = fp2_stream_builder.add_target(d);
+    for d in QuadNum::<BIG_P>::FACTORS.maximal_divisors(LIMIT) {
+        fp2_stream_builder = fp2_stream_builder.add_target(&d);
     }
 
     let targets: HashSet<_> = fp_stream_builder
         .into_iter()
-        .map(|x| Coord::from_chi_fp(&x, &fp_decomp))
+        .map(|(x, _)| FpNum::from_chi(&x, &fp_decomp))
         .chain(
             fp2_stream_builder
                 .into_iter()
-                .map(|x| Coord::from_chi_quad(&x, &fp2_decomp)),
+                .map(|(x, _)| QuadNum::from_chi(&x, &fp2_decomp)),
         )
-        .map(|x| x.into())
+        .map(u128::from)
         .collect();
 
     let mut group = c.benchmark_group("orbits");
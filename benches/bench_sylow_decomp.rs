@@ -0,0 +1,36 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use libbgs::numbers::*;
+
+const BIG_P: u128 = 1_000_000_000_000_000_124_399;
+
+#[derive(PartialEq, Eq)]
+struct Phantom {}
+
+impl_factors!(Phantom, 1_000_000_000_000_000_124_399);
+
+fn criterion_benchmark(c: &mut Criterion) {
+    c.bench_function("decompose fp", |b| {
+        b.iter(SylowDecomp::<Phantom, 7, FpNum<BIG_P>>::new)
+    });
+    c.bench_function("decompose quad", |b| {
+        b.iter(SylowDecomp::<Phantom, 11, QuadNum<BIG_P>>::new)
+    });
+
+    let decomp = SylowDecomp::<Phantom, 11, QuadNum<BIG_P>>::new();
+    let factors = <QuadNum<BIG_P> as Factor<Phantom>>::FACTORS;
+    let elems = (1..100)
+        .map(|i| SylowElem::new(std::array::from_fn(|j| i * 7919 % factors.factor(j))))
+        .collect::<Vec<_>>();
+    c.bench_function("to product quad", |b| {
+        b.iter(|| {
+            elems
+                .iter()
+                .map(|x| x.to_product(&decomp))
+                .collect::<Vec<_>>()
+        })
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -2,26 +2,48 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use rayon::iter::*;
 
 use libbgs::numbers::*;
+use libbgs::streams::*;
 
 const P: u128 = 5109751;
 
 #[derive(PartialEq, Eq)]
 struct Phantom {}
 
-impl Factor<Phantom, 4> for FpNum<P> {
-    const FACTORS: Factorization<4> = Factorization::new([(2, 1), (3, 3), (5, 3), (757, 1)]);
+impl Factor<Phantom> for FpNum<P> {
+    const FACTORS: Factorization = Factorization::new(&[(2, 1), (3, 3), (5, 3), (757, 1)]);
 }
 
 fn run_stream() {
-    let stream = SylowStreamBuilder::<Phantom, 4, FpNum<P>>::new()
-        .add_target([0, 3, 2, 1])
+    let stream = SylowStreamBuilder::<Phantom, 4, FpNum<P>, ()>::new()
+        .add_target(&[0, 3, 2, 1])
         .into_par_iter();
 
     assert_eq!(stream.count(), 272160);
 }
 
+fn convert_stream(decomp: &SylowDecomp<Phantom, 4, FpNum<P>>, batched: bool) -> u128 {
+    let stream = SylowStreamBuilder::<Phantom, 4, FpNum<P>, ()>::new()
+        .add_target(&[0, 3, 2, 1])
+        .into_iter()
+        .map(|(x, ())| x);
+    let products: Box<dyn Iterator<Item = FpNum<P>>> = if batched {
+        Box::new(decomp.to_products(stream))
+    } else {
+        Box::new(stream.map(|x| x.to_product(decomp)))
+    };
+    products.map(u128::from).fold(0, u128::wrapping_add)
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
-    c.bench_function("run stream", |b| b.iter(|| run_stream()));
+    c.bench_function("run stream", |b| b.iter(run_stream));
+
+    let decomp = SylowDecomp::new();
+    c.bench_function("convert stream", |b| {
+        b.iter(|| convert_stream(&decomp, false))
+    });
+    c.bench_function("convert stream batched", |b| {
+        b.iter(|| convert_stream(&decomp, true))
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -37,7 +37,7 @@ where
 {
     fn is_small(&self, c: &Coord<P>) -> bool {
         match c.rot_order() {
-            RotOrder::Parabola => false,
+            RotOrder::Parabola(_) => false,
             RotOrder::Hyperbola(ord) => ord <= self.hyper_lim,
             RotOrder::Ellipse(ord) => ord <= self.ellip_lim,
         }
@@ -127,35 +127,14 @@ where
 
     // Magic number used to permute cosets of <chi> to ensure all (s*chi + (s*chi)^-1) have order
     // dividing 2(p - 1) and not dividing (p - 1)
-    let magic = (1..P*P)
-        .map(|i| {
-            let j = standard_affine_shift(P * 2, i);
-            QuadNum::<P>::steinitz(j)
-        })
-        .filter(|c| *c != QuadNum::ZERO)
-        .find_map(|c| {
-            let twos = (QuadNum::<P>::FACTORS[0].1 + 1) as u128;
-            let pow = (P*P - 1) / intpow::<0>(2, twos);
-            let res = c.pow(pow);
-            if res.pow(intpow::<0>(2, twos - 1)) == QuadNum::ONE {
-                None 
-            } else {
-                Some(res)
-            }
-        })
-        .unwrap();
-    let magic = if QuadNum::<P>::FACTORS[0].1 == 1 {
-        magic * QuadNum::find_sylow_generator(1)
-    } else {
-        magic
-    };
+    let magic = strategy::coset_twist::<Ph, P>();
 
     let (a, b) = rayon::join(
         || process_trie(&elements_count, hyper_lim, &ctx.hyper_decomp, &ctx, |k, s| { k * (s + s.inverse())}),
         || process_trie(&elements_count, ellip_lim, &ctx.ellip_decomp, &ctx, |k, s| { 
                 let fix = s * magic;
                 let b = fix + fix.inverse();
-                assert_eq!(b.0, FpNum::ZERO);
+                assert_eq!(trace(b), FpNum::ZERO);
                 k * b.1
             }),
     );
@@ -198,10 +177,7 @@ where
         }
     });
     SylowStreamBuilder::new_with_trie(&trie)
-        .add_flag(flags::NO_PARABOLIC)
-        .add_flag(flags::NO_UPPER_HALF)
-        .add_flag(flags::LEQ)
-        .add_targets_leq(limit)
+        .for_unique_chi(limit)
         .into_par_iter()
         .map(|(chi, check)| {
             let a = C::from_chi(&chi, &decomp);
@@ -228,21 +204,18 @@ where
                     let chi_conj = C::from_chi_conj(&chi, &decomp).inverse();
 
                     SylowStreamBuilder::new_with_trie(&trie)
-                    .add_flag(flags::NO_UPPER_HALF)
-                    .add_targets_leq(P + 1)
-                    .set_quotient(Some(*gen))
+                    .for_coset_sweep(*gen)
                     .into_par_iter()
                     .map(|(x, _)| {
                         let b = a * get_coset_repr(chi_conj, x.to_product(decomp));
 
-                        if a == FpNum::from(0) && b == FpNum::from(0) {
-                            return 0;
-                        }
                         if !ctx.is_small(&Coord(b)) {
                             return 0;
                         }
-                        let Some(mut it) = Coord(a).part(Coord(b)) else {
-                            panic!("Attempted to look at coset solutions that don't exist: P={} a={} b={}.", P, u128::from(a), u128::from(b));
+                        let Some(mut it) = Coord(a).part_with(Coord(b), Degenerate::ExcludeZero) else {
+                            // Only the orbit of the excluded triple (0, 0, 0) may be empty.
+                            assert!(a == FpNum::ZERO && b == FpNum::ZERO, "Attempted to look at coset solutions that don't exist: P={} a={} b={}.", P, u128::from(a), u128::from(b));
+                            return 0;
                         };
                         let mut count = 0;
                         let res = if it
@@ -17,24 +17,16 @@ fn main() {
 
     const LIMIT: u128 = 10_000;
 
-    let mut fp_stream_builder = SylowStreamBuilder::new()
-        .add_flag(flags::NO_UPPER_HALF)
-        .add_flag(flags::NO_PARABOLIC)
-        .add_flag(flags::LEQ);
-    let mut fp2_stream_builder = SylowStreamBuilder::new()
-        .add_flag(flags::NO_UPPER_HALF)
-        .add_flag(flags::NO_PARABOLIC)
-        .add_flag(flags::LEQ);
-    println!("Adding these targets from F_p: ");
-    for d in FpNum::<BIG_P>::FACTORS.maximal_divisors(LIMIT) {
+    println!("Streaming these targets from F_p: ");
+    for d in FpNum::<BIG_P>::FACTORS.maximal_divisors::<7>(LIMIT) {
         println!("\t{d:?}");
-        fp_stream_builder = fp_stream_builder.add_target(&d);
     }
-    println!("Adding these targets from F_p^2: ");
+    println!("Streaming these targets from F_p^2: ");
     for d in QuadNum::<BIG_P>::FACTORS.maximal_divisors::<{ QuadNum::<BIG_P>::LENGTH }>(LIMIT) {
         println!("\t{d:?}");
-        fp2_stream_builder = fp2_stream_builder.add_target(&d);
     }
+    let fp_stream_builder = SylowStreamBuilder::new().for_unique_chi(LIMIT);
+    let fp2_stream_builder = SylowStreamBuilder::new().for_unique_chi(LIMIT);
 
     let tester = Mutex::new(OrbitTester::<BIG_P>::new());
     let count = AtomicUsize::new(0);
@@ -61,18 +53,15 @@ fn main() {
     let results = tester.into_inner().unwrap().run();
     println!("Testing complete.");
 
-    let mut repless_count = 0;
     for (x, disjoint) in results.results() {
-        let mut orbits = disjoint.get_sets().peekable();
-
-        if orbits.peek().is_none() {
-            repless_count += 1;
-            continue;
-        }
-
-        for (key, ord) in orbits {
+        for (key, ord) in disjoint.get_sets() {
             println!("For coordinate {x}: Representative: {key} is an orbit of order {ord}.");
         }
     }
-    println!("{repless_count} coordinates had no representative.");
+    let summary = results.summary();
+    println!("{} coordinates had no representative.", summary.repless);
+    println!(
+        "Largest set: {}; singleton sets: {}; associations made: {}.",
+        summary.largest_set, summary.singletons, summary.associations
+    );
 }
@@ -1,6 +1,16 @@
 //! Various number theory utility methods used throughout the libbgs crate.
 
-const fn gcd(mut a: u128, mut b: u128) -> u128 {
+pub mod u128x2;
+pub mod u256;
+
+#[doc(no_inline)]
+pub use u128x2::{carrying_add, carrying_mul, shrd};
+#[doc(no_inline)]
+pub use u256::U256;
+
+/// Returns the greatest common divisor of `a` and `b`.
+/// By convention, `gcd(a, 0) == a`, so in particular `gcd(0, 0) == 0`.
+pub const fn gcd(mut a: u128, mut b: u128) -> u128 {
     let mut t;
     while b != 0 {
         t = a % b;
@@ -10,8 +20,55 @@ const fn gcd(mut a: u128, mut b: u128) -> u128 {
     a
 }
 
+/// Returns the greatest common divisor $g$ of `a` and `b`, along with Bézout coefficients $x$ and
+/// $y$ satisfying $ax + by = g$.
+/// The coefficients are those found by the extended Euclidean algorithm, so $|x| \leq b / g$ and
+/// $|y| \leq a / g$ whenever both `a` and `b` are nonzero.
+/// The result may overflow if `a` or `b` is at least `2^127`.
+pub const fn egcd(a: u128, b: u128) -> (u128, i128, i128) {
+    let (mut r0, mut r1) = (a, b);
+    let (mut s0, mut s1): (i128, i128) = (1, 0);
+    let (mut t0, mut t1): (i128, i128) = (0, 1);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (s0, s1) = (s1, s0 - (q as i128) * s1);
+        (t0, t1) = (t1, t0 - (q as i128) * t1);
+    }
+    (r0, s0, t0)
+}
+
+/// Returns the least common multiple of `a` and `b`.
+/// By convention, the least common multiple of `0` and any integer is `0`.
+/// The result may overflow if the least common multiple is at least `2^128`.
+pub const fn lcm(a: u128, b: u128) -> u128 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    a / gcd(a, b) * b
+}
+
+/// Returns the multiplicative inverse of `a` modulo `m`, or `None` if `a` and `m` are not
+/// relatively prime.
+/// The inverse is computed with the extended Euclidean algorithm.
+/// This function will panic if `m == 0`, and may overflow if `m >= 2^127`.
+pub const fn mod_inverse(a: u128, m: u128) -> Option<u128> {
+    let (g, _, t) = egcd(m, a % m);
+    if g != 1 {
+        None
+    } else if t < 0 {
+        Some(m - (-t) as u128)
+    } else {
+        Some(t as u128)
+    }
+}
+
 /// Returns `x` to the power of `n`, modulo `m`.
+/// If `M == 0`, the power is not reduced, and overflow is only checked in debug builds: it panics
+/// in debug builds, and wraps silently in release builds.
+/// See [`checked_intpow`] for an alternative which always checks.
 pub const fn intpow<const M: u128>(mut x: u128, mut n: u128) -> u128 {
+    debug_assert!(M < 1 << 127, "intpow modulus must be less than 2^127");
     if n == 0 {
         return 1;
     }
@@ -19,25 +76,47 @@ pub const fn intpow<const M: u128>(mut x: u128, mut n: u128) -> u128 {
     while n > 1 {
         if n % 2 == 1 {
             y = if M == 0 {
-                y * x
+                unreduced_multiply(y, x)
             } else {
                 long_multiply::<M>(x, y)
             };
         }
         x = if M == 0 {
-            x * x
+            unreduced_multiply(x, x)
         } else {
             long_multiply::<M>(x, x)
         };
         n >>= 1;
     }
     if M == 0 {
-        y * x
+        unreduced_multiply(y, x)
     } else {
         long_multiply::<M>(y, x)
     }
 }
 
+/// Returns `x` to the power of `n`, modulo `m`, or `None` if the computation would overflow.
+/// Overflow is only possible when `M == 0` or `M >= 2^127`.
+pub const fn checked_intpow<const M: u128>(mut x: u128, mut n: u128) -> Option<u128> {
+    if n == 0 {
+        return Some(1);
+    }
+    let mut y = 1;
+    while n > 1 {
+        if n % 2 == 1 {
+            y = match checked_long_multiply::<M>(x, y) {
+                Some(y) => y,
+                None => return None,
+            };
+        }
+        x = match checked_long_multiply::<M>(x, x) {
+            Some(x) => x,
+            None => return None,
+        };
+        n >>= 1;
+    }
+    checked_long_multiply::<M>(y, x)
+}
 
 /// Returns a pseudo-random integer modulo `q`, unique for every `i` between `0` and `q`.
 /// This acts suitably well as a random number generator for several modular arithmetic operations,
@@ -54,9 +133,15 @@ pub const fn standard_affine_shift(q: u128, i: u128) -> u128 {
 /// Returns the product of `a` and `b` modulo `m`.
 /// This function will panic if `m >= 2^127`.
 /// Otherwise, it is guarenteed that there will not be integer overflow.
+/// If `M == 0`, the product is not reduced, and overflow is only checked in debug builds, as in
+/// [`intpow`]; see [`checked_long_multiply`] for an alternative which always checks.
 pub const fn long_multiply<const M: u128>(mut a: u128, mut b: u128) -> u128 {
+    debug_assert!(
+        M < 1 << 127,
+        "long_multiply modulus must be less than 2^127"
+    );
     if M == 0 {
-        return a * b;
+        return unreduced_multiply(a, b);
     }
 
     a %= M;
@@ -82,10 +167,64 @@ pub const fn long_multiply<const M: u128>(mut a: u128, mut b: u128) -> u128 {
     res
 }
 
+// Returns the product of `a` and `b`, for `intpow` and `long_multiply` with `M == 0`.
+const fn unreduced_multiply(a: u128, b: u128) -> u128 {
+    debug_assert!(
+        a.checked_mul(b).is_some(),
+        "unreduced product overflows a u128"
+    );
+    a.wrapping_mul(b)
+}
+
+/// Returns the product of `a` and `b` modulo `m`, or `None` if the computation would overflow.
+/// Overflow is only possible when `M == 0` or `M >= 2^127`.
+pub const fn checked_long_multiply<const M: u128>(a: u128, b: u128) -> Option<u128> {
+    if M == 0 {
+        a.checked_mul(b)
+    } else if M >= 1 << 127 {
+        None
+    } else {
+        Some(long_multiply::<M>(a, b))
+    }
+}
+
+/// Returns the product of `a` and `b` modulo `m`, as [`long_multiply`] does, for a modulus known
+/// only at runtime.
+/// Unlike [`long_multiply`], there is no overflow for any modulus, even `m >= 2^127`.
+/// This function will panic if `m == 0`.
+pub const fn mul_mod(mut a: u128, mut b: u128, m: u128) -> u128 {
+    a %= m;
+    let mut res = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            res = add_mod(res, a, m);
+        }
+        a = add_mod(a, a, m);
+        b >>= 1;
+    }
+    res
+}
+
+// Returns the sum of `a` and `b` modulo `m`, for `a, b < m`, without overflowing.
+const fn add_mod(a: u128, b: u128, m: u128) -> u128 {
+    if a >= m - b {
+        a - (m - b)
+    } else {
+        a + b
+    }
+}
+
 /// Returns a quadratic non-residue modulo `p`.
 /// That is, it returns an integer $a \in \mathbb{Z} / p\mathbb{Z}$ such that there is no $x$
 /// satisfying $x^2 = a \mod p$.
 pub fn find_nonresidue<const P: u128>() -> u128 {
+    find_nonresidue_with::<P>(Seed::DEFAULT)
+}
+
+/// Returns a quadratic non-residue modulo `p`, searching in the order given by `seed`.
+/// When $p \not\equiv 1 \mod 8$, a fixed non-residue is known, and the seed is ignored.
+/// See [`find_nonresidue`].
+pub fn find_nonresidue_with<const P: u128>(seed: Seed) -> u128 {
     if P % 4 == 3 {
         P - 1
     } else if P % 8 == 3 || P % 8 == 5 {
@@ -93,7 +232,7 @@ pub fn find_nonresidue<const P: u128>() -> u128 {
     } else {
         let mut res = 0;
         for i in 0..P {
-            let a = standard_affine_shift(P, i);
+            let a = seed.shift(P, i);
             let half_pow = intpow::<P>(a % P, (P - 1) / 2);
             if half_pow == P - 1 {
                 res = a;
@@ -104,33 +243,42 @@ pub fn find_nonresidue<const P: u128>() -> u128 {
     }
 }
 
-/// Returns the product of `a` and `b`, as two 128-bit words.
-/// The first element of the tuple is the high word, and the second is the low word.
-pub const fn carrying_mul(a: u128, b: u128) -> (u128, u128) {
-    let a_lo = a & 0xFF_FF_FF_FF_FF_FF_FF_FF;
-    let a_hi = a >> 64;
-    let b_lo = b & 0xFF_FF_FF_FF_FF_FF_FF_FF;
-    let b_hi = b >> 64;
-    
-    let cross = a_hi * b_lo + a_lo * b_hi;
-    let (c, res_lo) = carrying_add(a_lo * b_lo, (cross & 0xFF_FF_FF_FF_FF_FF_FF_FF) << 64);
-    let res_hi = a_hi * b_hi + (cross >> 64) + c;
-    (res_hi, res_lo)
-}
+/// A seed determining the order in which the pseudo-random searches throughout this library try
+/// their candidates.
+/// Every such search takes its candidates from [`Seed::shift`], so the results of a whole run are
+/// determined by a single recorded seed.
+/// The default seed, `Seed(0)`, tries candidates in the order of [`standard_affine_shift`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Seed(pub u64);
+
+impl Seed {
+    /// The default seed, which agrees with [`standard_affine_shift`].
+    pub const DEFAULT: Seed = Seed(0);
 
-/// Returns the sum of `a` and `b` as two 128-bit words.
-/// The first element of the tuple is the high word (guaranteed to be either 0 or 1) and the second
-/// is the low word.
-pub const fn carrying_add(a: u128, b: u128) -> (u128, u128) {
-    let lo = (a & 0xFF_FF_FF_FF_FF_FF_FF_FF) + (b & 0xFF_FF_FF_FF_FF_FF_FF_FF);
-    let hi = (a >> 64) + (b >> 64) + (lo >> 64);
-    (hi >> 64, (hi << 64) | (lo & 0xFF_FF_FF_FF_FF_FF_FF_FF))
+    /// Returns a pseudo-random integer modulo `q`, unique for every `i` between `0` and `q`.
+    /// For a fixed seed and `q`, this is an affine permutation $i \mapsto mi + a$ of
+    /// $\mathbb{Z} / q\mathbb{Z}$, with $m$ and $a$ derived from the seed.
+    pub const fn shift(&self, q: u128, i: u128) -> u128 {
+        if self.0 == 0 || q <= 1 {
+            return standard_affine_shift(q, i);
+        }
+        let x = splitmix64(self.0);
+        let y = splitmix64(x);
+        let mut m = 1 + ((x as u128) << 64 | y as u128) % (q - 1);
+        while gcd(m, q) != 1 {
+            m -= 1;
+        }
+        let a = (splitmix64(y) as u128) % q;
+        (mul_mod(m, i % q, q) + a) % q
+    }
 }
 
-/// Shifts `dst` to the right `n` bits, filling in from the left with the least significant bits of
-/// `src`.
-pub const fn shrd(dst: u128, src: u128, n: usize) -> u128 {
-    (dst >> n) | ((src & ((1 << n) - 1)) << (128 - n))
+// One step of the SplitMix64 generator.
+const fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
 }
 
 #[cfg(test)]
@@ -189,16 +337,94 @@ pub mod tests {
     }
 
     #[test]
-    fn carrying_multiply() {
-        let (hi, lo) = carrying_mul(BIG_P, BIG_P);
-        assert_eq!(hi, 0xb7a);
-        assert_eq!(lo, 0xbc6270503128ac1b3edc190389237521);
+    fn test_mod_inverse() {
+        for a in 1..13 {
+            let b = mod_inverse(a, 13).unwrap();
+            assert_eq!((a * b) % 13, 1);
+        }
+        assert_eq!(mod_inverse(0, 13), None);
+        assert_eq!(mod_inverse(6, 9), None);
+        let a = 538_744_077_496_950_347_511;
+        assert_eq!(mod_inverse(a, BIG_P), Some(10_022_347_072_413_323_143));
+    }
+
+    #[test]
+    fn test_seed_shift() {
+        for q in [1, 2, 97, 100, 1024] {
+            assert!((0..q).all(|i| Seed::DEFAULT.shift(q, i) == standard_affine_shift(q, i)));
+            for seed in [Seed(1), Seed(0xDEAD_BEEF)] {
+                let mut xs = (0..q).map(|i| seed.shift(q, i)).collect::<Vec<_>>();
+                assert_eq!(xs, (0..q).map(|i| seed.shift(q, i)).collect::<Vec<_>>());
+                xs.sort();
+                assert_eq!(xs, (0..q).collect::<Vec<_>>());
+            }
+        }
+        assert!(Seed(7).shift(BIG_P, BIG_P - 1) < BIG_P);
+        assert_ne!(
+            (0..8).map(|i| Seed(1).shift(97, i)).collect::<Vec<_>>(),
+            (0..8).map(|i| Seed(2).shift(97, i)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_nonresidue_with_seed() {
+        for seed in [Seed::DEFAULT, Seed(1), Seed(42)] {
+            let a = find_nonresidue_with::<113>(seed);
+            assert_eq!(intpow::<113>(a, 56), 112);
+        }
+        assert_eq!(
+            find_nonresidue::<113>(),
+            find_nonresidue_with::<113>(Seed::DEFAULT)
+        );
+    }
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(0, 7), 7);
+        assert_eq!(gcd(0, 0), 0);
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(lcm(0, 6), 0);
+        for (a, b) in [
+            (240, 46),
+            (46, 240),
+            (17, 5),
+            (0, 9),
+            (9, 0),
+            (1_000_000_007, 998_244_353),
+        ] {
+            let (g, x, y) = egcd(a, b);
+            assert_eq!(g, gcd(a, b));
+            assert_eq!(a as i128 * x + b as i128 * y, g as i128);
+        }
+    }
+
+    #[test]
+    fn test_mul_mod() {
+        assert_eq!(mul_mod(BIG_P - 1, BIG_P - 1, BIG_P), 1);
+        assert_eq!(mul_mod(u128::MAX - 1, 2, u128::MAX), u128::MAX - 2);
+        assert_eq!(mul_mod(3, 5, 1), 0);
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        assert_eq!(checked_intpow::<0>(2, 127), Some(1 << 127));
+        assert_eq!(checked_intpow::<0>(2, 128), None);
+        assert_eq!(checked_intpow::<0>(3, 0), Some(1));
+        assert_eq!(checked_intpow::<BIG_P>(3, BIG_P - 1), Some(1));
+        assert_eq!(checked_long_multiply::<0>(1 << 64, 1 << 63), Some(1 << 127));
+        assert_eq!(checked_long_multiply::<0>(1 << 64, 1 << 64), None);
+        assert_eq!(checked_long_multiply::<{ 1 << 127 }>(1, 1), None);
+        assert_eq!(
+            checked_long_multiply::<BIG_P>(538_744_077_496_950_347_511, 10_022_347_072_413_323_143),
+            Some(1)
+        );
     }
 
     #[test]
-    fn carrying_addition() {
-        let (hi, lo) = carrying_add(BIG_P << 58, BIG_P << 58);
-        assert_eq!(hi, 1);
-        assert_eq!(lo, 0xb1ae4d6e2ef50f2f7800000000000000);
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "unreduced product overflows a u128")]
+    fn test_unreduced_overflow() {
+        intpow::<0>(2, 128);
     }
 }
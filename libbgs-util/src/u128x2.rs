@@ -0,0 +1,153 @@
+//! Arithmetic on unsigned 256-bit integers represented as pairs of 128-bit words.
+//! These are the primitives underlying Montgomery multiplication of `FpNum` in `libbgs`.
+//!
+//! Functions returning a pair document their word order; [`carrying_mul`] and [`carrying_add`]
+//! return `(hi, lo)`, while [`widening_mul`] returns `(lo, hi)` to match the (unstable)
+//! `u128::widening_mul` in the standard library.
+
+const LO_MASK: u128 = 0xFF_FF_FF_FF_FF_FF_FF_FF;
+
+/// Returns the product of `a` and `b`, as two 128-bit words.
+/// The first element of the tuple is the high word, and the second is the low word.
+/// ```
+/// use libbgs_util::u128x2::carrying_mul;
+/// assert_eq!(carrying_mul(1 << 127, 4), (2, 0));
+/// assert_eq!(carrying_mul(u128::MAX, u128::MAX), (u128::MAX - 1, 1));
+/// ```
+pub const fn carrying_mul(a: u128, b: u128) -> (u128, u128) {
+    let (lo, hi) = widening_mul(a, b);
+    (hi, lo)
+}
+
+/// Returns the product of `a` and `b`, as two 128-bit words.
+/// The first element of the tuple is the low word, and the second is the high word.
+///
+/// Stable Rust has no 256-bit multiplication intrinsic, so this is computed from four
+/// 64-by-64-bit multiplications, each of which compiles to a single native multiplication on
+/// 64-bit targets.
+/// ```
+/// use libbgs_util::u128x2::{carrying_mul, widening_mul};
+/// assert_eq!(widening_mul(u128::MAX, u128::MAX), (1, u128::MAX - 1));
+/// let (hi, lo) = carrying_mul(12345 << 100, 67890 << 90);
+/// assert_eq!(widening_mul(12345 << 100, 67890 << 90), (lo, hi));
+/// ```
+pub const fn widening_mul(a: u128, b: u128) -> (u128, u128) {
+    let (a0, a1) = (a & LO_MASK, a >> 64);
+    let (b0, b1) = (b & LO_MASK, b >> 64);
+
+    let p00 = a0 * b0;
+    let p01 = a0 * b1;
+    let p10 = a1 * b0;
+    let p11 = a1 * b1;
+
+    // At most 3 (2^64 - 1), so this cannot overflow.
+    let mid = (p00 >> 64) + (p01 & LO_MASK) + (p10 & LO_MASK);
+    let lo = (p00 & LO_MASK) | (mid << 64);
+    let hi = p11 + (p01 >> 64) + (p10 >> 64) + (mid >> 64);
+    (lo, hi)
+}
+
+/// Returns the sum of `a` and `b` as two 128-bit words.
+/// The first element of the tuple is the high word (guaranteed to be either 0 or 1) and the second
+/// is the low word.
+/// ```
+/// use libbgs_util::u128x2::carrying_add;
+/// assert_eq!(carrying_add(u128::MAX, 1), (1, 0));
+/// assert_eq!(carrying_add(u128::MAX, u128::MAX), (1, u128::MAX - 1));
+/// ```
+pub const fn carrying_add(a: u128, b: u128) -> (u128, u128) {
+    let lo = (a & LO_MASK) + (b & LO_MASK);
+    let hi = (a >> 64) + (b >> 64) + (lo >> 64);
+    (hi >> 64, (hi << 64) | (lo & LO_MASK))
+}
+
+/// Shifts `dst` to the right `n` bits, filling in from the left with the least significant bits of
+/// `src`.
+/// That is, returns the low word of the 256-bit integer with high word `src` and low word `dst`,
+/// shifted right by `n` bits.
+/// Requires `0 < n < 128`.
+/// ```
+/// use libbgs_util::u128x2::shrd;
+/// assert_eq!(shrd(0xF0, 0b101, 4), 0x0F | (0b101 << 124));
+/// assert_eq!(shrd(u128::MAX, 0, 127), 1);
+/// ```
+pub const fn shrd(dst: u128, src: u128, n: usize) -> u128 {
+    (dst >> n) | ((src & ((1 << n) - 1)) << (128 - n))
+}
+
+/// Returns the quotient and remainder of the 256-bit integer with high word `hi` and low word
+/// `lo`, divided by `d`.
+/// Returns `None` if `d` is zero, or if the quotient does not fit in 128 bits, which is the case
+/// exactly when `hi >= d`.
+/// ```
+/// use libbgs_util::u128x2::{carrying_mul, checked_div_wide};
+/// let (hi, lo) = carrying_mul(u128::MAX, 1 << 100);
+/// assert_eq!(checked_div_wide(hi, lo, 1 << 101), Some((u128::MAX >> 1, 1 << 100)));
+/// assert_eq!(checked_div_wide(hi, lo, 1 << 100), Some((u128::MAX, 0)));
+/// assert_eq!(checked_div_wide(hi, lo, 1 << 99), None);
+/// ```
+pub const fn checked_div_wide(hi: u128, lo: u128, d: u128) -> Option<(u128, u128)> {
+    if hi >= d {
+        return None;
+    }
+    // Schoolbook division, one bit of the quotient at a time.
+    let mut rem = hi;
+    let mut quot = 0;
+    let mut i = 128;
+    while i > 0 {
+        i -= 1;
+        let carry = rem >> 127;
+        rem = (rem << 1) | ((lo >> i) & 1);
+        quot <<= 1;
+        if carry == 1 || rem >= d {
+            rem = rem.wrapping_sub(d);
+            quot |= 1;
+        }
+    }
+    Some((quot, rem))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BIG_P: u128 = 1_000_000_000_000_000_124_399;
+
+    #[test]
+    fn carrying_multiply() {
+        let (hi, lo) = carrying_mul(BIG_P, BIG_P);
+        assert_eq!(hi, 0xb7a);
+        assert_eq!(lo, 0xbc6270503128ac1b3edc190389237521);
+    }
+
+    #[test]
+    fn carrying_addition() {
+        let (hi, lo) = carrying_add(BIG_P << 58, BIG_P << 58);
+        assert_eq!(hi, 1);
+        assert_eq!(lo, 0xb1ae4d6e2ef50f2f7800000000000000);
+    }
+
+    #[test]
+    fn widening_multiply() {
+        assert_eq!(widening_mul(0, u128::MAX), (0, 0));
+        assert_eq!(widening_mul(1 << 127, 1 << 127), (0, 1 << 126));
+        assert_eq!(widening_mul(u128::MAX, 2), (u128::MAX - 1, 1));
+        // (2^128 - 1)(2^127 - 1) = 2^255 - 2^128 - 2^127 + 1.
+        assert_eq!(
+            widening_mul(u128::MAX, u128::MAX >> 1),
+            ((1 << 127) + 1, (1 << 127) - 2)
+        );
+    }
+
+    #[test]
+    fn wide_division() {
+        let (hi, lo) = carrying_mul(BIG_P, BIG_P);
+        assert_eq!(checked_div_wide(hi, lo + 5, BIG_P), Some((BIG_P, 5)));
+        let (hi, lo) = carrying_mul(u128::MAX, u128::MAX - 1);
+        assert_eq!(
+            checked_div_wide(hi, lo, u128::MAX),
+            Some((u128::MAX - 1, 0))
+        );
+        assert_eq!(checked_div_wide(0, 7, 0), None);
+    }
+}
@@ -0,0 +1,399 @@
+//! Unsigned 256-bit integers, for moduli too large for the `u128` arithmetic in [`crate::u128x2`].
+//! These underlie Montgomery multiplication of `Fp256` in `libbgs`.
+
+use std::cmp::Ordering;
+
+/// An unsigned 256-bit integer, stored as four 64-bit limbs, least significant first.
+///
+/// Arithmetic is provided as `const` methods in the manner of the primitive integer types, with
+/// `overflowing_` and `checked_` variants; there are no operator implementations, so that every
+/// overflow is handled explicitly.
+/// ```
+/// use libbgs_util::u256::U256;
+/// let x = U256::from_words(1, u128::MAX);
+/// assert_eq!(x.checked_add(&U256::ONE), Some(U256::from_words(2, 0)));
+/// assert_eq!(x.to_string(), "680564733841876926926749214863536422911");
+/// assert_eq!("680564733841876926926749214863536422911".parse(), Ok(x));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct U256([u64; 4]);
+
+/// The error returned when parsing a [`U256`] from a string fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseU256Error;
+
+impl U256 {
+    /// The integer 0.
+    pub const ZERO: U256 = U256([0; 4]);
+
+    /// The integer 1.
+    pub const ONE: U256 = U256([1, 0, 0, 0]);
+
+    /// The integer $2^{256} - 1$.
+    pub const MAX: U256 = U256([u64::MAX; 4]);
+
+    /// Returns the integer with the given limbs, least significant first.
+    pub const fn from_limbs(limbs: [u64; 4]) -> U256 {
+        U256(limbs)
+    }
+
+    /// Returns the limbs of this integer, least significant first.
+    pub const fn limbs(&self) -> [u64; 4] {
+        self.0
+    }
+
+    /// Returns the integer with high word `hi` and low word `lo`.
+    pub const fn from_words(hi: u128, lo: u128) -> U256 {
+        U256([lo as u64, (lo >> 64) as u64, hi as u64, (hi >> 64) as u64])
+    }
+
+    /// Converts a `u128` into a `U256`.
+    pub const fn from_u128(x: u128) -> U256 {
+        U256::from_words(0, x)
+    }
+
+    /// Returns the high 128 bits of this integer.
+    pub const fn hi(&self) -> u128 {
+        (self.0[3] as u128) << 64 | self.0[2] as u128
+    }
+
+    /// Returns the low 128 bits of this integer.
+    pub const fn lo(&self) -> u128 {
+        (self.0[1] as u128) << 64 | self.0[0] as u128
+    }
+
+    /// Returns this integer as a `u128`, or `None` if it does not fit.
+    pub const fn to_u128(&self) -> Option<u128> {
+        if self.hi() == 0 {
+            Some(self.lo())
+        } else {
+            None
+        }
+    }
+
+    /// True if this integer is zero.
+    pub const fn is_zero(&self) -> bool {
+        self.0[0] | self.0[1] | self.0[2] | self.0[3] == 0
+    }
+
+    /// Returns bit `i` of this integer, where bit 0 is the least significant.
+    /// Bits beyond the 256th are zero.
+    pub const fn bit(&self, i: u32) -> bool {
+        i < 256 && (self.0[(i / 64) as usize] >> (i % 64)) & 1 == 1
+    }
+
+    /// Returns the number of bits needed to represent this integer; zero needs no bits.
+    pub const fn bits(&self) -> u32 {
+        let mut i = 4;
+        while i > 0 {
+            i -= 1;
+            if self.0[i] != 0 {
+                return 64 * i as u32 + 64 - self.0[i].leading_zeros();
+            }
+        }
+        0
+    }
+
+    /// Compares two integers; this is the `const` equivalent of [`Ord::cmp`].
+    pub const fn const_cmp(&self, other: &U256) -> Ordering {
+        let mut i = 4;
+        while i > 0 {
+            i -= 1;
+            if self.0[i] < other.0[i] {
+                return Ordering::Less;
+            } else if self.0[i] > other.0[i] {
+                return Ordering::Greater;
+            }
+        }
+        Ordering::Equal
+    }
+
+    /// Returns the sum of the two integers modulo $2^{256}$, and whether it overflowed.
+    pub const fn overflowing_add(&self, other: &U256) -> (U256, bool) {
+        let mut res = [0; 4];
+        let mut carry = 0;
+        let mut i = 0;
+        while i < 4 {
+            let s = self.0[i] as u128 + other.0[i] as u128 + carry;
+            res[i] = s as u64;
+            carry = s >> 64;
+            i += 1;
+        }
+        (U256(res), carry == 1)
+    }
+
+    /// Returns the difference of the two integers modulo $2^{256}$, and whether it overflowed.
+    pub const fn overflowing_sub(&self, other: &U256) -> (U256, bool) {
+        let mut res = [0; 4];
+        let mut borrow = 0;
+        let mut i = 0;
+        while i < 4 {
+            let (d, b1) = self.0[i].overflowing_sub(other.0[i]);
+            let (d, b2) = d.overflowing_sub(borrow);
+            res[i] = d;
+            borrow = (b1 | b2) as u64;
+            i += 1;
+        }
+        (U256(res), borrow == 1)
+    }
+
+    /// Returns the sum of the two integers, or `None` if it overflows.
+    pub const fn checked_add(&self, other: &U256) -> Option<U256> {
+        match self.overflowing_add(other) {
+            (res, false) => Some(res),
+            (_, true) => None,
+        }
+    }
+
+    /// Returns the difference of the two integers, or `None` if it is negative.
+    pub const fn checked_sub(&self, other: &U256) -> Option<U256> {
+        match self.overflowing_sub(other) {
+            (res, false) => Some(res),
+            (_, true) => None,
+        }
+    }
+
+    /// Returns the product of the two integers as two 256-bit words.
+    /// The first element of the tuple is the low word, and the second is the high word, as for
+    /// [`crate::u128x2::widening_mul`].
+    pub const fn widening_mul(&self, other: &U256) -> (U256, U256) {
+        let mut res = [0u64; 8];
+        let mut i = 0;
+        while i < 4 {
+            let mut carry = 0;
+            let mut j = 0;
+            while j < 4 {
+                let t = self.0[i] as u128 * other.0[j] as u128 + res[i + j] as u128 + carry;
+                res[i + j] = t as u64;
+                carry = t >> 64;
+                j += 1;
+            }
+            res[i + 4] = carry as u64;
+            i += 1;
+        }
+        (
+            U256([res[0], res[1], res[2], res[3]]),
+            U256([res[4], res[5], res[6], res[7]]),
+        )
+    }
+
+    /// Returns the product of the two integers, or `None` if it overflows.
+    pub const fn checked_mul(&self, other: &U256) -> Option<U256> {
+        let (lo, hi) = self.widening_mul(other);
+        if hi.is_zero() {
+            Some(lo)
+        } else {
+            None
+        }
+    }
+
+    /// Shifts this integer left by `n` bits, discarding the bits shifted out.
+    /// Requires `n < 256`.
+    pub const fn shl(&self, n: u32) -> U256 {
+        let (limbs, bits) = ((n / 64) as usize, n % 64);
+        let mut res = [0; 4];
+        let mut i = 4;
+        while i > limbs {
+            i -= 1;
+            res[i] = self.0[i - limbs] << bits;
+            if bits > 0 && i > limbs {
+                res[i] |= self.0[i - limbs - 1] >> (64 - bits);
+            }
+        }
+        U256(res)
+    }
+
+    /// Shifts this integer right by `n` bits.
+    /// Requires `n < 256`.
+    pub const fn shr(&self, n: u32) -> U256 {
+        let (limbs, bits) = ((n / 64) as usize, n % 64);
+        let mut res = [0; 4];
+        let mut i = 0;
+        while i + limbs < 4 {
+            res[i] = self.0[i + limbs] >> bits;
+            if bits > 0 && i + limbs + 1 < 4 {
+                res[i] |= self.0[i + limbs + 1] << (64 - bits);
+            }
+            i += 1;
+        }
+        U256(res)
+    }
+
+    /// Returns the quotient and remainder of this integer divided by `d`, or `None` if `d` is
+    /// zero.
+    pub const fn checked_div_rem(&self, d: &U256) -> Option<(U256, U256)> {
+        if d.is_zero() {
+            return None;
+        }
+        // Schoolbook division, one bit of the quotient at a time.
+        let mut quot = [0; 4];
+        let mut rem = U256::ZERO;
+        let mut i = self.bits();
+        while i > 0 {
+            i -= 1;
+            let carry = rem.bit(255);
+            rem = rem.shl(1);
+            rem.0[0] |= self.bit(i) as u64;
+            if carry || !matches!(rem.const_cmp(d), Ordering::Less) {
+                rem = rem.overflowing_sub(d).0;
+                quot[(i / 64) as usize] |= 1 << (i % 64);
+            }
+        }
+        Some((U256(quot), rem))
+    }
+
+    /// Returns the remainder of this integer divided by `d`.
+    /// This function will panic if `d` is zero.
+    pub const fn rem(&self, d: &U256) -> U256 {
+        match self.checked_div_rem(d) {
+            Some((_, rem)) => rem,
+            None => panic!("attempt to calculate the remainder with a divisor of zero"),
+        }
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &U256) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &U256) -> Ordering {
+        self.const_cmp(other)
+    }
+}
+
+impl From<u128> for U256 {
+    fn from(x: u128) -> U256 {
+        U256::from_u128(x)
+    }
+}
+
+impl std::fmt::Display for U256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Peel off 19 decimal digits at a time, the most which fit in a `u64`.
+        const CHUNK: U256 = U256::from_u128(10_000_000_000_000_000_000);
+        let mut chunks = Vec::new();
+        let mut x = *self;
+        while !x.is_zero() {
+            let (q, r) = x.checked_div_rem(&CHUNK).unwrap();
+            chunks.push(r.0[0]);
+            x = q;
+        }
+        let mut s = match chunks.pop() {
+            Some(c) => c.to_string(),
+            None => String::from("0"),
+        };
+        for c in chunks.iter().rev() {
+            s.push_str(&format!("{c:019}"));
+        }
+        f.pad_integral(true, "", &s)
+    }
+}
+
+impl std::fmt::Debug for U256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+/// Parses a decimal integer.
+impl std::str::FromStr for U256 {
+    type Err = ParseU256Error;
+
+    fn from_str(s: &str) -> Result<U256, ParseU256Error> {
+        if s.is_empty() {
+            return Err(ParseU256Error);
+        }
+        let ten = U256::from_u128(10);
+        s.chars().try_fold(U256::ZERO, |acc, c| {
+            let d = c.to_digit(10).ok_or(ParseU256Error)?;
+            acc.checked_mul(&ten)
+                .and_then(|x| x.checked_add(&U256::from_u128(d as u128)))
+                .ok_or(ParseU256Error)
+        })
+    }
+}
+
+impl std::fmt::Display for ParseU256Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid or out of range 256-bit integer")
+    }
+}
+
+impl std::error::Error for ParseU256Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::u128x2::carrying_mul;
+
+    const BIG_P: u128 = 1_000_000_000_000_000_124_399;
+
+    #[test]
+    fn multiplies_like_u128x2() {
+        let (hi, lo) = carrying_mul(BIG_P, u128::MAX);
+        let (prod, over) = U256::from_u128(BIG_P).widening_mul(&U256::from_u128(u128::MAX));
+        assert_eq!(prod, U256::from_words(hi, lo));
+        assert!(over.is_zero());
+
+        let (lo, hi) = U256::MAX.widening_mul(&U256::MAX);
+        assert_eq!(
+            (lo, hi),
+            (U256::ONE, U256::MAX.overflowing_sub(&U256::ONE).0)
+        );
+        assert_eq!(U256::MAX.checked_mul(&U256::from_u128(2)), None);
+    }
+
+    #[test]
+    fn adds_and_subtracts() {
+        assert_eq!(U256::MAX.overflowing_add(&U256::ONE), (U256::ZERO, true));
+        assert_eq!(U256::ZERO.overflowing_sub(&U256::ONE), (U256::MAX, true));
+        let x = U256::from_words(5, 0);
+        assert_eq!(
+            x.checked_sub(&U256::ONE),
+            Some(U256::from_words(4, u128::MAX))
+        );
+        assert_eq!(U256::ONE.checked_sub(&x), None);
+        assert!(U256::ONE < x && x < U256::MAX);
+    }
+
+    #[test]
+    fn shifts_and_divides() {
+        let x = U256::from_u128(BIG_P);
+        assert_eq!(x.shl(170).shr(170), x);
+        assert_eq!(x.shl(200).shr(200), x.rem(&U256::ONE.shl(56)));
+        assert_eq!(x.shl(128), U256::from_words(BIG_P, 0));
+        assert_eq!(U256::ONE.shl(255).bits(), 256);
+        assert!(U256::ONE.shl(199).bit(199));
+
+        let (hi, lo) = carrying_mul(BIG_P, BIG_P);
+        let sq = U256::from_words(hi, lo);
+        assert_eq!(sq.checked_div_rem(&x), Some((x, U256::ZERO)));
+        let (q, r) = sq
+            .overflowing_add(&U256::from_u128(7))
+            .0
+            .checked_div_rem(&x)
+            .unwrap();
+        assert_eq!((q, r), (x, U256::from_u128(7)));
+        assert_eq!(U256::MAX.rem(&U256::from_u128(10)), U256::from_u128(5));
+        assert_eq!(x.checked_div_rem(&U256::ZERO), None);
+    }
+
+    #[test]
+    fn formats_and_parses() {
+        assert_eq!(U256::ZERO.to_string(), "0");
+        assert_eq!(U256::from_u128(BIG_P).to_string(), BIG_P.to_string());
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        assert_eq!(U256::MAX.to_string(), max);
+        assert_eq!(max.parse(), Ok(U256::MAX));
+        assert_eq!(
+            "115792089237316195423570985008687907853269984665640564039457584007913129639936"
+                .parse::<U256>(),
+            Err(ParseU256Error)
+        );
+        assert_eq!("".parse::<U256>(), Err(ParseU256Error));
+        assert_eq!("12a".parse::<U256>(), Err(ParseU256Error));
+    }
+}
@@ -0,0 +1,9 @@
+//! Results and reports of the Bourgain, Gamburd, and Sarnak search for connectivity of the
+//! Markoff graph modulo primes.
+mod registry;
+mod report;
+mod search;
+
+pub use registry::*;
+pub use report::*;
+pub use search::*;
@@ -0,0 +1,364 @@
+use std::collections::BTreeMap;
+
+use crate::bgs::{SearchConfig, Truncation};
+use crate::markoff::Parabolic;
+use crate::numbers::*;
+use crate::util::Seed;
+
+#[cfg(feature = "serde")]
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+/// The metadata of experiments on many primes, keyed by prime.
+/// With the `serde` feature, a registry serializes as a single map from primes to
+/// [`PrimeEntry`]s, so that the metadata of a whole campaign can be kept in one JSON file.
+/// Formats without 128-bit integers, such as TOML, cannot hold a registry.
+/// Deserializing fails if an entry is keyed by a prime other than its own.
+/// See [`SearchConfig::run_with_entry`] to search with the recorded decompositions.
+///
+/// # Example
+/// ```
+/// use libbgs::bgs::*;
+/// use libbgs::numbers::*;
+///
+/// struct Ph;
+/// impl_factors!(Ph, 13);
+///
+/// let mut registry = Registry::new();
+/// registry.record::<Ph, 13, 2, 2>(&SearchConfig::new());
+///
+/// let entry = registry.get(13).unwrap();
+/// assert_eq!(entry.hyper_factors, vec![(2, 2), (3, 1)]);
+/// let decomp = entry.hyper_decomp::<Ph, 13, 2>().unwrap();
+/// assert!(decomp.verify());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Registry {
+    entries: BTreeMap<u128, PrimeEntry>,
+}
+
+/// The metadata of experiments on a single prime $p$.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PrimeEntry {
+    /// The prime $p$.
+    pub prime: u128,
+    /// The prime factorization of $p - 1$, as pairs $(q, t)$ of primes and their powers, in
+    /// increasing order of $q$.
+    pub hyper_factors: Vec<(u128, usize)>,
+    /// The prime factorization of $p + 1$, in the same form as `hyper_factors`.
+    pub ellip_factors: Vec<(u128, usize)>,
+    /// The quadratic nonresidue $r$ used to write $\mathbb{F}\_{p^2}$ as
+    /// $\mathbb{F}\_p(\sqrt{r})$.
+    /// See [`QuadNum::R`].
+    pub nonresidue: u128,
+    /// The generators of the Sylow subgroups of $\mathbb{F}\_p^\times$, indexed as
+    /// `hyper_factors`.
+    pub hyper_generators: Vec<u128>,
+    /// The generators of the Sylow subgroups of the norm 1 subgroup of $\mathbb{F}\_{p^2}^\times$,
+    /// as the pairs $(a_0, a_1)$ of $a_0 + a_1\sqrt{r}$, indexed as `ellip_factors`.
+    pub ellip_generators: Vec<(u128, u128)>,
+    /// The configuration of searches modulo $p$.
+    pub profile: Profile,
+}
+
+/// The tunable parameters of a search, in a form which can be stored in a [`Registry`].
+/// See [`SearchConfig`]; the default profile is that of the default configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Profile {
+    /// The seed of the search's pseudo-random choices.
+    pub seed: u64,
+    /// The number of steps walked along each coset before the walk is truncated.
+    pub walk_budget: usize,
+    /// What a truncated coset walk counts as.
+    pub truncation: Truncation,
+    /// Whether the search splits its work into fixed chunks.
+    /// See [`SearchConfig::deterministic`].
+    pub deterministic: bool,
+    /// Whether the parabolic coordinates count as small coordinates.
+    pub parabolic: Parabolic,
+}
+
+impl Registry {
+    /// Returns an empty registry.
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    /// Adds `entry` to the registry, returning the entry it replaces for the same prime, if any.
+    pub fn insert(&mut self, entry: PrimeEntry) -> Option<PrimeEntry> {
+        self.entries.insert(entry.prime, entry)
+    }
+
+    /// Computes the entry of the prime `P` with [`PrimeEntry::compute`], and adds it to the
+    /// registry, replacing any entry for `P`.
+    pub fn record<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize>(
+        &mut self,
+        config: &SearchConfig,
+    ) -> &PrimeEntry
+    where
+        FpNum<P>: Factor<S>,
+        QuadNum<P>: Factor<S>,
+    {
+        self.insert(PrimeEntry::compute::<S, P, L_HYPER, L_ELLIP>(config));
+        &self.entries[&P]
+    }
+
+    /// Returns the entry of the prime `p`, if any.
+    pub fn get(&self, p: u128) -> Option<&PrimeEntry> {
+        self.entries.get(&p)
+    }
+
+    /// Removes and returns the entry of the prime `p`, if any.
+    pub fn remove(&mut self, p: u128) -> Option<PrimeEntry> {
+        self.entries.remove(&p)
+    }
+
+    /// Returns every entry, in increasing order of prime.
+    pub fn iter(&self) -> impl Iterator<Item = &PrimeEntry> {
+        self.entries.values()
+    }
+
+    /// Returns the number of primes in the registry.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if the registry has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Registry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Registry, D::Error> {
+        let entries = BTreeMap::<u128, PrimeEntry>::deserialize(deserializer)?;
+        if let Some((p, entry)) = entries.iter().find(|(p, entry)| **p != entry.prime) {
+            return Err(D::Error::custom(format!(
+                "the entry of {} is keyed by {p}",
+                entry.prime
+            )));
+        }
+        Ok(Registry { entries })
+    }
+}
+
+impl Extend<PrimeEntry> for Registry {
+    fn extend<I: IntoIterator<Item = PrimeEntry>>(&mut self, iter: I) {
+        for entry in iter {
+            self.insert(entry);
+        }
+    }
+}
+
+impl FromIterator<PrimeEntry> for Registry {
+    fn from_iter<I: IntoIterator<Item = PrimeEntry>>(iter: I) -> Registry {
+        let mut res = Registry::new();
+        res.extend(iter);
+        res
+    }
+}
+
+impl PrimeEntry {
+    /// Returns the entry of the prime `P`, with the factorizations of the `Factor`
+    /// implementations for `S`, and the generators of the Sylow decompositions found with the
+    /// seed of `config`.
+    /// This finds both Sylow decompositions, so takes as long as starting a search.
+    pub fn compute<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize>(
+        config: &SearchConfig,
+    ) -> PrimeEntry
+    where
+        FpNum<P>: Factor<S>,
+        QuadNum<P>: Factor<S>,
+    {
+        let hyper = SylowDecomp::<S, L_HYPER, FpNum<P>>::new_with_seed(config.seed);
+        let ellip = SylowDecomp::<S, L_ELLIP, QuadNum<P>>::new_with_seed(config.seed);
+        PrimeEntry {
+            prime: P,
+            hyper_factors: FpNum::<P>::FACTORS.factors().to_vec(),
+            ellip_factors: QuadNum::<P>::FACTORS.factors().to_vec(),
+            nonresidue: u128::from(QuadNum::<P>::R),
+            hyper_generators: (0..L_HYPER)
+                .map(|i| u128::from(*hyper.generator(i)))
+                .collect(),
+            ellip_generators: (0..L_ELLIP)
+                .map(|i| {
+                    let QuadNum(a0, a1) = *ellip.generator(i);
+                    (u128::from(a0), u128::from(a1))
+                })
+                .collect(),
+            profile: Profile::from(*config),
+        }
+    }
+
+    /// True if this entry is of the prime `P`, and agrees with the `Factor` implementations for
+    /// `S` and with the nonresidue [`QuadNum::R`].
+    pub fn matches<S, const P: u128>(&self) -> bool
+    where
+        FpNum<P>: Factor<S>,
+        QuadNum<P>: Factor<S>,
+    {
+        self.prime == P
+            && self.hyper_factors == FpNum::<P>::FACTORS.factors()
+            && self.ellip_factors == QuadNum::<P>::FACTORS.factors()
+            && self.nonresidue == u128::from(QuadNum::<P>::R)
+    }
+
+    /// Returns the Sylow decomposition of $\mathbb{F}\_p^\times$ with the recorded generators,
+    /// without searching for any, or `None` if this entry does not [match](PrimeEntry::matches)
+    /// or the generators fail [`SylowDecomp::verify`].
+    pub fn hyper_decomp<S, const P: u128, const L: usize>(
+        &self,
+    ) -> Option<SylowDecomp<S, L, FpNum<P>>>
+    where
+        FpNum<P>: Factor<S>,
+        QuadNum<P>: Factor<S>,
+    {
+        if !self.matches::<S, P>() || self.hyper_generators.len() != L {
+            return None;
+        }
+        let decomp = SylowDecomp::from_generators(std::array::from_fn(|i| {
+            FpNum::from(self.hyper_generators[i])
+        }));
+        decomp.verify().then_some(decomp)
+    }
+
+    /// Returns the Sylow decomposition of the norm 1 subgroup of $\mathbb{F}\_{p^2}^\times$ with
+    /// the recorded generators, as [`PrimeEntry::hyper_decomp`] does.
+    pub fn ellip_decomp<S, const P: u128, const L: usize>(
+        &self,
+    ) -> Option<SylowDecomp<S, L, QuadNum<P>>>
+    where
+        FpNum<P>: Factor<S>,
+        QuadNum<P>: Factor<S>,
+    {
+        if !self.matches::<S, P>() || self.ellip_generators.len() != L {
+            return None;
+        }
+        let decomp = SylowDecomp::from_generators(std::array::from_fn(|i| {
+            QuadNum::from(self.ellip_generators[i])
+        }));
+        decomp.verify().then_some(decomp)
+    }
+}
+
+impl Profile {
+    /// Returns the search configuration with these parameters.
+    pub fn config(&self) -> SearchConfig {
+        SearchConfig::new()
+            .with_seed(Seed(self.seed))
+            .with_walk_budget(self.walk_budget, self.truncation)
+            .with_deterministic(self.deterministic)
+            .with_parabolic(self.parabolic)
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Profile {
+        Profile::from(SearchConfig::default())
+    }
+}
+
+impl From<SearchConfig> for Profile {
+    fn from(config: SearchConfig) -> Profile {
+        Profile {
+            seed: config.seed.0,
+            walk_budget: config.walk_budget,
+            truncation: config.truncation,
+            deterministic: config.deterministic,
+            parabolic: config.parabolic,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Eq, Clone, Copy, Debug)]
+    struct Phantom {}
+
+    struct Other {}
+
+    impl_factors!(Phantom, 61, 1_000_000_000_000_000_124_399);
+
+    impl Factor<Other> for FpNum<61> {
+        const FACTORS: Factorization = Factorization::new(&[(4, 1), (3, 1), (5, 1)]);
+    }
+    impl Factor<Other> for QuadNum<61> {
+        const FACTORS: Factorization = Factorization::new(&[(2, 1), (31, 1)]);
+    }
+
+    #[test]
+    fn round_trips_generators() {
+        const BIG_P: u128 = 1_000_000_000_000_000_124_399;
+        let config = SearchConfig::new()
+            .with_seed(Seed(7))
+            .with_walk_budget(20, Truncation::Exact)
+            .with_deterministic(true)
+            .with_parabolic(Parabolic::Include);
+        let mut registry = Registry::new();
+        registry.record::<Phantom, 61, 3, 2>(&config);
+        registry.record::<Phantom, BIG_P, 7, 11>(&SearchConfig::new());
+        assert_eq!(registry.len(), 2);
+        assert_eq!(
+            registry.iter().map(|e| e.prime).collect::<Vec<_>>(),
+            vec![61, BIG_P]
+        );
+
+        let entry = registry.get(61).unwrap();
+        assert_eq!(entry.profile.config(), config);
+        assert!(entry.matches::<Phantom, 61>());
+        assert!(!entry.matches::<Other, 61>());
+        assert!(!entry.matches::<Phantom, BIG_P>());
+
+        let hyper = entry.hyper_decomp::<Phantom, 61, 3>().unwrap();
+        let expected = SylowDecomp::<Phantom, 3, FpNum<61>>::new_with_seed(Seed(7));
+        assert!((0..3).all(|i| hyper.generator(i) == expected.generator(i)));
+        let ellip = registry
+            .get(BIG_P)
+            .unwrap()
+            .ellip_decomp::<Phantom, BIG_P, 11>()
+            .unwrap();
+        let expected = SylowDecomp::<Phantom, 11, QuadNum<BIG_P>>::new();
+        assert!((0..11).all(|i| ellip.generator(i) == expected.generator(i)));
+    }
+
+    #[test]
+    fn default_profile() {
+        assert_eq!(Profile::default().config(), SearchConfig::default());
+    }
+
+    #[test]
+    fn rejects_inconsistent_entries() {
+        let mut entry = PrimeEntry::compute::<Phantom, 61, 3, 2>(&SearchConfig::new());
+        assert!(entry.hyper_decomp::<Phantom, 61, 2>().is_none());
+        entry.hyper_generators.swap(0, 1);
+        assert!(entry.hyper_decomp::<Phantom, 61, 3>().is_none());
+        assert!(entry.ellip_decomp::<Phantom, 61, 2>().is_some());
+        entry.nonresidue += 1;
+        assert!(entry.ellip_decomp::<Phantom, 61, 2>().is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes() {
+        let registry = [
+            PrimeEntry::compute::<Phantom, 61, 3, 2>(&SearchConfig::new()),
+            PrimeEntry::compute::<Phantom, 1_000_000_000_000_000_124_399, 7, 11>(
+                &SearchConfig::new(),
+            ),
+        ]
+        .into_iter()
+        .collect::<Registry>();
+        let json = serde_json::to_string(&registry).unwrap();
+        assert!(json.starts_with("{\"61\":{\"prime\":61,"));
+        assert_eq!(serde_json::from_str::<Registry>(&json).unwrap(), registry);
+
+        let json = json.replacen("{\"61\":", "{\"67\":", 1);
+        assert!(serde_json::from_str::<Registry>(&json).is_err());
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::streams::StreamEstimate;
+use crate::util::stats::Histogram;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The results of searching for the connectivity of the Markoff graph modulo a single prime.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SearchReport {
+    /// The prime $p$.
+    pub prime: u128,
+    /// The seed of the run's pseudo-random choices.
+    /// See [`crate::util::Seed`].
+    pub seed: u64,
+    /// The bound on the endgame breakpoint for hyperbolic orders.
+    /// See [`crate::markoff::Coord::endgame`].
+    pub hyper_endgame: u128,
+    /// The bound on the endgame breakpoint for elliptic orders.
+    pub ellip_endgame: u128,
+    /// The smallest order at which the middlegame begins, if any.
+    pub middle_game: Option<u128>,
+    /// The longest walk taken along any coset in the middlegame.
+    pub coset_max: usize,
+    /// The number of coset walks in the middlegame truncated by the walk budget.
+    /// See [`crate::bgs::SearchConfig::walk_budget`].
+    pub truncated_walks: usize,
+    /// The number of coset walks in the middlegame of each length, counting each length exactly.
+    pub walk_lengths: Histogram,
+    /// The result of the coset phase for hyperbolic orders.
+    pub hyper_count: u128,
+    /// The result of the coset phase for elliptic orders.
+    pub ellip_count: u128,
+    /// The contribution of each hyperbolic order to `hyper_count`.
+    pub hyper_orders: BTreeMap<u128, u128>,
+    /// The contribution of each elliptic order to `ellip_count`.
+    pub ellip_orders: BTreeMap<u128, u128>,
+    /// The total number of elements enumerated during the search.
+    pub elements: u128,
+    /// The time spent in each phase of the search.
+    pub timings: Timings,
+}
+
+/// A prediction of the work and memory of searching for the connectivity of the Markoff graph
+/// modulo a single prime, made without running the search.
+/// See [`crate::bgs::SearchConfig::dry_run`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SearchEstimate {
+    /// The prime $p$.
+    pub prime: u128,
+    /// The bound on the endgame breakpoint for hyperbolic orders.
+    pub hyper_endgame: u128,
+    /// The bound on the endgame breakpoint for elliptic orders.
+    pub ellip_endgame: u128,
+    /// The smallest order at which the middlegame begins, if any.
+    pub middle_game: Option<u128>,
+    /// Hyperbolic orders up to this limit are swept in the coset phase.
+    pub hyper_lim: u128,
+    /// Elliptic orders up to this limit are swept in the coset phase.
+    pub ellip_lim: u128,
+    /// The stream of hyperbolic $\chi$ swept in the coset phase.
+    pub hyper: StreamEstimate,
+    /// The stream of elliptic $\chi$ swept in the coset phase.
+    pub ellip: StreamEstimate,
+    /// The approximate number of cosets swept for hyperbolic $\chi$.
+    pub hyper_cosets: u128,
+    /// The approximate number of cosets swept for elliptic $\chi$.
+    pub ellip_cosets: u128,
+    /// A rough bound on the memory used by the search, in bytes: the Sylow decompositions, and
+    /// one sequential stream of each kind per thread.
+    pub memory: usize,
+}
+
+/// The time spent in each phase of a search, measured with [`std::time::Instant`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Timings {
+    /// The wall-clock time spent computing the endgame bounds.
+    pub endgame: Duration,
+    /// The wall-clock time spent finding the middlegame threshold and the limits for small orders.
+    pub thresholds: Duration,
+    /// The wall-clock time spent finding the Sylow decompositions.
+    pub decomposition: Duration,
+    /// The wall-clock time spent in the coset phase for hyperbolic orders.
+    pub hyperbola: Duration,
+    /// The wall-clock time spent in the coset phase for elliptic orders.
+    pub ellipse: Duration,
+    /// The time spent on each hyperbolic order of $\chi$ in the coset phase, summed over every
+    /// thread; these may add up to more than `hyperbola`.
+    pub hyper_orders: BTreeMap<u128, Duration>,
+    /// The time spent on each elliptic order of $\chi$ in the coset phase, summed over every
+    /// thread; these may add up to more than `ellipse`.
+    pub ellip_orders: BTreeMap<u128, Duration>,
+    /// The wall-clock time of the entire search.
+    pub total: Duration,
+}
+
+/// Percentile summary of a collection of integer measurements.
+/// Percentiles use the nearest-rank method.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Summary {
+    /// The number of measurements.
+    pub count: usize,
+    /// The smallest measurement.
+    pub min: u128,
+    /// The largest measurement.
+    pub max: u128,
+    /// The arithmetic mean of the measurements.
+    pub mean: f64,
+    /// The median measurement.
+    pub p50: u128,
+    /// The 90th percentile measurement.
+    pub p90: u128,
+    /// The 99th percentile measurement.
+    pub p99: u128,
+}
+
+/// Statistics aggregated over the [`SearchReport`]s for many primes.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CampaignReport {
+    /// The number of primes searched.
+    pub primes: usize,
+    /// The smallest and largest primes searched.
+    pub range: Option<(u128, u128)>,
+    /// The number of primes with no middlegame.
+    pub no_middle_game: usize,
+    /// The distribution of the middlegame thresholds, over the primes which have one.
+    pub middle_game: Option<Summary>,
+    /// The distribution of the longest coset walk of each prime.
+    pub coset_max: Option<Summary>,
+    /// The total number of elements enumerated over every prime.
+    pub elements: u128,
+    /// The total number of coset walks truncated by the walk budget over every prime.
+    pub truncated_walks: usize,
+    /// The number of coset walks of each length over every prime.
+    pub walk_lengths: Histogram,
+    /// The distribution of the wall-clock time of each phase, in milliseconds.
+    pub phases: BTreeMap<String, Summary>,
+    /// The total wall-clock time of each phase over every prime.
+    pub phase_totals: BTreeMap<String, Duration>,
+}
+
+impl Summary {
+    /// Summarizes the measurements in `values`, or returns `None` if there are none.
+    pub fn new(mut values: Vec<u128>) -> Option<Summary> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_unstable();
+        let n = values.len();
+        let rank = |q: usize| values[(q * n).div_ceil(100).max(1) - 1];
+        Some(Summary {
+            count: n,
+            min: values[0],
+            max: values[n - 1],
+            mean: values.iter().map(|x| *x as f64).sum::<f64>() / n as f64,
+            p50: rank(50),
+            p90: rank(90),
+            p99: rank(99),
+        })
+    }
+}
+
+impl Timings {
+    /// Returns the wall-clock time of each phase, by name, in the order they ran, followed by the
+    /// total.
+    pub fn phases(&self) -> [(&'static str, Duration); 6] {
+        [
+            ("endgame", self.endgame),
+            ("thresholds", self.thresholds),
+            ("decomposition", self.decomposition),
+            ("hyperbola", self.hyperbola),
+            ("ellipse", self.ellipse),
+            ("total", self.total),
+        ]
+    }
+}
+
+impl CampaignReport {
+    /// Aggregates the given reports.
+    pub fn new<'a, I>(reports: I) -> CampaignReport
+    where
+        I: IntoIterator<Item = &'a SearchReport>,
+    {
+        let mut res = CampaignReport::default();
+        let mut middle_games = Vec::new();
+        let mut coset_maxes = Vec::new();
+        let mut phases = BTreeMap::<String, Vec<u128>>::new();
+        for report in reports {
+            res.primes += 1;
+            res.range = Some(match res.range {
+                Some((lo, hi)) => (lo.min(report.prime), hi.max(report.prime)),
+                None => (report.prime, report.prime),
+            });
+            match report.middle_game {
+                Some(t) => middle_games.push(t),
+                None => res.no_middle_game += 1,
+            }
+            coset_maxes.push(report.coset_max as u128);
+            res.elements += report.elements;
+            res.truncated_walks += report.truncated_walks;
+            res.walk_lengths.merge(&report.walk_lengths);
+            for (name, dur) in report.timings.phases() {
+                phases
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(dur.as_millis());
+                *res.phase_totals.entry(name.to_string()).or_default() += dur;
+            }
+        }
+        res.middle_game = Summary::new(middle_games);
+        res.coset_max = Summary::new(coset_maxes);
+        res.phases = phases
+            .into_iter()
+            .filter_map(|(name, v)| Some((name, Summary::new(v)?)))
+            .collect();
+        res
+    }
+}
+
+impl FromIterator<SearchReport> for CampaignReport {
+    fn from_iter<I: IntoIterator<Item = SearchReport>>(iter: I) -> CampaignReport {
+        CampaignReport::new(&iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(
+        prime: u128,
+        middle_game: Option<u128>,
+        coset_max: usize,
+        millis: u64,
+    ) -> SearchReport {
+        SearchReport {
+            prime,
+            middle_game,
+            coset_max,
+            walk_lengths: {
+                let mut hist = Histogram::exact();
+                hist.extend([1, coset_max as u128]);
+                hist
+            },
+            elements: prime,
+            timings: Timings {
+                decomposition: Duration::from_millis(1),
+                hyperbola: Duration::from_millis(millis),
+                total: Duration::from_millis(millis + 1),
+                ..Timings::default()
+            },
+            ..SearchReport::default()
+        }
+    }
+
+    #[test]
+    fn summary_percentiles() {
+        let s = Summary::new((1..=100).collect()).unwrap();
+        assert_eq!((s.min, s.max, s.p50, s.p90, s.p99), (1, 100, 50, 90, 99));
+        assert_eq!(s.mean, 50.5);
+        let s = Summary::new(vec![7]).unwrap();
+        assert_eq!((s.min, s.max, s.p50, s.p90, s.p99), (7, 7, 7, 7, 7));
+        assert_eq!(Summary::new(Vec::new()), None);
+    }
+
+    #[test]
+    fn aggregates_reports() {
+        let campaign = [
+            report(4001, Some(40), 3, 10),
+            report(4003, None, 50, 30),
+            report(4007, Some(20), 7, 20),
+        ]
+        .into_iter()
+        .collect::<CampaignReport>();
+        assert_eq!(campaign.primes, 3);
+        assert_eq!(campaign.range, Some((4001, 4007)));
+        assert_eq!(campaign.no_middle_game, 1);
+        let middle_game = campaign.middle_game.as_ref().unwrap();
+        assert_eq!((middle_game.min, middle_game.max), (20, 40));
+        assert_eq!(campaign.coset_max.as_ref().unwrap().max, 50);
+        assert_eq!(campaign.walk_lengths.count(), 6);
+        assert_eq!(campaign.walk_lengths.median(), Some(1));
+        assert_eq!(campaign.walk_lengths.max(), Some(50));
+        assert_eq!(campaign.elements, 4001 + 4003 + 4007);
+        assert_eq!(campaign.phases["hyperbola"].p50, 20);
+        assert_eq!(campaign.phases["total"].max, 31);
+        assert_eq!(
+            campaign.phase_totals["decomposition"],
+            Duration::from_millis(3)
+        );
+        assert_eq!(
+            campaign.phase_totals["hyperbola"],
+            Duration::from_millis(60)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes() {
+        let mut search = report(4001, Some(40), 3, 10);
+        search.hyper_orders.insert(4000, 12);
+        search
+            .timings
+            .hyper_orders
+            .insert(4000, Duration::from_micros(5));
+        let json = serde_json::to_string(&search).unwrap();
+        assert_eq!(serde_json::from_str::<SearchReport>(&json).unwrap(), search);
+
+        let campaign = CampaignReport::new(&[search]);
+        let json = serde_json::to_string(&campaign).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CampaignReport>(&json).unwrap(),
+            campaign
+        );
+    }
+}
@@ -0,0 +1,877 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rayon::iter::*;
+use rayon::slice::ParallelSlice;
+
+use crate::bgs::{PrimeEntry, SearchEstimate, SearchReport, Timings};
+use crate::markoff::*;
+use crate::numbers::*;
+use crate::streams::*;
+use crate::util::stats::Histogram;
+use crate::util::Seed;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The state shared between the phases of a search modulo `P`.
+pub struct Context<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize>
+where
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    /// Hyperbolic coordinates with rotation order at most this limit are small.
+    pub hyper_lim: u128,
+    /// Elliptic coordinates with rotation order at most this limit are small.
+    pub ellip_lim: u128,
+    /// The Sylow decomposition of $\mathbb{F}\_p^\times$.
+    pub hyper_decomp: SylowDecomp<S, L_HYPER, FpNum<P>>,
+    /// The Sylow decomposition of the norm 1 subgroup of $\mathbb{F}\_{p^2}^\times$.
+    pub ellip_decomp: SylowDecomp<S, L_ELLIP, QuadNum<P>>,
+    /// The number of steps walked along each coset before the walk is truncated.
+    pub walk_budget: usize,
+    /// What a truncated coset walk counts as.
+    pub truncation: Truncation,
+    /// Whether to split the work into fixed chunks; see [`SearchConfig::deterministic`].
+    pub deterministic: bool,
+    /// Whether the parabolic coordinates $\pm 2$ are small; see [`SearchConfig::parabolic`].
+    pub parabolic: Parabolic,
+    coset_max: AtomicUsize,
+    truncated_walks: AtomicUsize,
+}
+
+/// The result of the coset phase for one of the hyperbolic or elliptic orders.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CosetPhase {
+    /// The contribution of each target order, keyed by the order of $\chi$.
+    pub orders: BTreeMap<u128, u128>,
+    /// The total number of elements enumerated.
+    pub elements: u128,
+    /// The time spent on each target order, summed over every thread.
+    pub times: BTreeMap<u128, Duration>,
+    /// The histogram of the lengths of the walks taken along cosets.
+    pub walk_lengths: Histogram,
+}
+
+/// The configuration of a search for the Markoff graph modulo a prime.
+/// The prime and its factorizations are given as type parameters to [`SearchConfig::run`] and
+/// [`SearchConfig::dry_run`], so that one configuration can be used for many primes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchConfig {
+    /// The seed of the search's pseudo-random choices.
+    pub seed: Seed,
+    /// The number of steps walked along each coset in the middlegame before the walk is
+    /// truncated.
+    /// Defaults to 50.
+    pub walk_budget: usize,
+    /// What a coset walk which is truncated before leaving the small coordinates counts as.
+    pub truncation: Truncation,
+    /// If true, the elements $\chi$ are generated in sequence and split into chunks of a fixed
+    /// size, each of which is searched on a single thread, and the results of the chunks are
+    /// combined in order.
+    /// Every statistic in the report, other than the timings, is then the same from run to run,
+    /// regardless of the number of threads or how `rayon` schedules the work, at the cost of
+    /// less even load balancing.
+    /// Defaults to false.
+    pub deterministic: bool,
+    /// Whether the parabolic coordinates $\pm 2$, whose rotations have order $p$ or $2p$ rather
+    /// than an order dividing $p \pm 1$, count as small coordinates.
+    /// With [`Parabolic::Exclude`], every walk reaching a parabolic coordinate leaves the small
+    /// coordinates.
+    /// Defaults to [`Parabolic::Exclude`].
+    pub parabolic: Parabolic,
+}
+
+/// The outcome given to a coset walk in the middlegame which takes `walk_budget` steps along
+/// small coordinates without reaching the end of its orbit.
+/// See [`SearchConfig::walk_budget`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Truncation {
+    /// The walk counts as staying among small coordinates.
+    #[default]
+    Succeed,
+    /// The walk counts as leaving the small coordinates.
+    Fail,
+    /// The rest of the orbit is walked without a budget, to decide exactly.
+    Exact,
+}
+
+// The thresholds found before any elements are enumerated.
+struct Thresholds<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize> {
+    middle_game: Option<u128>,
+    hyper_lim: u128,
+    ellip_lim: u128,
+    // The number of coordinates with each small order, at the nodes of the divisors of $p - 1$
+    // and of $p + 1$, or `None` at the nodes of orders which are not small.
+    hyper_counts: FactorTrie<S, L_HYPER, FpNum<P>, Option<u128>>,
+    ellip_counts: FactorTrie<S, L_ELLIP, QuadNum<P>, Option<u128>>,
+}
+
+// The contribution, the number of elements, and the histogram of walk lengths of a coset sweep.
+type Sweep = (u128, u128, Histogram);
+
+#[derive(Debug)]
+enum Check<const L: usize> {
+    Cosets([usize; L]),
+    SmallOrders(u128),
+}
+
+impl<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize> Context<S, P, L_HYPER, L_ELLIP>
+where
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    /// Creates a new `Context`, with the given limits for small orders.
+    pub fn new(hyper_lim: u128, ellip_lim: u128) -> Self {
+        Self::new_with_seed(hyper_lim, ellip_lim, Seed::DEFAULT)
+    }
+
+    /// Creates a new `Context`, with the given limits for small orders, finding the Sylow
+    /// decompositions in the order given by `seed`.
+    pub fn new_with_seed(hyper_lim: u128, ellip_lim: u128, seed: Seed) -> Self {
+        Self::new_with_decomps(
+            hyper_lim,
+            ellip_lim,
+            SylowDecomp::new_with_seed(seed),
+            SylowDecomp::new_with_seed(seed),
+        )
+    }
+
+    /// Creates a new `Context`, with the given limits for small orders and Sylow decompositions.
+    pub fn new_with_decomps(
+        hyper_lim: u128,
+        ellip_lim: u128,
+        hyper_decomp: SylowDecomp<S, L_HYPER, FpNum<P>>,
+        ellip_decomp: SylowDecomp<S, L_ELLIP, QuadNum<P>>,
+    ) -> Self {
+        Context {
+            hyper_lim,
+            ellip_lim,
+            hyper_decomp,
+            ellip_decomp,
+            walk_budget: DEFAULT_WALK_BUDGET,
+            truncation: Truncation::Succeed,
+            deterministic: false,
+            parabolic: Parabolic::Exclude,
+            coset_max: AtomicUsize::new(0),
+            truncated_walks: AtomicUsize::new(0),
+        }
+    }
+
+    /// True if the rotation order of `c` is small, that is, at most the corresponding limit.
+    pub fn is_small(&self, c: &Coord<P>) -> bool {
+        match c.rot_order::<S, S>() {
+            RotOrder::Parabola(_) => self.parabolic == Parabolic::Include,
+            RotOrder::Hyperbola(ord) => ord <= self.hyper_lim,
+            RotOrder::Ellipse(ord) => ord <= self.ellip_lim,
+        }
+    }
+
+    /// Returns the longest walk taken along any coset so far.
+    pub fn coset_max(&self) -> usize {
+        self.coset_max.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of coset walks truncated by the walk budget so far.
+    pub fn truncated_walks(&self) -> usize {
+        self.truncated_walks.load(Ordering::Relaxed)
+    }
+
+    // True if every coordinate yielded by `it` is small, walking at most `walk_budget` steps
+    // before applying the truncation policy.
+    // The length of the walk is added to `lengths`.
+    fn walk(&self, mut it: impl Iterator<Item = Coord<P>>, lengths: &mut Histogram) -> bool {
+        let mut count = 0;
+        let mut is_small = |c: Coord<P>| {
+            count += 1;
+            self.is_small(&c)
+        };
+        let res = it.by_ref().take(self.walk_budget).all(&mut is_small)
+            && match it.next() {
+                None => true,
+                Some(c) => {
+                    self.truncated_walks.fetch_add(1, Ordering::Relaxed);
+                    match self.truncation {
+                        Truncation::Succeed => true,
+                        Truncation::Fail => false,
+                        Truncation::Exact => is_small(c) && it.all(is_small),
+                    }
+                }
+            };
+        self.coset_max.fetch_max(count, Ordering::Relaxed);
+        lengths.add(count as u128);
+        res
+    }
+}
+
+const DEFAULT_WALK_BUDGET: usize = 50;
+
+// The number of elements $\chi$ in each chunk of a deterministic search.
+const DETERMINISTIC_CHUNK: usize = 16;
+
+impl Default for SearchConfig {
+    fn default() -> SearchConfig {
+        SearchConfig {
+            seed: Seed::DEFAULT,
+            walk_budget: DEFAULT_WALK_BUDGET,
+            truncation: Truncation::Succeed,
+            deterministic: false,
+            parabolic: Parabolic::Exclude,
+        }
+    }
+}
+
+impl CosetPhase {
+    /// Returns the sum of the contributions of every order.
+    pub fn total(&self) -> u128 {
+        self.orders.values().sum()
+    }
+
+    fn merge(mut self, other: CosetPhase) -> CosetPhase {
+        for (ord, count) in other.orders {
+            *self.orders.entry(ord).or_insert(0) += count;
+        }
+        self.elements += other.elements;
+        for (ord, time) in other.times {
+            *self.times.entry(ord).or_default() += time;
+        }
+        self.walk_lengths.merge(&other.walk_lengths);
+        self
+    }
+}
+
+/// Runs the search for the Markoff graph modulo `P`, returning a report of its results.
+/// `L_HYPER` and `L_ELLIP` must be the number of distinct prime factors of $p - 1$ and $p + 1$,
+/// respectively.
+pub fn run<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize>() -> SearchReport
+where
+    S: Send + Sync,
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    run_with_seed::<S, P, L_HYPER, L_ELLIP>(Seed::DEFAULT)
+}
+
+/// Runs the search for the Markoff graph modulo `P`, making every pseudo-random choice in the
+/// order given by `seed`.
+/// The seed is recorded in the report, so that the run can be reproduced exactly.
+/// See [`run`].
+pub fn run_with_seed<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize>(
+    seed: Seed,
+) -> SearchReport
+where
+    S: Send + Sync,
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    SearchConfig::new()
+        .with_seed(seed)
+        .run::<S, P, L_HYPER, L_ELLIP>()
+}
+
+impl SearchConfig {
+    /// Returns the default configuration.
+    pub fn new() -> SearchConfig {
+        SearchConfig::default()
+    }
+
+    /// Sets the seed of the search's pseudo-random choices.
+    pub fn with_seed(mut self, seed: Seed) -> SearchConfig {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the number of steps walked along each coset before the walk is truncated, and what a
+    /// truncated walk counts as.
+    pub fn with_walk_budget(mut self, walk_budget: usize, truncation: Truncation) -> SearchConfig {
+        self.walk_budget = walk_budget;
+        self.truncation = truncation;
+        self
+    }
+
+    /// Sets whether the search splits its work into fixed chunks, so that its statistics are
+    /// reproducible.
+    /// See [`SearchConfig::deterministic`].
+    pub fn with_deterministic(mut self, deterministic: bool) -> SearchConfig {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Sets whether the parabolic coordinates count as small.
+    /// See [`SearchConfig::parabolic`].
+    pub fn with_parabolic(mut self, parabolic: Parabolic) -> SearchConfig {
+        self.parabolic = parabolic;
+        self
+    }
+
+    /// Runs the search for the Markoff graph modulo `P`, returning a report of its results.
+    /// `L_HYPER` and `L_ELLIP` must be the number of distinct prime factors of $p - 1$ and
+    /// $p + 1$, respectively.
+    pub fn run<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize>(&self) -> SearchReport
+    where
+        S: Send + Sync,
+        FpNum<P>: Factor<S>,
+        QuadNum<P>: Factor<S>,
+    {
+        self.run_with_decomps::<S, P, L_HYPER, L_ELLIP>(|| {
+            (
+                SylowDecomp::new_with_seed(self.seed),
+                SylowDecomp::new_with_seed(self.seed),
+            )
+        })
+    }
+
+    /// Runs the search for the Markoff graph modulo `P` as [`SearchConfig::run`] does, but with
+    /// the Sylow decompositions recorded in `entry`, rather than finding new ones.
+    /// Returns `None` if `entry` does not hold valid decompositions for `P`; see
+    /// [`PrimeEntry::hyper_decomp`].
+    /// The configuration is `self`, not the profile of `entry`; to search with that profile,
+    /// call this method on [`crate::bgs::Profile::config`].
+    pub fn run_with_entry<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize>(
+        &self,
+        entry: &PrimeEntry,
+    ) -> Option<SearchReport>
+    where
+        S: Send + Sync,
+        FpNum<P>: Factor<S>,
+        QuadNum<P>: Factor<S>,
+    {
+        let hyper_decomp = entry.hyper_decomp::<S, P, L_HYPER>()?;
+        let ellip_decomp = entry.ellip_decomp::<S, P, L_ELLIP>()?;
+        Some(self.run_with_decomps(|| (hyper_decomp, ellip_decomp)))
+    }
+
+    fn run_with_decomps<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize>(
+        &self,
+        decomps: impl FnOnce() -> (
+            SylowDecomp<S, L_HYPER, FpNum<P>>,
+            SylowDecomp<S, L_ELLIP, QuadNum<P>>,
+        ),
+    ) -> SearchReport
+    where
+        S: Send + Sync,
+        FpNum<P>: Factor<S>,
+        QuadNum<P>: Factor<S>,
+    {
+        let seed = self.seed;
+        let start = Instant::now();
+        let mut timings = Timings::default();
+
+        let (hyper_endgame, ellip_endgame) = Coord::<P>::endgame::<S>();
+        timings.endgame = start.elapsed();
+
+        let now = Instant::now();
+        let th = thresholds::<S, P, L_HYPER, L_ELLIP>(hyper_endgame, ellip_endgame);
+        timings.thresholds = now.elapsed();
+
+        let now = Instant::now();
+        let (hyper_decomp, ellip_decomp) = decomps();
+        let mut ctx = Context::<S, P, L_HYPER, L_ELLIP>::new_with_decomps(
+            th.hyper_lim,
+            th.ellip_lim,
+            hyper_decomp,
+            ellip_decomp,
+        );
+        ctx.walk_budget = self.walk_budget;
+        ctx.truncation = self.truncation;
+        ctx.deterministic = self.deterministic;
+        ctx.parabolic = self.parabolic;
+
+        // Magic number used to permute cosets of <chi> to ensure all (s*chi + (s*chi)^-1) have
+        // order dividing 2(p - 1) and not dividing (p - 1).
+        let magic = strategy::coset_twist_with::<S, P>(seed);
+        timings.decomposition = now.elapsed();
+
+        let ((hyper, hyper_time), (ellip, ellip_time)) = rayon::join(
+            || {
+                let now = Instant::now();
+                let res = coset_phase(
+                    &ctx,
+                    &th.hyper_counts,
+                    th.hyper_lim,
+                    &ctx.hyper_decomp,
+                    |k, s| k * (s + s.inverse()),
+                );
+                (res, now.elapsed())
+            },
+            || {
+                let now = Instant::now();
+                let res = coset_phase(
+                    &ctx,
+                    &th.ellip_counts,
+                    th.ellip_lim,
+                    &ctx.ellip_decomp,
+                    |k, s| {
+                        debug_assert!(is_norm_one(s));
+                        let fix = s * magic;
+                        let b = fix + fix.inverse();
+                        assert_eq!(trace(b), FpNum::ZERO);
+                        k * b.1
+                    },
+                );
+                (res, now.elapsed())
+            },
+        );
+        timings.hyperbola = hyper_time;
+        timings.ellipse = ellip_time;
+        timings.total = start.elapsed();
+
+        SearchReport {
+            prime: P,
+            seed: seed.0,
+            hyper_endgame,
+            ellip_endgame,
+            middle_game: th.middle_game,
+            coset_max: ctx.coset_max(),
+            truncated_walks: ctx.truncated_walks(),
+            hyper_count: hyper.total(),
+            ellip_count: ellip.total(),
+            walk_lengths: hyper.walk_lengths.merged(ellip.walk_lengths),
+            elements: hyper.elements + ellip.elements,
+            hyper_orders: hyper.orders,
+            ellip_orders: ellip.orders,
+            timings: Timings {
+                hyper_orders: hyper.times,
+                ellip_orders: ellip.times,
+                ..timings
+            },
+        }
+    }
+
+    /// Predicts the work and memory of the search for the Markoff graph modulo `P`, without
+    /// finding the Sylow decompositions or running the streams of elements.
+    /// Only the thresholds are computed, as they would be by [`SearchConfig::run`], and the
+    /// streams are estimated by [`SylowStreamBuilder::estimate`].
+    pub fn dry_run<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize>(
+        &self,
+    ) -> SearchEstimate
+    where
+        S: Send + Sync,
+        FpNum<P>: Factor<S>,
+        QuadNum<P>: Factor<S>,
+    {
+        fn side<S, const L: usize, C: SylowDecomposable<S>>(limit: u128) -> (StreamEstimate, u128) {
+            let stream = SylowStreamBuilder::<S, L, C, ()>::new()
+                .for_unique_chi(limit)
+                .estimate();
+            // Each pair of inverses of order `ord` is swept over the `C::SIZE / ord` cosets of
+            // the subgroup it generates, up to inverses.
+            let cosets = DivisorStream::new(C::FACTORS.factors(), limit, false)
+                .map(|ds| {
+                    let ord = C::FACTORS.from_powers(&ds);
+                    C::count_elements_of_order(&ds).div_ceil(2) * (C::SIZE / ord).div_ceil(2)
+                })
+                .sum();
+            (stream, cosets)
+        }
+
+        let (hyper_endgame, ellip_endgame) = Coord::<P>::endgame::<S>();
+        let th = thresholds::<S, P, L_HYPER, L_ELLIP>(hyper_endgame, ellip_endgame);
+        let (hyper, hyper_cosets) = side::<S, L_HYPER, FpNum<P>>(th.hyper_lim);
+        let (ellip, ellip_cosets) = side::<S, L_ELLIP, QuadNum<P>>(th.ellip_lim);
+        let memory = std::mem::size_of::<Context<S, P, L_HYPER, L_ELLIP>>()
+            + rayon::current_num_threads() * (hyper.memory + ellip.memory);
+        SearchEstimate {
+            prime: P,
+            hyper_endgame,
+            ellip_endgame,
+            middle_game: th.middle_game,
+            hyper_lim: th.hyper_lim,
+            ellip_lim: th.ellip_lim,
+            hyper,
+            ellip,
+            hyper_cosets,
+            ellip_cosets,
+            memory,
+        }
+    }
+}
+
+// Finds the middlegame threshold, and the limits for small orders below the endgame bounds.
+fn thresholds<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize>(
+    hyper_endgame: u128,
+    ellip_endgame: u128,
+) -> Thresholds<S, P, L_HYPER, L_ELLIP>
+where
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    let mut factors = DivisorStream::new(FpNum::<P>::FACTORS.factors(), hyper_endgame, false)
+        .map(|x| FpNum::<P>::FACTORS.from_powers(&x))
+        .chain(
+            DivisorStream::new(QuadNum::<P>::FACTORS.factors(), ellip_endgame, false)
+                .map(|x| QuadNum::<P>::FACTORS.from_powers(&x)),
+        )
+        .collect::<Vec<_>>();
+    factors.sort_unstable();
+
+    let mut hyper_counts = FactorTrie::new_with(|_, _| None);
+    let mut ellip_counts = FactorTrie::new_with(|_, _| None);
+    let mut middle_game = None;
+
+    for t in factors {
+        let mut rhs = 0.0;
+        let mut count = 0;
+        for (d, c) in DivisorStream::new(FpNum::<P>::FACTORS.factors(), t, true)
+            .map(|x| {
+                (
+                    FpNum::<P>::FACTORS.from_powers(&x),
+                    FpNum::<P>::count_elements_of_order(&x),
+                )
+            })
+            .chain(
+                DivisorStream::new(QuadNum::<P>::FACTORS.factors(), t, true).map(|x| {
+                    (
+                        QuadNum::<P>::FACTORS.from_powers(&x),
+                        QuadNum::<P>::count_elements_of_order(&x),
+                    )
+                }),
+            )
+        {
+            let a = (6.0 * (t as f64) * (d as f64)).powf(1.0 / 3.0);
+            let b = 4.0 * (t as f64) * (d as f64) / (P as f64);
+            rhs += 1.5 * (if a >= b { a } else { b });
+            count += c;
+        }
+        if (t as f64) >= rhs {
+            middle_game.get_or_insert(t);
+        } else {
+            middle_game = None;
+        }
+        if let Some(c) = hyper_counts.get_by_value_mut(t) {
+            *c = Some(count);
+        }
+        if let Some(c) = ellip_counts.get_by_value_mut(t) {
+            *c = Some(count);
+        }
+    }
+    let (hyper_lim, ellip_lim) = if let Some(min) = middle_game {
+        (
+            std::cmp::min(min, hyper_endgame),
+            std::cmp::min(min, ellip_endgame),
+        )
+    } else {
+        (hyper_endgame, ellip_endgame)
+    };
+    Thresholds {
+        middle_game,
+        hyper_lim,
+        ellip_lim,
+        hyper_counts,
+        ellip_counts,
+    }
+}
+
+/// Runs the coset phase of the search over the elements $\chi$ of the cyclic group `C` with
+/// orders up to `limit`, where `counts` gives the number of coordinates with each small order
+/// dividing the size of `C`, at the node of that order, and `None` at the nodes of the other
+/// orders.
+/// Returns the contribution of each order of $\chi$ separately.
+pub fn coset_phase<
+    S,
+    const P: u128,
+    const L_HYPER: usize,
+    const L_ELLIP: usize,
+    C,
+    const L: usize,
+>(
+    ctx: &Context<S, P, L_HYPER, L_ELLIP>,
+    counts: &FactorTrie<S, L, C, Option<u128>>,
+    limit: u128,
+    decomp: &SylowDecomp<S, L, C>,
+    get_coset_repr: impl Fn(FpNum<P>, C) -> FpNum<P> + Send + Sync,
+) -> CosetPhase
+where
+    S: Send + Sync,
+    C: SylowDecomposable<S> + FromChi<S, P> + Send + Sync,
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    let trie = Arc::new(FactorTrie::<S, L, C, Check<L>>::new_with(|ds, _| {
+        let ord = C::order_from_powers(ds);
+        let cosets = C::SIZE / ord;
+        match counts.get(ds) {
+            Some(Some(count)) if limit == C::SIZE - 1 || *count > cosets => Check::Cosets(*ds),
+            Some(Some(_)) => Check::SmallOrders(ord),
+            _ => Check::Cosets(*ds),
+        }
+    }));
+    let add = |x: (u128, u128), y: (u128, u128)| (x.0 + y.0, x.1 + y.1);
+    let search = |chi: SylowElem<S, L, C>, check: &Check<L>| {
+        let now = Instant::now();
+        let a = C::from_chi(&chi, decomp);
+        let (ord, count, elements, walk_lengths) = match check {
+            Check::SmallOrders(ord) => {
+                let it = CoordStream::new(&ctx.hyper_decomp, &ctx.ellip_decomp, *ord, *ord);
+                let count_thirds = |b: Coord<P>| {
+                    let b = b.0;
+                    let count = match (a * a * b * b - 4 * (a * a + b * b)).int_sqrt() {
+                        None => 0,
+                        Some(disc) if disc == FpNum::<P>::ZERO => {
+                            ctx.is_small(&Coord(a * b * FpNum::TWO_INV)) as u128
+                        }
+                        Some(disc) => [
+                            (a * b - disc) * FpNum::TWO_INV,
+                            (a * b + disc) * FpNum::TWO_INV,
+                        ]
+                        .into_iter()
+                        .filter(|c| ctx.is_small(&Coord(*c)))
+                        .count() as u128,
+                    };
+                    (count, 1)
+                };
+                let (count, elements) = if ctx.deterministic {
+                    Iterator::map(it, count_thirds).fold((0, 0), add)
+                } else {
+                    ParallelIterator::map(it, count_thirds).reduce(|| (0, 0), add)
+                };
+                (*ord, count, elements, Histogram::exact())
+            }
+            Check::Cosets(gen) => {
+                let chi_conj = C::from_chi_conj(&chi, decomp).inverse();
+
+                let sweep = SylowStreamBuilder::new_with_trie(&trie).for_coset_sweep(*gen);
+                // Each thread counts the lengths of its own walks, and the histograms are merged
+                // once the sweep is done.
+                let walk_coset = |mut acc: Sweep, (x, _): (SylowElem<S, L, C>, _)| {
+                    let b = a * get_coset_repr(chi_conj, x.to_product(decomp));
+                    acc.1 += 1;
+
+                    if !ctx.is_small(&Coord(b)) {
+                        return acc;
+                    }
+                    // The fixed point $(0, 0, 0)$ is an isolated vertex, and has no walk to take.
+                    let Some(it) = Coord(a).part_with(Coord(b), Degenerate::ExcludeZero) else {
+                        assert!(
+                            Coord(a).part(Coord(b)).is_some(),
+                            "Attempted to look at coset solutions that don't exist: P={} a={} b={}.",
+                            P,
+                            u128::from(a),
+                            u128::from(b)
+                        );
+                        return acc;
+                    };
+                    if ctx.walk(it, &mut acc.2) {
+                        acc.0 += chi.order();
+                    }
+                    acc
+                };
+                let (count, elements, walk_lengths) = if ctx.deterministic {
+                    sweep.into_iter().fold(Sweep::default(), walk_coset)
+                } else {
+                    sweep
+                        .into_par_iter()
+                        .fold(Sweep::default, walk_coset)
+                        .reduce(Sweep::default, |x, y| {
+                            (x.0 + y.0, x.1 + y.1, x.2.merged(y.2))
+                        })
+                };
+                (C::order_from_powers(gen), count, elements, walk_lengths)
+            }
+        };
+        CosetPhase {
+            orders: BTreeMap::from([(ord, count)]),
+            elements: elements + 1,
+            times: BTreeMap::from([(ord, now.elapsed())]),
+            walk_lengths,
+        }
+    };
+
+    let builder = SylowStreamBuilder::new_sharing_trie(Arc::clone(&trie)).for_unique_chi(limit);
+    if ctx.deterministic {
+        builder
+            .into_iter()
+            .collect::<Vec<_>>()
+            .par_chunks(DETERMINISTIC_CHUNK)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .map(|(chi, check)| search(*chi, check))
+                    .fold(CosetPhase::default(), CosetPhase::merge)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .fold(CosetPhase::default(), CosetPhase::merge)
+    } else {
+        builder
+            .into_par_iter()
+            .map(|(chi, check)| search(chi, &check))
+            .reduce(CosetPhase::default, CosetPhase::merge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bgs::Registry;
+
+    #[derive(PartialEq, Eq)]
+    struct Ph {}
+
+    impl_factors!(Ph, 4001, 41);
+
+    #[test]
+    fn breakdown_sums_to_total() {
+        // 4000 = 2^5 * 5^3 and 4002 = 2 * 3 * 23 * 29
+        let report = run::<Ph, 4001, 2, 4>();
+        assert_eq!(report.prime, 4001);
+        assert_eq!(
+            report.hyper_orders.values().sum::<u128>(),
+            report.hyper_count
+        );
+        assert_eq!(
+            report.ellip_orders.values().sum::<u128>(),
+            report.ellip_count
+        );
+        for ord in report.hyper_orders.keys() {
+            assert_eq!(4000 % ord, 0);
+        }
+        for ord in report.ellip_orders.keys() {
+            assert_eq!(4002 % ord, 0);
+        }
+        assert!(report.elements > 0);
+        assert!(report
+            .timings
+            .hyper_orders
+            .keys()
+            .eq(report.hyper_orders.keys()));
+        assert!(report
+            .timings
+            .ellip_orders
+            .keys()
+            .eq(report.ellip_orders.keys()));
+        assert!(report.timings.total >= report.timings.hyperbola.max(report.timings.ellipse));
+    }
+
+    #[test]
+    fn dry_run_matches_run() {
+        let config = SearchConfig::new();
+        let estimate = config.dry_run::<Ph, 4001, 2, 4>();
+        let report = config.run::<Ph, 4001, 2, 4>();
+        assert_eq!(estimate.middle_game, report.middle_game);
+        assert_eq!(
+            (estimate.hyper_endgame, estimate.ellip_endgame),
+            (report.hyper_endgame, report.ellip_endgame)
+        );
+        // Every element of the outer streams is counted by the report, along with the elements
+        // of its cosets.
+        assert!(estimate.hyper.elements + estimate.ellip.elements <= report.elements);
+        assert!(estimate.hyper_cosets > 0 && estimate.ellip_cosets > 0);
+        assert!(estimate.memory > 0);
+    }
+
+    #[test]
+    fn seed_is_recorded() {
+        let default = run::<Ph, 4001, 2, 4>();
+        let seeded = run_with_seed::<Ph, 4001, 2, 4>(Seed(17));
+        assert_eq!((default.seed, seeded.seed), (0, 17));
+        assert_eq!(seeded.hyper_orders, default.hyper_orders);
+        assert_eq!(seeded.ellip_orders, default.ellip_orders);
+    }
+
+    #[test]
+    fn walk_budget() {
+        let default = run::<Ph, 4001, 2, 4>();
+        assert!(default.coset_max <= 50);
+        assert_eq!(default.walk_lengths.max(), Some(default.coset_max as u128));
+
+        let exact = SearchConfig::new()
+            .with_walk_budget(0, Truncation::Exact)
+            .run::<Ph, 4001, 2, 4>();
+        let fail = SearchConfig::new()
+            .with_walk_budget(0, Truncation::Fail)
+            .run::<Ph, 4001, 2, 4>();
+        // With no budget, every walk is truncated, and failing them all counts nothing.
+        assert_eq!(exact.truncated_walks, fail.truncated_walks);
+        assert!(fail.truncated_walks > 0);
+        assert_eq!(fail.hyper_count + fail.ellip_count, 0);
+        let lengths = fail.walk_lengths.buckets().collect::<Vec<_>>();
+        assert_eq!(lengths, [(0..=0, fail.truncated_walks as u128)]);
+
+        // Walking exactly can only count fewer cosets than succeeding on truncation.
+        let long = SearchConfig::new()
+            .with_walk_budget(usize::MAX, Truncation::Fail)
+            .run::<Ph, 4001, 2, 4>();
+        assert_eq!(long.truncated_walks, 0);
+        assert_eq!(long.hyper_orders, exact.hyper_orders);
+        assert_eq!(long.ellip_orders, exact.ellip_orders);
+        assert!(exact.hyper_count <= default.hyper_count);
+        assert!(exact.ellip_count <= default.ellip_count);
+    }
+
+    #[test]
+    fn runs_with_registered_decomps() {
+        let config = SearchConfig::new()
+            .with_seed(Seed(5))
+            .with_deterministic(true);
+        let mut registry = Registry::new();
+        let entry = registry.record::<Ph, 4001, 2, 4>(&config);
+        let found = entry
+            .profile
+            .config()
+            .run_with_entry::<Ph, 4001, 2, 4>(entry);
+        let expected = config.run::<Ph, 4001, 2, 4>();
+        assert_eq!(
+            found.map(|report| SearchReport {
+                timings: Timings::default(),
+                ..report
+            }),
+            Some(SearchReport {
+                timings: Timings::default(),
+                ..expected
+            })
+        );
+
+        let mut entry = entry.clone();
+        entry.hyper_generators.swap(0, 1);
+        assert!(config.run_with_entry::<Ph, 4001, 2, 4>(&entry).is_none());
+    }
+
+    #[test]
+    fn deterministic_runs_agree() {
+        let config = SearchConfig::new()
+            .with_walk_budget(3, Truncation::Succeed)
+            .with_deterministic(true);
+        let runs = [1, 4].map(|threads| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            let report = pool.install(|| config.run::<Ph, 4001, 2, 4>());
+            SearchReport {
+                timings: Timings::default(),
+                ..report
+            }
+        });
+        assert_eq!(runs[0], runs[1]);
+
+        let default = SearchConfig::new()
+            .with_walk_budget(3, Truncation::Succeed)
+            .run::<Ph, 4001, 2, 4>();
+        assert_eq!(runs[0].hyper_orders, default.hyper_orders);
+        assert_eq!(runs[0].ellip_orders, default.ellip_orders);
+        assert_eq!(runs[0].elements, default.elements);
+    }
+
+    #[test]
+    fn parabolic_coordinates() {
+        // $41 \equiv 1 \pmod 4$, so there are parabolic triples.
+        let exclude = SearchConfig::new()
+            .with_walk_budget(usize::MAX, Truncation::Fail)
+            .run::<Ph, 41, 2, 3>();
+        let include = SearchConfig::new()
+            .with_walk_budget(usize::MAX, Truncation::Fail)
+            .with_parabolic(Parabolic::Include)
+            .run::<Ph, 41, 2, 3>();
+        assert_eq!(exclude.elements, include.elements);
+        assert_eq!(exclude.hyper_orders, include.hyper_orders);
+        // Seven more coset walks stay among small coordinates, all of elliptic order 7.
+        let mut expected = exclude.ellip_orders.clone();
+        *expected.get_mut(&7).unwrap() += 7;
+        assert_eq!(include.ellip_orders, expected);
+        assert_eq!(include.ellip_count, exclude.ellip_count + 7);
+    }
+}
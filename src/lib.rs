@@ -3,7 +3,9 @@
 
 //! A library for the manipulation and investigation of Markoff numbers and Markoff graphs modulo
 //! primes, specifically with a focus on the Bourgain, Gamburd, and Sarnak algorithm.
+pub mod bgs;
 pub mod markoff;
 pub mod numbers;
 pub mod streams;
 pub mod util;
+pub mod verification;
@@ -1,12 +1,24 @@
 //! Markoff numbers and structures for manipulating, organizing, and investigating them.
+pub mod bfs;
 mod bloom_filter;
+pub mod census;
 mod coord;
 mod disjoint;
+mod enumerate;
 mod orbit_tester;
+pub mod stats;
+pub mod strategy;
+mod surface;
 mod triple;
+mod visited;
+mod witness;
 
 pub use bloom_filter::*;
 pub use coord::*;
 pub use disjoint::*;
+pub use enumerate::*;
 pub use orbit_tester::*;
+pub use surface::*;
 pub use triple::*;
+pub use visited::*;
+pub use witness::*;
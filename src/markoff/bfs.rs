@@ -0,0 +1,421 @@
+//! A parallel, level synchronous breadth first search over graphs of Markoff triples, and the
+//! searches built on it.
+use std::collections::HashSet;
+
+use either::*;
+use rayon::prelude::*;
+
+use crate::markoff::census::{decode, encode, triples};
+use crate::markoff::*;
+use crate::numbers::FpNum;
+
+/// A graph whose vertices are Markoff triples modulo `P`, given by the neighbors of each vertex.
+pub trait Neighbors<const P: u128>: Sync {
+    /// Appends the neighbors of `t` to `out`.
+    fn neighbors(&self, t: &Triple<P>, out: &mut Vec<Triple<P>>);
+
+    /// Returns every vertex of the graph, or `None` if the vertices cannot be listed.
+    /// Graphs listing their vertices must be undirected, that is, every vertex must be a
+    /// neighbor of each of its neighbors; only these graphs may take bottom up steps.
+    /// See [`ParallelBfs::direction_optimizing`].
+    fn vertices(&self) -> Option<Vec<Triple<P>>> {
+        None
+    }
+}
+
+/// The Markoff graph modulo `P`, whose edges are the Vieta involutions.
+/// Its vertices are the nonzero Markoff triples.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Vieta;
+
+/// The triples at one distance from the start of a [`ParallelBfs`].
+/// For primes below $2^{42}$, the triples are stored packed into single integers, a third of the
+/// size of a [`Triple`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Level<const P: u128> {
+    depth: usize,
+    triples: Either<Vec<u128>, Vec<Triple<P>>>,
+}
+
+/// A breadth first search over a graph of Markoff triples, expanding each level of the search in
+/// parallel.
+///
+/// The search starts from a set of triples, and visits the graph one [`Level`] at a time.
+/// Each step is top down, looking up the neighbors of the triples in the frontier, unless the
+/// search is [direction optimizing](ParallelBfs::direction_optimizing), in which case steps with
+/// large frontiers are instead bottom up, checking each unvisited triple for a neighbor in the
+/// frontier.
+/// Which triples have been visited is tracked by a [`VisitedSet`], such as a `HashSet`, a
+/// [`Bitset`], or a [`BloomFilter`] (which may skip triples, on false positives).
+///
+/// # Example
+/// ```
+/// use std::collections::HashSet;
+/// use libbgs::markoff::*;
+/// use libbgs::markoff::bfs::*;
+/// use libbgs::numbers::FpNum;
+///
+/// let start = Triple::<13>::new(FpNum::from(3), FpNum::from(3), FpNum::from(3)).unwrap();
+/// let mut sizes = Vec::new();
+/// ParallelBfs::new(Vieta, HashSet::new(), [start]).run(|level| sizes.push(level.len()));
+/// assert_eq!(sizes[..3], [1, 3, 6]);
+/// ```
+pub struct ParallelBfs<const P: u128, N, V> {
+    neighbors: N,
+    visited: V,
+    frontier: Level<P>,
+    seen: usize,
+    alpha: usize,
+    unvisited: Option<Vec<Triple<P>>>,
+}
+
+impl<const P: u128> Neighbors<P> for Vieta {
+    fn neighbors(&self, t: &Triple<P>, out: &mut Vec<Triple<P>>) {
+        out.extend([Pos::A, Pos::B, Pos::C].map(|pos| t.vieta(pos)));
+    }
+
+    fn vertices(&self) -> Option<Vec<Triple<P>>> {
+        Some(triples::<P>().collect())
+    }
+}
+
+impl<const P: u128, N: Neighbors<P>> Neighbors<P> for &N {
+    fn neighbors(&self, t: &Triple<P>, out: &mut Vec<Triple<P>>) {
+        (*self).neighbors(t, out)
+    }
+
+    fn vertices(&self) -> Option<Vec<Triple<P>>> {
+        (*self).vertices()
+    }
+}
+
+impl<const P: u128> Level<P> {
+    const PACKED: bool = P < 1 << 42;
+
+    fn new(depth: usize, triples: Vec<Triple<P>>) -> Level<P> {
+        let triples = if Self::PACKED {
+            Left(triples.iter().map(encode).collect())
+        } else {
+            Right(triples)
+        };
+        Level { depth, triples }
+    }
+
+    /// Returns the distance of these triples from the start of the search.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the number of triples at this distance.
+    pub fn len(&self) -> usize {
+        self.triples.as_ref().either(Vec::len, Vec::len)
+    }
+
+    /// True if there are no triples at this distance, that is, the search is over.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the triples at this distance.
+    pub fn iter(&self) -> impl Iterator<Item = Triple<P>> + '_ {
+        self.triples
+            .as_ref()
+            .map_either(|v| v.iter().map(|x| decode(*x)), |v| v.iter().copied())
+    }
+
+    /// Returns a parallel iterator over the triples at this distance.
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = Triple<P>> + '_ {
+        self.triples.as_ref().map_either(
+            |v| v.par_iter().map(|x| decode(*x)),
+            |v| v.par_iter().copied(),
+        )
+    }
+}
+
+impl<const P: u128, N: Neighbors<P>, V: VisitedSet<Triple<P>> + Sync> ParallelBfs<P, N, V> {
+    /// Returns a search of the graph `neighbors` starting from the triples `starts`, which make
+    /// up the first level.
+    /// Triples already in `visited` are never visited again, including among the starts.
+    pub fn new(
+        neighbors: N,
+        mut visited: V,
+        starts: impl IntoIterator<Item = Triple<P>>,
+    ) -> ParallelBfs<P, N, V> {
+        let starts = starts
+            .into_iter()
+            .filter(|t| visited.insert(t))
+            .collect::<Vec<_>>();
+        ParallelBfs {
+            neighbors,
+            visited,
+            seen: starts.len(),
+            frontier: Level::new(0, starts),
+            alpha: 0,
+            unvisited: None,
+        }
+    }
+
+    /// Makes this search direction optimizing, taking a bottom up step whenever the frontier has
+    /// at least a `1 / alpha` fraction of the triples not yet visited.
+    /// Bottom up steps touch every unvisited triple once, rather than every neighbor of the
+    /// frontier, which is far cheaper in the middle levels of a search of a whole graph.
+    /// This lists the vertices of the graph, and has no effect if they cannot be listed.
+    /// See [`Neighbors::vertices`].
+    pub fn direction_optimizing(mut self, alpha: usize) -> Self {
+        self.unvisited = self.neighbors.vertices();
+        self.alpha = alpha;
+        self
+    }
+
+    /// Returns the current level of the search.
+    pub fn frontier(&self) -> &Level<P> {
+        &self.frontier
+    }
+
+    /// Returns the set of triples visited so far.
+    pub fn visited(&self) -> &V {
+        &self.visited
+    }
+
+    /// Returns the set of triples visited so far, ending the search.
+    pub fn into_visited(self) -> V {
+        self.visited
+    }
+
+    /// Expands the frontier to the next level, returning false if the new level is empty, that
+    /// is, the search is over.
+    pub fn advance(&mut self) -> bool {
+        let next = match &mut self.unvisited {
+            Some(unvisited)
+                if self.frontier.len() * self.alpha
+                    >= unvisited.len() - self.seen.min(unvisited.len()) =>
+            {
+                self.bottom_up()
+            }
+            _ => self.top_down(),
+        };
+        let next = next
+            .into_iter()
+            .filter(|t| self.visited.insert(t))
+            .collect::<Vec<_>>();
+        self.seen += next.len();
+        self.frontier = Level::new(self.frontier.depth + 1, next);
+        !self.frontier.is_empty()
+    }
+
+    /// Runs the search to the end, calling `f` on each level in turn, and returns the set of
+    /// visited triples.
+    pub fn run(mut self, mut f: impl FnMut(&Level<P>)) -> V {
+        while !self.frontier.is_empty() {
+            f(&self.frontier);
+            self.advance();
+        }
+        self.visited
+    }
+
+    // Returns the unvisited neighbors of the frontier, possibly with repeats.
+    fn top_down(&self) -> Vec<Triple<P>> {
+        let (neighbors, visited) = (&self.neighbors, &self.visited);
+        self.frontier
+            .par_iter()
+            .flat_map_iter(|t| {
+                let mut out = Vec::new();
+                neighbors.neighbors(&t, &mut out);
+                out.into_iter().filter(|u| !visited.contains(u))
+            })
+            .collect()
+    }
+
+    // Returns the unvisited triples with a neighbor in the frontier.
+    fn bottom_up(&mut self) -> Vec<Triple<P>> {
+        let unvisited = self.unvisited.as_mut().unwrap();
+        let visited = &self.visited;
+        *unvisited = unvisited
+            .par_iter()
+            .filter(|t| !visited.contains(t))
+            .copied()
+            .collect();
+        self.seen = 0;
+        let frontier = self.frontier.iter().collect::<HashSet<_>>();
+        let neighbors = &self.neighbors;
+        unvisited
+            .par_iter()
+            .filter(|t| {
+                let mut out = Vec::new();
+                neighbors.neighbors(t, &mut out);
+                out.iter().any(|u| frontier.contains(u))
+            })
+            .copied()
+            .collect()
+    }
+}
+
+/// Returns the number of triples in the connected component of `start` in the graph `neighbors`,
+/// using `visited` to track the triples visited.
+pub fn component_size<const P: u128>(
+    neighbors: impl Neighbors<P>,
+    visited: impl VisitedSet<Triple<P>> + Sync,
+    start: Triple<P>,
+) -> u128 {
+    let mut res = 0;
+    ParallelBfs::new(neighbors, visited, [start]).run(|level| res += level.len() as u128);
+    res
+}
+
+/// Returns the number of triples at each distance from `start` in the graph `neighbors`, and a
+/// triple of greatest distance, using `visited` to track the triples visited.
+pub fn levels<const P: u128>(
+    neighbors: impl Neighbors<P>,
+    visited: impl VisitedSet<Triple<P>> + Sync,
+    start: Triple<P>,
+) -> (Vec<u128>, Triple<P>) {
+    let mut histogram = Vec::new();
+    let mut last = start;
+    ParallelBfs::new(neighbors, visited, [start]).run(|level| {
+        histogram.push(level.len() as u128);
+        last = level.iter().next().unwrap();
+    });
+    (histogram, last)
+}
+
+/// Returns the least distance from `from` to `to` in the undirected graph `neighbors`, or `None`
+/// if it is more than `max_depth`, or if they are not connected.
+///
+/// The search is a breadth first search from both ends, one set in `visited` for each, expanding
+/// whichever frontier is smaller, so it visits on the order of the square root of the triples
+/// that a one sided search would.
+pub fn distance<const P: u128, N: Neighbors<P>, V: VisitedSet<Triple<P>> + Sync>(
+    neighbors: N,
+    visited: [V; 2],
+    from: Triple<P>,
+    to: Triple<P>,
+    max_depth: usize,
+) -> Option<usize> {
+    if from == to {
+        return Some(0);
+    }
+    let [v0, v1] = visited;
+    let mut searches = [
+        ParallelBfs::new(&neighbors, v0, [from]),
+        ParallelBfs::new(&neighbors, v1, [to]),
+    ];
+    let depth = |searches: &[ParallelBfs<P, &N, V>; 2]| {
+        searches[0].frontier().depth() + searches[1].frontier().depth()
+    };
+    while depth(&searches) < max_depth {
+        let side = usize::from(searches[0].frontier().len() > searches[1].frontier().len());
+        if !searches[side].advance() {
+            return None;
+        }
+        // The first level to meet the other search is at exactly the distance between them.
+        let (met, other) = (&searches[side], &searches[1 - side]);
+        if met.frontier().iter().any(|t| other.visited().contains(&t)) {
+            return Some(depth(&searches));
+        }
+    }
+    None
+}
+
+/// Returns the least number of Vieta involutions taking `t` to $(3, 3, 3)$, or `None` if it is
+/// more than `max_depth`.
+/// See [`Triple::distance_to_fundamental`].
+pub(crate) fn distance_to_fundamental<const P: u128, V: VisitedSet<Triple<P>> + Sync>(
+    t: Triple<P>,
+    visited: [V; 2],
+    max_depth: usize,
+) -> Option<usize> {
+    let fundamental = Triple::new_unchecked(FpNum::from(3), FpNum::from(3), FpNum::from(3));
+    distance(Vieta, visited, t, fundamental, max_depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A plain breadth first search of the Markoff graph.
+    fn plain_levels<const P: u128>(start: Triple<P>) -> Vec<HashSet<Triple<P>>> {
+        let mut seen = HashSet::from([start]);
+        let mut res = vec![HashSet::from([start])];
+        loop {
+            let next = res
+                .last()
+                .unwrap()
+                .iter()
+                .flat_map(|t| [Pos::A, Pos::B, Pos::C].map(|pos| t.vieta(pos)))
+                .filter(|u| seen.insert(*u))
+                .collect::<HashSet<_>>();
+            if next.is_empty() {
+                return res;
+            }
+            res.push(next);
+        }
+    }
+
+    fn search_levels<const P: u128>(
+        search: ParallelBfs<P, Vieta, impl VisitedSet<Triple<P>> + Sync>,
+    ) -> Vec<HashSet<Triple<P>>> {
+        let mut res = Vec::new();
+        search.run(|level| {
+            assert_eq!(level.depth(), res.len());
+            res.push(level.iter().collect::<HashSet<_>>());
+        });
+        res
+    }
+
+    #[test]
+    fn backends_match_plain_search() {
+        let start = Triple::<31>::new_unchecked(FpNum::from(3), FpNum::from(3), FpNum::from(3));
+        let expected = plain_levels(start);
+        assert_eq!(
+            search_levels(ParallelBfs::new(Vieta, HashSet::new(), [start])),
+            expected
+        );
+        assert_eq!(
+            search_levels(ParallelBfs::new(Vieta, Bitset::new(), [start])),
+            expected
+        );
+        for alpha in [1, 4, 1000] {
+            assert_eq!(
+                search_levels(
+                    ParallelBfs::new(Vieta, Bitset::new(), [start]).direction_optimizing(alpha)
+                ),
+                expected
+            );
+        }
+        let total = expected.iter().map(HashSet::len).sum::<usize>() as u128;
+        assert_eq!(component_size(Vieta, HashSet::new(), start), total);
+
+        // A Bloom filter may skip triples, but never visits one twice.
+        let hashes = vec![|t: &Triple<31>| (encode(t) % (1 << 16)) as usize];
+        let bloom = component_size(Vieta, BloomFilter::new(1 << 16, hashes), start);
+        assert!(bloom <= total);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_backend() {
+        let path = std::env::temp_dir().join(format!("libbgs-bfs-{}", std::process::id()));
+        let start = Triple::<31>::new_unchecked(FpNum::from(3), FpNum::from(3), FpNum::from(3));
+        let visited = MmapBitset::create(&path).unwrap();
+        assert_eq!(
+            search_levels(ParallelBfs::new(Vieta, visited, [start])),
+            plain_levels(start)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn distances_match_plain_search() {
+        let start = Triple::<31>::new_unchecked(FpNum::from(3), FpNum::from(3), FpNum::from(3));
+        for (d, level) in plain_levels(start).iter().enumerate() {
+            for t in level.iter().take(5) {
+                let visited = [Bitset::new(), Bitset::new()];
+                assert_eq!(distance(Vieta, visited, *t, start, d), Some(d));
+                if d > 0 {
+                    let visited = [HashSet::new(), HashSet::new()];
+                    assert_eq!(distance(Vieta, visited, start, *t, d - 1), None);
+                }
+            }
+        }
+    }
+}
@@ -8,9 +8,33 @@ use std::sync::Arc;
 pub struct BloomFilter<T, F> {
     masks: Vec<u8>,
     hashes: Arc<Vec<F>>,
+    family: Option<u64>,
     _phantom: PhantomData<T>,
 }
 
+/// The reasons two Bloom filters may be incompatible for set operations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BloomFilterError {
+    /// The filters have different sizes, in bytes.
+    SizeMismatch(usize, usize),
+    /// The filters may not use the same hashes: they were neither cloned from a common filter,
+    /// nor created with the same family identifier.
+    FamilyMismatch,
+}
+
+impl std::fmt::Display for BloomFilterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BloomFilterError::SizeMismatch(a, b) => {
+                write!(f, "Bloom filters have different sizes: {a} and {b} bytes")
+            }
+            BloomFilterError::FamilyMismatch => write!(f, "Bloom filters use different hashes"),
+        }
+    }
+}
+
+impl std::error::Error for BloomFilterError {}
+
 impl<T, F> BloomFilter<T, F>
 where
     F: Fn(&T) -> usize + Send + Sync,
@@ -21,10 +45,43 @@ where
         BloomFilter {
             masks: vec![0; bits >> 3],
             hashes: Arc::new(hashes),
+            family: None,
             _phantom: PhantomData,
         }
     }
 
+    /// Create a new Bloom filter, as with [`BloomFilter::new`], belonging to the hash family
+    /// `family`.
+    /// Filters are only compatible for set operations if they use the same hashes; two filters
+    /// with the same family identifier are trusted to do so, even if created separately.
+    /// Otherwise, only a filter and its clones are compatible.
+    pub fn with_family(bits: usize, hashes: Vec<F>, family: u64) -> BloomFilter<T, F> {
+        BloomFilter {
+            family: Some(family),
+            ..BloomFilter::new(bits, hashes)
+        }
+    }
+
+    /// Returns the family identifier of this filter, if it was created with one.
+    pub fn family(&self) -> Option<u64> {
+        self.family
+    }
+
+    /// Checks that `self` and `other` have the same size and use the same hashes.
+    pub fn check_compatible(&self, other: &Self) -> Result<(), BloomFilterError> {
+        if self.masks.len() != other.masks.len() {
+            return Err(BloomFilterError::SizeMismatch(
+                self.masks.len(),
+                other.masks.len(),
+            ));
+        }
+        let same_family = self.family.is_some() && self.family == other.family;
+        if !same_family && !Arc::ptr_eq(&self.hashes, &other.hashes) {
+            return Err(BloomFilterError::FamilyMismatch);
+        }
+        Ok(())
+    }
+
     /// Add `elem` to the Bloom filter.
     pub fn add(&mut self, elem: &T) {
         self.hashes.iter().for_each(|hash| {
@@ -52,14 +109,42 @@ where
         self.is_member_prob(elem) && confirm(elem)
     }
 
+    /// Returns an estimate of the number of distinct elements added to the filter, from the
+    /// fraction of its bits which are set.
+    pub fn estimate_len(&self) -> usize {
+        let m = (self.masks.len() * 8) as f64;
+        let k = self.hashes.len() as f64;
+        let set = self.masks.iter().map(|x| x.count_ones()).sum::<u32>() as f64;
+        if set >= m {
+            return usize::MAX;
+        }
+        (-m / k * (1.0 - set / m).ln()).round() as usize
+    }
+
     /// Modifies `self` to include elements from `other`.
     /// The false positivity rate of the resultant bloom filter will be greater than or equal to
     /// the maximum of the false positivity rates of the two operands.
-    pub fn union(&mut self, other: &Self) {
-        let l = usize::max(self.masks.len(), other.masks.len());
-        for i in 0..l {
-            self.masks[i] |= other.masks[i];
+    /// Fails, leaving `self` unchanged, if the filters are not compatible.
+    /// See [`BloomFilter::check_compatible`].
+    pub fn union(&mut self, other: &Self) -> Result<(), BloomFilterError> {
+        self.check_compatible(other)?;
+        for (a, b) in self.masks.iter_mut().zip(&other.masks) {
+            *a |= b;
         }
+        Ok(())
+    }
+
+    /// Modifies `self` to include only elements which are also in `other`.
+    /// Every element added to both filters remains a member, but the result may have more false
+    /// positives than a filter built from the intersection directly.
+    /// Fails, leaving `self` unchanged, if the filters are not compatible.
+    /// See [`BloomFilter::check_compatible`].
+    pub fn intersect(&mut self, other: &Self) -> Result<(), BloomFilterError> {
+        self.check_compatible(other)?;
+        for (a, b) in self.masks.iter_mut().zip(&other.masks) {
+            *a &= b;
+        }
+        Ok(())
     }
 }
 
@@ -68,6 +153,7 @@ impl<T, F> Clone for BloomFilter<T, F> {
         BloomFilter {
             masks: self.masks.clone(),
             hashes: Arc::clone(&self.hashes),
+            family: self.family,
             _phantom: PhantomData,
         }
     }
@@ -78,11 +164,14 @@ mod tests {
     use super::*;
     use libbgs_util::*;
 
+    type Hash = Box<dyn Fn(&u128) -> usize + Send + Sync>;
+
     #[test]
     fn test_bloom_filter() {
-        let mut hashes = Vec::<Box<dyn Fn(&u128) -> usize + Send + Sync>>::new();
-        hashes.push(Box::new(|x| (x % 10_000) as usize));
-        hashes.push(Box::new(|x| ((x >> 32) % 10_000) as usize));
+        let hashes: Vec<Hash> = vec![
+            Box::new(|x| (x % 10_000) as usize),
+            Box::new(|x| ((x >> 32) % 10_000) as usize),
+        ];
         let mut filter = BloomFilter::<u128, _>::new(10_000, hashes);
         for i in 100_000..101_000 {
             filter.add(&intpow::<0>(i * 1000 + i * 10 + i, 2));
@@ -102,4 +191,53 @@ mod tests {
         }
         assert!(!all);
     }
+
+    fn hashes() -> Vec<Hash> {
+        vec![
+            Box::new(|x| (x % 1_000) as usize),
+            Box::new(|x| ((x / 1_000) % 1_000) as usize),
+        ]
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let empty = BloomFilter::<u128, _>::new(1_000, hashes());
+        let mut a = empty.clone();
+        let mut b = empty.clone();
+        a.add(&1_001);
+        a.add(&2_002);
+        b.add(&2_002);
+        b.add(&3_003);
+
+        let mut union = a.clone();
+        union.union(&b).unwrap();
+        assert!([1_001, 2_002, 3_003]
+            .iter()
+            .all(|x| union.is_member_prob(x)));
+
+        let mut intersection = a.clone();
+        intersection.intersect(&b).unwrap();
+        assert!(intersection.is_member_prob(&2_002));
+        assert!(!intersection.is_member_prob(&1_001));
+        assert!(!intersection.is_member_prob(&3_003));
+    }
+
+    #[test]
+    fn test_compatibility() {
+        let mut a = BloomFilter::<u128, _>::new(1_000, hashes());
+        let b = BloomFilter::<u128, _>::new(1_000, hashes());
+        assert_eq!(a.union(&b), Err(BloomFilterError::FamilyMismatch));
+        let c = BloomFilter::<u128, _>::new(2_000, hashes());
+        assert_eq!(
+            a.intersect(&c),
+            Err(BloomFilterError::SizeMismatch(125, 250))
+        );
+
+        let mut d = BloomFilter::<u128, _>::with_family(1_000, hashes(), 7);
+        let e = BloomFilter::<u128, _>::with_family(1_000, hashes(), 7);
+        assert_eq!(d.family(), Some(7));
+        assert_eq!(d.union(&e), Ok(()));
+        let f = BloomFilter::<u128, _>::with_family(1_000, hashes(), 8);
+        assert_eq!(d.union(&f), Err(BloomFilterError::FamilyMismatch));
+    }
 }
@@ -0,0 +1,701 @@
+//! Censuses of the connected components of the Markoff graph modulo small primes.
+use std::collections::{HashMap, HashSet};
+
+use rayon::prelude::*;
+
+use crate::markoff::*;
+use crate::numbers::*;
+use crate::util::stats::Histogram;
+
+/// The connected components of the Markoff graph modulo `P`, or of a subgraph of it.
+/// The vertices of the graph are the nonzero Markoff triples modulo `P`, and the edges are the
+/// Vieta involutions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Census<const P: u128> {
+    /// Every connected component, largest first.
+    /// Components of the same size are ordered by their representatives.
+    pub components: Vec<Component<P>>,
+}
+
+/// A connected component of the Markoff graph modulo `P`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Component<const P: u128> {
+    /// The number of triples in this component.
+    pub size: u128,
+    /// The least triple in this component, ordering triples lexicographically by coordinates.
+    pub representative: Triple<P>,
+    /// True if this component contains a fundamental-type triple, that is, one of the images of
+    /// $(3, 3, 3)$ under permutations and negations of pairs of coordinates.
+    pub fundamental: bool,
+}
+
+/// The result of building the Markoff graph modulo `P` one coordinate at a time, in increasing
+/// order of rotation order.
+/// See [`connectivity_threshold`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Threshold {
+    /// The least rotation order at which the graph becomes connected, or `None` if it never
+    /// does.
+    pub order: Option<u128>,
+    /// For each rotation order, the number of components once every coordinate of at most that
+    /// order has been inserted.
+    pub history: Vec<(u128, usize)>,
+}
+
+/// Breadth-first search statistics for the component of the Markoff graph modulo `P` containing
+/// $(3, 3, 3)$.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DepthStats {
+    /// The number of triples at each distance from $(3, 3, 3)$.
+    pub histogram: Vec<u128>,
+    /// The greatest distance from $(3, 3, 3)$ to any triple in its component.
+    pub eccentricity: usize,
+    /// Lower and upper bounds on the diameter of the component.
+    pub diameter_bound: (usize, usize),
+}
+
+impl<const P: u128> Census<P> {
+    /// True if the graph has exactly one connected component.
+    pub fn is_connected(&self) -> bool {
+        self.components.len() == 1
+    }
+
+    /// Returns the total number of triples in every component.
+    pub fn triples(&self) -> u128 {
+        self.components.iter().map(|c| c.size).sum()
+    }
+
+    /// Returns the histogram of the sizes of the components, with a bucket for each power of
+    /// two.
+    pub fn size_histogram(&self) -> Histogram {
+        let mut res = Histogram::log2();
+        res.extend(self.components.iter().map(|c| c.size));
+        res
+    }
+}
+
+impl DepthStats {
+    /// Returns the histogram of the distances of the triples from $(3, 3, 3)$, counting each
+    /// distance exactly.
+    pub fn depths(&self) -> Histogram {
+        let mut res = Histogram::exact();
+        for (depth, n) in self.histogram.iter().enumerate() {
+            res.add_n(depth as u128, *n);
+        }
+        res
+    }
+}
+
+// Packs a triple into a single integer.
+pub(crate) fn encode<const P: u128>(t: &Triple<P>) -> u128 {
+    (u128::from(t.a()) * P + u128::from(t.b())) * P + u128::from(t.c())
+}
+
+pub(crate) fn decode<const P: u128>(x: u128) -> Triple<P> {
+    Triple::new_unchecked(
+        FpNum::from(x / (P * P)),
+        FpNum::from(x / P % P),
+        FpNum::from(x % P),
+    )
+}
+
+/// Returns every nonzero Markoff triple modulo `P`.
+pub fn triples<const P: u128>() -> impl Iterator<Item = Triple<P>> {
+    (0..P).flat_map(|a| enumerate_with_coordinate(Coord::from(a)))
+}
+
+/// Lists every connected component of the Markoff graph modulo `P`.
+/// This visits every triple, so is only feasible for small primes.
+/// The triples are joined into components on every `rayon` thread at once, with a
+/// [`ConcurrentDisjoint`], holding a few words of memory for each triple.
+pub fn component_census<const P: u128>() -> Census<P> {
+    induced_census(|_| true)
+}
+
+/// Lists every connected component of the subgraph of the Markoff graph modulo `P` induced by the
+/// triples admitted by `parabolic`.
+/// See [`component_census`].
+pub fn component_census_with<const P: u128>(parabolic: Parabolic) -> Census<P> {
+    induced_census(|t| parabolic.admits(t))
+}
+
+// Lists every connected component of the subgraph of the Markoff graph modulo `P` induced by the
+// triples satisfying `include`.
+pub(crate) fn induced_census<const P: u128>(
+    include: impl Fn(&Triple<P>) -> bool + Sync,
+) -> Census<P> {
+    Census {
+        components: induced_components(include).0,
+    }
+}
+
+// Returns the connected components of the subgraph induced by the triples satisfying `include`,
+// in the order of `Census::components`, together with each of those triples, encoded, in
+// ascending order, with the index of its component.
+// The triples are listed and joined on every `rayon` thread at once, each triple standing for its
+// index in the sorted list in a `ConcurrentDisjoint`, so that the representative of each component
+// is its least triple.
+fn induced_components<const P: u128>(
+    include: impl Fn(&Triple<P>) -> bool + Sync,
+) -> (Vec<Component<P>>, Vec<(u128, u32)>) {
+    let fundamental =
+        Triple::<P>::new_unchecked(FpNum::from(3), FpNum::from(3), FpNum::from(3)).canonical_form();
+    let mut keys = (0..P)
+        .into_par_iter()
+        .flat_map_iter(|a| enumerate_with_coordinate(Coord::from(a)))
+        .filter(&include)
+        .map(|t| encode(&t))
+        .collect::<Vec<_>>();
+    keys.par_sort_unstable();
+    let index = |t: &Triple<P>| keys.binary_search(&encode(t)).unwrap();
+
+    let disjoint = ConcurrentDisjoint::new(keys.len());
+    keys.par_iter().enumerate().for_each(|(i, x)| {
+        let t = decode::<P>(*x);
+        for pos in [Pos::A, Pos::B, Pos::C] {
+            let u = t.vieta(pos);
+            if include(&u) {
+                disjoint.associate(i, index(&u));
+            }
+        }
+    });
+    let roots = (0..keys.len())
+        .into_par_iter()
+        .map(|i| disjoint.find(i))
+        .collect::<Vec<_>>();
+
+    // For each component, its size and whether it is fundamental.
+    let mut components = HashMap::<usize, (u128, bool)>::new();
+    for (x, root) in keys.iter().zip(&roots) {
+        let entry = components.entry(*root).or_default();
+        entry.0 += 1;
+        entry.1 |= decode::<P>(*x).canonical_form() == fundamental;
+    }
+    let mut components = components.into_iter().collect::<Vec<_>>();
+    components.sort_by_key(|(root, (size, _))| (std::cmp::Reverse(*size), *root));
+
+    let mut ids = vec![0; keys.len()];
+    for (id, (root, _)) in components.iter().enumerate() {
+        ids[*root] = id as u32;
+    }
+    let labels = keys
+        .iter()
+        .zip(roots)
+        .map(|(x, root)| (*x, ids[root]))
+        .collect();
+    let components = components
+        .into_iter()
+        .map(|(root, (size, fundamental))| Component {
+            size,
+            representative: decode(keys[root]),
+            fundamental,
+        })
+        .collect();
+    (components, labels)
+}
+
+/// The version of the binary snapshot format written by [`write_snapshot`].
+pub const SNAPSHOT_VERSION: u16 = 1;
+
+const SNAPSHOT_MAGIC: [u8; 4] = *b"BGSC";
+
+/// Nonzero Markoff triples modulo `P`, each labeled by its connected component, in a form which
+/// can be stored compactly with [`write_snapshot`] and [`read_snapshot`].
+/// The snapshot is held in memory; see [`write_snapshot_from`] and [`SnapshotReader`] to stream
+/// triples instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot<const P: u128> {
+    /// A hash of the configuration which produced the data, chosen by the writer.
+    /// Readers can compare it against their own configuration to detect data from a different
+    /// run.
+    pub config_hash: u64,
+    /// Each triple, with the index of its component in [`Census::components`], sorted by triple.
+    pub triples: Vec<(Triple<P>, u32)>,
+}
+
+/// The reasons a snapshot may fail to load.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The snapshot could not be read, or ended early.
+    Io(std::io::Error),
+    /// The data does not begin with the snapshot header.
+    BadMagic,
+    /// The snapshot was written in an unsupported version of the format.
+    Version(u16),
+    /// The snapshot is of triples modulo a different prime.
+    Prime(u128),
+    /// A coordinate is not reduced modulo the prime.
+    BadCoordinate(u128),
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "could not read snapshot: {e}"),
+            SnapshotError::BadMagic => write!(f, "not a census snapshot"),
+            SnapshotError::Version(v) => write!(f, "unsupported snapshot version {v}"),
+            SnapshotError::Prime(p) => write!(f, "snapshot is of triples modulo {p}"),
+            SnapshotError::BadCoordinate(x) => write!(f, "coordinate {x} is out of range"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(e: std::io::Error) -> SnapshotError {
+        SnapshotError::Io(e)
+    }
+}
+
+/// Labels every nonzero Markoff triple modulo `P` by its connected component, as in
+/// [`component_census`], recording `config_hash` in the snapshot.
+/// This visits every triple, so is only feasible for small primes.
+pub fn component_snapshot<const P: u128>(config_hash: u64) -> Snapshot<P> {
+    component_snapshot_with(Parabolic::Include, config_hash)
+}
+
+/// Labels every triple admitted by `parabolic` by its connected component in the induced
+/// subgraph, as in [`component_census_with`], recording `config_hash` in the snapshot.
+/// See [`component_snapshot`].
+pub fn component_snapshot_with<const P: u128>(
+    parabolic: Parabolic,
+    config_hash: u64,
+) -> Snapshot<P> {
+    let (_, labels) = induced_components::<P>(|t| parabolic.admits(t));
+    Snapshot {
+        config_hash,
+        triples: labels.into_iter().map(|(x, id)| (decode(x), id)).collect(),
+    }
+}
+
+// Returns the number of bytes needed to store a coordinate modulo `P`.
+fn coord_width<const P: u128>() -> usize {
+    (128 - (P - 1).leading_zeros() as usize).div_ceil(8).max(1)
+}
+
+/// Writes `snapshot` in a compact binary format.
+/// The header holds a magic number, the format version, the prime, the configuration hash, and
+/// the number of triples, all little endian.
+/// Each triple follows as its three coordinates, each in the fewest bytes which hold every
+/// residue modulo `P`, and then its component as 4 bytes.
+///
+/// The writes are small, so `writer` should be buffered.
+/// See [`write_snapshot_from`] to write a snapshot without holding it in memory.
+pub fn write_snapshot<W: std::io::Write, const P: u128>(
+    writer: W,
+    snapshot: &Snapshot<P>,
+) -> std::io::Result<()> {
+    write_snapshot_from(
+        writer,
+        snapshot.config_hash,
+        snapshot.triples.iter().copied(),
+    )
+}
+
+/// Writes the triples yielded by `triples`, each with the index of its component, in the format
+/// of [`write_snapshot`], one at a time as they are yielded.
+/// The number of triples is written first, so must be known in advance.
+pub fn write_snapshot_from<W, I, const P: u128>(
+    mut writer: W,
+    config_hash: u64,
+    triples: I,
+) -> std::io::Result<()>
+where
+    W: std::io::Write,
+    I: IntoIterator<Item = (Triple<P>, u32)>,
+    I::IntoIter: ExactSizeIterator,
+{
+    let triples = triples.into_iter();
+    let width = coord_width::<P>();
+    writer.write_all(&SNAPSHOT_MAGIC)?;
+    writer.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+    writer.write_all(&P.to_le_bytes())?;
+    writer.write_all(&config_hash.to_le_bytes())?;
+    writer.write_all(&(triples.len() as u64).to_le_bytes())?;
+    for (t, id) in triples {
+        for x in [t.a(), t.b(), t.c()] {
+            writer.write_all(&u128::from(x).to_le_bytes()[..width])?;
+        }
+        writer.write_all(&id.to_le_bytes())?;
+    }
+    writer.flush()
+}
+
+/// Reads a snapshot written by [`write_snapshot`].
+/// The triples are not checked to be solutions.
+/// See [`SnapshotReader`] to read a snapshot without holding it in memory.
+pub fn read_snapshot<R: std::io::Read, const P: u128>(
+    reader: R,
+) -> Result<Snapshot<P>, SnapshotError> {
+    let reader = SnapshotReader::<R, P>::new(reader)?;
+    Ok(Snapshot {
+        config_hash: reader.config_hash(),
+        triples: reader.collect::<Result<_, _>>()?,
+    })
+}
+
+/// An iterator reading the triples of a snapshot written by [`write_snapshot`] one at a time,
+/// each with the index of its component, so that snapshots larger than memory can be scanned.
+/// The triples are not checked to be solutions.
+/// After the first error, no more triples are read.
+pub struct SnapshotReader<R, const P: u128> {
+    reader: R,
+    config_hash: u64,
+    remaining: u64,
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl std::io::Read) -> std::io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+impl<R: std::io::Read, const P: u128> SnapshotReader<R, P> {
+    /// Reads and checks the header of the snapshot in `reader`, leaving the triples to be read by
+    /// the iterator.
+    pub fn new(mut reader: R) -> Result<SnapshotReader<R, P>, SnapshotError> {
+        if read_bytes::<4>(&mut reader)? != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = u16::from_le_bytes(read_bytes(&mut reader)?);
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::Version(version));
+        }
+        let p = u128::from_le_bytes(read_bytes(&mut reader)?);
+        if p != P {
+            return Err(SnapshotError::Prime(p));
+        }
+        let config_hash = u64::from_le_bytes(read_bytes(&mut reader)?);
+        let remaining = u64::from_le_bytes(read_bytes(&mut reader)?);
+        Ok(SnapshotReader {
+            reader,
+            config_hash,
+            remaining,
+        })
+    }
+
+    /// Returns the configuration hash recorded in the snapshot.
+    pub fn config_hash(&self) -> u64 {
+        self.config_hash
+    }
+
+    /// Returns the number of triples not yet read, according to the header.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    fn read_triple(&mut self) -> Result<(Triple<P>, u32), SnapshotError> {
+        let width = coord_width::<P>();
+        let mut coords = [FpNum::ZERO; 3];
+        for x in &mut coords {
+            let mut buf = [0; 16];
+            self.reader.read_exact(&mut buf[..width])?;
+            let raw = u128::from_le_bytes(buf);
+            if raw >= P {
+                return Err(SnapshotError::BadCoordinate(raw));
+            }
+            *x = FpNum::from(raw);
+        }
+        let id = u32::from_le_bytes(read_bytes(&mut self.reader)?);
+        Ok((Triple::new_unchecked(coords[0], coords[1], coords[2]), id))
+    }
+}
+
+impl<R: std::io::Read, const P: u128> Iterator for SnapshotReader<R, P> {
+    type Item = Result<(Triple<P>, u32), SnapshotError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let res = self.read_triple();
+        self.remaining = if res.is_ok() { self.remaining - 1 } else { 0 };
+        Some(res)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, usize::try_from(self.remaining).ok())
+    }
+}
+
+/// Computes breadth-first search statistics for the component of the Markoff graph modulo `P`
+/// containing $(3, 3, 3)$, which is conjecturally the whole graph.
+/// The diameter bounds come from a second search started at a triple farthest from
+/// $(3, 3, 3)$: the diameter is at least the eccentricity of either starting triple, and at most
+/// twice the eccentricity of either.
+pub fn depth_stats<const P: u128>() -> DepthStats {
+    let start = Triple::<P>::new_unchecked(FpNum::from(3), FpNum::from(3), FpNum::from(3));
+    let (histogram, far) = bfs::levels(bfs::Vieta, HashSet::new(), start);
+    let eccentricity = histogram.len() - 1;
+    let far_eccentricity = bfs::levels(bfs::Vieta, HashSet::new(), far).0.len() - 1;
+    DepthStats {
+        histogram,
+        eccentricity,
+        diameter_bound: (
+            usize::max(eccentricity, far_eccentricity),
+            2 * usize::min(eccentricity, far_eccentricity),
+        ),
+    }
+}
+
+/// True if the Markoff graph modulo `P` is connected, that is, every nonzero triple is in the
+/// component of $(3, 3, 3)$.
+/// This visits every triple, so is only feasible for small primes.
+pub fn is_connected<const P: u128>() -> bool {
+    is_connected_with::<P>(HashSet::new())
+}
+
+/// True if the Markoff graph modulo `P` is connected, as in [`is_connected`], tracking the
+/// triples visited in `visited`, which should be empty.
+/// With a [`Bitset`], this takes $p^3 / 8$ bytes however many triples there are.
+pub fn is_connected_with<const P: u128>(visited: impl VisitedSet<Triple<P>> + Sync) -> bool {
+    let start = Triple::<P>::new_unchecked(FpNum::from(3), FpNum::from(3), FpNum::from(3));
+    bfs::component_size(bfs::Vieta, visited, start) == triples::<P>().count() as u128
+}
+
+/// Measures how many coordinates are needed for the Markoff graph modulo `P` to be connected.
+/// Starting from every nonzero triple as its own component, coordinates $a$ are inserted in
+/// increasing order of [`RotOrder::order`], so that the parabolic coordinates $2$ and $-2$ come
+/// first, as orders 1 and 2.
+/// Inserting $a$ joins every triple with $a$ in some position to its images under the two Vieta
+/// involutions fixing that position, so that every orbit of $\text{rot}\_a$ is joined.
+/// This records the least order at which the graph becomes connected, which shows how sharp the
+/// endgame bound is.
+pub fn connectivity_threshold<S, const P: u128>() -> Threshold
+where
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    connectivity_threshold_with::<S, P>(Parabolic::Include)
+}
+
+/// Measures how many coordinates are needed for the subgraph of the Markoff graph modulo `P`
+/// induced by the triples admitted by `parabolic` to be connected, as in
+/// [`connectivity_threshold`].
+/// When parabolic triples are excluded, the parabolic coordinates are never inserted.
+pub fn connectivity_threshold_with<S, const P: u128>(parabolic: Parabolic) -> Threshold
+where
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    let mut coords = (0..P)
+        .filter(|a| parabolic.admits_coord(&Coord::<P>::from(*a)))
+        .map(|a| (Coord::<P>::from(a).rot_order::<S, S>().order(), a))
+        .collect::<Vec<_>>();
+    coords.sort_unstable();
+
+    let mut disjoint = Disjoint::new();
+    let mut components = 0;
+    for t in triples::<P>().filter(|t| parabolic.admits(t)) {
+        let x = encode(&t);
+        disjoint.associate(x, x);
+        components += 1;
+    }
+    // Joins the components of `t` and `u`, returning true if they were different components.
+    let mut join = |t: Triple<P>, u: Triple<P>| {
+        let (x, y) = (encode(&t), encode(&u));
+        let merged = disjoint.find(&x).unwrap().0 != disjoint.find(&y).unwrap().0;
+        disjoint.associate(x, y);
+        merged
+    };
+
+    let mut res = Threshold {
+        order: None,
+        history: Vec::new(),
+    };
+    for (i, (ord, a)) in coords.iter().enumerate() {
+        for t in enumerate_with_coordinate::<P>(Coord::from(*a)) {
+            let (a, b, c) = (t.a(), t.b(), t.c());
+            let s = Triple::new_unchecked(b, a, c);
+            let u = Triple::new_unchecked(b, c, a);
+            components -= [
+                (t, t.vieta(Pos::B)),
+                (t, t.vieta(Pos::C)),
+                (s, s.vieta(Pos::A)),
+                (s, s.vieta(Pos::C)),
+                (u, u.vieta(Pos::A)),
+                (u, u.vieta(Pos::B)),
+            ]
+            .into_iter()
+            .filter(|(t, u)| parabolic.admits(t) && parabolic.admits(u) && join(*t, *u))
+            .count();
+        }
+        if coords.get(i + 1).is_none_or(|(next, _)| next != ord) {
+            res.history.push((*ord, components));
+            if components == 1 {
+                res.order.get_or_insert(*ord);
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoding_round_trips() {
+        for t in triples::<13>() {
+            assert_eq!(decode::<13>(encode(&t)), t);
+        }
+    }
+
+    #[test]
+    fn census_small_primes() {
+        // There are p^2 + 3(-1/p)p nonzero Markoff triples modulo p.
+        let census = component_census::<13>();
+        assert_eq!(census.triples(), 13 * 13 + 3 * 13);
+        assert!(census.is_connected());
+        assert!(census.components[0].fundamental);
+        // The one component, of 208 triples, lies in the bucket from 128 to 255.
+        let sizes = census.size_histogram().buckets().collect::<Vec<_>>();
+        assert_eq!(sizes, vec![(128..=255, 1)]);
+
+        let census = component_census::<23>();
+        assert_eq!(census.triples(), 23 * 23 - 3 * 23);
+        assert!(census.is_connected());
+        assert!(census.components[0].fundamental);
+        assert!(is_connected::<13>() && is_connected::<23>());
+        assert!(is_connected_with::<23>(Bitset::new()));
+    }
+
+    #[test]
+    fn depth_stats_small_primes() {
+        let stats = depth_stats::<13>();
+        assert_eq!(stats.histogram[0], 1);
+        assert_eq!(
+            stats.histogram.iter().sum::<u128>(),
+            component_census::<13>().triples()
+        );
+        assert_eq!(stats.eccentricity, stats.histogram.len() - 1);
+        let depths = stats.depths();
+        assert_eq!(depths.count(), component_census::<13>().triples());
+        assert_eq!(depths.max(), Some(stats.eccentricity as u128));
+        let (lo, hi) = stats.diameter_bound;
+        assert!(stats.eccentricity <= lo && lo <= hi && hi <= 2 * stats.eccentricity);
+    }
+
+    #[test]
+    fn snapshot_round_trips() {
+        let snapshot = component_snapshot::<23>(0xfeed);
+        assert_eq!(
+            snapshot.triples.len() as u128,
+            component_census::<23>().triples()
+        );
+        assert!(snapshot.triples.iter().all(|(_, id)| *id == 0));
+
+        // Without the parabolic triples, the graph modulo 13 falls into several components.
+        let census = component_census_with::<13>(Parabolic::Exclude);
+        let snapshot = component_snapshot_with::<13>(Parabolic::Exclude, 0xfeed);
+        assert!(census.components.len() > 1);
+        assert!(snapshot
+            .triples
+            .windows(2)
+            .all(|w| encode(&w[0].0) < encode(&w[1].0)));
+        let mut sizes = vec![0; census.components.len()];
+        for (t, id) in &snapshot.triples {
+            sizes[*id as usize] += 1;
+            let component = &census.components[*id as usize];
+            assert!(encode(&component.representative) <= encode(t));
+        }
+        assert_eq!(
+            sizes,
+            census.components.iter().map(|c| c.size).collect::<Vec<_>>()
+        );
+
+        let mut bytes = Vec::new();
+        write_snapshot(&mut bytes, &snapshot).unwrap();
+        // A 38 byte header, then one byte for each coordinate and four for each component.
+        assert_eq!(bytes.len(), 38 + 7 * snapshot.triples.len());
+        assert_eq!(read_snapshot::<_, 13>(bytes.as_slice()).unwrap(), snapshot);
+        let mut streamed = Vec::new();
+        write_snapshot_from(&mut streamed, 0xfeed, snapshot.triples.iter().copied()).unwrap();
+        assert_eq!(streamed, bytes);
+        let reader = SnapshotReader::<_, 13>::new(bytes.as_slice()).unwrap();
+        assert_eq!(reader.config_hash(), 0xfeed);
+        assert_eq!(reader.remaining(), snapshot.triples.len() as u64);
+        assert!(reader
+            .map(Result::unwrap)
+            .eq(snapshot.triples.iter().copied()));
+
+        assert!(matches!(
+            read_snapshot::<_, 29>(bytes.as_slice()),
+            Err(SnapshotError::Prime(13))
+        ));
+        assert!(matches!(
+            read_snapshot::<_, 13>(&bytes[..bytes.len() - 1]),
+            Err(SnapshotError::Io(_))
+        ));
+        let mut truncated = SnapshotReader::<_, 13>::new(&bytes[..bytes.len() - 1]).unwrap();
+        assert!(truncated.by_ref().any(|res| res.is_err()));
+        assert!(truncated.next().is_none());
+        bytes[38] = 13;
+        assert!(matches!(
+            read_snapshot::<_, 13>(bytes.as_slice()),
+            Err(SnapshotError::BadCoordinate(13))
+        ));
+        bytes[0] = 0;
+        assert!(matches!(
+            read_snapshot::<_, 13>(bytes.as_slice()),
+            Err(SnapshotError::BadMagic)
+        ));
+    }
+
+    #[derive(PartialEq, Eq)]
+    struct Ph {}
+
+    impl_factors!(Ph, 13, 23);
+
+    #[test]
+    fn connectivity_threshold_small_primes() {
+        let threshold = connectivity_threshold::<Ph, 13>();
+        assert!(threshold
+            .history
+            .windows(2)
+            .all(|w| w[0].0 < w[1].0 && w[0].1 >= w[1].1));
+        assert_eq!(threshold.history.last().unwrap().1, 1);
+        let order = threshold.order.unwrap();
+        assert!(threshold
+            .history
+            .iter()
+            .all(|(ord, n)| (*n == 1) == (*ord >= order)));
+
+        let threshold = connectivity_threshold::<Ph, 23>();
+        assert_eq!(threshold.history.last().unwrap().1, 1);
+        assert!(threshold.order.is_some());
+    }
+
+    #[test]
+    fn parabolic_small_primes() {
+        // Modulo 13, there are 12p - 24 = 132 parabolic triples: 2p on each of the two parabolic
+        // fibers in each position, less the 8 with two parabolic coordinates in each pair of
+        // positions.
+        let parabolic = parabolic_triples::<13>().count() as u128;
+        assert_eq!(parabolic, 132);
+        let census = component_census_with::<13>(Parabolic::Exclude);
+        assert_eq!(census.triples(), 13 * 13 + 3 * 13 - parabolic);
+        let threshold = connectivity_threshold_with::<Ph, 13>(Parabolic::Exclude);
+        assert_eq!(threshold.history.last().unwrap().1, census.components.len());
+
+        // Modulo 23, there are none, so excluding them only skips the empty parabolic fibers, of
+        // orders 1 and 2.
+        assert_eq!(
+            component_census_with::<23>(Parabolic::Exclude).triples(),
+            component_census::<23>().triples()
+        );
+        let exclude = connectivity_threshold_with::<Ph, 23>(Parabolic::Exclude);
+        let include = connectivity_threshold::<Ph, 23>();
+        assert_eq!(exclude.order, include.order);
+        assert_eq!(include.history[..2], [(1, 460), (2, 460)]);
+        assert_eq!(exclude.history, include.history[2..]);
+    }
+}
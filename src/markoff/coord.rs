@@ -1,6 +1,9 @@
 use either::*;
 
+use crate::markoff::{Degenerate, Triple};
 use crate::numbers::*;
+use libbgs_util::u128x2::checked_div_wide;
+use libbgs_util::*;
 
 /// A coordinate for a Markoff triple.
 /// May represent any of $a$, $b$, or $c$ in a Markoff triple $(a, b, c)$.
@@ -8,55 +11,269 @@ use crate::numbers::*;
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Coord<const P: u128>(pub FpNum<P>);
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 /// The order of a rotation map, along with which type of conic it is.
+/// The order is that of the element $\chi$ with $a = \chi + \chi^{-1}$; see [`Coord::to_chi`].
 pub enum RotOrder {
     /// An orbit of order dividing $p - 1$ (and not equal to 2).
     Hyperbola(u128),
     /// An orbit of order dividing $p + 1$ (and not equal to 2).
     Ellipse(u128),
-    /// An orbit of order exactly 1 or 2.
+    /// The coordinate $a = 2$, with order 1, or $a = -2$, with order 2.
+    /// The rotation maps themselves are not diagonalizable, and have orders $p$ and $2p$,
+    /// respectively.
+    Parabola(u128),
+}
+
+/// The type of conic traced out by the orbit of a rotation map, without its order.
+/// Conics are ordered hyperbolas first, then ellipses, then parabolas, and display as the letters
+/// `H`, `E`, and `P`, so that sorting triples by their [`Triple::classify`] signatures groups them
+/// as `HHH`, `HHE`, and so on.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Conic {
+    /// An orbit of order dividing $p - 1$.
+    Hyperbola,
+    /// An orbit of order dividing $p + 1$.
+    Ellipse,
+    /// The coordinates $\pm 2$, whose $\chi = \pm 1$ has order exactly 1 or 2.
     Parabola,
 }
 
+impl RotOrder {
+    /// Returns the type of conic of this order.
+    pub fn conic(&self) -> Conic {
+        match self {
+            RotOrder::Hyperbola(_) => Conic::Hyperbola,
+            RotOrder::Ellipse(_) => Conic::Ellipse,
+            RotOrder::Parabola(_) => Conic::Parabola,
+        }
+    }
+
+    /// Returns the order, without the type of conic.
+    pub fn order(&self) -> u128 {
+        match self {
+            RotOrder::Hyperbola(d) | RotOrder::Ellipse(d) | RotOrder::Parabola(d) => *d,
+        }
+    }
+
+    /// Returns the type of conic and the order as a pair.
+    /// This is the inverse of [`RotOrder::from_parts`].
+    pub fn parts(&self) -> (Conic, u128) {
+        (self.conic(), self.order())
+    }
+
+    /// Returns the order of the given type of conic.
+    pub fn from_parts(conic: Conic, order: u128) -> RotOrder {
+        match conic {
+            Conic::Hyperbola => RotOrder::Hyperbola(order),
+            Conic::Ellipse => RotOrder::Ellipse(order),
+            Conic::Parabola => RotOrder::Parabola(order),
+        }
+    }
+}
+
+impl From<RotOrder> for (Conic, u128) {
+    fn from(src: RotOrder) -> (Conic, u128) {
+        src.parts()
+    }
+}
+
+impl From<(Conic, u128)> for RotOrder {
+    fn from((conic, order): (Conic, u128)) -> RotOrder {
+        RotOrder::from_parts(conic, order)
+    }
+}
+
+impl std::fmt::Display for Conic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Conic::Hyperbola => write!(f, "H"),
+            Conic::Ellipse => write!(f, "E"),
+            Conic::Parabola => write!(f, "P"),
+        }
+    }
+}
+
 impl<const P: u128> Coord<P> {
+    /// The parabolic coordinates, $2$ and $-2$, whose rotation maps have orders 1 and 2,
+    /// respectively.
+    /// See [`RotOrder::Parabola`].
+    pub const PARABOLIC: [Coord<P>; 2] = [
+        Coord(FpNum::from_u128(2)),
+        Coord(FpNum::from_u128(2).const_neg()),
+    ];
+
+    /// True if this coordinate is $\pm 2$.
+    /// Unlike [`Coord::rot_order`], this needs no factorization of $p \pm 1$.
+    pub fn is_parabolic(&self) -> bool {
+        Self::PARABOLIC.contains(self)
+    }
+
     /// Returns an element $\chi$ such that, for a coordinate $a$, $a = \chi + \chi^{-1}$.
     /// If $a$ is a quadratic residue modulo `P`, then $\chi \in \mathbb{F}\_p$, and the result
     /// will be a `Right<FpNum<P>>`. Otherwise, $\chi \in \mathbb{F}\_{p^2}$, and the result will
     /// be a `Left<QuadNum<P>>`.
     pub fn to_chi(&self) -> Either<QuadNum<P>, FpNum<P>> {
-        let disc = self.0.pow(2) - FpNum::from(4);
-        let two_inv = FpNum::from(2).inverse();
-        QuadNum::int_sqrt_either(disc).map_either(
-            |x| (QuadNum::<P>::from(self.0) + x) * QuadNum::<P>::from(two_inv),
-            |x| (self.0 + x) * two_inv,
-        )
+        self.to_chi_const()
+    }
+
+    /// Returns the same element $\chi$ as [`Coord::to_chi`], but may be used in `const`
+    /// contexts, such as to build tables of $\chi$ for fixed coordinates at compile time.
+    /// ```
+    /// use either::*;
+    /// use libbgs::markoff::Coord;
+    /// use libbgs::numbers::*;
+    ///
+    /// const CHIS: [Either<QuadNum<13>, FpNum<13>>; 13] = {
+    ///     let mut res = [Right(FpNum::ZERO); 13];
+    ///     let mut i = 0;
+    ///     while i < 13 {
+    ///         res[i] = Coord(FpNum::from_u128(i as u128)).to_chi_const();
+    ///         i += 1;
+    ///     }
+    ///     res
+    /// };
+    /// assert_eq!(CHIS[5], Coord(FpNum::from(5)).to_chi());
+    /// ```
+    pub const fn to_chi_const(&self) -> Either<QuadNum<P>, FpNum<P>> {
+        let disc = self.0.const_mul(&self.0).const_sub(&FpNum::from_u128(4));
+        let a = self.0.const_mul(&FpNum::TWO_INV);
+        match QuadNum::int_sqrt_either(disc) {
+            Left(x) => Left(QuadNum(a, x.1.const_mul(&FpNum::TWO_INV))),
+            Right(x) => Right(a.const_add(&x.const_mul(&FpNum::TWO_INV))),
+        }
     }
 
     /// Returns an iterator yielding the coordinates $(b, c)$ contained in the orbit with fixed coordinate
     /// $a$ (the coordinate on which `rot` is called), beginning with $(a, b, c)$.
-    pub fn rot(self, b: Coord<P>, c: Coord<P>) -> impl Iterator<Item = (Coord<P>, Coord<P>)> {
-        std::iter::successors(Some((b, c)), move |(y, z)| {
-            let (b_, c_) = (*z, self.0 * z.0 + FpNum::from(P) - y.0);
-            if b_ == b && c_ == c.0 {
-                None
-            } else {
-                Some((b_, Coord(c_)))
-            }
-        })
+    /// The orbit is walked lazily, until it returns to $(b, c)$; see [`Coord::rot_sized`] for an
+    /// iterator which knows its length in advance.
+    pub fn rot(self, b: Coord<P>, c: Coord<P>) -> Rot<P> {
+        Rot {
+            a: self.0,
+            start: (b.0, c.0),
+            next: Some((b.0, c.0)),
+            inverse: false,
+        }
+    }
+
+    /// Returns an iterator yielding the same coordinates as [`Coord::rot`], beginning with
+    /// $(a, b, c)$, but walking the orbit in the opposite direction.
+    pub fn rot_inverse(self, b: Coord<P>, c: Coord<P>) -> Rot<P> {
+        Rot {
+            inverse: true,
+            ..self.rot(b, c)
+        }
+    }
+
+    /// Returns an iterator yielding the same coordinates as [`Coord::rot`], which knows the
+    /// length of the orbit, [`Coord::rot_cycle_len`], and so may also be walked from the end.
+    /// Finding the length takes an order computation before the first pair is yielded.
+    pub fn rot_sized<S1, S2>(self, b: Coord<P>, c: Coord<P>) -> SizedRot<P>
+    where
+        FpNum<P>: Factor<S1>,
+        QuadNum<P>: Factor<S2>,
+    {
+        let a = self.0;
+        SizedRot {
+            a,
+            front: (b.0, c.0),
+            back: (a * b.0 - c.0, b.0),
+            remaining: self.rot_cycle_len::<S1, S2>(b, c),
+        }
     }
 
     /// Returns an iterator yielding the values $b$ such that $(a, b, c)$ is a Markoff triple for
     /// some value $c$.
     pub fn part(self, b: Coord<P>) -> Option<Box<dyn Iterator<Item = Coord<P>>>> {
         let a = self.0;
-        let Some(disc) = (a * a * b.0 * b.0 - 4 * (a * a + b.0 * b.0)).int_sqrt() else {
-            return None;
-        };
+        let disc = (a * a * b.0 * b.0 - 4 * (a * a + b.0 * b.0)).int_sqrt()?;
         let c = (a * b.0 + disc) * FpNum::from(2).inverse();
         Some(Box::new(self.rot(b, Coord(c)).map(|x| x.1)))
     }
 
+    /// Returns an iterator yielding the values $b$ as [`Coord::part`] does, skipping those for
+    /// which the triple $(a, b, c)$ is not admitted by `degenerate`.
+    /// Returns `None` if there are no such values, including when every triple in the orbit is
+    /// degenerate, as when $a = b = 0$.
+    pub fn part_with(
+        self,
+        b: Coord<P>,
+        degenerate: Degenerate,
+    ) -> Option<Box<dyn Iterator<Item = Coord<P>>>> {
+        let a = self.0;
+        let disc = (a * a * b.0 * b.0 - 4 * (a * a + b.0 * b.0)).int_sqrt()?;
+        let c = (a * b.0 + disc) * FpNum::from(2).inverse();
+        // Rotations fix $a$, so if $a = 0$ then either every triple in the orbit is admitted, or
+        // none is; otherwise, at most four triples in the orbit are degenerate.
+        if a == FpNum::ZERO && !degenerate.admits(&Triple::new_unchecked(a, b.0, c)) {
+            return None;
+        }
+        Some(Box::new(
+            self.rot(b, Coord(c))
+                .filter(move |(y, z)| degenerate.admits(&Triple::new_unchecked(a, y.0, z.0)))
+                .map(|x| x.1),
+        ))
+    }
+
+    /// Returns the number of pairs in the orbit of $(b, c)$ under $\text{rot}\_a$, where $a$ is
+    /// the coordinate on which `rot_cycle_len` is called.
+    /// This is the order of $\chi$, except for the fixed point $(0, 0)$, and when $a = \pm 2$, where
+    /// the orbit may be as long as $2p$.
+    pub fn rot_cycle_len<S1, S2>(&self, b: Coord<P>, c: Coord<P>) -> u128
+    where
+        FpNum<P>: Factor<S1>,
+        QuadNum<P>: Factor<S2>,
+    {
+        let two = FpNum::from(2);
+        if b.0 == FpNum::ZERO && c.0 == FpNum::ZERO {
+            1
+        } else if self.0 == two {
+            if b.0 == c.0 {
+                1
+            } else {
+                P
+            }
+        } else if self.0 == -two {
+            if b.0 == -c.0 {
+                2
+            } else {
+                2 * P
+            }
+        } else {
+            self.to_chi().either(|l| l.order(), |r| r.order())
+        }
+    }
+
+    /// Returns the map $\text{rot}\_a$ as an action on the parameterization of its orbits, where
+    /// $a$ is the coordinate on which `rot_as_power_map` is called.
+    /// Every orbit of $\text{rot}\_a$ is parameterized as
+    /// $$(b, c) = (\mu \psi + \nu \psi^{-1}, \mu \chi \psi + \nu \chi^{-1} \psi^{-1})$$
+    /// for fixed $\mu$ and $\nu$, where $a = \chi + \chi^{-1}$; $\text{rot}\_a$ multiplies
+    /// $\psi$ by $\chi$.
+    /// In the Sylow decomposition, this is the addition of the coordinates of $\chi$, so that an
+    /// orbit may be walked without any arithmetic in $\mathbb{F}\_p$ or $\mathbb{F}\_{p^2}$,
+    /// converting back to $(b, c)$ only when needed.
+    ///
+    /// Returns `None` if $\chi$ does not lie in the group `C`, or if $a = \pm 2$, where the orbits
+    /// are not parameterized this way.
+    /// Finding the Sylow coordinates of $\chi$ takes a discrete logarithm; see
+    /// [`SylowElem::from_product`].
+    pub fn rot_as_power_map<S, const L: usize, C>(
+        &self,
+        decomp: &SylowDecomp<S, L, C>,
+    ) -> Option<impl Fn(&SylowElem<S, L, C>) -> SylowElem<S, L, C>>
+    where
+        C: FromChi<S, P>,
+    {
+        let two = FpNum::from(2);
+        if self.0 == two || self.0 == -two {
+            return None;
+        }
+        let chi = SylowElem::from_product(&C::chi_of(self)?, decomp)?;
+        Some(move |psi: &SylowElem<S, L, C>| psi.multiply(&chi))
+    }
+
     /// Returns the order of the map $\text{rot}\_a$, that is, $\lvert \langle \text{rot}\_a \rangle \rvert$, along with the type of [`RotOrder`] that it is.
     pub fn rot_order<S1, S2>(&self) -> RotOrder
     where
@@ -68,8 +285,7 @@ impl<const P: u128> Coord<P> {
             .as_ref()
             .map_either(|l| l.order(), |r| r.order())
         {
-            Left(1) | Right(1) => RotOrder::Parabola,
-            Left(2) | Right(2) => RotOrder::Parabola,
+            Left(d @ (1 | 2)) | Right(d @ (1 | 2)) => RotOrder::Parabola(d),
             Left(d) => RotOrder::Ellipse(d),
             Right(d) => RotOrder::Hyperbola(d),
         }
@@ -78,7 +294,24 @@ impl<const P: u128> Coord<P> {
     /// Returns an upper bound on the endgame breakpoint.
     /// That is, every triple with order larger than the value returned by this method is
     /// guarenteed to lie in the endgame.
+    ///
+    /// The bounds are $8\sqrt{p} \cdot n\tau(n) / \phi(n)$ for $n = p \mp 1$, rounded up, and
+    /// are computed in integer arithmetic, so they are the same on every platform.
+    /// They may exceed the exact values by a little, but never fall below them.
     pub fn endgame<S>() -> (u128, u128)
+    where
+        FpNum<P>: Factor<S>,
+        QuadNum<P>: Factor<S>,
+    {
+        let hyper = endgame_bound(P, P - 1, &FpNum::FACTORS, P - 2);
+        let ellip = endgame_bound(P, P + 1, &QuadNum::FACTORS, P);
+        (hyper, ellip)
+    }
+
+    /// Returns an estimate of the bounds of [`Coord::endgame`], computed in floating point.
+    /// This loses precision for large primes, so the estimate may be smaller than the true bound,
+    /// and may differ between platforms.
+    pub fn endgame_estimate<S>() -> (u128, u128)
     where
         FpNum<P>: Factor<S>,
         QuadNum<P>: Factor<S>,
@@ -93,18 +326,127 @@ impl<const P: u128> Coord<P> {
     }
 }
 
+// Returns $\lceil 8\sqrt{p} \cdot n\tau(n) / \phi(n) \rceil$, or an integer slightly larger,
+// capped at `cap`.
+fn endgame_bound(p: u128, n: u128, factors: &Factorization, cap: u128) -> u128 {
+    // An integer at least $8\sqrt{p}$; since $64p$ is not a square, this is exactly
+    // $\lceil 8\sqrt{p} \rceil$ unless $64p$ overflows.
+    let s = match p.checked_mul(64) {
+        Some(q) => q.isqrt() + 1,
+        None => 8 * (p.isqrt() + 1),
+    };
+    let (tau, phi) = (factors.tau(), factors.phi());
+    let ceil_div = |a: u128| {
+        let (hi, lo) = carrying_mul(s, a);
+        let (q, r) = checked_div_wide(hi, lo, phi)?;
+        q.checked_add((r != 0) as u128)
+    };
+    let bound = match n.checked_mul(tau) {
+        Some(a) => ceil_div(a),
+        None => ceil_div(n).and_then(|x| x.checked_mul(tau)),
+    };
+    bound.map_or(cap, |b| u128::min(b, cap))
+}
+
 impl<const P: u128> From<u128> for Coord<P> {
     fn from(src: u128) -> Coord<P> {
         Coord(FpNum::from(src))
     }
 }
 
+impl<const P: u128> From<FpNum<P>> for Coord<P> {
+    fn from(src: FpNum<P>) -> Coord<P> {
+        Coord(src)
+    }
+}
+
 impl<const P: u128> From<Coord<P>> for u128 {
     fn from(src: Coord<P>) -> u128 {
         u128::from(src.0)
     }
 }
 
+/// An iterator over the orbit of a pair $(b, c)$ under $\text{rot}\_a$, in either direction.
+/// See [`Coord::rot`] and [`Coord::rot_inverse`].
+#[derive(Clone, Copy, Debug)]
+pub struct Rot<const P: u128> {
+    a: FpNum<P>,
+    start: (FpNum<P>, FpNum<P>),
+    next: Option<(FpNum<P>, FpNum<P>)>,
+    inverse: bool,
+}
+
+/// An iterator over the orbit of a pair $(b, c)$ under $\text{rot}\_a$, which knows the length
+/// of the orbit, and may be walked from either end.
+/// See [`Coord::rot_sized`].
+///
+/// The length of the orbit may not fit in a `usize` for primes above $2^{63}$, in which case
+/// `size_hint` has no upper bound and `len` panics; [`SizedRot::remaining`] is always exact.
+#[derive(Clone, Copy, Debug)]
+pub struct SizedRot<const P: u128> {
+    a: FpNum<P>,
+    front: (FpNum<P>, FpNum<P>),
+    back: (FpNum<P>, FpNum<P>),
+    remaining: u128,
+}
+
+impl<const P: u128> Iterator for Rot<P> {
+    type Item = (Coord<P>, Coord<P>);
+
+    fn next(&mut self) -> Option<(Coord<P>, Coord<P>)> {
+        let (y, z) = self.next?;
+        let next = if self.inverse {
+            (self.a * y - z, y)
+        } else {
+            (z, self.a * z - y)
+        };
+        self.next = Some(next).filter(|x| *x != self.start);
+        Some((Coord(y), Coord(z)))
+    }
+}
+
+impl<const P: u128> SizedRot<P> {
+    /// Returns the number of pairs not yet yielded from either end.
+    pub fn remaining(&self) -> u128 {
+        self.remaining
+    }
+}
+
+impl<const P: u128> Iterator for SizedRot<P> {
+    type Item = (Coord<P>, Coord<P>);
+
+    fn next(&mut self) -> Option<(Coord<P>, Coord<P>)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let (y, z) = self.front;
+        self.front = (z, self.a * z - y);
+        Some((Coord(y), Coord(z)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match usize::try_from(self.remaining) {
+            Ok(n) => (n, Some(n)),
+            Err(_) => (usize::MAX, None),
+        }
+    }
+}
+
+impl<const P: u128> DoubleEndedIterator for SizedRot<P> {
+    fn next_back(&mut self) -> Option<(Coord<P>, Coord<P>)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let (y, z) = self.back;
+        self.back = (self.a * y - z, y);
+        Some((Coord(y), Coord(z)))
+    }
+}
+
+impl<const P: u128> ExactSizeIterator for SizedRot<P> {}
+
 /// Common trait for the `from_chi` and `from_chi_conj` methods to be defined on both `FpNum` and
 /// `QuadNum`.
 pub trait FromChi<S, const P: u128>: SylowDecomposable<S>
@@ -120,6 +462,9 @@ pub trait FromChi<S, const P: u128>: SylowDecomposable<S>
         chi: &SylowElem<S, L, Self>,
         decomp: &SylowDecomp<S, L, Self>,
     ) -> FpNum<P>;
+
+    /// Returns the element $\chi$ of [`Coord::to_chi`], if it lies in this group.
+    fn chi_of(a: &Coord<P>) -> Option<Self>;
 }
 
 impl<S, const P: u128> FromChi<S, P> for FpNum<P>
@@ -143,6 +488,10 @@ where
         let chi = chi.to_product(decomp);
         chi - chi_inv
     }
+
+    fn chi_of(a: &Coord<P>) -> Option<FpNum<P>> {
+        a.to_chi().right()
+    }
 }
 
 impl<S, const P: u128> FromChi<S, P> for QuadNum<P>
@@ -170,6 +519,10 @@ where
         assert_eq!(res.0, FpNum::<P>::ZERO);
         res.1
     }
+
+    fn chi_of(a: &Coord<P>) -> Option<QuadNum<P>> {
+        a.to_chi().left()
+    }
 }
 
 #[cfg(test)]
@@ -181,6 +534,36 @@ mod tests {
 
     impl_factors!(Ph, 3001);
 
+    #[derive(PartialEq, Eq)]
+    struct Ph13 {}
+
+    impl_factors!(Ph13, 13);
+
+    #[derive(PartialEq, Eq)]
+    struct PhEndgame {}
+
+    impl_factors!(PhEndgame, 13, 61, 1009, 3001, 7919, 65537);
+
+    #[test]
+    fn to_chi_const_table() {
+        const CHIS: [Either<QuadNum<3001>, FpNum<3001>>; 64] = {
+            let mut res = [Right(FpNum::ZERO); 64];
+            let mut i = 0;
+            while i < 64 {
+                res[i] = Coord(FpNum::from_u128(i as u128 * 47)).to_chi_const();
+                i += 1;
+            }
+            res
+        };
+        for (i, chi) in CHIS.into_iter().enumerate() {
+            let a = FpNum::from(i as u128 * 47);
+            match chi {
+                Left(chi) => assert_eq!(chi + chi.inverse(), QuadNum::from(a)),
+                Right(chi) => assert_eq!(chi + chi.inverse(), a),
+            }
+        }
+    }
+
     #[test]
     fn from_chi() {
         for i in 0..3000 {
@@ -197,4 +580,179 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn rot_len_matches_orbit() {
+        let pairs = [(0, 0), (1, 1), (1, 3000), (5, 17)];
+        for a in (0..3001).step_by(7).chain([2, 2999]) {
+            let a = Coord::<3001>::from(a);
+            for (b, c) in pairs.map(|(b, c)| (Coord::from(b), Coord::from(c))) {
+                let mut cycle = 1;
+                let (mut y, mut z) = (c.0, a.0 * c.0 - b.0);
+                while (y, z) != (b.0, c.0) {
+                    (y, z) = (z, a.0 * z - y);
+                    cycle += 1;
+                }
+                let it = a.rot_sized::<Ph, Ph>(b, c);
+                assert_eq!(it.remaining(), cycle);
+                assert_eq!(it.size_hint(), (cycle as usize, Some(cycle as usize)));
+                assert_eq!(it.count() as u128, cycle);
+                assert_eq!(a.rot(b, c).count() as u128, cycle);
+
+                let mut forward = a.rot(b, c).map(|(y, z)| (y.0, z.0)).collect::<Vec<_>>();
+                let mut backward = a
+                    .rot_inverse(b, c)
+                    .map(|(y, z)| (y.0, z.0))
+                    .collect::<Vec<_>>();
+                assert_eq!(backward[0], (b.0, c.0));
+                backward[1..].reverse();
+                assert_eq!(forward, backward);
+                assert_eq!(
+                    a.rot_sized::<Ph, Ph>(b, c)
+                        .map(|(y, z)| (y.0, z.0))
+                        .collect::<Vec<_>>(),
+                    forward
+                );
+                forward.reverse();
+                assert_eq!(
+                    a.rot_sized::<Ph, Ph>(b, c)
+                        .rev()
+                        .map(|(y, z)| (y.0, z.0))
+                        .collect::<Vec<_>>(),
+                    forward
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rot_sized_len() {
+        for a in (0..3001).step_by(13).chain([2, 2999]) {
+            let a = Coord::<3001>::from(a);
+            let (b, c) = (Coord::from(5), Coord::from(17));
+            let mut it = a.rot_sized::<Ph, Ph>(b, c);
+            let mut len = it.len();
+            assert_eq!(len as u128, a.rot_cycle_len::<Ph, Ph>(b, c));
+            let mut front = true;
+            while len > 0 {
+                if front {
+                    it.next().unwrap();
+                } else {
+                    it.next_back().unwrap();
+                }
+                front = !front;
+                assert_eq!(it.len(), len - 1);
+                len -= 1;
+            }
+            assert!(it.next().is_none());
+        }
+    }
+
+    #[test]
+    fn rot_as_power_map() {
+        let hyper = SylowDecomp::<Ph, 3, FpNum<3001>>::new();
+        let ellip = SylowDecomp::<Ph, 3, QuadNum<3001>>::new();
+        let (b, c) = (Coord::from(5), Coord::from(17));
+        for a in (3..2999).step_by(89).map(Coord::<3001>::from) {
+            match a.to_chi() {
+                Left(chi) => {
+                    assert!(a.rot_as_power_map(&hyper).is_none());
+                    let rot = a.rot_as_power_map(&ellip).unwrap();
+                    for x in [[0, 0, 0], [1, 0, 0], [1, 1, 1], [0, 18, 40]] {
+                        let x = SylowElem::new(x);
+                        assert_eq!(rot(&x).to_product(&ellip), x.to_product(&ellip) * chi);
+                    }
+                }
+                Right(chi) => {
+                    assert!(a.rot_as_power_map(&ellip).is_none());
+                    let rot = a.rot_as_power_map(&hyper).unwrap();
+                    let mu = (c.0 - b.0 * chi.inverse()) * (chi - chi.inverse()).inverse();
+                    let nu = b.0 - mu;
+                    let mut psi = SylowElem::ONE;
+                    for (y, _) in a.rot(b, c) {
+                        let x = psi.to_product(&hyper);
+                        assert_eq!(y.0, mu * x + nu * x.inverse());
+                        psi = rot(&psi);
+                    }
+                    assert_eq!(psi, SylowElem::ONE);
+                }
+            }
+        }
+        assert!(Coord::<3001>::from(2).rot_as_power_map(&hyper).is_none());
+    }
+
+    #[test]
+    fn part_with_degenerate() {
+        // -1 = 5^2 modulo 13, so (1, 0, 5) is a degenerate Markoff triple.
+        let zero = Coord::<13>::from(0);
+        assert_eq!(zero.part(zero).unwrap().count(), 1);
+        assert!(zero.part_with(zero, Degenerate::ExcludeZero).is_none());
+        assert_eq!(
+            zero.part_with(Coord::from(1), Degenerate::ExcludeZero)
+                .unwrap()
+                .count(),
+            zero.part(Coord::from(1)).unwrap().count()
+        );
+        assert!(zero
+            .part_with(Coord::from(1), Degenerate::Exclude)
+            .is_none());
+
+        let one = Coord::<13>::from(1);
+        let all = one.part(zero).unwrap().collect::<Vec<_>>();
+        let nonzero = one
+            .part_with(zero, Degenerate::Exclude)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert!(all.contains(&zero));
+        assert!(!nonzero.contains(&zero));
+        assert!(!nonzero.is_empty() && nonzero.len() < all.len());
+    }
+
+    #[test]
+    fn endgame_bounds() {
+        fn check<const P: u128>()
+        where
+            FpNum<P>: Factor<PhEndgame>,
+            QuadNum<P>: Factor<PhEndgame>,
+        {
+            let (hyper, ellip) = Coord::<P>::endgame::<PhEndgame>();
+            let (hyper_est, ellip_est) = Coord::<P>::endgame_estimate::<PhEndgame>();
+            let cases = [
+                (hyper, hyper_est, P - 1, FpNum::<P>::FACTORS, P - 2),
+                (ellip, ellip_est, P + 1, QuadNum::<P>::FACTORS, P),
+            ];
+            for (bound, est, n, factors, cap) in cases {
+                let (tau, phi) = (factors.tau(), factors.phi());
+                // Squaring both sides, $bound \cdot \phi(n) \geq 8\sqrt{p} \cdot n\tau(n)$.
+                assert!(bound == cap || (bound * phi).pow(2) >= 64 * P * (n * tau).pow(2));
+                assert!(bound >= est);
+                assert!(bound <= est + (n * tau).div_ceil(phi) + 1);
+            }
+        }
+
+        check::<13>();
+        check::<61>();
+        check::<1009>();
+        check::<3001>();
+        check::<7919>();
+        check::<65537>();
+    }
+
+    #[test]
+    fn parabola_orders() {
+        assert_eq!(
+            Coord::<13>::from(2).rot_order::<Ph13, Ph13>(),
+            RotOrder::Parabola(1)
+        );
+        assert_eq!(
+            Coord::<13>::from(11).rot_order::<Ph13, Ph13>(),
+            RotOrder::Parabola(2)
+        );
+        for a in 0..13 {
+            let ord = Coord::<13>::from(a).rot_order::<Ph13, Ph13>();
+            let parts: (Conic, u128) = ord.into();
+            assert_eq!(parts, (ord.conic(), ord.order()));
+            assert_eq!(RotOrder::from(parts), ord);
+        }
+    }
 }
@@ -1,5 +1,10 @@
 use either::{Either, Left, Right};
 use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /// A set of disjoint sets of instances of `K`.
 pub struct Disjoint<K> {
@@ -7,7 +12,32 @@ pub struct Disjoint<K> {
     orbits: HashSet<K>,
 }
 
-impl<K: Eq + Clone + std::hash::Hash> Disjoint<K> {
+/// A set of disjoint sets of the indices `0..n`, which may be merged from many threads at once.
+/// Unlike [`Disjoint`], it does not track the sizes of the sets, and its keys are fixed when it is
+/// created.
+///
+/// Each index points towards a lesser index of its set, so the representative of a set is always
+/// its least index.
+/// Sets are merged by swapping the parent of one representative with a compare-and-swap, and
+/// paths are halved as they are walked, so no locks are taken.
+pub struct ConcurrentDisjoint {
+    parents: Vec<AtomicUsize>,
+}
+
+/// A partition of a set of instances of `K` into disjoint sets, frozen from a [`Disjoint`] by
+/// [`Disjoint::into_partition`].
+/// Each set is identified by its index.
+/// The sets are ordered by their least elements, and the elements of each set are sorted, so the
+/// indices depend only on the partition, and not on the order in which sets were merged.
+///
+/// With the `serde` feature, a `Partition` serializes as its list of sets.
+#[derive(Clone, Debug)]
+pub struct Partition<K> {
+    sets: Vec<Vec<K>>,
+    index: HashMap<K, usize>,
+}
+
+impl<K: Eq + Clone + Hash> Disjoint<K> {
     /// Creates a new, empty set of disjoint sets.
     pub fn new() -> Disjoint<K> {
         Disjoint {
@@ -62,6 +92,31 @@ impl<K: Eq + Clone + std::hash::Hash> Disjoint<K> {
         }
     }
 
+    /// Returns the representative of the disjoint set containing `key`, and the size of that set,
+    /// or `None` if `key` was never added.
+    /// Two keys are in the same disjoint set if and only if they have the same representative.
+    pub fn find(&self, key: &K) -> Option<(K, u128)> {
+        self.root(key)
+    }
+
+    /// Freezes this set of disjoint sets into a [`Partition`].
+    pub fn into_partition(self) -> Partition<K>
+    where
+        K: Ord,
+    {
+        let mut sets = HashMap::<K, Vec<K>>::new();
+        for key in self.disjoint.keys() {
+            let (root, _) = self.root(key).unwrap();
+            sets.entry(root).or_default().push(key.clone());
+        }
+        let mut sets = sets.into_values().collect::<Vec<_>>();
+        for set in &mut sets {
+            set.sort();
+        }
+        sets.sort_by(|x, y| x[0].cmp(&y[0]));
+        Partition::from_sets(sets)
+    }
+
     fn root(&self, key: &K) -> Option<(K, u128)> {
         match self.disjoint.get(key) {
             None => None,
@@ -71,6 +126,128 @@ impl<K: Eq + Clone + std::hash::Hash> Disjoint<K> {
     }
 }
 
+impl ConcurrentDisjoint {
+    /// Creates a new set of disjoint sets, in which each of the indices `0..n` is a singleton.
+    pub fn new(n: usize) -> ConcurrentDisjoint {
+        ConcurrentDisjoint {
+            parents: (0..n).map(AtomicUsize::new).collect(),
+        }
+    }
+
+    /// Returns the representative of the disjoint set containing `i`, which is its least index.
+    /// While other threads are merging sets, the representative may change as soon as it is
+    /// returned.
+    /// This method will panic if `i` is out of bounds.
+    pub fn find(&self, mut i: usize) -> usize {
+        loop {
+            let parent = self.parents[i].load(Ordering::Acquire);
+            if parent == i {
+                return i;
+            }
+            let grandparent = self.parents[parent].load(Ordering::Acquire);
+            // Losing this race to another thread only leaves the path longer.
+            let _ = self.parents[i].compare_exchange_weak(
+                parent,
+                grandparent,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            );
+            i = grandparent;
+        }
+    }
+
+    /// Merges the disjoint sets containing `i` and `j`, returning true if they were different
+    /// sets.
+    /// This method will panic if `i` or `j` is out of bounds.
+    pub fn associate(&self, i: usize, j: usize) -> bool {
+        let (mut i, mut j) = (i, j);
+        loop {
+            i = self.find(i);
+            j = self.find(j);
+            if i == j {
+                return false;
+            }
+            let (lo, hi) = (usize::min(i, j), usize::max(i, j));
+            // This fails only if another thread has given `hi` a parent in the meantime.
+            if self.parents[hi]
+                .compare_exchange(hi, lo, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                return true;
+            }
+        }
+    }
+
+    /// Returns the number of indices.
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// True if there are no indices.
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+}
+
+impl<K: Eq + Clone + Hash> Partition<K> {
+    fn from_sets(sets: Vec<Vec<K>>) -> Partition<K> {
+        let index = sets
+            .iter()
+            .enumerate()
+            .flat_map(|(i, set)| set.iter().map(move |k| (k.clone(), i)))
+            .collect();
+        Partition { sets, index }
+    }
+
+    /// Returns the index of the set containing `key`, or `None` if `key` is in no set.
+    pub fn set_of(&self, key: &K) -> Option<usize> {
+        self.index.get(key).copied()
+    }
+
+    /// Returns the elements of the set with index `i`, in ascending order.
+    /// This method will panic if `i` is out of bounds.
+    pub fn set(&self, i: usize) -> &[K] {
+        &self.sets[i]
+    }
+
+    /// Returns an `Iterator` yielding the elements of each set, in order of their indices.
+    pub fn sets(&self) -> impl Iterator<Item = &[K]> {
+        self.sets.iter().map(Vec::as_slice)
+    }
+
+    /// Returns the number of sets.
+    pub fn len(&self) -> usize {
+        self.sets.len()
+    }
+
+    /// True if there are no sets.
+    pub fn is_empty(&self) -> bool {
+        self.sets.is_empty()
+    }
+}
+
+impl<K: PartialEq> PartialEq for Partition<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.sets == other.sets
+    }
+}
+
+impl<K: Eq> Eq for Partition<K> {}
+
+#[cfg(feature = "serde")]
+impl<K: Serialize> Serialize for Partition<K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.sets.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, K: Deserialize<'de> + Eq + Clone + Hash> Deserialize<'de> for Partition<K> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<Vec<K>>::deserialize(deserializer).map(Partition::from_sets)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,4 +262,58 @@ mod tests {
         let orbits: Vec<(&u32, u128)> = disjoint.get_sets().collect();
         assert_eq!(orbits.len(), 2);
     }
+
+    #[test]
+    fn test_concurrent_assoc() {
+        use rayon::prelude::*;
+
+        let disjoint = ConcurrentDisjoint::new(1000);
+        // Joins each index to the others with the same tens, and the first ten of each hundred
+        // to 0, leaving 91 sets.
+        let merges = (0..1000usize)
+            .into_par_iter()
+            .map(|i| {
+                let mut res = usize::from(disjoint.associate(i, i - i % 10));
+                if i % 100 == 0 {
+                    res += usize::from(disjoint.associate(i, 0));
+                }
+                res
+            })
+            .sum::<usize>();
+        assert_eq!(merges, 1000 - 91);
+        assert_eq!(disjoint.len(), 1000);
+        assert_eq!((0..1000).filter(|i| disjoint.find(*i) == *i).count(), 91);
+        assert_eq!(disjoint.find(305), 0);
+        assert_eq!(disjoint.find(999), 990);
+        assert!(!disjoint.associate(999, 990));
+        assert!(disjoint.associate(999, 5));
+        assert_eq!(disjoint.find(995), 0);
+    }
+
+    #[test]
+    fn test_into_partition() {
+        let mut disjoint: Disjoint<u32> = Disjoint::new();
+        for (x, y) in [(9, 4), (1, 2), (6, 7), (2, 3), (4, 5), (6, 2), (10, 10)] {
+            disjoint.associate(x, y);
+        }
+        let partition = disjoint.into_partition();
+        assert_eq!(partition.len(), 3);
+        assert_eq!(partition.set(0), [1, 2, 3, 6, 7]);
+        assert_eq!(partition.set(1), [4, 5, 9]);
+        assert_eq!(partition.set(2), [10]);
+        assert_eq!(partition.set_of(&7), Some(0));
+        assert_eq!(partition.set_of(&9), Some(1));
+        assert_eq!(partition.set_of(&8), None);
+        assert_eq!(partition.sets().map(<[u32]>::len).sum::<usize>(), 9);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&partition).unwrap();
+            assert_eq!(json, "[[1,2,3,6,7],[4,5,9],[10]]");
+            assert_eq!(
+                serde_json::from_str::<Partition<u32>>(&json).unwrap(),
+                partition
+            );
+        }
+    }
 }
@@ -0,0 +1,97 @@
+use rayon::prelude::*;
+
+use crate::markoff::*;
+use crate::numbers::*;
+
+/// Returns every nonzero Markoff triple modulo `P` with first coordinate `a`.
+/// These triples are the points $(b, c)$ of the fiber over $a$, the conic
+/// $b^2 + c^2 - abc = -a^2$, which is the union of the orbits of $\text{rot}\_a$; the fiber is
+/// walked one value of $b$ at a time, so that no factorization of $p \pm 1$ is needed.
+/// Triples are yielded in order of $b$.
+///
+/// See [`par_enumerate_with_coordinate`] for a parallel version.
+pub fn enumerate_with_coordinate<const P: u128>(a: Coord<P>) -> impl Iterator<Item = Triple<P>> {
+    (0..P).flat_map(move |b| fiber_at(a.0, FpNum::from(b)))
+}
+
+/// Returns every nonzero Markoff triple modulo `P` with first coordinate `a`, as
+/// [`enumerate_with_coordinate`] does, but splitting the fiber between `rayon` threads.
+/// The triples are yielded in no particular order.
+pub fn par_enumerate_with_coordinate<const P: u128>(
+    a: Coord<P>,
+) -> impl ParallelIterator<Item = Triple<P>> {
+    (0..P)
+        .into_par_iter()
+        .flat_map_iter(move |b| fiber_at(a.0, FpNum::from(b)))
+}
+
+/// Returns every Markoff triple modulo `P` with some coordinate of $\pm 2$, exactly once.
+/// These are found on the fibers over the parabolic coordinates, on which $(b - c)^2 = -4$ or
+/// $(b + c)^2 = -4$, so there are none unless $p \equiv 1 \pmod 4$.
+/// See [`Parabolic`].
+pub fn parabolic_triples<const P: u128>() -> impl Iterator<Item = Triple<P>> {
+    let fibers = || {
+        Coord::<P>::PARABOLIC
+            .into_iter()
+            .flat_map(enumerate_with_coordinate)
+    };
+    // A triple is yielded only for the first position holding a parabolic coordinate.
+    let first = fibers();
+    let second = fibers()
+        .filter(|t| !Coord(t.b()).is_parabolic())
+        .map(|t| Triple::new_unchecked(t.b(), t.a(), t.c()));
+    let third = fibers()
+        .filter(|t| !Coord(t.b()).is_parabolic() && !Coord(t.c()).is_parabolic())
+        .map(|t| Triple::new_unchecked(t.b(), t.c(), t.a()));
+    first.chain(second).chain(third)
+}
+
+// Returns the nonzero triples $(a, b, c)$ for fixed $a$ and $b$, of which there are at most two.
+fn fiber_at<const P: u128>(a: FpNum<P>, b: FpNum<P>) -> impl Iterator<Item = Triple<P>> {
+    Surface::MARKOFF
+        .thirds(a, b)
+        .into_iter()
+        .map(move |c| Triple::new_unchecked(a, b, c))
+        .filter(|t| Degenerate::ExcludeZero.admits(t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slices_partition_triples() {
+        let key = |t: &Triple<13>| (u128::from(t.b()), u128::from(t.c()));
+        let mut total = 0;
+        for a in 0..13 {
+            let mut slice = enumerate_with_coordinate(Coord::<13>::from(a)).collect::<Vec<_>>();
+            assert!(slice
+                .iter()
+                .all(|t| t.a() == FpNum::from(a) && Triple::new(t.a(), t.b(), t.c()).is_some()));
+            let mut par = par_enumerate_with_coordinate(Coord::<13>::from(a)).collect::<Vec<_>>();
+            slice.sort_by_key(key);
+            par.sort_by_key(key);
+            assert_eq!(par, slice);
+            total += slice.len();
+        }
+        assert_eq!(total, census::triples::<13>().count());
+        // With $a = 0$, the fiber is $b = \pm 5 c$, since $5^2 = -1$ modulo 13.
+        assert_eq!(enumerate_with_coordinate(Coord::<13>::from(0)).count(), 24);
+    }
+
+    #[test]
+    fn finds_parabolic_triples() {
+        let mut found = parabolic_triples::<13>().collect::<Vec<_>>();
+        let mut expected = census::triples::<13>()
+            .filter(|t| t.is_parabolic())
+            .collect::<Vec<_>>();
+        found.sort_by_key(census::encode);
+        expected.sort_by_key(census::encode);
+        assert_eq!(found, expected);
+        // Each parabolic fiber holds $2p$ triples.
+        for a in Coord::<13>::PARABOLIC {
+            assert_eq!(enumerate_with_coordinate(a).count(), 26);
+        }
+        assert_eq!(parabolic_triples::<23>().count(), 0);
+    }
+}
@@ -4,17 +4,40 @@ use std::thread;
 use itertools::*;
 use rayon::prelude::*;
 
-use crate::markoff::Disjoint;
-use crate::numbers::{FpNum, GroupElem};
+use crate::markoff::{Coord, Disjoint, Surface, Triple};
+use crate::numbers::FpNum;
 
 /// Configures tests to be run on orbits of the Markoff graph modulo `P`.
+/// By default, the orbits are those of the surface $x^2 + y^2 + z^2 = xyz$; see
+/// [`OrbitTester::with_surface`] to test another [`Surface`].
 pub struct OrbitTester<const P: u128> {
+    surface: Surface<P>,
     targets: HashSet<u128>,
+    // The targets which have been processed by a previous run, and the results of those runs.
+    done: HashSet<u128>,
+    results: OrbitTesterResults,
 }
 
 /// The results of a successfully run `OrbitTester`.
 pub struct OrbitTesterResults {
     results: HashMap<u128, Disjoint<u128>>,
+    associations: u128,
+}
+
+/// Summary statistics of the results of an `OrbitTester`.
+/// See [`OrbitTesterResults::summary`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OrbitTesterSummary {
+    /// The number of targets tested.
+    pub targets: usize,
+    /// The number of targets with no representative, that is, with no disjoint sets.
+    pub repless: usize,
+    /// The size of the largest disjoint set of any target.
+    pub largest_set: u128,
+    /// The number of disjoint sets, over every target, containing a single element.
+    pub singletons: usize,
+    /// The total number of associations made between elements while running the test.
+    pub associations: u128,
 }
 
 type Msg = (u128, u128, u128);
@@ -23,76 +46,141 @@ impl<const P: u128> OrbitTester<P> {
     /// Consume and run this `OrbitTester`, blocking until completion, and returning the results.
     /// This method may spawn multiple worker threads, which are guarenteed to be joined before
     /// `run` returns.
-    pub fn run(self) -> OrbitTesterResults {
-        let mut results = HashMap::with_capacity(self.targets.len());
-        for x in &self.targets {
+    pub fn run(mut self) -> OrbitTesterResults {
+        self.run_incremental();
+        self.results
+    }
+
+    /// Runs this `OrbitTester` on the targets added since the last run, blocking until
+    /// completion, and returns the results for every target so far.
+    /// Only the pairs of coordinates involving a new target are processed, so growing the set of
+    /// targets gradually costs no more than testing every target at once.
+    /// The results are the same as those of [`OrbitTester::run`] on every target.
+    pub fn run_incremental(&mut self) -> &OrbitTesterResults {
+        let mut new = self
+            .targets
+            .difference(&self.done)
+            .copied()
+            .collect::<Vec<_>>();
+        new.sort_unstable();
+        let mut old = self.done.iter().copied().collect::<Vec<_>>();
+        old.sort_unstable();
+
+        let mut results = std::mem::take(&mut self.results.results);
+        for x in &new {
             results.insert(*x, Disjoint::new());
         }
-
-        let mut inv2 = FpNum::<P>::from(2);
-        inv2 = inv2.inverse();
+        let associations = self.results.associations;
+        let done = &self.done;
+        let surface = self.surface;
 
         let (tx, rx) = std::sync::mpsc::sync_channel::<Msg>(1024);
 
         let handle = thread::spawn(move || {
+            let mut associations = associations;
             for (x, y, z) in rx.iter() {
                 if results.contains_key(&z) {
                     if let Some(disjoint) = results.get_mut(&x) {
                         disjoint.associate(y, y);
+                        associations += 1;
                     }
                     if let Some(disjoint) = results.get_mut(&y) {
                         disjoint.associate(x, z);
+                        associations += 1;
                     }
                 }
             }
 
-            results
+            (results, associations)
         });
 
-        self.targets
-            .iter()
+        // Pairs of new targets, and pairs of an old and a new target, each ordered so that the
+        // smaller coordinate comes first.
+        new.iter()
             .combinations_with_replacement(2)
-            .map(|v| (v[0], v[1]))
+            .map(|v| (*v[0], *v[1], false))
+            .chain(
+                old.iter()
+                    .cartesian_product(new.iter())
+                    .map(|(x, y)| (*x.min(y), *x.max(y), true)),
+            )
             .par_bridge()
-            .for_each(|(x, y)| {
-                let x = FpNum::from(*x);
-                let y = FpNum::from(*y);
-
-                // We use the non-normalized equation: x^2 + y^2 + z^2 - xyz = 0
-                let disc = x * y - 4 * (x * x + y * y);
-                let neg_b = x * y;
-
-                match disc.int_sqrt().map(u128::from) {
-                    Some(0) => {
-                        let z = neg_b * inv2;
-                        _ = tx.send((u128::from(x), u128::from(y), u128::from(z)));
+            .for_each(|(x, y, mixed)| {
+                for z in Self::thirds(&surface, x, y) {
+                    _ = tx.send((x, y, z));
+                    // A pair of old targets with a new third coordinate was not processed by any
+                    // previous run. Each such triple is found here from the pair of its smaller
+                    // old coordinate with its new coordinate.
+                    if mixed && done.contains(&z) {
+                        let (a, n) = if done.contains(&x) { (x, y) } else { (y, x) };
+                        if a <= z {
+                            _ = tx.send((a, z, n));
+                        }
                     }
-                    Some(root_disc) => {
-                        let z = (neg_b + FpNum::from(root_disc)) * inv2;
-                        _ = tx.send((u128::from(x), u128::from(y), u128::from(z)));
-                        let z = (neg_b - FpNum::from(root_disc)) * inv2;
-                        _ = tx.send((u128::from(x), u128::from(y), u128::from(z)));
-                    }
-                    None => {}
                 }
             });
         drop(tx);
 
-        let results = handle.join().unwrap();
+        let (results, associations) = handle.join().unwrap();
+        self.results = OrbitTesterResults {
+            results,
+            associations,
+        };
+        self.done.extend(new);
+        &self.results
+    }
 
-        OrbitTesterResults { results }
+    // Returns the coordinates `z` such that `(x, y, z)` lies on the surface.
+    fn thirds(surface: &Surface<P>, x: u128, y: u128) -> Vec<u128> {
+        surface
+            .thirds(FpNum::from(x), FpNum::from(y))
+            .into_iter()
+            .map(u128::from)
+            .collect()
     }
 
     /// Creates a new `OrbetTester` with default settings and no targets.
     pub fn new() -> OrbitTester<P> {
         OrbitTester {
+            surface: Surface::MARKOFF,
             targets: HashSet::new(),
+            done: HashSet::new(),
+            results: OrbitTesterResults {
+                results: HashMap::new(),
+                associations: 0,
+            },
         }
     }
 
-    /// Adds a target order to the list of orders to be tested.
-    pub fn add_target(&mut self, t: u128) {
-        self.targets.insert(t);
+    /// Sets the surface whose orbits are tested.
+    /// To compare against data recorded on another surface, such as the classical normalization
+    /// $x^2 + y^2 + z^2 = 3xyz$, either test that surface here, or carry the data's coordinates
+    /// onto this surface with [`Surface::scale_to`].
+    /// Panics if called after the tester has been run.
+    pub fn with_surface(mut self, surface: Surface<P>) -> OrbitTester<P> {
+        assert!(
+            self.done.is_empty(),
+            "Cannot change the surface of a tester which has been run."
+        );
+        self.surface = surface;
+        self
+    }
+
+    /// Returns the surface whose orbits are tested.
+    pub fn surface(&self) -> Surface<P> {
+        self.surface
+    }
+
+    /// Adds a target coordinate to the list of coordinates to be tested.
+    pub fn add_target(&mut self, t: impl Into<Coord<P>>) {
+        self.targets.insert(u128::from(t.into()));
+    }
+
+    /// Adds every coordinate of the triple `t` to the list of coordinates to be tested.
+    pub fn add_triple(&mut self, t: &Triple<P>) {
+        for x in [t.a(), t.b(), t.c()] {
+            self.add_target(x);
+        }
     }
 }
 
@@ -103,4 +191,137 @@ impl OrbitTesterResults {
     pub fn results(&self) -> impl Iterator<Item = (&u128, &Disjoint<u128>)> {
         self.results.iter()
     }
+
+    /// Returns summary statistics of the results.
+    pub fn summary(&self) -> OrbitTesterSummary {
+        let mut res = OrbitTesterSummary {
+            targets: self.results.len(),
+            associations: self.associations,
+            ..OrbitTesterSummary::default()
+        };
+        for disjoint in self.results.values() {
+            let mut sets = disjoint.get_sets().peekable();
+            if sets.peek().is_none() {
+                res.repless += 1;
+            }
+            for (_, size) in sets {
+                res.largest_set = res.largest_set.max(size);
+                if size == 1 {
+                    res.singletons += 1;
+                }
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes() {
+        let mut tester = OrbitTester::<13>::new();
+        for x in 0..13 {
+            tester.add_target(x);
+        }
+        let results = tester.run();
+        let summary = results.summary();
+        assert_eq!(summary.targets, 13);
+        assert!(summary.associations > 0);
+
+        let mut repless = 0;
+        let mut largest = 0;
+        for (_, disjoint) in results.results() {
+            let sizes = disjoint.get_sets().map(|(_, d)| d).collect::<Vec<_>>();
+            repless += sizes.is_empty() as usize;
+            largest = sizes.into_iter().fold(largest, u128::max);
+        }
+        assert_eq!(summary.repless, repless);
+        assert_eq!(summary.largest_set, largest);
+        assert!(summary.largest_set <= 13);
+    }
+
+    #[test]
+    fn accepts_coords_and_triples() {
+        let mut tester = OrbitTester::<13>::new();
+        tester.add_target(Coord::<13>::from(5));
+        tester.add_target(FpNum::<13>::from(5));
+        tester.add_triple(&Triple::new_unchecked(
+            FpNum::from(3),
+            FpNum::from(3),
+            FpNum::from(3),
+        ));
+        tester.add_target(3);
+        assert_eq!(tester.run().summary().targets, 2);
+    }
+
+    fn partitions(results: &OrbitTesterResults) -> Vec<(u128, Vec<u128>)> {
+        let mut res = results
+            .results()
+            .map(|(x, disjoint)| {
+                let mut sizes = disjoint.get_sets().map(|(_, d)| d).collect::<Vec<_>>();
+                sizes.sort();
+                (*x, sizes)
+            })
+            .collect::<Vec<_>>();
+        res.sort();
+        res
+    }
+
+    #[test]
+    fn tests_other_surfaces() {
+        for surface in [Surface::MARKOFF, Surface::CLASSICAL] {
+            let mut tester = OrbitTester::<23>::new().with_surface(surface);
+            assert_eq!(tester.surface(), surface);
+            for x in 0..23 {
+                tester.add_target(x);
+            }
+            // Every target is in the results, so each point $(x, y, z)$ with $x \le y$ makes two
+            // associations.
+            let mut points = 0;
+            for x in 0..23 {
+                for y in x..23 {
+                    points += (0..23)
+                        .filter(|z| surface.contains(x.into(), y.into(), FpNum::from(*z)))
+                        .count() as u128;
+                }
+            }
+            assert_eq!(tester.run().summary().associations, 2 * points);
+        }
+    }
+
+    #[test]
+    fn markoff_discriminant() {
+        // The tester once took the discriminant of $z^2 - xyz + x^2 + y^2$ to be
+        // $xy - 4(x^2 + y^2)$, rather than $(xy)^2 - 4(x^2 + y^2)$. Modulo 13, the old formula
+        // gives the nonresidue 8 for $(x, y) = (1, 2)$, missing the points $(1, 2, 4)$ and
+        // $(1, 2, 11)$.
+        let (x, y) = (FpNum::<13>::from(1), FpNum::from(2));
+        let mut thirds = OrbitTester::<13>::thirds(&Surface::MARKOFF, 1, 2);
+        thirds.sort();
+        assert_eq!(thirds, [4, 11]);
+        assert!(thirds
+            .into_iter()
+            .all(|z| Surface::MARKOFF.contains(x, y, FpNum::from(z))));
+    }
+
+    #[test]
+    fn incremental_matches_full() {
+        let mut full = OrbitTester::<23>::new();
+        let mut incremental = OrbitTester::<23>::new();
+        for x in 0..23 {
+            full.add_target(x);
+        }
+        for batch in [0..5, 5..6, 6..17, 17..23] {
+            for x in batch {
+                incremental.add_target(x);
+            }
+            incremental.run_incremental();
+        }
+        let full = full.run();
+        let incremental = incremental.run();
+        assert_eq!(partitions(&incremental), partitions(&full));
+        assert_eq!(incremental.summary(), full.summary());
+    }
 }
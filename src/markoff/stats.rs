@@ -0,0 +1,138 @@
+//! Statistics about the distribution of coordinates and orbits modulo a prime.
+use std::collections::BTreeMap;
+
+use crate::markoff::*;
+use crate::numbers::*;
+use crate::streams::DivisorStream;
+use libbgs_util::intpow;
+
+/// The number of coordinates $a \in \mathbb{F}\_p$ whose rotation map has each order.
+/// See [`Coord::rot_order`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OrderHistogram {
+    /// Pairs $(d, n)$ such that exactly $n$ coordinates have order `RotOrder::Hyperbola(d)`,
+    /// sorted by $d$.
+    pub hyperbola: Vec<(u128, u128)>,
+    /// Pairs $(d, n)$ such that exactly $n$ coordinates have order `RotOrder::Ellipse(d)`,
+    /// sorted by $d$.
+    pub ellipse: Vec<(u128, u128)>,
+    /// Pairs $(d, n)$ such that exactly $n$ coordinates have order `RotOrder::Parabola(d)`,
+    /// sorted by $d$: the coordinate 2, with $d = 1$ and a rotation of order $p$, and the
+    /// coordinate $-2$, with $d = 2$ and a rotation of order $2p$.
+    pub parabola: Vec<(u128, u128)>,
+}
+
+impl OrderHistogram {
+    /// Returns the number of coordinates with rotation order `order`.
+    pub fn get(&self, order: &RotOrder) -> u128 {
+        fn find(v: &[(u128, u128)], d: u128) -> u128 {
+            v.binary_search_by_key(&d, |(d, _)| *d)
+                .map_or(0, |i| v[i].1)
+        }
+        match order {
+            RotOrder::Hyperbola(d) => find(&self.hyperbola, *d),
+            RotOrder::Ellipse(d) => find(&self.ellipse, *d),
+            RotOrder::Parabola(d) => find(&self.parabola, *d),
+        }
+    }
+
+    /// Returns the total number of coordinates counted by this histogram.
+    pub fn total(&self) -> u128 {
+        self.hyperbola
+            .iter()
+            .chain(self.ellipse.iter())
+            .chain(self.parabola.iter())
+            .map(|(_, n)| n)
+            .sum()
+    }
+}
+
+/// Returns the histogram of rotation orders of the coordinates in $\mathbb{F}\_p$.
+/// The counts are computed analytically: the coordinates with rotation order $d > 2$ are
+/// $\chi + \chi^{-1}$ for the $\phi(d)$ elements $\chi$ of order $d$, identifying $\chi$ with
+/// $\chi^{-1}$, so there are $\phi(d) / 2$ of them.
+/// The two remaining coordinates, $\pm 2$, are parabolic.
+pub fn order_histogram<S, const P: u128>() -> OrderHistogram
+where
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    fn counts(factors: &'static [(u128, usize)], n: u128) -> Vec<(u128, u128)> {
+        let mut res = DivisorStream::new(factors, n, false)
+            .map(|ds| {
+                ds.iter().zip(factors).filter(|(d, _)| **d > 0).fold(
+                    (1, 1),
+                    |(d, phi), (e, (p, _))| {
+                        let pe = intpow::<0>(*p, (*e - 1) as u128);
+                        (d * pe * p, phi * pe * (p - 1))
+                    },
+                )
+            })
+            .filter(|(d, _)| *d > 2)
+            .map(|(d, phi)| (d, phi / 2))
+            .collect::<Vec<_>>();
+        res.sort();
+        res
+    }
+
+    OrderHistogram {
+        hyperbola: counts(FpNum::<P>::FACTORS.factors(), P - 1),
+        ellipse: counts(QuadNum::<P>::FACTORS.factors(), P + 1),
+        parabola: vec![(1, 1), (2, 1)],
+    }
+}
+
+/// Returns the histogram of rotation orders of the coordinates in $\mathbb{F}\_p$, by computing
+/// the rotation order of every coordinate.
+/// This takes time linear in `P`, and is intended for cross-checking [`order_histogram`].
+pub fn order_histogram_empirical<S, const P: u128>() -> OrderHistogram
+where
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    let mut hyperbola = BTreeMap::new();
+    let mut ellipse = BTreeMap::new();
+    let mut parabola = BTreeMap::new();
+    for a in 0..P {
+        match Coord::<P>::from(a).rot_order::<S, S>() {
+            RotOrder::Hyperbola(d) => *hyperbola.entry(d).or_insert(0) += 1,
+            RotOrder::Ellipse(d) => *ellipse.entry(d).or_insert(0) += 1,
+            RotOrder::Parabola(d) => *parabola.entry(d).or_insert(0) += 1,
+        }
+    }
+    OrderHistogram {
+        hyperbola: hyperbola.into_iter().collect(),
+        ellipse: ellipse.into_iter().collect(),
+        parabola: parabola.into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Eq)]
+    struct Ph {}
+
+    impl_factors!(Ph, 7, 13, 3001);
+
+    #[test]
+    fn analytic_matches_empirical() {
+        assert_eq!(
+            order_histogram::<Ph, 7>(),
+            order_histogram_empirical::<Ph, 7>()
+        );
+        assert_eq!(
+            order_histogram::<Ph, 13>(),
+            order_histogram_empirical::<Ph, 13>()
+        );
+        let hist = order_histogram::<Ph, 3001>();
+        assert_eq!(hist, order_histogram_empirical::<Ph, 3001>());
+        assert_eq!(hist.total(), 3001);
+        assert_eq!(hist.get(&RotOrder::Hyperbola(3000)), 400);
+        assert_eq!(hist.get(&RotOrder::Ellipse(7)), 0);
+        assert_eq!(hist.get(&RotOrder::Parabola(1)), 1);
+        assert_eq!(hist.get(&RotOrder::Parabola(2)), 1);
+        assert_eq!(hist.get(&RotOrder::Parabola(3)), 0);
+    }
+}
@@ -0,0 +1,146 @@
+//! Constructions used by the search strategy of the Bourgain, Gamburd, and Sarnak algorithm.
+use crate::markoff::census::{self, Census};
+use crate::markoff::*;
+use crate::numbers::*;
+use libbgs_util::*;
+
+/// Returns an element $m \in \mathbb{F}\_{p^2}$ of norm $-1$, that is, with $m^{p + 1} = -1$.
+/// Multiplying by $m$ permutes the cosets of the norm 1 subgroup so that, for every $s$ of norm
+/// 1, $s m + (s m)^{-1}$ has no component in the prime subfield; that is, it is $x \sqrt{r}$ for
+/// some $x \in \mathbb{F}\_p$.
+///
+/// The element is found with order exactly $2^{v + 1}$, where $v$ is the 2-adic valuation of
+/// $p + 1$. When $v = 1$, it is further multiplied by the generator of the Sylow subgroup of the
+/// norm 1 subgroup belonging to the second prime factor of $p + 1$.
+pub fn coset_twist<S, const P: u128>() -> QuadNum<P>
+where
+    QuadNum<P>: Factor<S>,
+{
+    coset_twist_with::<S, P>(Seed::DEFAULT)
+}
+
+/// Returns an element of norm $-1$, searching in the order given by `seed`.
+/// See [`coset_twist`].
+pub fn coset_twist_with<S, const P: u128>(seed: Seed) -> QuadNum<P>
+where
+    QuadNum<P>: Factor<S>,
+{
+    let twos = (QuadNum::<P>::FACTORS[0].1 + 1) as u128;
+    let magic = (1..P * P)
+        .map(|i| {
+            let j = seed.shift(P * 2, i);
+            QuadNum::<P>::steinitz(j)
+        })
+        .filter(|c| *c != QuadNum::ZERO)
+        .find_map(|c| {
+            let pow = (P * P - 1) / intpow::<0>(2, twos);
+            let res = c.pow(pow);
+            if res.pow(intpow::<0>(2, twos - 1)) == QuadNum::ONE {
+                None
+            } else {
+                Some(res)
+            }
+        })
+        .unwrap();
+    if QuadNum::<P>::FACTORS[0].1 == 1 {
+        magic * <QuadNum<P> as SylowDecomposable<S>>::find_sylow_generator_with(1, seed)
+    } else {
+        magic
+    }
+}
+
+/// Returns the connected components of the subgraph of the Markoff graph modulo `P` induced by
+/// the triples whose coordinates all have rotation order greater than `bound`, as given by
+/// [`RotOrder::order`], so that the parabolic coordinates $2$ and $-2$ count as orders 1 and 2.
+/// This is the complement of the cage of triples with some coordinate of order at most `bound`.
+/// Every triple is visited, so this is only feasible for small primes.
+pub fn cage_complement_components<S, const P: u128>(bound: u128) -> Census<P>
+where
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    let large = (0..P)
+        .map(|a| Coord::<P>::from(a).rot_order::<S, S>().order() > bound)
+        .collect::<Vec<_>>();
+    census::induced_census(|t: &Triple<P>| {
+        [t.a(), t.b(), t.c()]
+            .into_iter()
+            .all(|x| large[u128::from(x) as usize])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Eq)]
+    struct Ph {}
+
+    // Two primes from each residue class modulo 8.
+    impl_factors!(Ph, 17, 41, 11, 19, 13, 29, 7, 23);
+
+    fn check<const P: u128>()
+    where
+        QuadNum<P>: Factor<Ph>,
+    {
+        let m = coset_twist::<Ph, P>();
+        let minus_one = QuadNum::from((P - 1, 0));
+        assert_eq!(m.pow(P + 1), minus_one);
+        assert_eq!(norm(m), -FpNum::ONE);
+        if QuadNum::<P>::FACTORS[0].1 > 1 {
+            let twos = QuadNum::<P>::FACTORS[0].1 as u128 + 1;
+            assert_eq!(m.pow(intpow::<0>(2, twos - 1)), minus_one);
+        }
+        for s in Norm1::<P>::all() {
+            let fix = s.get() * m;
+            assert_eq!(trace(fix + fix.inverse()), FpNum::ZERO, "P = {P}");
+        }
+    }
+
+    #[test]
+    fn twist_one_mod_eight() {
+        check::<17>();
+        check::<41>();
+    }
+
+    #[test]
+    fn twist_three_mod_eight() {
+        check::<11>();
+        check::<19>();
+    }
+
+    #[test]
+    fn twist_five_mod_eight() {
+        check::<13>();
+        check::<29>();
+    }
+
+    #[test]
+    fn twist_seven_mod_eight() {
+        check::<7>();
+        check::<23>();
+    }
+
+    #[test]
+    fn cage_complement() {
+        // With no cage, the complement is the whole graph.
+        let all = cage_complement_components::<Ph, 13>(0);
+        assert_eq!(all, census::component_census::<13>());
+        // With the whole graph caged, the complement is empty.
+        assert!(cage_complement_components::<Ph, 13>(14)
+            .components
+            .is_empty());
+
+        let some = cage_complement_components::<Ph, 13>(6);
+        assert!(some.triples() < all.triples());
+        for c in some.components {
+            let t = c.representative;
+            for x in [t.a(), t.b(), t.c()] {
+                assert!(match Coord(x).rot_order::<Ph, Ph>() {
+                    RotOrder::Hyperbola(d) | RotOrder::Ellipse(d) => d > 6,
+                    RotOrder::Parabola(_) => false,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::numbers::*;
+
+/// A Markoff-type surface $x^2 + y^2 + z^2 = kxyz + m$ modulo `P`.
+/// The rest of this library works on the surface $x^2 + y^2 + z^2 = xyz$, which is
+/// [`Surface::MARKOFF`]; older datasets use the classical normalization
+/// $x^2 + y^2 + z^2 = 3xyz$, which is [`Surface::CLASSICAL`].
+///
+/// Scaling every coordinate by $\lambda \neq 0$ carries the surface with coefficients $(k, m)$ to
+/// the surface with coefficients $(k / \lambda, \lambda^2 m)$; see [`Surface::scale_to`].
+/// ```
+/// use libbgs::markoff::Surface;
+/// use libbgs::numbers::FpNum;
+///
+/// let lambda = Surface::<13>::CLASSICAL.scale_to(&Surface::MARKOFF).unwrap();
+/// assert_eq!(lambda, FpNum::from(3));
+/// // $(1, 1, 1)$ lies on the classical surface, and $(3, 3, 3)$ on the Markoff surface.
+/// let one = FpNum::from(1);
+/// assert!(Surface::<13>::CLASSICAL.contains(one, one, one));
+/// assert!(Surface::<13>::MARKOFF.contains(lambda * one, lambda * one, lambda * one));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Surface<const P: u128> {
+    /// The coefficient $k$ of $xyz$.
+    pub k: FpNum<P>,
+    /// The constant term $m$.
+    pub m: FpNum<P>,
+}
+
+impl<const P: u128> Surface<P> {
+    /// The surface $x^2 + y^2 + z^2 = xyz$, on which [`crate::markoff::Triple`]s lie.
+    pub const MARKOFF: Surface<P> = Surface::new(FpNum::from_u128(1), FpNum::from_u128(0));
+
+    /// The classically normalized Markoff surface $x^2 + y^2 + z^2 = 3xyz$.
+    pub const CLASSICAL: Surface<P> = Surface::new(FpNum::from_u128(3), FpNum::from_u128(0));
+
+    /// Returns the surface $x^2 + y^2 + z^2 = kxyz + m$.
+    pub const fn new(k: FpNum<P>, m: FpNum<P>) -> Surface<P> {
+        Surface { k, m }
+    }
+
+    /// True if $(x, y, z)$ lies on this surface.
+    pub fn contains(&self, x: FpNum<P>, y: FpNum<P>, z: FpNum<P>) -> bool {
+        x * x + y * y + z * z == self.k * x * y * z + self.m
+    }
+
+    /// Returns the coordinates $z$ such that $(x, y, z)$ lies on this surface, of which there are
+    /// at most two.
+    /// These are the roots of $z^2 - kxy z + (x^2 + y^2 - m)$; when there are two, they are
+    /// exchanged by the Vieta involution $z \mapsto kxy - z$.
+    pub fn thirds(&self, x: FpNum<P>, y: FpNum<P>) -> Vec<FpNum<P>> {
+        let neg_b = self.k * x * y;
+        let disc = neg_b * neg_b - 4 * (x * x + y * y - self.m);
+        match disc.int_sqrt() {
+            None => Vec::new(),
+            Some(root) if root == FpNum::ZERO => vec![neg_b * FpNum::TWO_INV],
+            Some(root) => vec![
+                (neg_b + root) * FpNum::TWO_INV,
+                (neg_b - root) * FpNum::TWO_INV,
+            ],
+        }
+    }
+
+    /// Returns the factor $\lambda$ such that $(x, y, z) \mapsto (\lambda x, \lambda y, \lambda z)$
+    /// carries this surface onto `other`, or `None` if there is no such factor.
+    /// Coordinates on `other` are carried back to this surface by $\lambda^{-1}$.
+    /// Surfaces with $k = 0$ are only related by scaling if their constant terms are also
+    /// related, and then $\lambda$ is not unique; `None` is returned for them.
+    pub fn scale_to(&self, other: &Surface<P>) -> Option<FpNum<P>> {
+        if self.k == FpNum::ZERO || other.k == FpNum::ZERO {
+            return None;
+        }
+        let lambda = self.k * other.k.inverse();
+        (lambda * lambda * self.m == other.m).then_some(lambda)
+    }
+}
+
+impl<const P: u128> Default for Surface<P> {
+    fn default() -> Surface<P> {
+        Surface::MARKOFF
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thirds_lie_on_surface() {
+        let surfaces = [
+            Surface::<23>::MARKOFF,
+            Surface::CLASSICAL,
+            Surface::new(FpNum::from(5), FpNum::from(7)),
+        ];
+        for s in surfaces {
+            let mut count = 0;
+            for x in 0..23 {
+                for y in 0..23 {
+                    let (x, y) = (FpNum::from(x), FpNum::from(y));
+                    let zs = s.thirds(x, y);
+                    assert!(zs.iter().all(|z| s.contains(x, y, *z)));
+                    let brute = (0..23).filter(|z| s.contains(x, y, FpNum::from(*z)));
+                    assert_eq!(zs.len(), brute.count());
+                    count += zs.len();
+                }
+            }
+            assert!(count > 0);
+        }
+    }
+
+    #[test]
+    fn scales_between_surfaces() {
+        let twisted = Surface::<23>::new(FpNum::from(5), FpNum::from(7));
+        let lambda = twisted
+            .scale_to(&Surface::new(FpNum::from(10), FpNum::from(19)))
+            .unwrap();
+        // $\lambda = 5 / 10 = 1/2$, and $7 / 4 = 19$ modulo 23.
+        assert_eq!(lambda, FpNum::TWO_INV);
+        assert_eq!(twisted.scale_to(&Surface::MARKOFF), None);
+        assert_eq!(
+            Surface::<23>::MARKOFF.scale_to(&Surface::CLASSICAL),
+            Some(FpNum::from(3).inverse())
+        );
+    }
+}
@@ -1,10 +1,59 @@
-use crate::numbers::FpNum;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::markoff::{bfs, Conic, Coord, RotOrder, VisitedSet};
+use crate::numbers::{Factor, FpNum, GroupElem, QuadNum};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// A Markoff triple modulo `P`.
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct Triple<const P: u128>(FpNum<P>, FpNum<P>, FpNum<P>);
 
+/// A cache of the representatives given by [`Triple::canonical_in_orbit`].
+/// The cache remembers the representative of every canonical form walked, and stops each walk as
+/// soon as it meets one of them, so that no canonical form is walked twice, however many triples
+/// are looked up.
+/// It holds an entry for each canonical form walked, of which there are about $p^2 / 24$.
+#[derive(Clone, Debug, Default)]
+pub struct OrbitCache<const P: u128> {
+    reps: HashMap<Triple<P>, Triple<P>>,
+}
+
+/// Which degenerate triples, those with a coordinate of zero, to admit.
+/// Modulo primes $p \equiv 1 \pmod 4$, these are $(0, b, \pm i b)$ and its permutations, where
+/// $i^2 = -1$; modulo every prime, $(0, 0, 0)$ is a degenerate triple, and is an isolated vertex of
+/// the Markoff graph.
+/// See [`Triple::degenerate_triples`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Degenerate {
+    /// Admit every triple.
+    Include,
+    /// Admit every triple except $(0, 0, 0)$.
+    #[default]
+    ExcludeZero,
+    /// Admit only triples with every coordinate nonzero.
+    Exclude,
+}
+
+/// Which parabolic triples, those with a coordinate of $\pm 2$, to admit.
+/// The coordinates $\pm 2$ are those whose rotation maps are parabolic: the rotation at $2$ is
+/// unipotent, of order $p$, and the rotation at $-2$ has order $2p$, although the corresponding
+/// $\chi = \pm 1$ has order 1 or 2; see [`RotOrder::Parabola`].
+/// Modulo primes $p \equiv 3 \pmod 4$, there are no parabolic triples.
+/// See [`crate::markoff::parabolic_triples`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Parabolic {
+    /// Admit every triple.
+    Include,
+    /// Admit only triples with no coordinate of $\pm 2$.
+    #[default]
+    Exclude,
+}
+
 /// The three coordinates of a Markoff triple.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Pos {
     /// The first coordinate.
     A,
@@ -24,6 +73,17 @@ impl<const P: u128> Triple<P> {
         }
     }
 
+    /// Creates a Markoff triple, but only if `a`, `b`, and `c` are a solution which is admitted by
+    /// `degenerate`.
+    pub fn new_with(
+        a: FpNum<P>,
+        b: FpNum<P>,
+        c: FpNum<P>,
+        degenerate: Degenerate,
+    ) -> Option<Triple<P>> {
+        Triple::new(a, b, c).filter(|t| degenerate.admits(t))
+    }
+
     /// Creates a new Markoff triple, without checking that it is actually a solution.
     pub fn new_unchecked(a: FpNum<P>, b: FpNum<P>, c: FpNum<P>) -> Triple<P> {
         Triple(a, b, c)
@@ -38,6 +98,81 @@ impl<const P: u128> Triple<P> {
         }
     }
 
+    /// True if this triple is fixed by the Vieta involution at `pos`, that is, if twice the
+    /// coordinate at `pos` is the product of the other two.
+    pub fn is_fixed_by(&self, pos: Pos) -> bool {
+        let Triple(a, b, c) = *self;
+        let two = FpNum::from(2);
+        match pos {
+            Pos::A => two * a == b * c,
+            Pos::B => two * b == a * c,
+            Pos::C => two * c == a * b,
+        }
+    }
+
+    /// True if this triple has a coordinate of zero.
+    pub fn is_degenerate(&self) -> bool {
+        [self.0, self.1, self.2].contains(&FpNum::ZERO)
+    }
+
+    /// True if this triple has a coordinate of $\pm 2$.
+    /// See [`Parabolic`].
+    pub fn is_parabolic(&self) -> bool {
+        [self.0, self.1, self.2]
+            .iter()
+            .any(|x| Coord(*x).is_parabolic())
+    }
+
+    /// Returns every Markoff triple fixed by the Vieta involution at `pos`, including
+    /// $(0, 0, 0)$.
+    /// Writing $x$ for the coordinate at `pos`, and $y$ and $z$ for the others, these are the
+    /// solutions of $x = yz / 2$ and $z^2 = 4y^2 / (y^2 - 4)$, so there are at most $2p$ of them.
+    pub fn fixed_points(pos: Pos) -> Vec<Triple<P>> {
+        let two = FpNum::<P>::from(2);
+        let mut res = Vec::new();
+        for y in (0..P).map(FpNum::from) {
+            if y == two || y == -two {
+                continue;
+            }
+            let Some(z) = (FpNum::from(4) * y * y * (y * y - FpNum::from(4)).inverse()).int_sqrt()
+            else {
+                continue;
+            };
+            let zs = if z == FpNum::ZERO {
+                vec![z]
+            } else {
+                vec![z, -z]
+            };
+            for z in zs {
+                let x = y * z * FpNum::TWO_INV;
+                res.push(match pos {
+                    Pos::A => Triple(x, y, z),
+                    Pos::B => Triple(y, x, z),
+                    Pos::C => Triple(y, z, x),
+                });
+            }
+        }
+        res
+    }
+
+    /// Returns every degenerate Markoff triple, that is, every solution with a coordinate of
+    /// zero, beginning with $(0, 0, 0)$.
+    /// Unless $p \equiv 1 \pmod 4$, this is $(0, 0, 0)$ alone.
+    /// See [`Degenerate`].
+    pub fn degenerate_triples() -> Vec<Triple<P>> {
+        let mut res = vec![Triple(FpNum::ZERO, FpNum::ZERO, FpNum::ZERO)];
+        let Some(i) = (-FpNum::<P>::from(1)).int_sqrt() else {
+            return res;
+        };
+        for y in (1..P).map(FpNum::from) {
+            for z in [i * y, -i * y] {
+                let zero = FpNum::ZERO;
+                res.extend([Triple(zero, y, z), Triple(y, zero, z), Triple(y, z, zero)]);
+            }
+        }
+        res
+    }
+
     /// Returns the first coordinate.
     #[inline(always)]
     pub fn a(&self) -> FpNum<P> {
@@ -65,4 +200,330 @@ impl<const P: u128> Triple<P> {
             Pos::C => self.2,
         }
     }
+
+    /// Returns the least triple, ordering triples lexicographically by their coordinates, obtained
+    /// from this triple by permuting its coordinates and negating pairs of its coordinates.
+    /// This is a canonical representative of the orbit of this triple under these 24 symmetries.
+    pub fn canonical_form(&self) -> Triple<P> {
+        let Triple(a, b, c) = *self;
+        [(a, b, c), (-a, -b, c), (-a, b, -c), (a, -b, -c)]
+            .into_iter()
+            .flat_map(|(a, b, c)| {
+                [
+                    (a, b, c),
+                    (a, c, b),
+                    (b, a, c),
+                    (b, c, a),
+                    (c, a, b),
+                    (c, b, a),
+                ]
+            })
+            .map(|(a, b, c)| Triple(a, b, c))
+            .min_by_key(Triple::key)
+            .unwrap()
+    }
+
+    /// Returns a canonical representative of the orbit of this triple under the group generated
+    /// by the Vieta involutions, permutations of the coordinates, and negations of pairs of
+    /// coordinates.
+    /// Two triples lie in the same orbit if and only if they have the same canonical
+    /// representative.
+    ///
+    /// The orbit containing $(3, 3, 3)$, which is conjecturally every triple other than
+    /// $(0, 0, 0)$, is represented by the [`Triple::canonical_form`] of $(3, 3, 3)$, and the walk
+    /// stops as soon as it is reached.
+    /// Every other orbit is walked exhaustively, and is represented by its least triple.
+    ///
+    /// Each call walks the orbit afresh, visiting up to about $p^2 / 24$ canonical forms, so
+    /// takes $O(p^2)$ time and memory.
+    /// To find the representatives of many triples, use an [`OrbitCache`].
+    pub fn canonical_in_orbit(&self) -> Triple<P> {
+        self.canonical_in_orbit_with(HashSet::new())
+    }
+
+    /// Returns the same representative as [`Triple::canonical_in_orbit`], tracking the canonical
+    /// forms visited in `seen`, which should be empty.
+    /// With an approximate set, such as a [`BloomFilter`](crate::markoff::BloomFilter), the walk
+    /// may stop early and return a triple which is not the least in its orbit.
+    pub fn canonical_in_orbit_with(&self, mut seen: impl VisitedSet<Triple<P>>) -> Triple<P> {
+        self.walk_orbit(&mut seen, |_| None)
+    }
+
+    // Walks the canonical forms in the orbit of this triple, recording them in `seen`, until
+    // reaching the fundamental triple or a form whose representative is given by `known`.
+    fn walk_orbit(
+        &self,
+        seen: &mut impl VisitedSet<Triple<P>>,
+        known: impl Fn(&Triple<P>) -> Option<Triple<P>>,
+    ) -> Triple<P> {
+        let fundamental = Triple(FpNum::from(3), FpNum::from(3), FpNum::from(3)).canonical_form();
+        let start = self.canonical_form();
+        if let Some(res) = known(&start) {
+            return res;
+        }
+        let mut least = start;
+        seen.insert(&start);
+        let mut queue = VecDeque::from([start]);
+        // The symmetries normalize the group generated by the Vieta involutions, so it suffices
+        // to walk the canonical forms.
+        while let Some(t) = queue.pop_front() {
+            if t == fundamental {
+                return fundamental;
+            }
+            if t.key() < least.key() {
+                least = t;
+            }
+            for pos in [Pos::A, Pos::B, Pos::C] {
+                let next = t.vieta(pos).canonical_form();
+                if let Some(res) = known(&next) {
+                    return res;
+                }
+                if seen.insert(&next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        least
+    }
+
+    /// Returns the least number of Vieta involutions taking this triple to $(3, 3, 3)$, the image
+    /// modulo `P` of the fundamental solution, or `None` if it takes more than `max_depth`
+    /// involutions, or if they are not in the same orbit.
+    ///
+    /// The search is a breadth first search from both ends, expanding whichever frontier is
+    /// smaller, so it visits on the order of the square root of the triples that a one sided
+    /// search would.
+    pub fn distance_to_fundamental(&self, max_depth: usize) -> Option<usize> {
+        self.distance_to_fundamental_with([HashSet::new(), HashSet::new()], max_depth)
+    }
+
+    /// Returns the same distance as [`Triple::distance_to_fundamental`], tracking the triples
+    /// visited from each end in the two sets `visited`, which should be empty.
+    pub fn distance_to_fundamental_with<V: VisitedSet<Triple<P>> + Sync>(
+        &self,
+        visited: [V; 2],
+        max_depth: usize,
+    ) -> Option<usize> {
+        bfs::distance_to_fundamental(*self, visited, max_depth)
+    }
+
+    /// Returns the orders of the rotation maps at each coordinate, in the order $a$, $b$, $c$.
+    /// See [`Coord::rot_order`].
+    pub fn rot_orders<S1, S2>(&self) -> [RotOrder; 3]
+    where
+        FpNum<P>: Factor<S1>,
+        QuadNum<P>: Factor<S2>,
+    {
+        [self.0, self.1, self.2].map(|x| Coord(x).rot_order::<S1, S2>())
+    }
+
+    /// Returns the types of conic of the rotation maps at each coordinate, in the order $a$, $b$,
+    /// $c$.
+    pub fn classify<S1, S2>(&self) -> [Conic; 3]
+    where
+        FpNum<P>: Factor<S1>,
+        QuadNum<P>: Factor<S2>,
+    {
+        self.rot_orders::<S1, S2>().map(|o| o.conic())
+    }
+
+    fn key(&self) -> (u128, u128, u128) {
+        (self.0.into(), self.1.into(), self.2.into())
+    }
+}
+
+impl<const P: u128> OrbitCache<P> {
+    /// Returns an empty cache.
+    pub fn new() -> OrbitCache<P> {
+        OrbitCache {
+            reps: HashMap::new(),
+        }
+    }
+
+    /// Returns the same representative as [`Triple::canonical_in_orbit`], walking only the
+    /// canonical forms not already in the cache.
+    pub fn canonical_in_orbit(&mut self, t: &Triple<P>) -> Triple<P> {
+        let mut seen = HashSet::new();
+        let res = t.walk_orbit(&mut seen, |u| self.reps.get(u).copied());
+        self.reps.extend(seen.into_iter().map(|u| (u, res)));
+        res
+    }
+
+    /// Returns the number of canonical forms in the cache.
+    pub fn len(&self) -> usize {
+        self.reps.len()
+    }
+
+    /// True if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.reps.is_empty()
+    }
+}
+
+impl Parabolic {
+    /// True if `t` is admitted.
+    pub fn admits<const P: u128>(&self, t: &Triple<P>) -> bool {
+        match self {
+            Parabolic::Include => true,
+            Parabolic::Exclude => !t.is_parabolic(),
+        }
+    }
+
+    /// True if the coordinate `a` is admitted, that is, if triples with `a` in some position are
+    /// admitted.
+    pub fn admits_coord<const P: u128>(&self, a: &Coord<P>) -> bool {
+        match self {
+            Parabolic::Include => true,
+            Parabolic::Exclude => !a.is_parabolic(),
+        }
+    }
+}
+
+impl Degenerate {
+    /// True if `t` is admitted.
+    pub fn admits<const P: u128>(&self, t: &Triple<P>) -> bool {
+        match self {
+            Degenerate::Include => true,
+            Degenerate::ExcludeZero => *t != Triple(FpNum::ZERO, FpNum::ZERO, FpNum::ZERO),
+            Degenerate::Exclude => !t.is_degenerate(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers::*;
+    use std::collections::HashMap;
+
+    #[derive(PartialEq, Eq)]
+    struct Ph {}
+
+    impl_factors!(Ph, 13);
+
+    fn triples<const P: u128>() -> impl Iterator<Item = Triple<P>> {
+        (0..P).flat_map(|a| {
+            (0..P).flat_map(move |b| {
+                (0..P).filter_map(move |c| {
+                    Triple::new(FpNum::from(a), FpNum::from(b), FpNum::from(c))
+                })
+            })
+        })
+    }
+
+    #[test]
+    fn canonical_in_orbit_is_invariant() {
+        for t in triples::<13>() {
+            let canon = t.canonical_in_orbit();
+            assert_eq!(canon.canonical_in_orbit(), canon);
+            assert_eq!(canon, canon.canonical_form());
+            for pos in [Pos::A, Pos::B, Pos::C] {
+                assert_eq!(t.vieta(pos).canonical_in_orbit(), canon);
+            }
+            let Triple(a, b, c) = t;
+            assert_eq!(Triple(c, a, b).canonical_in_orbit(), canon);
+            assert_eq!(Triple(-a, b, -c).canonical_in_orbit(), canon);
+        }
+    }
+
+    #[test]
+    fn orbit_cache_matches_uncached() {
+        let mut cache = OrbitCache::new();
+        assert!(cache.is_empty());
+        let forms = triples::<13>()
+            .map(|t| t.canonical_form())
+            .collect::<HashSet<_>>();
+        for t in triples::<13>() {
+            assert_eq!(cache.canonical_in_orbit(&t), t.canonical_in_orbit());
+            assert!(cache.len() <= forms.len());
+        }
+        let zero = Triple::<13>(FpNum::ZERO, FpNum::ZERO, FpNum::ZERO);
+        assert_eq!(cache.canonical_in_orbit(&zero), zero);
+    }
+
+    #[test]
+    fn canonical_in_orbit_separates_orbits() {
+        let zero = Triple::<23>(FpNum::ZERO, FpNum::ZERO, FpNum::ZERO);
+        assert_eq!(zero.canonical_in_orbit(), zero);
+        let fundamental = Triple::<23>(FpNum::from(3), FpNum::from(3), FpNum::from(3));
+        let canon = fundamental.canonical_in_orbit();
+        assert_ne!(canon, zero);
+        assert_eq!(canon, fundamental.canonical_form());
+    }
+
+    #[test]
+    fn classify_matches_rot_order() {
+        let t = Triple::<13>::new(FpNum::from(3), FpNum::from(3), FpNum::from(3)).unwrap();
+        // 3 = chi + 1 / chi with chi generating the norm 1 subgroup of F_169.
+        assert_eq!(t.rot_orders::<Ph, Ph>(), [RotOrder::Ellipse(14); 3]);
+        assert_eq!(t.classify::<Ph, Ph>(), [Conic::Ellipse; 3]);
+        for t in triples::<13>() {
+            let sig = t.classify::<Ph, Ph>();
+            for (x, conic) in [t.a(), t.b(), t.c()].into_iter().zip(sig) {
+                assert_eq!(Coord(x).rot_order::<Ph, Ph>().conic(), conic);
+            }
+        }
+        let sig = [Conic::Hyperbola, Conic::Hyperbola, Conic::Ellipse];
+        assert_eq!(sig.map(|c| c.to_string()).concat(), "HHE");
+    }
+
+    #[test]
+    fn distance_to_fundamental_matches_bfs() {
+        let fundamental = Triple::<13>(FpNum::from(3), FpNum::from(3), FpNum::from(3));
+        let mut dists = HashMap::from([(fundamental, 0)]);
+        let mut queue = VecDeque::from([fundamental]);
+        while let Some(t) = queue.pop_front() {
+            for pos in [Pos::A, Pos::B, Pos::C] {
+                let u = t.vieta(pos);
+                if !dists.contains_key(&u) {
+                    dists.insert(u, dists[&t] + 1);
+                    queue.push_back(u);
+                }
+            }
+        }
+        let max = *dists.values().max().unwrap();
+        for t in triples::<13>() {
+            assert_eq!(t.distance_to_fundamental(max), dists.get(&t).copied());
+            if let Some(d) = dists.get(&t).filter(|d| **d > 0) {
+                assert_eq!(t.distance_to_fundamental(d - 1), None);
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_and_degenerate_triples() {
+        fn check<const P: u128>() {
+            for pos in [Pos::A, Pos::B, Pos::C] {
+                let expected = triples::<P>()
+                    .filter(|t| t.is_fixed_by(pos))
+                    .collect::<HashSet<_>>();
+                let fixed = Triple::<P>::fixed_points(pos);
+                assert_eq!(fixed.len(), expected.len());
+                assert_eq!(fixed.into_iter().collect::<HashSet<_>>(), expected);
+            }
+            let degenerate = Triple::<P>::degenerate_triples();
+            let expected = triples::<P>()
+                .filter(Triple::is_degenerate)
+                .collect::<HashSet<_>>();
+            assert_eq!(degenerate.len(), expected.len());
+            assert_eq!(degenerate.into_iter().collect::<HashSet<_>>(), expected);
+
+            let zero = FpNum::ZERO;
+            assert!(Triple::<P>::new_with(zero, zero, zero, Degenerate::Include).is_some());
+            assert_eq!(
+                Triple::<P>::new_with(zero, zero, zero, Degenerate::ExcludeZero),
+                None
+            );
+            for t in triples::<P>() {
+                let (a, b, c) = (t.a(), t.b(), t.c());
+                assert_eq!(
+                    Triple::new_with(a, b, c, Degenerate::Exclude).is_some(),
+                    !t.is_degenerate()
+                );
+            }
+        }
+        check::<13>();
+        check::<23>();
+        assert_eq!(Triple::<23>::degenerate_triples().len(), 1);
+    }
 }
@@ -0,0 +1,245 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::markoff::census::encode;
+use crate::markoff::*;
+
+/// A set of elements already visited by a search, such as a [`ParallelBfs`](bfs::ParallelBfs).
+/// Searches generic over their visited set let callers trade memory for exactness: a `HashSet`
+/// is exact and grows with the search, a [`Bitset`] (or `MmapBitset`, with the `mmap` feature)
+/// is exact with a fixed size proportional to $p^3$, and a [`BloomFilter`] has a fixed size of
+/// the caller's choosing, but may report elements as visited which were not.
+pub trait VisitedSet<T> {
+    /// Marks `x` as visited, returning true if it was not visited before.
+    fn insert(&mut self, x: &T) -> bool;
+
+    /// True if `x` has been visited.
+    fn contains(&self, x: &T) -> bool;
+
+    /// Returns the number of elements visited.
+    /// Approximate sets return an estimate.
+    fn len(&self) -> usize;
+
+    /// True if no elements have been visited.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Marks every element visited in `other` as visited in `self`.
+    fn merge(&mut self, other: &Self);
+}
+
+/// A [`VisitedSet`] of triples holding one bit for every triple modulo `P`, whether or not it is
+/// a Markoff triple.
+/// This takes $p^3 / 8$ bytes, so is only feasible for small primes, but is far faster than a
+/// hash set when most triples are visited.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bitset<const P: u128> {
+    bits: Vec<u8>,
+    len: usize,
+}
+
+/// A [`VisitedSet`] of triples holding one bit for every triple modulo `P`, like [`Bitset`], in
+/// a memory mapped file, so that searches may use more bits than fit in memory.
+///
+/// Requires the `mmap` feature.
+#[cfg(feature = "mmap")]
+pub struct MmapBitset<const P: u128> {
+    map: memmap2::MmapMut,
+    len: usize,
+}
+
+impl<T: Hash + Eq + Clone> VisitedSet<T> for HashSet<T> {
+    fn insert(&mut self, x: &T) -> bool {
+        HashSet::insert(self, x.clone())
+    }
+
+    fn contains(&self, x: &T) -> bool {
+        HashSet::contains(self, x)
+    }
+
+    fn len(&self) -> usize {
+        HashSet::len(self)
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.extend(other.iter().cloned());
+    }
+}
+
+/// Merging Bloom filters panics if they are not compatible; see
+/// [`BloomFilter::check_compatible`].
+impl<T, F> VisitedSet<T> for BloomFilter<T, F>
+where
+    F: Fn(&T) -> usize + Send + Sync,
+{
+    fn insert(&mut self, x: &T) -> bool {
+        if self.is_member_prob(x) {
+            return false;
+        }
+        self.add(x);
+        true
+    }
+
+    fn contains(&self, x: &T) -> bool {
+        self.is_member_prob(x)
+    }
+
+    fn len(&self) -> usize {
+        self.estimate_len()
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.union(other).expect("Bloom filters are not compatible");
+    }
+}
+
+impl<const P: u128> Bitset<P> {
+    /// Returns an empty set.
+    pub fn new() -> Bitset<P> {
+        Bitset {
+            bits: vec![0; bitset_len::<P>()],
+            len: 0,
+        }
+    }
+}
+
+impl<const P: u128> VisitedSet<Triple<P>> for Bitset<P> {
+    fn insert(&mut self, t: &Triple<P>) -> bool {
+        let res = set_bit(&mut self.bits, encode(t));
+        self.len += usize::from(res);
+        res
+    }
+
+    fn contains(&self, t: &Triple<P>) -> bool {
+        get_bit(&self.bits, encode(t))
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.len = merge_bits(&mut self.bits, &other.bits);
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl<const P: u128> MmapBitset<P> {
+    /// Returns an empty set, stored in the file at `path`, which is created or truncated.
+    pub fn create(path: impl AsRef<std::path::Path>) -> std::io::Result<MmapBitset<P>> {
+        let file = std::fs::File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(bitset_len::<P>() as u64)?;
+        // SAFETY: the file was just truncated by this process, and is not shared with anyone
+        // else; modifying it elsewhere while the search runs is undefined behavior.
+        let map = unsafe { memmap2::MmapMut::map_mut(&file)? };
+        Ok(MmapBitset { map, len: 0 })
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl<const P: u128> VisitedSet<Triple<P>> for MmapBitset<P> {
+    fn insert(&mut self, t: &Triple<P>) -> bool {
+        let res = set_bit(&mut self.map, encode(t));
+        self.len += usize::from(res);
+        res
+    }
+
+    fn contains(&self, t: &Triple<P>) -> bool {
+        get_bit(&self.map, encode(t))
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.len = merge_bits(&mut self.map, &other.map);
+    }
+}
+
+fn bitset_len<const P: u128>() -> usize {
+    let bits = P.checked_pow(3).expect("P is too large for a bitset");
+    usize::try_from(bits.div_ceil(8)).expect("P is too large for a bitset")
+}
+
+fn get_bit(bits: &[u8], i: u128) -> bool {
+    bits[(i >> 3) as usize] & (1 << (i & 0b111)) != 0
+}
+
+// Sets bit `i`, returning true if it was not already set.
+fn set_bit(bits: &mut [u8], i: u128) -> bool {
+    let mask = 1 << (i & 0b111);
+    let byte = &mut bits[(i >> 3) as usize];
+    let res = *byte & mask == 0;
+    *byte |= mask;
+    res
+}
+
+// Sets every bit set in `other`, returning the number of bits set.
+fn merge_bits(bits: &mut [u8], other: &[u8]) -> usize {
+    bits.iter_mut()
+        .zip(other)
+        .map(|(a, b)| {
+            *a |= b;
+            a.count_ones() as usize
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers::FpNum;
+
+    fn check<V: VisitedSet<Triple<13>>>(mut a: V, mut b: V) {
+        let ts = census::triples::<13>().collect::<Vec<_>>();
+        assert!(a.is_empty());
+        assert!(ts[..100].iter().all(|t| a.insert(t)));
+        assert!(!a.insert(&ts[0]));
+        assert!(ts[50..150].iter().all(|t| b.insert(t) && b.contains(t)));
+        assert_eq!((a.len(), b.len()), (100, 100));
+        a.merge(&b);
+        assert!(ts[..150].iter().all(|t| a.contains(t)));
+        assert_eq!(a.len(), 150);
+    }
+
+    #[test]
+    fn exact_sets() {
+        check(HashSet::new(), HashSet::new());
+        check(Bitset::new(), Bitset::new());
+        let zero = Triple::<13>::new_unchecked(FpNum::ZERO, FpNum::ZERO, FpNum::ZERO);
+        assert!(!Bitset::new().contains(&zero));
+    }
+
+    #[test]
+    fn bloom_len_estimate() {
+        let hashes = vec![
+            |t: &Triple<13>| (encode(t) * 7 % (1 << 12)) as usize,
+            |t: &Triple<13>| (encode(t) * 13 % (1 << 12)) as usize,
+        ];
+        let mut filter = BloomFilter::new(1 << 12, hashes);
+        for t in census::triples::<13>().take(150) {
+            filter.insert(&t);
+        }
+        assert!((140..160).contains(&VisitedSet::len(&filter)));
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_set() {
+        let dir = std::env::temp_dir();
+        let path = |i| dir.join(format!("libbgs-visited-{}-{i}", std::process::id()));
+        check(
+            MmapBitset::create(path(0)).unwrap(),
+            MmapBitset::create(path(1)).unwrap(),
+        );
+        std::fs::remove_file(path(0)).unwrap();
+        std::fs::remove_file(path(1)).unwrap();
+    }
+}
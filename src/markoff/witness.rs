@@ -0,0 +1,137 @@
+use crate::markoff::*;
+use crate::numbers::*;
+use crate::streams::*;
+
+/// Returns a Markoff triple $(a, b, c)$ whose coordinates have rotation orders `d_a`, `d_b`, and
+/// `d_c` respectively, or `None` if there is no such triple.
+/// See [`Coord::rot_order`].
+///
+/// The candidates for $a$ and $b$ are the coordinates $\chi + \chi^{-1}$ for $\chi$ of the given
+/// orders, found with a [`SylowStreamBuilder`]; each pair of candidates is completed to a triple
+/// by solving for $c$.
+/// The constants `L_HYPER` and `L_ELLIP` are the numbers of prime factors of $p - 1$ and $p + 1$.
+/// The search takes time proportional to the product of the numbers of coordinates of orders
+/// `d_a` and `d_b`, so it is fastest to put the rarest orders first.
+///
+/// # Example
+/// ```
+/// use libbgs::markoff::*;
+/// use libbgs::numbers::*;
+///
+/// #[derive(PartialEq, Eq)]
+/// struct Ph;
+/// impl_factors!(Ph, 13);
+///
+/// let d = RotOrder::Ellipse(14);
+/// let t = find_triple_with_orders::<Ph, 13, 2, 2>(d, d, d).unwrap();
+/// assert_eq!(t.rot_orders::<Ph, Ph>(), [d; 3]);
+///
+/// // No coordinate has order 5, which divides neither 12 nor 14.
+/// let d = RotOrder::Hyperbola(5);
+/// assert_eq!(find_triple_with_orders::<Ph, 13, 2, 2>(d, d, d), None);
+/// ```
+pub fn find_triple_with_orders<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize>(
+    d_a: RotOrder,
+    d_b: RotOrder,
+    d_c: RotOrder,
+) -> Option<Triple<P>>
+where
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    let two_inv = FpNum::from(2).inverse();
+    let bs = coords_of_order::<S, P, L_HYPER, L_ELLIP>(d_b);
+    for a in coords_of_order::<S, P, L_HYPER, L_ELLIP>(d_a) {
+        for b in &bs {
+            let b = *b;
+            let Some(disc) = (a * a * b * b - 4 * (a * a + b * b)).int_sqrt() else {
+                continue;
+            };
+            for c in [(a * b + disc) * two_inv, (a * b - disc) * two_inv] {
+                if Coord(c).rot_order::<S, S>() == d_c {
+                    return Triple::new(a, b, c);
+                }
+            }
+        }
+    }
+    None
+}
+
+// Returns every coordinate with rotation order `d`, up to the choice of chi or its inverse.
+fn coords_of_order<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize>(
+    d: RotOrder,
+) -> Vec<FpNum<P>>
+where
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    fn from_stream<S, const P: u128, const L: usize, C: FromChi<S, P>>(d: u128) -> Vec<FpNum<P>> {
+        let Some(ds) = C::powers_from_order::<L>(d) else {
+            return Vec::new();
+        };
+        let decomp = SylowDecomp::<S, L, C>::new();
+        SylowStreamBuilder::<S, L, C, ()>::new()
+            .add_flag(flags::NO_UPPER_HALF)
+            .add_target(&ds)
+            .into_iter()
+            .map(|(chi, ())| C::from_chi(&chi, &decomp))
+            .collect()
+    }
+
+    match d {
+        RotOrder::Parabola(1) => vec![FpNum::from(2)],
+        RotOrder::Parabola(2) => vec![-FpNum::from(2)],
+        RotOrder::Hyperbola(d) if d > 2 => from_stream::<S, P, L_HYPER, FpNum<P>>(d),
+        RotOrder::Ellipse(d) if d > 2 => from_stream::<S, P, L_ELLIP, QuadNum<P>>(d),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[derive(PartialEq, Eq)]
+    struct Ph {}
+
+    impl_factors!(Ph, 13);
+
+    #[test]
+    fn finds_every_signature() {
+        let mut found = HashSet::new();
+        for a in 0..13 {
+            for b in 0..13 {
+                for c in 0..13 {
+                    if let Some(t) =
+                        Triple::<13>::new(FpNum::from(a), FpNum::from(b), FpNum::from(c))
+                    {
+                        found.insert(t.rot_orders::<Ph, Ph>());
+                    }
+                }
+            }
+        }
+        let orders = [3, 4, 6, 12]
+            .map(RotOrder::Hyperbola)
+            .into_iter()
+            .chain([7, 14].map(RotOrder::Ellipse))
+            .chain([1, 2].map(RotOrder::Parabola))
+            .collect::<Vec<_>>();
+        for d_a in &orders {
+            for d_b in &orders {
+                for d_c in &orders {
+                    let sig = [*d_a, *d_b, *d_c];
+                    let t = find_triple_with_orders::<Ph, 13, 2, 2>(*d_a, *d_b, *d_c);
+                    assert_eq!(t.is_some(), found.contains(&sig));
+                    assert!(t.is_none_or(|t| t.rot_orders::<Ph, Ph>() == sig));
+                }
+            }
+        }
+        assert!(find_triple_with_orders::<Ph, 13, 2, 2>(
+            RotOrder::Hyperbola(5),
+            RotOrder::Parabola(1),
+            RotOrder::Parabola(2)
+        )
+        .is_none());
+    }
+}
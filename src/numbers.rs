@@ -1,20 +1,45 @@
 //! Types and utilities for manipulating numbers in various types of finite fields.
 //!
-//! This module contains modular integers (i.e., $\mathbb{Z} / p\mathbb{Z}$ for prime $p$),
-//! their quadratic finite field extensions (i.e., $\mathbb{Z} / p^2\mathbb{Z}$ for prime $p$), and
-//! decompositions into direct sums of Sylow subgroups.
+//! This module contains modular integers (i.e., $\mathbb{Z} / p\mathbb{Z}$ for prime $p$), with a
+//! choice of Montgomery or Barrett reduction,
+//! their quadratic finite field extensions (i.e., $\mathbb{Z} / p^2\mathbb{Z}$ for prime $p$),
+//! extensions of any degree, and decompositions into direct sums of Sylow subgroups.
 extern crate libbgs_macros;
+mod barrett;
+mod cyclic;
+mod ext_field;
+#[cfg(feature = "bigint")]
+mod factor_backend;
 mod factor_trie;
 mod factorization;
+mod fixed_base;
 mod fp;
+mod fp256;
+#[cfg(feature = "bigint")]
+mod fp_big;
 mod group;
+mod homomorphism;
+#[cfg(feature = "bigint")]
+mod prime_list;
 mod quad_field;
 mod sylow;
 
+pub use barrett::*;
+pub use cyclic::*;
+pub use ext_field::*;
+#[cfg(feature = "bigint")]
+pub use factor_backend::*;
 pub use factor_trie::*;
 pub use factorization::*;
+pub use fixed_base::*;
 pub use fp::*;
+pub use fp256::*;
+#[cfg(feature = "bigint")]
+pub use fp_big::*;
 pub use group::*;
+pub use homomorphism::*;
 pub use libbgs_macros::*;
+#[cfg(feature = "bigint")]
+pub use prime_list::*;
 pub use quad_field::*;
 pub use sylow::*;
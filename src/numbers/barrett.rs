@@ -0,0 +1,381 @@
+use std::ops::*;
+
+use crate::numbers::*;
+use libbgs_util::*;
+
+/// An integer modulo `N`, reduced by Barrett's method.
+///
+/// Unlike [`FpNum`], which keeps its elements in Montgomery form, a `Barrett` keeps the integer
+/// in the range `0..N` it represents, so converting to and from a `u128` costs a single remainder
+/// at most.
+/// Each multiplication costs about the same as for `FpNum`, so `Barrett` is faster when elements
+/// are used for only a few operations each, as when enumerating a field in Steinitz order, and
+/// `FpNum` is faster for long chains of arithmetic, such as exponentiation.
+/// See [`FpBackend`] to write code for either.
+///
+/// `N` must be odd, and less than $2^{126}$.
+///
+/// # Example
+/// ```
+/// use libbgs::numbers::{Barrett, FpNum};
+/// let x = Barrett::<7>::from(5);
+/// let y = Barrett::<7>::from(6);
+/// assert_eq!(x * y, Barrett::from(30 % 7));
+/// assert_eq!(FpNum::from(x * y), FpNum::from(5) * FpNum::from(6));
+/// ```
+///
+/// For more information on the reduction, see: Barrett, Paul (1986). "Implementing the Rivest
+/// Shamir and Adleman Public Key Encryption Algorithm on a Standard Digital Signal Processor".
+/// Advances in Cryptology - CRYPTO '86. 311-323.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Barrett<const N: u128>(u128);
+
+/// A representation of the integers modulo `P`, with the arithmetic common to [`FpNum`], which
+/// uses Montgomery multiplication, and [`Barrett`].
+/// Code generic over an `FpBackend` selects the reduction by its type parameter, and converts to
+/// `FpNum`, which the rest of this crate uses, with `From`.
+pub trait FpBackend<const P: u128>:
+    GroupElem
+    + Copy
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Neg<Output = Self>
+    + From<u128>
+    + Into<u128>
+    + Into<FpNum<P>>
+    + Send
+    + Sync
+{
+    /// The constant 0.
+    const ZERO: Self;
+}
+
+impl<const N: u128> Barrett<N> {
+    /// The constant 0.
+    pub const ZERO: Barrett<N> = Barrett(0);
+
+    /// Returns the integer in the range `0..N` represented by this number.
+    #[inline(always)]
+    pub const fn raw(&self) -> u128 {
+        self.0
+    }
+
+    /// Converts a `u128` into the integer modulo `N` it represents.
+    pub const fn from_u128(src: u128) -> Barrett<N> {
+        Barrett(src % N)
+    }
+
+    // The number of bits in N.
+    const K: u32 = {
+        assert!(N % 2 == 1 && N > 1, "The modulus must be odd.");
+        assert!(N < 1 << 126, "The modulus must be less than 2^126.");
+        N.ilog2() + 1
+    };
+
+    // The "magic" number floor(4^K / N), which fits in K + 1 bits.
+    const MU: u128 = {
+        let (hi, lo) = if 2 * Self::K >= 128 {
+            (1 << (2 * Self::K - 128), 0)
+        } else {
+            (0, 1 << (2 * Self::K))
+        };
+        match u128x2::checked_div_wide(hi, lo, N) {
+            Some((q, _)) => q,
+            None => panic!("The modulus is too large."),
+        }
+    };
+
+    // Reduces the 256-bit integer x = (hi, lo), which must be less than N^2.
+    const fn reduce((hi, lo): (u128, u128)) -> Barrett<N> {
+        // The estimate q of x / N is at most 2 less than the true quotient, so the remainder
+        // x - qN is less than 3N, which fits in 128 bits.
+        let q = shrd(lo, hi, Self::K as usize - 1);
+        let (hi, lo_q) = carrying_mul(q, Self::MU);
+        let q = shrd(lo_q, hi, Self::K as usize + 1);
+        let mut r = lo.wrapping_sub(q.wrapping_mul(N));
+        while r >= N {
+            r -= N;
+        }
+        Barrett(r)
+    }
+
+    // These next several methods are constant implementations of the usual std::ops methods, as
+    // for `FpNum`.
+    pub(crate) const fn const_mul(&self, rhs: &Barrett<N>) -> Barrett<N> {
+        Barrett::reduce(carrying_mul(self.0, rhs.0))
+    }
+
+    /// Returns the multiplicative inverse of this number, or `None` if it is zero.
+    /// This uses the extended Euclidean algorithm, and may be used in `const` contexts.
+    pub const fn const_inverse(&self) -> Option<Barrett<N>> {
+        match mod_inverse(self.0, N) {
+            Some(inv) => Some(Barrett(inv)),
+            None => None,
+        }
+    }
+
+    pub(crate) const fn const_add(&self, rhs: &Barrett<N>) -> Barrett<N> {
+        let sum = self.0 + rhs.0;
+        if sum >= N {
+            Barrett(sum - N)
+        } else {
+            Barrett(sum)
+        }
+    }
+
+    pub(crate) const fn const_sub(&self, rhs: &Barrett<N>) -> Barrett<N> {
+        if self.0 >= rhs.0 {
+            Barrett(self.0 - rhs.0)
+        } else {
+            Barrett(N + self.0 - rhs.0)
+        }
+    }
+
+    pub(crate) const fn const_neg(&self) -> Barrett<N> {
+        if self.0 == 0 {
+            Barrett(0)
+        } else {
+            Barrett(N - self.0)
+        }
+    }
+
+    pub(crate) const fn const_pow(self, mut n: u128) -> Barrett<N> {
+        let mut x = self;
+        let mut y = Barrett::<N>::from_u128(1);
+        while n > 0 {
+            if n % 2 == 1 {
+                y = y.const_mul(&x);
+            }
+            x = x.const_mul(&x);
+            n >>= 1;
+        }
+        y
+    }
+}
+
+impl<const N: u128> GroupElem for Barrett<N> {
+    const ONE: Self = Barrett::from_u128(1);
+    const SIZE: u128 = N - 1;
+
+    fn multiply(&self, other: &Barrett<N>) -> Barrett<N> {
+        self.const_mul(other)
+    }
+
+    fn inverse(&self) -> Barrett<N> {
+        let Some(inv) = self.const_inverse() else {
+            panic!("Attempted to take the multiplicative inverse of zero.");
+        };
+        inv
+    }
+
+    fn pow(&self, n: u128) -> Barrett<N> {
+        self.const_pow(n)
+    }
+}
+
+impl<const P: u128> FpBackend<P> for FpNum<P> {
+    const ZERO: FpNum<P> = FpNum::ZERO;
+}
+
+impl<const P: u128> FpBackend<P> for Barrett<P> {
+    const ZERO: Barrett<P> = Barrett::ZERO;
+}
+
+impl<const N: u128> From<u128> for Barrett<N> {
+    fn from(src: u128) -> Barrett<N> {
+        Barrett::from_u128(src)
+    }
+}
+
+impl<const N: u128> From<Barrett<N>> for u128 {
+    fn from(src: Barrett<N>) -> u128 {
+        src.0
+    }
+}
+
+impl<const P: u128> From<Barrett<P>> for FpNum<P> {
+    fn from(src: Barrett<P>) -> FpNum<P> {
+        FpNum::from_u128(src.0)
+    }
+}
+
+impl<const P: u128> From<FpNum<P>> for Barrett<P> {
+    fn from(src: FpNum<P>) -> Barrett<P> {
+        Barrett(u128::from(src))
+    }
+}
+
+impl<const N: u128> std::fmt::Debug for Barrett<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Barrett").field(&self.0).finish()
+    }
+}
+
+impl<const N: u128> Add<Barrett<N>> for Barrett<N> {
+    type Output = Barrett<N>;
+    fn add(self, rhs: Barrett<N>) -> Barrett<N> {
+        self.const_add(&rhs)
+    }
+}
+
+impl<const N: u128> Add<Barrett<N>> for &Barrett<N> {
+    type Output = Barrett<N>;
+    fn add(self, rhs: Barrett<N>) -> Barrett<N> {
+        self.const_add(&rhs)
+    }
+}
+
+impl<const N: u128> Add<&Barrett<N>> for Barrett<N> {
+    type Output = Barrett<N>;
+    fn add(self, rhs: &Barrett<N>) -> Barrett<N> {
+        self.const_add(rhs)
+    }
+}
+
+impl<const N: u128> Add<&Barrett<N>> for &Barrett<N> {
+    type Output = Barrett<N>;
+    fn add(self, rhs: &Barrett<N>) -> Barrett<N> {
+        self.const_add(rhs)
+    }
+}
+
+impl<const N: u128> Sub<Barrett<N>> for Barrett<N> {
+    type Output = Barrett<N>;
+    fn sub(self, rhs: Barrett<N>) -> Barrett<N> {
+        self.const_sub(&rhs)
+    }
+}
+
+impl<const N: u128> Sub<Barrett<N>> for &Barrett<N> {
+    type Output = Barrett<N>;
+    fn sub(self, rhs: Barrett<N>) -> Barrett<N> {
+        self.const_sub(&rhs)
+    }
+}
+
+impl<const N: u128> Sub<&Barrett<N>> for Barrett<N> {
+    type Output = Barrett<N>;
+    fn sub(self, rhs: &Barrett<N>) -> Barrett<N> {
+        self.const_sub(rhs)
+    }
+}
+
+impl<const N: u128> Sub<&Barrett<N>> for &Barrett<N> {
+    type Output = Barrett<N>;
+    fn sub(self, rhs: &Barrett<N>) -> Barrett<N> {
+        self.const_sub(rhs)
+    }
+}
+
+impl<const N: u128> Mul<Barrett<N>> for Barrett<N> {
+    type Output = Barrett<N>;
+    fn mul(self, rhs: Barrett<N>) -> Barrett<N> {
+        self.const_mul(&rhs)
+    }
+}
+
+impl<const N: u128> Mul<Barrett<N>> for &Barrett<N> {
+    type Output = Barrett<N>;
+    fn mul(self, rhs: Barrett<N>) -> Barrett<N> {
+        self.const_mul(&rhs)
+    }
+}
+
+impl<const N: u128> Mul<&Barrett<N>> for Barrett<N> {
+    type Output = Barrett<N>;
+    fn mul(self, rhs: &Barrett<N>) -> Barrett<N> {
+        self.const_mul(rhs)
+    }
+}
+
+impl<const N: u128> Mul<&Barrett<N>> for &Barrett<N> {
+    type Output = Barrett<N>;
+    fn mul(self, rhs: &Barrett<N>) -> Barrett<N> {
+        self.const_mul(rhs)
+    }
+}
+
+impl<const N: u128> Mul<Barrett<N>> for u128 {
+    type Output = Barrett<N>;
+    fn mul(self, rhs: Barrett<N>) -> Barrett<N> {
+        rhs.mul(Barrett::from(self))
+    }
+}
+
+impl<const N: u128> MulAssign<Barrett<N>> for Barrett<N> {
+    fn mul_assign(&mut self, rhs: Barrett<N>) {
+        *self = self.const_mul(&rhs);
+    }
+}
+
+impl<const N: u128> Neg for Barrett<N> {
+    type Output = Barrett<N>;
+    fn neg(self) -> Barrett<N> {
+        self.const_neg()
+    }
+}
+
+impl<const N: u128> Neg for &Barrett<N> {
+    type Output = Barrett<N>;
+    fn neg(self) -> Barrett<N> {
+        self.const_neg()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BIG_P: u128 = 1_000_000_000_000_000_124_399;
+
+    // A prime just below 2^126.
+    const HUGE_P: u128 = (1 << 126) - 137;
+
+    fn sum_of_powers<B: FpBackend<P>, const P: u128>(x: u128) -> u128 {
+        let x = B::from(x);
+        let mut y = B::ONE;
+        let mut sum = B::ZERO;
+        for _ in 0..100 {
+            sum = sum + y;
+            y = y * x - B::ONE;
+        }
+        (-sum).into()
+    }
+
+    #[test]
+    fn matches_montgomery() {
+        for (a, b) in [(0, 0), (1, 12), (7, 11), (12, 12), (5, 9), (3, 6)] {
+            let (x, y) = (Barrett::<13>::from(a), Barrett::<13>::from(b));
+            let (u, v) = (FpNum::<13>::from(a), FpNum::<13>::from(b));
+            assert_eq!(FpNum::from(x * y), u * v);
+            assert_eq!(FpNum::from(x + y), u + v);
+            assert_eq!(FpNum::from(x - y), u - v);
+            assert_eq!(FpNum::from(-x), -u);
+        }
+        for a in [2, 3, 1 << 70, BIG_P - 1, u128::MAX % BIG_P] {
+            let (x, u) = (Barrett::<BIG_P>::from(a), FpNum::<BIG_P>::from(a));
+            assert_eq!(FpNum::from(x * x), u * u);
+            assert_eq!(FpNum::from(x.pow(BIG_P / 3)), u.pow(BIG_P / 3));
+            assert_eq!(Barrett::from(u.inverse()), x.inverse());
+        }
+        assert_eq!(Barrett::<13>::from(100), Barrett::from(9));
+        assert_eq!(
+            sum_of_powers::<Barrett<BIG_P>, BIG_P>(12345),
+            sum_of_powers::<FpNum<BIG_P>, BIG_P>(12345)
+        );
+    }
+
+    #[test]
+    fn reduces_near_limit() {
+        let x = Barrett::<HUGE_P>::from(HUGE_P - 1);
+        assert_eq!(x * x, Barrett::ONE);
+        assert_eq!(x.pow(HUGE_P - 1), Barrett::ONE);
+        let y = Barrett::<HUGE_P>::from(u128::MAX);
+        assert_eq!(y * y.inverse(), Barrett::ONE);
+        // Fermat's little theorem.
+        for a in [2, 3, 1 << 100, HUGE_P / 3] {
+            assert_eq!(Barrett::<HUGE_P>::from(a).pow(HUGE_P - 1), Barrett::ONE);
+        }
+    }
+}
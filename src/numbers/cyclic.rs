@@ -0,0 +1,184 @@
+use crate::numbers::*;
+use libbgs_util::*;
+
+/// An element of the additive cyclic group $\mathbb{Z} / N\mathbb{Z}$, written multiplicatively
+/// to implement [`GroupElem`].
+/// That is, `multiply` is addition modulo `N`, `ONE` is the residue 0, and `pow` is
+/// multiplication by an integer.
+///
+/// Every question about this group has an obvious answer: the element `x` has order
+/// `N / gcd(x, N)`, and the generator of each Sylow subgroup is known without searching.
+/// This makes it a cheap reference implementation for testing code which is generic over
+/// [`SylowDecomposable`] groups.
+///
+/// The factorization of `N` is provided with [`impl_cyclic_factors`].
+///
+/// # Example
+/// ```
+/// use libbgs::numbers::*;
+///
+/// struct Ph;
+/// impl_cyclic_factors!(Ph, 12);
+///
+/// let x = CyclicNum::<12>::from(8);
+/// assert_eq!(x.multiply(&CyclicNum::from(7)), CyclicNum::from(3));
+/// assert_eq!(x.order::<Ph>(), 3);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct CyclicNum<const N: u128>(u128);
+
+impl<const N: u128> CyclicNum<N> {
+    /// Returns the residue of `n` modulo `N`.
+    pub const fn from_u128(n: u128) -> CyclicNum<N> {
+        CyclicNum(n % N)
+    }
+
+    /// Returns the canonical representative of this element, in the range `0..N`.
+    pub const fn value(&self) -> u128 {
+        self.0
+    }
+}
+
+impl<S, const N: u128> SylowDecomposable<S> for CyclicNum<N>
+where
+    CyclicNum<N>: Factor<S>,
+{
+    fn find_sylow_generator(i: usize) -> CyclicNum<N> {
+        let (p, t) = Self::FACTORS[i];
+        CyclicNum(N / intpow::<0>(p, t as u128))
+    }
+}
+
+impl<const N: u128> GroupElem for CyclicNum<N> {
+    const ONE: Self = CyclicNum(0);
+    const SIZE: u128 = N;
+
+    fn multiply(&self, other: &CyclicNum<N>) -> CyclicNum<N> {
+        if self.0 >= N - other.0 {
+            CyclicNum(self.0 - (N - other.0))
+        } else {
+            CyclicNum(self.0 + other.0)
+        }
+    }
+
+    fn inverse(&self) -> CyclicNum<N> {
+        if self.0 == 0 {
+            *self
+        } else {
+            CyclicNum(N - self.0)
+        }
+    }
+}
+
+impl<const N: u128> From<u128> for CyclicNum<N> {
+    fn from(src: u128) -> CyclicNum<N> {
+        CyclicNum::from_u128(src)
+    }
+}
+
+impl<const N: u128> From<CyclicNum<N>> for u128 {
+    fn from(src: CyclicNum<N>) -> u128 {
+        src.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streams::*;
+
+    #[derive(PartialEq, Eq, Clone, Copy, Debug)]
+    struct Phantom {}
+
+    impl_cyclic_factors!(Phantom, 60, 1267650600228229401496703205376);
+
+    #[test]
+    fn adds_without_overflow() {
+        const N: u128 = u128::MAX - 10;
+        let x = CyclicNum::<N>::from(N - 1);
+        assert_eq!(x.multiply(&x), CyclicNum::from(N - 2));
+        assert_eq!(x.inverse(), CyclicNum::from(1));
+        assert_eq!(x.pow(N), CyclicNum::ONE);
+    }
+
+    #[test]
+    fn orders_match_gcd() {
+        for x in 0..60 {
+            let expected = 60 / gcd(x, 60);
+            assert_eq!(CyclicNum::<60>::from(x).order::<Phantom>(), expected);
+        }
+    }
+
+    #[test]
+    fn sylow_elem_orders() {
+        let decomp = SylowDecomp::<Phantom, 3, CyclicNum<60>>::new();
+        for a in 0..4 {
+            for b in 0..3 {
+                for c in 0..5 {
+                    let x = SylowElem::<Phantom, 3, CyclicNum<60>>::new([a, b, c]);
+                    let expected = x.to_product(&decomp).order::<Phantom>();
+                    assert_eq!(x.order(), expected);
+                    let [e2, e3, e5] = x.order_powers();
+                    assert_eq!(
+                        2u128.pow(e2 as u32) * 3u128.pow(e3 as u32) * 5u128.pow(e5 as u32),
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unreduced_order_powers() {
+        // The coordinates are 2^3, 3 * 3 and 5^2 + 1, which reduce to 0, 0 and 1.
+        let x = SylowElem::<Phantom, 3, CyclicNum<60>>::new([8, 9, 26]);
+        assert_eq!(x.order_powers(), [0, 0, 1]);
+        assert_eq!(x.order(), 5);
+    }
+
+    #[test]
+    fn sylow_from_product() {
+        let decomp = SylowDecomp::<Phantom, 3, CyclicNum<60>>::new();
+        for a in 0..4 {
+            for b in 0..3 {
+                for c in 0..5 {
+                    let x = SylowElem::<Phantom, 3, CyclicNum<60>>::new([a, b, c]);
+                    let y = SylowElem::from_product(&x.to_product(&decomp), &decomp);
+                    assert_eq!(y, Some(x));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sylow_generators() {
+        let decomp = SylowDecomp::<Phantom, 3, CyclicNum<60>>::new();
+        assert!(decomp.verify());
+        assert_eq!(*decomp.generator(0), CyclicNum::from(15));
+
+        let decomp = SylowDecomp::<Phantom, 1, CyclicNum<{ 1 << 100 }>>::new();
+        assert!(decomp.verify());
+        assert_eq!(decomp.generator(0).order::<Phantom>(), 1 << 100);
+    }
+
+    #[test]
+    fn stream_counts_are_totients() {
+        for (d, ds) in [
+            (1, [0, 0, 0]),
+            (4, [2, 0, 0]),
+            (6, [1, 1, 0]),
+            (60, [2, 1, 1]),
+        ] {
+            let count = SylowStreamBuilder::<Phantom, 3, CyclicNum<60>, ()>::new()
+                .add_target(&ds)
+                .into_iter()
+                .count() as u128;
+            let phi = (1..=d).filter(|x| gcd(*x, d) == 1).count() as u128;
+            assert_eq!(count, phi);
+            assert_eq!(
+                <CyclicNum<60> as SylowDecomposable<Phantom>>::count_elements_of_order(&ds),
+                phi
+            );
+        }
+    }
+}
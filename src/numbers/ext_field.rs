@@ -0,0 +1,478 @@
+use std::ops::*;
+
+use crate::numbers::*;
+use libbgs_util::*;
+
+/// An element of the finite field $\mathbb{F}\_{p^K}$.
+/// An element $x$ is represented in the polynomial basis as
+/// $x = a_0 + a_1\alpha + \cdots + a_{K - 1}\alpha^{K - 1}$, where $\alpha$ is a root of the monic
+/// irreducible polynomial [`ExtNum::MODULUS`].
+///
+/// Unlike [`QuadNum`], the `GroupElem` and `SylowDecomposable` implementations of `ExtNum`
+/// describe the full multiplicative group $\mathbb{F}\_{p^K}^\times$, of order $p^K - 1$, which
+/// must fit in a `u128`. The factorization of $p^K - 1$ is given by implementing [`Factor`].
+///
+/// # Example
+/// ```
+/// use libbgs::numbers::*;
+///
+/// struct Ph;
+/// impl Factor<Ph> for ExtNum<7, 3> {
+///     // 7^3 - 1 = 342 = 2 * 3^2 * 19
+///     const FACTORS: Factorization = Factorization::new(&[(2, 1), (3, 2), (19, 1)]);
+/// }
+///
+/// let decomp = SylowDecomp::<Ph, 3, ExtNum<7, 3>>::new();
+/// assert!(decomp.verify());
+/// let x = ExtNum::<7, 3>::from([1, 2, 3]);
+/// let y = SylowElem::from_product(&x, &decomp).unwrap().to_product(&decomp);
+/// assert_eq!(x, y);
+/// assert_eq!(x * x.inverse(), ExtNum::ONE);
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct ExtNum<const P: u128, const K: usize>(
+    /// The coefficients $a_0, \ldots, a_{K - 1}$, when writing this `ExtNum` as
+    /// $a_0 + a_1\alpha + \cdots + a_{K - 1}\alpha^{K - 1}$.
+    pub [FpNum<P>; K],
+);
+
+/// An iterator yielding every element of $\mathbb{F}\_{p^K}$ in order of Steinitz index.
+/// See [`ExtNum::all`].
+#[derive(Clone, Debug)]
+pub struct ExtNumIter<const P: u128, const K: usize> {
+    next: u128,
+    end: u128,
+}
+
+impl<const P: u128, const K: usize> ExtNum<P, K> {
+    /// The coefficients $m_0, \ldots, m_{K - 1}$ of the monic irreducible polynomial
+    /// $\alpha^K + m_{K - 1}\alpha^{K - 1} + \cdots + m_0$ defining this field.
+    /// This is the irreducible polynomial of least Steinitz index, found by Ben-Or's test when
+    /// the constant is first used.
+    pub const MODULUS: [FpNum<P>; K] = find_modulus::<P, K>();
+
+    /// The number of elements of $\mathbb{F}\_{p^K}$.
+    pub const ORDER: u128 = match checked_intpow::<0>(P, K as u128) {
+        Some(n) => n,
+        None => panic!("The field is too large for its order to fit in a u128."),
+    };
+
+    /// The constant zero.
+    pub const ZERO: ExtNum<P, K> = ExtNum([FpNum::ZERO; K]);
+
+    /// The root $\alpha$ of [`ExtNum::MODULUS`] generating this field over $\mathbb{F}\_p$.
+    pub const ALPHA: ExtNum<P, K> = ExtNum(mul_alpha(&unit::<P, K>(), &Self::MODULUS));
+
+    /// True if this number is zero; false otherwise.
+    pub fn is_zero(&self) -> bool {
+        *self == ExtNum::ZERO
+    }
+
+    /// Returns the Steinitz element of $\mathbb{F}\_{p^K}$ with index `i`, whose coefficients are
+    /// the base-$p$ digits of `i`, least significant first.
+    pub const fn steinitz(mut i: u128) -> ExtNum<P, K> {
+        let mut res = [FpNum::ZERO; K];
+        let mut j = 0;
+        while j < K {
+            res[j] = FpNum::from_u128(i % P);
+            i /= P;
+            j += 1;
+        }
+        ExtNum(res)
+    }
+
+    /// Returns the Steinitz index of this element; that is, the inverse of
+    /// [`ExtNum::steinitz`].
+    pub fn steinitz_index(&self) -> u128 {
+        self.0
+            .iter()
+            .rev()
+            .fold(0, |acc, a| acc * P + u128::from(*a))
+    }
+
+    /// Returns an iterator yielding every element of $\mathbb{F}\_{p^K}$ exactly once, in order of
+    /// Steinitz index, beginning with zero.
+    pub fn all() -> ExtNumIter<P, K> {
+        ExtNumIter {
+            next: 0,
+            end: Self::ORDER,
+        }
+    }
+
+    /// Returns the image of this element under the Frobenius automorphism, $x \mapsto x^p$.
+    pub fn frobenius(&self) -> ExtNum<P, K> {
+        self.pow(P)
+    }
+
+    /// Returns the norm of this element down to the prime subfield, that is, the product of its
+    /// conjugates, $x^{(p^K - 1) / (p - 1)}$.
+    pub fn norm(&self) -> FpNum<P> {
+        self.pow((Self::ORDER - 1) / (P - 1)).0[0]
+    }
+
+    /// Returns the trace of this element down to the prime subfield, that is, the sum of its
+    /// conjugates.
+    pub fn trace(&self) -> FpNum<P> {
+        let mut x = *self;
+        let mut res = x;
+        for _ in 1..K {
+            x = x.frobenius();
+            res = res + x;
+        }
+        res.0[0]
+    }
+}
+
+// Returns the polynomial 1, reduced modulo a polynomial of degree `K`.
+const fn unit<const P: u128, const K: usize>() -> [FpNum<P>; K] {
+    let mut res = [FpNum::ZERO; K];
+    if K > 0 {
+        res[0] = FpNum::from_u128(1);
+    }
+    res
+}
+
+// Returns $a\alpha$, reduced by $\alpha^K = -(m_{K - 1}\alpha^{K - 1} + \cdots + m_0)$.
+const fn mul_alpha<const P: u128, const K: usize>(
+    a: &[FpNum<P>; K],
+    m: &[FpNum<P>; K],
+) -> [FpNum<P>; K] {
+    let mut res = [FpNum::ZERO; K];
+    if K == 0 {
+        return res;
+    }
+    let top = a[K - 1];
+    let mut i = K;
+    while i > 0 {
+        i -= 1;
+        let shifted = if i == 0 { FpNum::ZERO } else { a[i - 1] };
+        res[i] = shifted.const_sub(&top.const_mul(&m[i]));
+    }
+    res
+}
+
+// Returns the product $ab$ modulo the polynomial with lower coefficients `m`, by Horner's rule
+// on the coefficients of `b`.
+const fn poly_mul_mod<const P: u128, const K: usize>(
+    a: &[FpNum<P>; K],
+    b: &[FpNum<P>; K],
+    m: &[FpNum<P>; K],
+) -> [FpNum<P>; K] {
+    let mut res = [FpNum::ZERO; K];
+    let mut i = K;
+    while i > 0 {
+        i -= 1;
+        res = mul_alpha(&res, m);
+        let mut j = 0;
+        while j < K {
+            res[j] = res[j].const_add(&a[j].const_mul(&b[i]));
+            j += 1;
+        }
+    }
+    res
+}
+
+const fn poly_pow_mod<const P: u128, const K: usize>(
+    a: &[FpNum<P>; K],
+    mut n: u128,
+    m: &[FpNum<P>; K],
+) -> [FpNum<P>; K] {
+    let mut x = *a;
+    let mut res = unit::<P, K>();
+    while n > 0 {
+        if n % 2 == 1 {
+            res = poly_mul_mod(&res, &x, m);
+        }
+        x = poly_mul_mod(&x, &x, m);
+        n >>= 1;
+    }
+    res
+}
+
+// True if `h` is a unit modulo the polynomial with lower coefficients `m`, that is, if
+// multiplication by `h` is invertible.
+// The columns of the matrix of multiplication by `h` are $h\alpha^j$; it is reduced to echelon
+// form to find its rank.
+const fn is_unit<const P: u128, const K: usize>(h: &[FpNum<P>; K], m: &[FpNum<P>; K]) -> bool {
+    let mut rows = [[FpNum::ZERO; K]; K];
+    let mut col = *h;
+    let mut j = 0;
+    while j < K {
+        let mut i = 0;
+        while i < K {
+            rows[i][j] = col[i];
+            i += 1;
+        }
+        col = mul_alpha(&col, m);
+        j += 1;
+    }
+
+    let mut c = 0;
+    while c < K {
+        let mut pivot = c;
+        while pivot < K && rows[pivot][c].raw() == 0 {
+            pivot += 1;
+        }
+        if pivot == K {
+            return false;
+        }
+        let tmp = rows[c];
+        rows[c] = rows[pivot];
+        rows[pivot] = tmp;
+        let Some(inv) = rows[c][c].const_inverse() else {
+            unreachable!();
+        };
+        let mut r = c + 1;
+        while r < K {
+            let f = rows[r][c].const_mul(&inv);
+            let mut k = c;
+            while k < K {
+                rows[r][k] = rows[r][k].const_sub(&f.const_mul(&rows[c][k]));
+                k += 1;
+            }
+            r += 1;
+        }
+        c += 1;
+    }
+    true
+}
+
+// Ben-Or's test: a polynomial $f$ of degree $K$ is irreducible if and only if
+// $\gcd(\alpha^{p^i} - \alpha, f) = 1$ for every $i \leq K / 2$, since otherwise $f$ has a factor
+// of degree dividing $i$.
+const fn is_irreducible<const P: u128, const K: usize>(m: &[FpNum<P>; K]) -> bool {
+    let alpha = mul_alpha(&unit::<P, K>(), m);
+    let mut frob = alpha;
+    let mut i = 1;
+    while i <= K / 2 {
+        frob = poly_pow_mod(&frob, P, m);
+        let mut h = frob;
+        let mut j = 0;
+        while j < K {
+            h[j] = h[j].const_sub(&alpha[j]);
+            j += 1;
+        }
+        if !is_unit(&h, m) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn find_modulus<const P: u128, const K: usize>() -> [FpNum<P>; K] {
+    assert!(K > 0, "An extension field must have positive degree.");
+    let mut i = 0;
+    loop {
+        let m = ExtNum::<P, K>::steinitz(i).0;
+        if is_irreducible(&m) {
+            return m;
+        }
+        i += 1;
+    }
+}
+
+impl<const P: u128, const K: usize> Iterator for ExtNumIter<P, K> {
+    type Item = ExtNum<P, K>;
+
+    fn next(&mut self) -> Option<ExtNum<P, K>> {
+        if self.next == self.end {
+            return None;
+        }
+        self.next += 1;
+        Some(ExtNum::steinitz(self.next - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = usize::try_from(self.end - self.next).ok();
+        (len.unwrap_or(usize::MAX), len)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<ExtNum<P, K>> {
+        self.next = u128::min(self.next.saturating_add(n as u128), self.end);
+        self.next()
+    }
+}
+
+impl<const P: u128, const K: usize> ExactSizeIterator for ExtNumIter<P, K> {}
+
+impl<const P: u128, const K: usize> GroupElem for ExtNum<P, K> {
+    const ONE: Self = ExtNum(unit::<P, K>());
+    const SIZE: u128 = Self::ORDER - 1;
+
+    fn multiply(&self, other: &ExtNum<P, K>) -> ExtNum<P, K> {
+        ExtNum(poly_mul_mod(&self.0, &other.0, &Self::MODULUS))
+    }
+
+    fn inverse(&self) -> ExtNum<P, K> {
+        if self.is_zero() {
+            panic!("Attempted to take the multiplicative inverse of zero.");
+        }
+        self.pow(Self::SIZE - 1)
+    }
+}
+
+impl<S, const P: u128, const K: usize> SylowDecomposable<S> for ExtNum<P, K>
+where
+    ExtNum<P, K>: Factor<S>,
+{
+    fn find_sylow_generator(i: usize) -> ExtNum<P, K> {
+        <ExtNum<P, K> as SylowDecomposable<S>>::find_sylow_generator_with(i, Seed::DEFAULT)
+    }
+
+    fn find_sylow_generator_with(i: usize, seed: Seed) -> ExtNum<P, K> {
+        (1..Self::ORDER)
+            .map(|j| ExtNum::steinitz(seed.shift(Self::ORDER, j)))
+            .filter(|c| !c.is_zero())
+            .find_map(|c| ExtNum::is_sylow_generator(&c, Self::FACTORS[i]))
+            .unwrap()
+    }
+
+    fn find_sylow_generators_with<const L: usize>(seed: Seed) -> [ExtNum<P, K>; L] {
+        let candidates = (1..Self::ORDER)
+            .map(|j| ExtNum::steinitz(seed.shift(Self::ORDER, j)))
+            .filter(|c| !c.is_zero());
+        Self::first_sylow_generators(candidates, [None; L])
+    }
+}
+
+impl<const P: u128, const K: usize> From<FpNum<P>> for ExtNum<P, K> {
+    fn from(value: FpNum<P>) -> ExtNum<P, K> {
+        let mut res = ExtNum::ZERO;
+        res.0[0] = value;
+        res
+    }
+}
+
+impl<const P: u128, const K: usize> From<[u128; K]> for ExtNum<P, K> {
+    fn from(value: [u128; K]) -> ExtNum<P, K> {
+        ExtNum(value.map(FpNum::from))
+    }
+}
+
+impl<const P: u128, const K: usize> Add<Self> for ExtNum<P, K> {
+    type Output = ExtNum<P, K>;
+    fn add(self, other: Self) -> ExtNum<P, K> {
+        ExtNum(std::array::from_fn(|i| self.0[i] + other.0[i]))
+    }
+}
+
+impl<const P: u128, const K: usize> Sub<Self> for ExtNum<P, K> {
+    type Output = ExtNum<P, K>;
+    fn sub(self, other: Self) -> ExtNum<P, K> {
+        ExtNum(std::array::from_fn(|i| self.0[i] - other.0[i]))
+    }
+}
+
+impl<const P: u128, const K: usize> Neg for ExtNum<P, K> {
+    type Output = ExtNum<P, K>;
+    fn neg(self) -> ExtNum<P, K> {
+        ExtNum(self.0.map(|a| -a))
+    }
+}
+
+impl<const P: u128, const K: usize> Mul<Self> for ExtNum<P, K> {
+    type Output = ExtNum<P, K>;
+    fn mul(self, other: Self) -> ExtNum<P, K> {
+        self.multiply(&other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streams::SylowStreamBuilder;
+    use std::collections::HashMap;
+
+    #[derive(PartialEq, Eq)]
+    struct Phantom {}
+
+    impl Factor<Phantom> for ExtNum<7, 3> {
+        const FACTORS: Factorization = Factorization::new(&[(2, 1), (3, 2), (19, 1)]);
+    }
+
+    impl Factor<Phantom> for ExtNum<3, 5> {
+        const FACTORS: Factorization = Factorization::new(&[(2, 1), (11, 2)]);
+    }
+
+    impl Factor<Phantom> for ExtNum<5, 4> {
+        const FACTORS: Factorization = Factorization::new(&[(2, 4), (3, 1), (13, 1)]);
+    }
+
+    fn is_field<const P: u128, const K: usize>() {
+        let units = ExtNum::<P, K>::all().skip(1).collect::<Vec<_>>();
+        assert_eq!(units.len() as u128, ExtNum::<P, K>::SIZE);
+        for x in &units {
+            assert_eq!(*x * x.inverse(), ExtNum::ONE);
+            assert_eq!(x.pow(P), x.frobenius());
+            assert_eq!(x.pow(ExtNum::<P, K>::ORDER), *x);
+            assert_eq!(
+                ExtNum::from(x.norm()),
+                x.pow((ExtNum::<P, K>::ORDER - 1) / (P - 1))
+            );
+        }
+    }
+
+    #[test]
+    fn forms_fields() {
+        is_field::<7, 3>();
+        is_field::<3, 5>();
+        is_field::<5, 4>();
+        is_field::<11, 1>();
+        assert_eq!(ExtNum::<7, 1>::MODULUS, [FpNum::ZERO]);
+    }
+
+    #[test]
+    fn steinitz_index_inverts_steinitz() {
+        for (i, x) in ExtNum::<5, 3>::all().enumerate() {
+            assert_eq!(x.steinitz_index(), i as u128);
+        }
+        assert_eq!(ExtNum::<5, 3>::all().len(), 125);
+        assert_eq!(ExtNum::<3, 5>::steinitz(3), ExtNum::ALPHA);
+    }
+
+    #[test]
+    fn rejects_reducible_moduli() {
+        // $x^2 + 1 = (x + 2)(x + 3)$ modulo 5, while $x^2 + 2$ is irreducible.
+        assert!(!is_irreducible::<5, 2>(
+            &FpNum::from_slice(&[1, 0]).try_into().unwrap()
+        ));
+        assert!(is_irreducible::<5, 2>(
+            &FpNum::from_slice(&[2, 0]).try_into().unwrap()
+        ));
+        // $x^4 + 1 = (x^2 + x + 2)(x^2 + 2x + 2)$ has no roots modulo 3, but is reducible.
+        assert!(!is_irreducible::<3, 4>(
+            &FpNum::from_slice(&[1, 0, 0, 0]).try_into().unwrap()
+        ));
+    }
+
+    #[test]
+    fn trace_is_linear() {
+        let xs = ExtNum::<5, 4>::all().step_by(37).collect::<Vec<_>>();
+        for x in &xs {
+            for y in &xs {
+                assert_eq!((*x + *y).trace(), x.trace() + y.trace());
+            }
+        }
+        assert_eq!(ExtNum::<5, 4>::ONE.trace(), FpNum::from(4));
+    }
+
+    #[test]
+    fn streams_elements_of_each_order() {
+        let decomp = SylowDecomp::<Phantom, 3, ExtNum<7, 3>>::new();
+        assert!(decomp.verify());
+
+        let mut orders = HashMap::new();
+        for x in ExtNum::<7, 3>::all().skip(1) {
+            *orders.entry(x.order::<Phantom>()).or_insert(0) += 1;
+        }
+        for (ds, ord) in [([1, 0, 0], 2), ([0, 2, 0], 9), ([1, 1, 1], 114)] {
+            let count = SylowStreamBuilder::<Phantom, 3, ExtNum<7, 3>, ()>::new()
+                .add_target(&ds)
+                .into_iter()
+                .inspect(|(x, _)| assert_eq!(x.to_product(&decomp).order::<Phantom>(), ord))
+                .count();
+            assert_eq!(count, orders[&ord]);
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use num_bigint::BigUint;
+
+use crate::numbers::BigFactorization;
+
+/// Algorithms for factoring integers at runtime, for orders which are not known when the program
+/// is compiled.
+/// The default backend is [`PollardRho`], which is pure Rust; others delegate to external
+/// computer algebra systems, which are far faster for integers with several large prime factors.
+///
+/// Requires the `bigint` feature.
+pub trait FactorBackend {
+    /// Returns the prime factorization of `n`, or `None` if this backend could not factor it.
+    /// The factorization of 1 is empty.
+    /// This method will panic if `n` is 0.
+    fn factor(&self, n: &BigUint) -> Option<BigFactorization>;
+
+    /// Returns the factorizations of $p - 1$ and $p + 1$, the orders of the groups of `FpNum` and
+    /// `QuadNum`.
+    /// This method will panic if `p` is less than 2.
+    fn factor_p_pm1(&self, p: &BigUint) -> Option<(BigFactorization, BigFactorization)> {
+        let one = BigUint::from(1u8);
+        assert!(*p > one, "p must be at least 2");
+        Some((self.factor(&(p - &one))?, self.factor(&(p + &one))?))
+    }
+}
+
+/// A pure Rust [`FactorBackend`], using trial division by small primes, then Pollard's rho
+/// algorithm with Brent's cycle detection, with the Miller-Rabin test for primality.
+/// Factoring gives up, returning `None`, when a composite resists `iterations` steps of the rho
+/// algorithm for every one of several starting points.
+///
+/// Primality is proven for integers below $3.3 \cdot 10^{24}$; larger factors are only
+/// probable primes, with error probability below $4^{-32}$.
+///
+/// Requires the `bigint` feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PollardRho {
+    /// The maximum number of steps of the rho algorithm for each starting point.
+    pub iterations: u64,
+}
+
+/// A [`FactorBackend`] running the `factor` function of [PARI/GP](https://pari.math.u-bordeaux.fr/)
+/// in a child process.
+///
+/// Requires the `pari` feature, and a `gp` executable at run time.
+#[cfg(feature = "pari")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PariGp {
+    /// The command used to run `gp`.
+    pub command: std::path::PathBuf,
+}
+
+const SMALL_PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+// The least strong pseudoprime to each of the first 13 prime bases.
+const PROVEN_BOUND: u128 = 3_317_044_064_679_887_385_961_981;
+
+impl Default for PollardRho {
+    fn default() -> PollardRho {
+        PollardRho {
+            iterations: 1 << 24,
+        }
+    }
+}
+
+impl FactorBackend for PollardRho {
+    fn factor(&self, n: &BigUint) -> Option<BigFactorization> {
+        assert!(*n != BigUint::ZERO, "cannot factor 0");
+        let mut primes = Vec::new();
+        let mut n = n.clone();
+        for p in SMALL_PRIMES {
+            while (&n % p) == BigUint::ZERO {
+                n /= p;
+                primes.push(BigUint::from(p));
+            }
+        }
+
+        let mut composites = vec![n];
+        while let Some(n) = composites.pop() {
+            if n == BigUint::from(1u8) {
+                continue;
+            }
+            if is_probable_prime(&n) {
+                primes.push(n);
+                continue;
+            }
+            let d = self.split(&n)?;
+            composites.push(&n / &d);
+            composites.push(d);
+        }
+
+        primes.sort();
+        let mut factors: Vec<(BigUint, usize)> = Vec::new();
+        for p in primes {
+            match factors.last_mut() {
+                Some((q, t)) if *q == p => *t += 1,
+                _ => factors.push((p, 1)),
+            }
+        }
+        Some(BigFactorization::new(factors))
+    }
+}
+
+impl PollardRho {
+    // Returns a nontrivial divisor of the odd composite `n`.
+    fn split(&self, n: &BigUint) -> Option<BigUint> {
+        let one = BigUint::from(1u8);
+        for c in 1..=16u32 {
+            let f = |x: &BigUint| (x * x + c) % n;
+            let mut y = BigUint::from(2u8);
+            let mut x = y.clone();
+            let mut q = one.clone();
+            let mut g = one.clone();
+            let mut ys = y.clone();
+            let mut r: u64 = 1;
+            let mut steps = 0;
+            // Brent's variant, batching the gcds over 128 steps at a time.
+            while g == one && steps < self.iterations {
+                x = y.clone();
+                for _ in 0..r {
+                    y = f(&y);
+                }
+                let mut k = 0;
+                while k < r && g == one {
+                    ys = y.clone();
+                    for _ in 0..(r - k).min(128) {
+                        y = f(&y);
+                        q = q * abs_diff(&x, &y) % n;
+                    }
+                    g = gcd(&q, n);
+                    k += 128;
+                }
+                steps += r;
+                r *= 2;
+            }
+            if g == *n {
+                // The batch overshot; retrace it one step at a time.
+                loop {
+                    ys = f(&ys);
+                    g = gcd(&abs_diff(&x, &ys), n);
+                    if g != one {
+                        break;
+                    }
+                }
+            }
+            if g != one && g != *n {
+                return Some(g);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(feature = "pari")]
+impl Default for PariGp {
+    fn default() -> PariGp {
+        PariGp {
+            command: "gp".into(),
+        }
+    }
+}
+
+#[cfg(feature = "pari")]
+impl FactorBackend for PariGp {
+    fn factor(&self, n: &BigUint) -> Option<BigFactorization> {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+
+        assert!(*n != BigUint::ZERO, "cannot factor 0");
+        let mut child = Command::new(&self.command)
+            .args(["-q", "-f", "-D", "colors=no"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+        writeln!(
+            child.stdin.take()?,
+            "v = factor({n}); for(i = 1, #v~, print(v[i, 1], \" \", v[i, 2]))"
+        )
+        .ok()?;
+        let output = child.wait_with_output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let factors = String::from_utf8(output.stdout)
+            .ok()?
+            .lines()
+            .map(|line| {
+                let (p, t) = line.trim().split_once(' ')?;
+                Some((p.parse().ok()?, t.parse().ok()?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let res = BigFactorization::new(factors);
+        (res.value() == n).then_some(res)
+    }
+}
+
+/// True if `n` is prime, with the caveats described on [`PollardRho`].
+pub(crate) fn is_probable_prime(n: &BigUint) -> bool {
+    let one = BigUint::from(1u8);
+    if *n <= one {
+        return false;
+    }
+    for p in SMALL_PRIMES {
+        if *n == BigUint::from(p) {
+            return true;
+        }
+        if (n % p) == BigUint::ZERO {
+            return false;
+        }
+    }
+    let n_minus_one = n - &one;
+    let s = n_minus_one.trailing_zeros().unwrap();
+    let d = &n_minus_one >> s;
+    // The first 13 primes suffice to prove primality below `PROVEN_BOUND`. Above it, no set of
+    // bases is known to suffice, so every base is used, and each base a composite passes cuts the
+    // chance of error by a factor of 4.
+    let bases = if *n < BigUint::from(PROVEN_BOUND) {
+        13
+    } else {
+        SMALL_PRIMES.len()
+    };
+    SMALL_PRIMES[..bases].iter().all(|a| {
+        let mut x = BigUint::from(*a).modpow(&d, n);
+        if x == one || x == n_minus_one {
+            return true;
+        }
+        for _ in 1..s {
+            x = &x * &x % n;
+            if x == n_minus_one {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+fn gcd(a: &BigUint, b: &BigUint) -> BigUint {
+    let (mut a, mut b) = (a.clone(), b.clone());
+    while b != BigUint::ZERO {
+        let r = &a % &b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+fn abs_diff(a: &BigUint, b: &BigUint) -> BigUint {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(n: u128) -> BigUint {
+        BigUint::from(n)
+    }
+
+    #[test]
+    fn primality() {
+        let primes = [2, 131, 137, 1_000_000_007, 1_000_000_000_000_000_124_399];
+        let composites = [
+            1,
+            561,
+            137 * 139,
+            3_215_031_751,
+            1_000_000_007 * 998_244_353,
+        ];
+        assert!(primes.iter().all(|p| is_probable_prime(&big(*p))));
+        assert!(composites.iter().all(|n| !is_probable_prime(&big(*n))));
+        // This passes the first 13 bases, and is only caught by the later ones.
+        assert!(!is_probable_prime(&big(PROVEN_BOUND)));
+    }
+
+    #[test]
+    fn factors_big_p_pm1() {
+        const BIG_P: u128 = 1_000_000_000_000_000_124_399;
+        let (minus, plus) = PollardRho::default().factor_p_pm1(&big(BIG_P)).unwrap();
+        let expected = [
+            (2, 1),
+            (7, 1),
+            (13, 1),
+            (29, 2),
+            (43, 1),
+            (705737, 1),
+            (215288719, 1),
+        ]
+        .map(|(p, t)| (big(p), t));
+        assert_eq!(minus.factors(), expected);
+        assert_eq!(*plus.value(), big(BIG_P + 1));
+        assert!(plus.factors().iter().all(|(p, _)| is_probable_prime(p)));
+    }
+
+    #[test]
+    fn factors_beyond_u128() {
+        let p = big(1_000_000_007);
+        let q = big(1_000_000_000_000_000_124_399);
+        let n = &p * &p * &q * big(12);
+        let res = PollardRho::default().factor(&n).unwrap();
+        assert_eq!(*res.value(), n);
+        assert_eq!(res.factors(), [(big(2), 2), (big(3), 1), (p, 2), (q, 1)]);
+        assert!(PollardRho::default().factor(&big(1)).unwrap().is_empty());
+    }
+}
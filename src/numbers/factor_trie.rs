@@ -1,3 +1,4 @@
+use std::fmt;
 use std::marker::PhantomData;
 
 use crate::numbers::Factor;
@@ -15,6 +16,12 @@ pub struct FactorTrie<S, const L: usize, C, T> {
     _phantom: PhantomData<(S, C)>,
 }
 
+/// An iterator over the nodes of a [`FactorTrie`], yielding each node's powers and owned data in
+/// a pre-order traversal.
+pub struct FactorTrieIntoIter<S, const L: usize, C, T> {
+    stack: Vec<FactorTrie<S, L, C, T>>,
+}
+
 impl<S, const L: usize, C> FactorTrie<S, L, C, ()>
 where
     C: Factor<S>,
@@ -50,6 +57,19 @@ where
         res
     }
 
+    /// Returns the data of the node for the divisor `d` of `C::FACTORS`, or `None` if `d` is not
+    /// a divisor, or its node is not in the trie.
+    /// The lookup walks down from this node, so this should be called on the root.
+    pub fn get_by_value(&self, d: u128) -> Option<&T> {
+        self.get(&C::powers_from_order(d)?)
+    }
+
+    /// Returns the data of the node for the divisor `d` of `C::FACTORS` mutably, as
+    /// [`FactorTrie::get_by_value`] does.
+    pub fn get_by_value_mut(&mut self, d: u128) -> Option<&mut T> {
+        self.get_mut(&C::powers_from_order(d)?)
+    }
+
     fn new_helper<F>(&mut self, t: [usize; L], f: &F) 
     where
         F: Fn(&[usize; L], usize) -> T,
@@ -96,6 +116,30 @@ impl<S, const L: usize, C, T> FactorTrie<S, L, C, T> {
         }))
     }
 
+    /// Returns the data of the node with powers `ds`, or `None` if there is no such node.
+    /// The lookup walks down from this node, so this should be called on the root.
+    pub fn get(&self, ds: &[usize; L]) -> Option<&T> {
+        let mut node = self;
+        for (i, d) in ds.iter().enumerate() {
+            for _ in 0..*d {
+                node = node.child(i)?;
+            }
+        }
+        Some(&node.data)
+    }
+
+    /// Returns the data of the node with powers `ds` mutably, or `None` if there is no such node.
+    /// The lookup walks down from this node, so this should be called on the root.
+    pub fn get_mut(&mut self, ds: &[usize; L]) -> Option<&mut T> {
+        let mut node = self;
+        for (i, d) in ds.iter().enumerate() {
+            for _ in 0..*d {
+                node = node.child_mut(i)?;
+            }
+        }
+        Some(&mut node.data)
+    }
+
     /// Updates a single node in the trie.
     pub fn update<F>(&mut self, t: &[usize; L], gen: F)
     where
@@ -126,6 +170,29 @@ impl<S, const L: usize, C, T> FactorTrie<S, L, C, T> {
         }
     }
 
+    /// Transforms this trie into an equivalent trie with the same shape, but all data mapped via
+    /// the fallible function `f`.
+    /// Returns the first error `f` returns, in a pre-order traversal.
+    pub fn try_map<U, E, F>(self, f: &F) -> Result<FactorTrie<S, L, C, U>, E>
+    where
+        F: Fn(T, &[usize; L], usize) -> Result<U, E>,
+    {
+        let data = f(self.data, &self.ds, self.i)?;
+        let mut children: [Option<Child<S, L, C, U>>; L] = std::array::from_fn(|_| None);
+        for (res, child) in children.iter_mut().zip(self.children) {
+            if let Some(n) = child {
+                *res = Some(Box::new(n.try_map(f)?));
+            }
+        }
+        Ok(FactorTrie {
+            i: self.i,
+            ds: self.ds,
+            data,
+            children,
+            _phantom: PhantomData,
+        })
+    }
+
     /// Returns a trie of borrowed data.
     pub fn as_ref(&self) -> FactorTrie<S, L, C, &T> {
         FactorTrie {
@@ -166,6 +233,68 @@ impl<S, const L: usize, C, T> FactorTrie<S, L, C, T> {
         });
     }
 
+    /// Runs `f` on each node, in a pre-order traversal, allowing the data to be modified in place.
+    pub fn for_each_mut<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(&mut T, [usize; L]),
+    {
+        f(&mut self.data, self.ds);
+        self.children.iter_mut().for_each(|o| match o {
+            None => {}
+            Some(b) => b.for_each_mut(f),
+        });
+    }
+
+    /// Removes every node more than `d` prime factors, counted with multiplicity, away from the
+    /// root.
+    /// The depth of a node is the sum of its `ds`.
+    pub fn truncate_depth(mut self, d: usize) -> Self {
+        if self.ds.iter().sum::<usize>() >= d {
+            self.children = std::array::from_fn(|_| None);
+        } else {
+            self.children = self
+                .children
+                .map(|o| o.map(|n| Box::new(n.truncate_depth(d))));
+        }
+        self
+    }
+
+    /// Returns the trie of the divisors supported on the primes at `indices` in the factorization.
+    /// Entry `k` of the `ds` of the new trie is entry `indices[k]` of the `ds` of the
+    /// corresponding node in this trie, and every node with a nonzero power of some other prime
+    /// is dropped.
+    /// For example, projecting onto every index except that of $2$ keeps only the odd divisors.
+    ///
+    /// The indices of the projected trie no longer agree with `C::FACTORS`, so it should not be
+    /// passed to methods which look up primes by index, such as `SylowStreamBuilder::new_with_trie`.
+    /// Panics unless `indices` is strictly increasing and every index is less than `L`.
+    pub fn project<const M: usize>(self, indices: &[usize; M]) -> FactorTrie<S, M, C, T> {
+        assert!(
+            indices.windows(2).all(|w| w[0] < w[1]) && indices.iter().all(|i| *i < L),
+            "Projection indices must be strictly increasing and in range."
+        );
+        fn help<S, const L: usize, const M: usize, C, T>(
+            node: FactorTrie<S, L, C, T>,
+            i: usize,
+            indices: &[usize; M],
+        ) -> FactorTrie<S, M, C, T> {
+            let mut children = node.children;
+            FactorTrie {
+                i,
+                ds: std::array::from_fn(|k| node.ds[indices[k]]),
+                data: node.data,
+                children: std::array::from_fn(|k| {
+                    children[indices[k]]
+                        .take()
+                        .map(|n| Box::new(help(*n, k, indices)))
+                }),
+                _phantom: PhantomData,
+            }
+        }
+        let i = indices.iter().position(|j| *j == self.i).unwrap_or(0);
+        help(self, i, indices)
+    }
+
     /// The index of this node's word in the prime factorization array.
     pub fn index(&self) -> usize {
         self.i
@@ -192,6 +321,77 @@ impl<S, const L: usize, C, T> FactorTrie<S, L, C, T> {
     }
 }
 
+impl<S, const L: usize, C, T> IntoIterator for FactorTrie<S, L, C, T> {
+    type Item = ([usize; L], T);
+    type IntoIter = FactorTrieIntoIter<S, L, C, T>;
+
+    fn into_iter(self) -> FactorTrieIntoIter<S, L, C, T> {
+        FactorTrieIntoIter { stack: vec![self] }
+    }
+}
+
+impl<S, const L: usize, C, T> Iterator for FactorTrieIntoIter<S, L, C, T> {
+    type Item = ([usize; L], T);
+
+    fn next(&mut self) -> Option<([usize; L], T)> {
+        let node = self.stack.pop()?;
+        self.stack
+            .extend(node.children.into_iter().rev().flatten().map(|n| *n));
+        Some((node.ds, node.data))
+    }
+}
+
+impl<S, const L: usize, C, T: fmt::Debug> FactorTrie<S, L, C, T> {
+    // Writes this node and its descendants, one per line, indented by depth.
+    fn fmt_tree<F>(&self, f: &mut fmt::Formatter<'_>, depth: usize, label: &F) -> fmt::Result
+    where
+        F: Fn(&[usize; L]) -> String,
+    {
+        writeln!(
+            f,
+            "{:indent$}{}: {:?}",
+            "",
+            label(&self.ds),
+            self.data,
+            indent = 2 * depth
+        )?;
+        for child in self.children.iter().flatten() {
+            child.fmt_tree(f, depth + 1, label)?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders the trie as an indented tree, with one node per line, labelled by its powers.
+impl<S, const L: usize, C, T: fmt::Debug> fmt::Debug for FactorTrie<S, L, C, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_tree(f, 0, &|ds| format!("{ds:?}"))
+    }
+}
+
+/// Renders the trie as an indented tree, with one node per line, labelled by its divisor as a
+/// product of prime powers.
+impl<S, const L: usize, C: Factor<S>, T: fmt::Debug> fmt::Display for FactorTrie<S, L, C, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_tree(f, 0, &|ds| {
+            let powers = ds
+                .iter()
+                .enumerate()
+                .filter(|(_, d)| **d > 0)
+                .map(|(i, d)| match (C::FACTORS[i].0, d) {
+                    (p, 1) => format!("{p}"),
+                    (p, d) => format!("{p}^{d}"),
+                })
+                .collect::<Vec<_>>();
+            if powers.is_empty() {
+                String::from("1")
+            } else {
+                powers.join(" * ")
+            }
+        })
+    }
+}
+
 impl<S, const L: usize, C, T: Clone> Clone for FactorTrie<S, L, C, T> {
     fn clone(&self) -> Self {
         FactorTrie {
@@ -203,3 +403,120 @@ impl<S, const L: usize, C, T: Clone> Clone for FactorTrie<S, L, C, T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers::*;
+
+    #[derive(PartialEq, Eq)]
+    struct Phantom {}
+
+    impl_factors!(Phantom, 61);
+
+    fn nodes<S, const L: usize, C, T>(trie: &FactorTrie<S, L, C, T>) -> Vec<[usize; L]> {
+        let mut res = Vec::new();
+        trie.for_each(&mut |_, ds| res.push(ds));
+        res.sort();
+        res
+    }
+
+    #[test]
+    fn truncates_depth() {
+        // 60 = 2^2 * 3 * 5
+        let trie = FactorTrie::<Phantom, 3, FpNum<61>, ()>::new();
+        assert_eq!(nodes(&trie).len(), 12);
+        let shallow = trie.clone().truncate_depth(1);
+        assert_eq!(
+            nodes(&shallow),
+            vec![[0, 0, 0], [0, 0, 1], [0, 1, 0], [1, 0, 0]]
+        );
+        assert_eq!(nodes(&trie.clone().truncate_depth(4)), nodes(&trie));
+        assert_eq!(nodes(&trie.truncate_depth(0)), vec![[0, 0, 0]]);
+    }
+
+    #[test]
+    fn gets_by_value() {
+        let mut trie = FactorTrie::<Phantom, 3, FpNum<61>, u128>::new_with(|ds, _| {
+            <FpNum<61> as Factor<Phantom>>::FACTORS.from_powers(ds)
+        });
+        for d in 1..=60 {
+            let expected = if 60 % d == 0 { Some(&d) } else { None };
+            assert_eq!(trie.get_by_value(d), expected);
+        }
+        assert_eq!(trie.get_by_value(0), None);
+        assert_eq!(trie.get_by_value(120), None);
+        assert_eq!(trie.get(&[2, 1, 1]), Some(&60));
+        assert_eq!(trie.clone().truncate_depth(2).get_by_value(30), None);
+
+        *trie.get_by_value_mut(12).unwrap() = 0;
+        assert_eq!(trie.get(&[2, 1, 0]), Some(&0));
+        assert_eq!(trie.get_mut(&[3, 0, 0]), None);
+    }
+
+    #[test]
+    fn into_iter_matches_for_each() {
+        let trie = FactorTrie::<Phantom, 3, FpNum<61>, u128>::new_with(|ds, _| {
+            <FpNum<61> as Factor<Phantom>>::FACTORS.from_powers(ds)
+        });
+        let mut expected = Vec::new();
+        trie.for_each(&mut |d, ds| expected.push((ds, *d)));
+        assert_eq!(trie.into_iter().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn try_maps() {
+        let trie = FactorTrie::<Phantom, 3, FpNum<61>, ()>::new();
+        let strs = trie.clone().map(&|_, ds, _| {
+            <FpNum<61> as Factor<Phantom>>::FACTORS
+                .from_powers(ds)
+                .to_string()
+        });
+        let parsed = strs.clone().try_map(&|s, _, _| s.parse::<u128>()).unwrap();
+        assert_eq!(parsed.get_by_value(12), Some(&12));
+
+        let bad = strs.map(&|s, ds, _| {
+            if ds == &[1, 1, 0] {
+                String::from("six")
+            } else {
+                s
+            }
+        });
+        assert!(bad.try_map(&|s, _, _| s.parse::<u128>()).is_err());
+    }
+
+    #[test]
+    fn prints_tree() {
+        let trie = FactorTrie::<Phantom, 3, FpNum<61>, bool>::new_with(|ds, _| ds[0] == 0)
+            .truncate_depth(2);
+        let expected = "\
+1: true
+  2: false
+    2^2: false
+    2 * 3: false
+    2 * 5: false
+  3: true
+    3 * 5: true
+  5: true
+";
+        assert_eq!(trie.to_string(), expected);
+        let debug = format!("{trie:?}");
+        assert!(debug.starts_with("[0, 0, 0]: true\n  [1, 0, 0]: false\n    [2, 0, 0]: false\n"));
+        assert_eq!(debug.lines().count(), 8);
+    }
+
+    #[test]
+    fn projects() {
+        let trie = FactorTrie::<Phantom, 3, FpNum<61>, u128>::new_with(|ds, _| {
+            <FpNum<61> as Factor<Phantom>>::FACTORS.from_powers(ds)
+        });
+        let odd = trie.project(&[1, 2]);
+        assert_eq!(nodes(&odd), vec![[0, 0], [0, 1], [1, 0], [1, 1]]);
+        let mut divisors = Vec::new();
+        odd.for_each(&mut |d, _| divisors.push(*d));
+        divisors.sort();
+        assert_eq!(divisors, vec![1, 3, 5, 15]);
+        assert_eq!(odd.child(0).unwrap().index(), 0);
+        assert_eq!(odd.child(0).unwrap().child(1).unwrap().ds(), &[1, 1]);
+    }
+}
@@ -1,7 +1,7 @@
 use std::ops::Index;
 
 use crate::streams::DivisorStream;
-use libbgs_util::intpow;
+use libbgs_util::{checked_intpow, intpow, lcm};
 
 /// When called with phantom type marker `Ph` and a list of integers, each integer `P` is turned
 /// into an implementation of `Factor<Ph> for FpNum<P>` and `Factor<Ph> for QuadNum<P>`.
@@ -21,6 +21,88 @@ macro_rules! impl_factors {
 }
 pub use impl_factors;
 
+/// When called with phantom type marker `Ph` and a list of integers, each integer `N` is turned
+/// into an implementation of `Factor<Ph> for CyclicNum<N>`.
+#[macro_export]
+macro_rules! impl_cyclic_factors {
+    ($mrk:ident, $($n:literal),+ $(,)?) => {$(
+        impl Factor<$mrk> for CyclicNum<$n> {
+            const FACTORS: Factorization = Factorization::new(make_factor!($n));
+        }
+    )+};
+}
+pub use impl_cyclic_factors;
+
+/// When called with phantom type marker `Ph` and a prime `P`, defines type aliases for the Sylow
+/// decompositions of $\mathbb{F}\_p^\times$ and of the norm 1 subgroup of $\mathbb{F}\_{p^2}^\times$,
+/// and for the types built on them, with the number of prime factors of $p \pm 1$ filled in.
+/// This avoids writing `{ FpNum::<P>::LENGTH }` as a const generic argument, which requires
+/// `generic_const_exprs` in a generic context.
+///
+/// The constants `HYPER_LENGTH` and `ELLIP_LENGTH`, and the types `HyperDecomp`, `HyperElem`,
+/// `HyperTrie<T>`, and `HyperStreamBuilder<T>`, along with their `Ellip` counterparts, are
+/// defined in the current module; to define them for several primes, call this macro once in a
+/// module for each prime.
+///
+/// # Example
+/// ```
+/// use libbgs::numbers::*;
+///
+/// pub struct Ph;
+/// impl_factors!(Ph, 13);
+///
+/// mod p13 {
+///     libbgs::numbers::sylow_types!(super::Ph, 13);
+/// }
+///
+/// fn main() {
+///     let decomp = p13::HyperDecomp::new();
+///     let x = p13::HyperElem::from_product(&FpNum::from(5), &decomp).unwrap();
+///     assert_eq!(x.order(), 4);
+///     assert_eq!((p13::HYPER_LENGTH, p13::ELLIP_LENGTH), (2, 2));
+/// }
+/// ```
+#[macro_export]
+macro_rules! sylow_types {
+    ($mrk:ty, $p:literal) => {
+        /// The number of distinct prime factors of $p - 1$.
+        pub const HYPER_LENGTH: usize =
+            <$crate::numbers::FpNum<$p> as $crate::numbers::Factor<$mrk>>::LENGTH;
+        /// The number of distinct prime factors of $p + 1$.
+        pub const ELLIP_LENGTH: usize =
+            <$crate::numbers::QuadNum<$p> as $crate::numbers::Factor<$mrk>>::LENGTH;
+        /// The Sylow decomposition of $\mathbb{F}\_p^\times$.
+        pub type HyperDecomp =
+            $crate::numbers::SylowDecomp<$mrk, HYPER_LENGTH, $crate::numbers::FpNum<$p>>;
+        /// The Sylow decomposition of the norm 1 subgroup of $\mathbb{F}\_{p^2}^\times$.
+        pub type EllipDecomp =
+            $crate::numbers::SylowDecomp<$mrk, ELLIP_LENGTH, $crate::numbers::QuadNum<$p>>;
+        /// An element of [`HyperDecomp`].
+        pub type HyperElem =
+            $crate::numbers::SylowElem<$mrk, HYPER_LENGTH, $crate::numbers::FpNum<$p>>;
+        /// An element of [`EllipDecomp`].
+        pub type EllipElem =
+            $crate::numbers::SylowElem<$mrk, ELLIP_LENGTH, $crate::numbers::QuadNum<$p>>;
+        /// A trie of the divisors of $p - 1$.
+        pub type HyperTrie<T = ()> =
+            $crate::numbers::FactorTrie<$mrk, HYPER_LENGTH, $crate::numbers::FpNum<$p>, T>;
+        /// A trie of the divisors of $p + 1$.
+        pub type EllipTrie<T = ()> =
+            $crate::numbers::FactorTrie<$mrk, ELLIP_LENGTH, $crate::numbers::QuadNum<$p>, T>;
+        /// A builder of streams of elements of [`HyperDecomp`].
+        pub type HyperStreamBuilder<T = ()> =
+            $crate::streams::SylowStreamBuilder<$mrk, HYPER_LENGTH, $crate::numbers::FpNum<$p>, T>;
+        /// A builder of streams of elements of [`EllipDecomp`].
+        pub type EllipStreamBuilder<T = ()> = $crate::streams::SylowStreamBuilder<
+            $mrk,
+            ELLIP_LENGTH,
+            $crate::numbers::QuadNum<$p>,
+            T,
+        >;
+    };
+}
+pub use sylow_types;
+
 /// A prime power decomposition of a positive integer.
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Factorization {
@@ -41,15 +123,52 @@ pub struct Factorization {
 pub trait Factor<S> {
     /// The prime factorization of this object.
     const FACTORS: Factorization;
+
+    /// The number of distinct prime factors of `FACTORS`.
+    /// See [`sylow_types`] for using this as a const generic argument on stable Rust.
+    const LENGTH: usize = Self::FACTORS.len();
+
+    /// Converts an array of powers on the prime factors into the divisor of `FACTORS` they
+    /// represent.
+    /// Returns 0 if any power exceeds the power of the corresponding prime in `FACTORS`.
+    fn order_from_powers<const L: usize>(ds: &[usize; L]) -> u128 {
+        Self::FACTORS.from_powers(ds)
+    }
+
+    /// Converts a divisor of `FACTORS` into the array of powers on its prime factors.
+    /// Returns `None` if `n` does not divide the product of the first `L` prime powers of
+    /// `FACTORS`.
+    /// This method will panic if `L` is larger than the number of prime factors.
+    fn powers_from_order<const L: usize>(mut n: u128) -> Option<[usize; L]> {
+        if n == 0 {
+            return None;
+        }
+        let mut res = [0; L];
+        for (i, e) in res.iter_mut().enumerate() {
+            let (p, t) = Self::FACTORS[i];
+            while *e < t && n.is_multiple_of(p) {
+                n /= p;
+                *e += 1;
+            }
+        }
+        (n == 1).then_some(res)
+    }
 }
 
 impl Factorization {
     /// Creates a new factorization from the given prime powers.
+    /// This method will panic if the value of the factorization does not fit into a `u128`.
     pub const fn new(factors: &'static [(u128, usize)]) -> Factorization {
-        let mut value = 1;
+        let mut value: u128 = 1;
         let mut i = 0;
         while i < factors.len() {
-            value *= intpow::<0>(factors[i].0, factors[i].1 as u128);
+            let Some(pow) = checked_intpow::<0>(factors[i].0, factors[i].1 as u128) else {
+                panic!("Factorization value overflows a u128.");
+            };
+            let Some(prod) = value.checked_mul(pow) else {
+                panic!("Factorization value overflows a u128.");
+            };
+            value = prod;
             i += 1;
         }
         Factorization {
@@ -145,6 +264,28 @@ impl Factorization {
         }
         res
     }
+
+    /// Returns $\lambda$(`&self`), the Carmichael function of this integer.
+    /// The Carmichael function $\lambda(n)$ is the exponent of the group of units
+    /// $(\mathbb{Z} / n\mathbb{Z})^\times$, that is, the smallest positive integer $m$ such that
+    /// $a^m \equiv 1 \mod n$ for every $a$ relatively prime to $n$. See OEIS sequence [A002322].
+    ///
+    /// [A002322]: https://oeis.org/A002322
+    pub const fn carmichael(&self) -> u128 {
+        let mut res = 1;
+        let mut i = 0;
+        while i < self.factors.len() {
+            let (p, t) = self.factors[i];
+            let lambda = if p == 2 && t >= 3 {
+                intpow::<0>(2, (t - 2) as u128)
+            } else {
+                intpow::<0>(p, (t - 1) as u128) * (p - 1)
+            };
+            res = lcm(res, lambda);
+            i += 1;
+        }
+        res
+    }
 }
 
 impl Index<usize> for Factorization {
@@ -154,3 +295,94 @@ impl Index<usize> for Factorization {
         &self.factors[index]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers::*;
+    use libbgs_util::*;
+
+    #[derive(PartialEq, Eq)]
+    struct Phantom {}
+
+    impl_factors!(Phantom, 61);
+
+    #[test]
+    fn carmichael() {
+        assert_eq!(Factorization::new(&[]).carmichael(), 1);
+        assert_eq!(Factorization::new(&[(2, 1)]).carmichael(), 1);
+        assert_eq!(Factorization::new(&[(2, 2)]).carmichael(), 2);
+        assert_eq!(Factorization::new(&[(2, 5)]).carmichael(), 8);
+        assert_eq!(
+            Factorization::new(&[(2, 3), (3, 2), (5, 1)]).carmichael(),
+            12
+        );
+        assert_eq!(
+            Factorization::new(&[(3, 1), (5, 1), (7, 1)]).carmichael(),
+            12
+        );
+    }
+
+    #[test]
+    fn powers_round_trip() {
+        for d in (1..=60).filter(|d| 60 % d == 0) {
+            let ds = <FpNum<61> as Factor<Phantom>>::powers_from_order::<3>(d).unwrap();
+            assert_eq!(<FpNum<61> as Factor<Phantom>>::order_from_powers(&ds), d);
+        }
+        assert_eq!(
+            <FpNum<61> as Factor<Phantom>>::powers_from_order::<3>(12),
+            Some([2, 1, 0])
+        );
+        assert_eq!(
+            <FpNum<61> as Factor<Phantom>>::powers_from_order::<3>(0),
+            None
+        );
+        assert_eq!(
+            <FpNum<61> as Factor<Phantom>>::powers_from_order::<3>(8),
+            None
+        );
+        assert_eq!(
+            <FpNum<61> as Factor<Phantom>>::powers_from_order::<3>(7),
+            None
+        );
+        assert_eq!(
+            <FpNum<61> as Factor<Phantom>>::powers_from_order::<2>(5),
+            None
+        );
+        assert_eq!(
+            <FpNum<61> as Factor<Phantom>>::order_from_powers(&[3, 0, 0]),
+            0
+        );
+    }
+
+    #[allow(dead_code)]
+    mod p61 {
+        use super::Phantom;
+        sylow_types!(Phantom, 61);
+    }
+
+    #[test]
+    fn sylow_types() {
+        assert_eq!((p61::HYPER_LENGTH, p61::ELLIP_LENGTH), (3, 2));
+        let decomp = p61::EllipDecomp::new();
+        assert!(decomp.verify());
+        let count = p61::HyperStreamBuilder::new()
+            .add_target(&[2, 1, 0])
+            .into_iter()
+            .count();
+        assert_eq!(count, 4);
+        let trie = p61::HyperTrie::new();
+        assert_eq!(trie.ds(), &[0; 3]);
+    }
+
+    #[test]
+    fn carmichael_kills_units() {
+        let factors = <FpNum<61> as Factor<Phantom>>::FACTORS;
+        // The units of Z / 60Z are killed by the Carmichael function of 60.
+        let lambda = factors.carmichael();
+        assert_eq!(lambda, 4);
+        for a in (1..60).filter(|a| mod_inverse(*a, 60).is_some()) {
+            assert_eq!(intpow::<60>(a, lambda), 1);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use crate::numbers::GroupElem;
+
+// The most teeth of a comb; each table holds at most 2^MAX_TEETH elements.
+const MAX_TEETH: u32 = 8;
+
+/// Precomputed powers of a fixed element $g$ of a group, for raising $g$ to many different powers
+/// up to a fixed maximum.
+///
+/// This is the comb method of Lim and Lee: the $b$ bits of an exponent are split into $h$ rows of
+/// $a = \lceil b / h \rceil$ bits, and the table holds, for each subset of the rows, the product of
+/// $g^{2^{a i}}$ over the rows $i$ in the subset.
+/// Each power then takes $a$ squarings and $a$ multiplications, reading one column of bits at a
+/// time, rather than the $b$ squarings of [`GroupElem::pow`].
+/// The table holds $2^h$ elements, with $h$ at most 8.
+///
+/// # Example
+/// ```
+/// use libbgs::numbers::{FixedBase, FpNum, GroupElem};
+/// let g = FpNum::<1009>::from(11);
+/// let table = FixedBase::new(g, 1008);
+/// assert!((0..=1008).all(|n| table.pow(n) == g.pow(n)));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixedBase<C> {
+    base: C,
+    max_exp: u128,
+    // The number of bits in each row.
+    width: u32,
+    table: Vec<C>,
+}
+
+impl<C: GroupElem> FixedBase<C> {
+    /// Precomputes the powers of `base` needed to raise it to any power up to `max_exp`.
+    pub fn new(base: C, max_exp: u128) -> FixedBase<C> {
+        let bits = u128::BITS - max_exp.leading_zeros();
+        let teeth = bits.clamp(1, MAX_TEETH);
+        let width = bits.div_ceil(teeth).max(1);
+        let mut table = Vec::with_capacity(1 << teeth);
+        table.push(C::ONE);
+        // After row i, the table holds every subset of the rows up to i, and `row` is
+        // $g^{2^{a (i + 1)}}$.
+        let mut row = base.clone();
+        for _ in 0..teeth {
+            for j in 0..table.len() {
+                table.push(table[j].multiply(&row));
+            }
+            for _ in 0..width {
+                row = row.multiply(&row);
+            }
+        }
+        FixedBase {
+            base,
+            max_exp,
+            width,
+            table,
+        }
+    }
+
+    /// Returns the element this table raises to powers.
+    pub fn base(&self) -> &C {
+        &self.base
+    }
+
+    /// Returns the greatest power this table can raise its base to.
+    pub fn max_exp(&self) -> u128 {
+        self.max_exp
+    }
+
+    /// Raises the base to the power of `n`.
+    /// This method will panic if `n` is greater than [`FixedBase::max_exp`].
+    pub fn pow(&self, n: u128) -> C {
+        FixedBase::multi_pow(std::slice::from_ref(self), &[n])
+    }
+
+    /// Returns the product of the base of `tables[i]` raised to the power of `exps[i]`, over every
+    /// `i`.
+    /// The columns of every table are read together, so the squarings are shared between them.
+    /// This method will panic if `tables` and `exps` have different lengths, or if any exponent
+    /// is greater than the `max_exp` of its table.
+    pub fn multi_pow(tables: &[FixedBase<C>], exps: &[u128]) -> C {
+        assert_eq!(
+            tables.len(),
+            exps.len(),
+            "There must be one exponent for each table."
+        );
+        for (t, n) in tables.iter().zip(exps) {
+            assert!(
+                *n <= t.max_exp,
+                "The exponent {n} is greater than the maximum {}.",
+                t.max_exp
+            );
+        }
+        let columns = tables.iter().map(|t| t.width).max().unwrap_or(0);
+        let mut res = C::ONE;
+        for col in (0..columns).rev() {
+            res = res.multiply(&res);
+            for (t, n) in tables.iter().zip(exps) {
+                if col >= t.width {
+                    continue;
+                }
+                let mut idx = 0;
+                let mut bits = *n >> col;
+                let mut i = 0;
+                while bits > 0 {
+                    idx |= ((bits & 1) as usize) << i;
+                    bits >>= t.width;
+                    i += 1;
+                }
+                if idx > 0 {
+                    res = res.multiply(&t.table[idx]);
+                }
+            }
+        }
+        res
+    }
+
+    /// True if the table is consistent with its base, recomputing it; false otherwise.
+    pub fn verify(&self) -> bool {
+        *self == FixedBase::new(self.base.clone(), self.max_exp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers::*;
+
+    const BIG_P: u128 = 1_000_000_000_000_000_124_399;
+
+    #[test]
+    fn matches_pow() {
+        let g = FpNum::<BIG_P>::from(3);
+        for max_exp in [0, 1, 2, 255, 256, 1 << 20, BIG_P - 2] {
+            let table = FixedBase::new(g, max_exp);
+            assert!(table.table.len() <= 256);
+            for n in [0, 1, max_exp / 3, max_exp.saturating_sub(1), max_exp] {
+                let n = n.min(max_exp);
+                assert_eq!(table.pow(n), g.pow(n), "{n} <= {max_exp}");
+            }
+        }
+        let table = FixedBase::new(QuadNum::<BIG_P>::from((3, 5)), u128::MAX);
+        assert_eq!(table.pow(u128::MAX), QuadNum::from((3, 5)).pow(u128::MAX));
+        assert!(table.verify());
+    }
+
+    #[test]
+    fn multi_pow_matches_pow() {
+        let tables = [(2, 10), (3, 1 << 40), (5, BIG_P - 2)]
+            .map(|(g, max)| FixedBase::new(FpNum::<BIG_P>::from(g), max));
+        let exps = [7, (1 << 40) - 3, BIG_P / 5];
+        let naive = tables
+            .iter()
+            .zip(exps)
+            .fold(FpNum::ONE, |acc, (t, n)| acc * t.base().pow(n));
+        assert_eq!(FixedBase::multi_pow(&tables, &exps), naive);
+        assert_eq!(FixedBase::<FpNum<BIG_P>>::multi_pow(&[], &[]), FpNum::ONE);
+    }
+
+    #[test]
+    #[should_panic]
+    fn exponent_too_large() {
+        FixedBase::new(FpNum::<13>::from(2), 5).pow(6);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::ops::*;
 
 use crate::numbers::*;
@@ -35,6 +36,39 @@ impl<const P: u128> FpNum<P> {
         self.const_pow((P - 1) / 2)
     }
 
+    /// Returns the Legendre symbol of each element of `xs`, as `1` for nonzero quadratic residues,
+    /// `-1` for nonresidues, and `0` for zero.
+    ///
+    /// Rather than exponentiating, this computes each symbol by quadratic reciprocity, in the
+    /// manner of the binary GCD algorithm.
+    /// When `P` is small and `xs` is long enough, a table of the quadratic residues modulo `P` is
+    /// built once and shared between the elements instead.
+    /// ```
+    /// use libbgs::numbers::FpNum;
+    /// let xs = [0, 1, 2, 3, 4, 5, 6].map(FpNum::<7>::from);
+    /// assert_eq!(FpNum::legendre_batch(&xs), vec![0, 1, 1, -1, 1, -1, -1]);
+    /// ```
+    pub fn legendre_batch(xs: &[FpNum<P>]) -> Vec<i8> {
+        if P == 2 {
+            return xs.iter().map(|x| (*x != FpNum::ZERO) as i8).collect();
+        }
+        if P <= RESIDUE_TABLE_LIMIT && (xs.len() as u128) * 8 >= P {
+            let mut table = vec![false; P as usize];
+            for i in 1..=P / 2 {
+                table[(i * i % P) as usize] = true;
+            }
+            return xs
+                .iter()
+                .map(|x| match u128::from(*x) {
+                    0 => 0,
+                    x if table[x as usize] => 1,
+                    _ => -1,
+                })
+                .collect();
+        }
+        xs.iter().map(|x| jacobi(u128::from(*x), P)).collect()
+    }
+
     /// Calculates this number's square root, if it is a quadratic residue; otherwise, returns
     /// `None`.
     pub const fn int_sqrt(&self) -> Option<FpNum<P>> {
@@ -45,7 +79,7 @@ impl<const P: u128> FpNum<P> {
         // Tonelli algorithm
         let mut s = 0;
         let mut q = P - 1;
-        while q % 2 == 0 {
+        while q.is_multiple_of(2) {
             s += 1;
             q /= 2;
         }
@@ -59,7 +93,7 @@ impl<const P: u128> FpNum<P> {
             i += 1;
         };
         let mut c = z.const_pow(q);
-        let mut r = self.const_pow((q + 1) / 2);
+        let mut r = self.const_pow(q.div_ceil(2));
         let mut t = self.const_pow(q);
         let mut m = s;
 
@@ -83,8 +117,174 @@ impl<const P: u128> FpNum<P> {
         Some(r)
     }
 
+    /// Returns an `n`-th root of this number, that is, some $y$ with $y^n = x$, or `None` if
+    /// there is none.
+    ///
+    /// Writing $g = \gcd(n, p - 1)$, this number has an `n`-th root exactly when it is a $g$-th
+    /// power.
+    /// The $g$-th root is taken one prime factor $q$ of $g$ at a time, by the method of Adleman,
+    /// Manders and Miller, a generalization of the Tonelli algorithm, and then raised to the
+    /// inverse of $n / g$ modulo $(p - 1) / g$.
+    /// The prime factors of $g$ are found by trial division, which takes up to $\sqrt{g}$ steps
+    /// when $g$ has a large prime factor: about $2^{31.5}$ for $g$ near $2^{63}$, which dominates
+    /// the rest of the cost. See [`FpNum::nth_root_factored`] to take them from a factorization of
+    /// $p - 1$ instead.
+    /// For each prime $q$ dividing $g$, with $q^s$ the largest power of $q$ dividing $p - 1$, each
+    /// $q$-th root takes $O(s \log p)$ multiplications and $s - 1$ discrete logarithms in the
+    /// subgroup of order $q$ by [`FpNum::dlog`], each of which stores up to $\min(q, \sqrt{p})$
+    /// powers; so the cost is dominated by the large primes $q$ whose squares divide $p - 1$.
+    /// Panics if `n` is zero.
+    /// ```
+    /// use libbgs::numbers::*;
+    /// // 1009 - 1 = 2^4 * 3^2 * 7, so only a third of the nonzero numbers are cubes.
+    /// let x = FpNum::<1009>::from(11);
+    /// let y = (x * x * x).nth_root(3).unwrap();
+    /// assert_eq!(y * y * y, x * x * x);
+    /// assert_eq!(x.nth_root(3), None);
+    /// // Every number is a fifth power.
+    /// assert_eq!(x.nth_root(5).unwrap().pow(5), x);
+    /// ```
+    pub fn nth_root(&self, n: u128) -> Option<FpNum<P>> {
+        let mut g = gcd(n, P - 1);
+        let mut factors = Vec::new();
+        let mut q = 2;
+        while q <= g / q {
+            let mut t = 0;
+            while g.is_multiple_of(q) {
+                g /= q;
+                t += 1;
+            }
+            if t > 0 {
+                factors.push((q, t));
+            }
+            q += 1;
+        }
+        if g > 1 {
+            factors.push((g, 1));
+        }
+        self.nth_root_from(n, &factors)
+    }
+
+    /// Returns an `n`-th root of this number, as [`FpNum::nth_root`] does, taking the prime
+    /// factors of $\gcd(n, p - 1)$ from the factorization of $p - 1$ rather than by trial
+    /// division.
+    /// Panics if `n` is zero.
+    pub fn nth_root_factored<S>(&self, n: u128) -> Option<FpNum<P>>
+    where
+        FpNum<P>: Factor<S>,
+    {
+        let factors = <FpNum<P> as Factor<S>>::FACTORS
+            .factors()
+            .iter()
+            .map(|(q, t)| {
+                let mut m = n;
+                let mut k = 0;
+                while k < *t && m.is_multiple_of(*q) {
+                    m /= q;
+                    k += 1;
+                }
+                (*q, k)
+            })
+            .filter(|(_, k)| *k > 0)
+            .collect::<Vec<_>>();
+        self.nth_root_from(n, &factors)
+    }
+
+    // Returns an `n`-th root, given the factorization of $g = \gcd(n, p - 1)$.
+    fn nth_root_from(&self, n: u128, factors: &[(u128, usize)]) -> Option<FpNum<P>> {
+        assert!(n > 0, "There is no zeroth root.");
+        if *self == FpNum::ZERO {
+            return Some(FpNum::ZERO);
+        }
+        let g = gcd(n, P - 1);
+        if self.pow((P - 1) / g) != FpNum::ONE {
+            return None;
+        }
+        // Every $q$-th root of a $g$-th power is again a $(g / q)$-th power, since $g$ divides
+        // $p - 1$, so the roots can be taken one prime at a time.
+        let mut y = *self;
+        for (q, t) in factors {
+            for _ in 0..*t {
+                y = y.prime_root(*q);
+            }
+        }
+        // Now $y^g = x$, and $x^{(p - 1) / g} = 1$, so any exponent $u$ with
+        // $u n / g \equiv 1$ modulo $(p - 1) / g$ gives a root.
+        let m = (P - 1) / g;
+        let Some(u) = mod_inverse((n / g) % m, m) else {
+            unreachable!();
+        };
+        Some(y.pow(u))
+    }
+
+    // Returns a `q`-th root of this number, for a prime `q` dividing $p - 1$, given that this
+    // number is a nonzero `q`-th power.
+    fn prime_root(&self, q: u128) -> FpNum<P> {
+        let mut s = 0;
+        let mut t = P - 1;
+        while t.is_multiple_of(q) {
+            s += 1;
+            t /= q;
+        }
+
+        // Since $q u \equiv 1$ modulo $t$, the error $e = r^q / x = x^{q u - 1}$ is a power of
+        // $x^t$, which lies in the subgroup of order $q^{s - 1}$.
+        let Some(u) = mod_inverse(q % t, t) else {
+            unreachable!();
+        };
+        let r = self.pow(u);
+        let e = r.pow(q) * self.inverse();
+
+        // A generator $c$ of the subgroup of order $q^s$, from a number that is not a $q$-th
+        // power.
+        let z = if q == 2 {
+            FpNum::find_nonresidue()
+        } else {
+            let mut i = 0;
+            loop {
+                let z = FpNum::from_u128(Seed::DEFAULT.shift(P, i));
+                if z != FpNum::ZERO && z.pow((P - 1) / q) != FpNum::ONE {
+                    break z;
+                }
+                i += 1;
+            }
+        };
+        let c = z.pow(t);
+
+        // Find $l$ with $e = c^l$, one base $q$ digit at a time, by the Pohlig-Hellman algorithm.
+        let gamma = c.pow(intpow::<0>(q, s - 1));
+        let mut l = 0;
+        let mut q_i = 1;
+        let mut rest = e;
+        for i in 0..s {
+            let h = rest.pow(intpow::<0>(q, s - 1 - i));
+            // A zero digit needs no discrete logarithm. The first digit is always zero, since $e$
+            // lies in the subgroup of order $q^{s - 1}$.
+            if h == FpNum::ONE {
+                q_i *= q;
+                continue;
+            }
+            let Some(d) = FpNum::dlog(gamma, h) else {
+                unreachable!();
+            };
+            rest *= c.pow(d * q_i).inverse();
+            l += d * q_i;
+            q_i *= q;
+        }
+
+        // Since $e$ lies in the subgroup of order $q^{s - 1}$, $q$ divides $l$, and
+        // $(r / c^{l / q})^q = x e / c^l = x$.
+        r * c.pow(l / q).inverse()
+    }
+
     /// Returns a quadratic nonresidue modulo `p`.
     pub const fn find_nonresidue() -> FpNum<P> {
+        Self::find_nonresidue_with(Seed::DEFAULT)
+    }
+
+    /// Returns a quadratic nonresidue modulo `p`, searching in the order given by `seed`.
+    /// When $p \not\equiv 1 \mod 8$, a fixed nonresidue is known, and the seed is ignored.
+    pub const fn find_nonresidue_with(seed: Seed) -> FpNum<P> {
         if P % 4 == 3 {
             FpNum::<P>::from_u128(P - 1)
         } else if P % 8 == 3 || P % 8 == 5 {
@@ -92,7 +292,7 @@ impl<const P: u128> FpNum<P> {
         } else {
             let mut i = 0;
             let res = loop {
-                let a = standard_affine_shift(P, i);
+                let a = seed.shift(P, i);
                 if intpow::<P>(a, (P - 1) / 2) == P - 1 {
                     break a;
                 }
@@ -110,17 +310,141 @@ impl<const P: u128> FpNum<P> {
 
 
     /// Converts a `u128` into its Montgomery representation.
-    /// This operation is expensive.
     pub const fn from_u128(src: u128) -> FpNum<P> {
-        let r2 = long_multiply::<P>(Self::R, Self::R);
-        FpNum::<P>::redc2(carrying_mul(src, r2))
+        FpNum::<P>::redc2(carrying_mul(src, Self::R_SQUARED))
+    }
+
+    /// Converts each integer in `xs` into its Montgomery representation.
+    /// ```
+    /// use libbgs::numbers::FpNum;
+    /// let xs = FpNum::<7>::from_slice(&[3, 10, 5]);
+    /// assert_eq!(xs, vec![FpNum::from(3), FpNum::from(3), FpNum::from(5)]);
+    /// assert_eq!(FpNum::to_u128s(&xs), vec![3, 3, 5]);
+    /// ```
+    pub fn from_slice(xs: &[u128]) -> Vec<FpNum<P>> {
+        FpNum::from_iter(xs.iter().copied())
+    }
+
+    /// Converts each integer yielded by `xs` into its Montgomery representation.
+    /// See [`FpNum::from_slice`].
+    // `FromIterator` cannot be implemented for `Vec<FpNum<P>>` outside of `std`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_iter(xs: impl IntoIterator<Item = u128>) -> Vec<FpNum<P>> {
+        xs.into_iter()
+            .map(|x| FpNum::<P>::redc2(carrying_mul(x, Self::R_SQUARED)))
+            .collect()
+    }
+
+    /// Converts each element of `xs` out of its Montgomery representation, into the integer in
+    /// the range `0..P` it represents.
+    /// This is the reverse of [`FpNum::from_slice`].
+    pub fn to_u128s(xs: &[FpNum<P>]) -> Vec<u128> {
+        xs.iter().map(|x| FpNum::<P>::redc2((0, x.0)).0).collect()
+    }
+
+    /// Replaces each element of `xs` with its multiplicative inverse.
+    ///
+    /// This uses Montgomery's trick, taking a single inversion and $3(n - 1)$ multiplications for
+    /// $n$ elements, rather than $n$ inversions.
+    /// This method will panic if any element is zero.
+    /// ```
+    /// use libbgs::numbers::{FpNum, GroupElem};
+    /// let mut xs = FpNum::<13>::from_slice(&[2, 3, 12]);
+    /// FpNum::batch_inverse(&mut xs);
+    /// assert_eq!(FpNum::to_u128s(&xs), vec![7, 9, 12]);
+    /// ```
+    pub fn batch_inverse(xs: &mut [FpNum<P>]) {
+        let Some(last) = xs.len().checked_sub(1) else {
+            return;
+        };
+        // prefix[i] is the product of the first i + 1 elements.
+        let mut prefix = Vec::with_capacity(xs.len());
+        let mut acc = FpNum::ONE;
+        for x in xs.iter() {
+            acc *= *x;
+            prefix.push(acc);
+        }
+        // Now `inv` is the inverse of the product of the elements up to and including `i`.
+        let mut inv = prefix[last].inverse();
+        for i in (1..=last).rev() {
+            let x = xs[i];
+            xs[i] = inv * prefix[i - 1];
+            inv *= x;
+        }
+        xs[0] = inv;
+    }
+
+    /// Returns the least $k \geq 0$ such that $\text{base}^k = \text{target}$, or `None` if
+    /// `target` is not a power of `base`, or either is zero.
+    ///
+    /// This uses the baby-step giant-step algorithm, and so needs no factorization of $p - 1$,
+    /// unlike [`SylowElem::from_product`]; it is meant for experiments with primes for which no
+    /// `Factor` implementation has been written.
+    /// It stores up to $\sqrt{p}$ powers of `base`, but at most $2^{20}$; see
+    /// [`FpNum::dlog_with_memory`] to choose the number stored.
+    /// ```
+    /// use libbgs::numbers::FpNum;
+    /// let base = FpNum::<1009>::from(11);
+    /// assert_eq!(FpNum::dlog(base, FpNum::from(11 * 11 * 11 % 1009)), Some(3));
+    /// assert_eq!(FpNum::dlog(FpNum::<1009>::from(1008), FpNum::from(2)), None);
+    /// ```
+    pub fn dlog(base: FpNum<P>, target: FpNum<P>) -> Option<u128> {
+        let memory = u128::min((P - 1).isqrt() + 1, DLOG_MEMORY as u128);
+        FpNum::dlog_with_memory(base, target, memory as usize)
+    }
+
+    /// Returns the least $k \geq 0$ such that $\text{base}^k = \text{target}$, as
+    /// [`FpNum::dlog`] does, storing at most `memory` powers of `base`.
+    /// This takes about `memory` steps to store the powers, and about $p / \text{memory}$ steps
+    /// to search them, so the fastest choice is about $\sqrt{p}$.
+    /// Panics if `memory` is zero.
+    pub fn dlog_with_memory(base: FpNum<P>, target: FpNum<P>, memory: usize) -> Option<u128> {
+        assert!(
+            memory > 0,
+            "Baby-step giant-step needs room for at least one power."
+        );
+        if base == FpNum::ZERO || target == FpNum::ZERO {
+            return None;
+        }
+        let mut baby_steps = HashMap::with_capacity(memory);
+        let mut x = FpNum::ONE;
+        for j in 0..memory as u128 {
+            baby_steps.entry(x).or_insert(j);
+            x *= base;
+            if x == FpNum::ONE {
+                // Every power of `base` has been stored.
+                return baby_steps.get(&target).copied();
+            }
+        }
+
+        // Now `x` is $\text{base}^m$; divide `target` by it until it lands among the baby steps.
+        let giant_step = x.inverse();
+        let mut y = target;
+        let mut i = 0;
+        while i < P - 1 {
+            if let Some(j) = baby_steps.get(&y) {
+                return Some(i + j);
+            }
+            y *= giant_step;
+            i += memory as u128;
+        }
+        None
+    }
+
+    /// Returns the multiplicative order of this number, found by [`FpNum::dlog`] rather than
+    /// from a factorization of $p - 1$ as by [`GroupElem::order`].
+    /// Panics if this number is zero.
+    pub fn order_unfactored(&self) -> u128 {
+        assert!(*self != FpNum::ZERO, "Zero has no multiplicative order.");
+        // The least $k$ with $x^k = x^{-1}$ is one less than the order of $x$.
+        FpNum::dlog(*self, self.inverse()).unwrap() + 1
     }
 
     // Elements are represented as aR + N in this system.
     // Chosen to be a power of two to turn multiplication / division into shift operations.
     const R: u128 = {
         let mut n = P.next_power_of_two();
-        while n.ilog2() % 4 != 0 {
+        while !n.ilog2().is_multiple_of(4) {
             n <<= 1;
         }
         n
@@ -134,30 +458,29 @@ impl<const P: u128> FpNum<P> {
     const SHIFT: u32 = Self::R.ilog2();
 
     // "Magic" number R' such that RR' is congruent to 1 mod N
-    const MAGIC: u128 = {
-        let mut r0 = Self::R;
-        let mut r1 = P;
-        let mut t0: i128 = 0;
-        let mut t1: i128 = 1;
-        let mut s0: i128 = 1;
-        let mut s1: i128 = 0;
-        while r1 != 0 {
-            let q = r0 / r1;
-            (r0, r1) = (r1, r0 - q * r1);
-            (t0, t1) = (t1, t0 - (q as i128) * t1);
-            (s0, s1) = (s1, s0 - (q as i128) * s1);
-        }
-        if t0 < 0 {
-            (-t0) as u128
-        } else {
-            Self::R - (t0 as u128)
-        }
+    const MAGIC: u128 = match mod_inverse(P, Self::R) {
+        Some(inv) => Self::R - inv,
+        None => panic!("The modulus must be odd."),
     };
 
-    #[allow(dead_code)]
+    // R^2 mod P, used to convert integers into Montgomery form.
+    const R_SQUARED: u128 = long_multiply::<P>(Self::R, Self::R);
+
+    // R^3 mod P, used to return inverses computed on the Montgomery representation to
+    // Montgomery form.
+    const R_CUBED: u128 = {
+        let r = Self::R % P;
+        long_multiply::<P>(long_multiply::<P>(r, r), r)
+    };
+
+    // True if every representation fits in 63 bits, so that products fit in a single `u128`.
+    // Then R is at most 2^64, and each multiplication in `redc` is 64 by 64 bits.
+    const SMALL: bool = P < 1 << 63;
+
+    // Reduces x < P^2, for moduli with `SMALL` set.
     const fn redc(x: u128) -> FpNum<P> {
-        let m = ((x & Self::MASK) * Self::MAGIC) & Self::MASK;
-        let t = (x + m * P) >> Self::SHIFT;
+        let m = (((x & Self::MASK) as u64 as u128) * Self::MAGIC) & Self::MASK;
+        let t = (x + (m as u64 as u128) * P) >> Self::SHIFT;
         if t >= P {
             FpNum(t - P)
         } else {
@@ -182,10 +505,51 @@ impl<const P: u128> FpNum<P> {
     // Once const impls lands in Rust, they will be removed, and the impl std::ops will be made
     // const.
     pub(crate) const fn const_mul(&self, rhs: &FpNum<P>) -> FpNum<P> {
+        if Self::SMALL {
+            // A single native 64 by 64 bit multiplication, rather than four.
+            return FpNum::<P>::redc((self.0 as u64 as u128) * (rhs.0 as u64 as u128));
+        }
         let (hi, lo) = carrying_mul(self.0, rhs.0);
         FpNum::<P>::redc2((hi, lo))
     }
 
+    /// Returns the multiplicative inverse of this number, or `None` if it is zero.
+    /// Unlike `GroupElem::inverse`, this uses the extended Euclidean algorithm rather than
+    /// exponentiation, and may be used in `const` contexts.
+    pub const fn const_inverse(&self) -> Option<FpNum<P>> {
+        // If this number is aR, then the inverse of the representation is a^{-1}R^{-1}, and
+        // multiplying by R^3 in Montgomery form gives a^{-1}R.
+        match mod_inverse(self.0, P) {
+            Some(inv) => Some(FpNum(inv).const_mul(&FpNum(Self::R_CUBED))),
+            None => None,
+        }
+    }
+
+    pub(crate) const fn const_add(&self, rhs: &FpNum<P>) -> FpNum<P> {
+        let sum = self.0 + rhs.0;
+        if sum >= P {
+            FpNum(sum - P)
+        } else {
+            FpNum(sum)
+        }
+    }
+
+    pub(crate) const fn const_sub(&self, rhs: &FpNum<P>) -> FpNum<P> {
+        if self.0 >= rhs.0 {
+            FpNum(self.0 - rhs.0)
+        } else {
+            FpNum(P + self.0 - rhs.0)
+        }
+    }
+
+    pub(crate) const fn const_neg(&self) -> FpNum<P> {
+        if self.0 == 0 {
+            FpNum(0)
+        } else {
+            FpNum(P - self.0)
+        }
+    }
+
     pub(crate) const fn const_pow(self, mut n: u128) -> FpNum<P> {
         let mut x = self;
         let mut y = FpNum::<P>::from_u128(1);
@@ -208,15 +572,29 @@ where
     FpNum<P>: Factor<S>,
 {
     fn find_sylow_generator(i: usize) -> FpNum<P> {
+        <FpNum<P> as SylowDecomposable<S>>::find_sylow_generator_with(i, Seed::DEFAULT)
+    }
+
+    fn find_sylow_generator_with(i: usize, seed: Seed) -> FpNum<P> {
         match Self::FACTORS[i] {
             (2, 1) => FpNum::from(FpNum::<P>::SIZE),
             (p, t) => (1..FpNum::<P>::SIZE)
-                .map(|j| FpNum::from(standard_affine_shift(P, j)))
+                .map(|j| FpNum::from(seed.shift(P, j)))
                 .filter(|c| *c != FpNum::ZERO)
                 .find_map(|c| <FpNum<P> as SylowDecomposable<S>>::is_sylow_generator(&c, (p, t)))
                 .unwrap(),
         }
     }
+
+    fn find_sylow_generators_with<const L: usize>(seed: Seed) -> [FpNum<P>; L] {
+        let known = std::array::from_fn(|i| {
+            (<FpNum<P> as Factor<S>>::FACTORS[i] == (2, 1)).then_some(FpNum::from(FpNum::<P>::SIZE))
+        });
+        let candidates = (1..FpNum::<P>::SIZE)
+            .map(|j| FpNum::from(seed.shift(P, j)))
+            .filter(|c| *c != FpNum::ZERO);
+        <FpNum<P> as SylowDecomposable<S>>::first_sylow_generators(candidates, known)
+    }
 }
 
 impl<const P: u128> GroupElem for FpNum<P> {
@@ -228,10 +606,39 @@ impl<const P: u128> GroupElem for FpNum<P> {
     }
 
     fn inverse(&self) -> FpNum<P> {
-        if *self == Self::ZERO {
+        let Some(inv) = self.const_inverse() else {
             panic!("Attempted to take the multiplicative inverse of zero.");
+        };
+        inv
+    }
+}
+
+// The most powers stored by `FpNum::dlog`.
+const DLOG_MEMORY: usize = 1 << 20;
+
+// Moduli up to this size may use a table of quadratic residues in `FpNum::legendre_batch`.
+const RESIDUE_TABLE_LIMIT: u128 = 1 << 16;
+
+// Returns the Jacobi symbol of `a` modulo the odd integer `n`.
+fn jacobi(mut a: u128, mut n: u128) -> i8 {
+    let mut res = 1;
+    a %= n;
+    while a != 0 {
+        let z = a.trailing_zeros();
+        a >>= z;
+        if z % 2 == 1 && (n % 8 == 3 || n % 8 == 5) {
+            res = -res;
         }
-        self.pow(Self::SIZE - 1)
+        (a, n) = (n, a);
+        if a % 4 == 3 && n % 4 == 3 {
+            res = -res;
+        }
+        a %= n;
+    }
+    if n == 1 {
+        res
+    } else {
+        0
     }
 }
 
@@ -255,95 +662,70 @@ impl<const P: u128> std::fmt::Debug for FpNum<P> {
     }
 }
 
+/// Overwrites the Montgomery representation of this number with zero.
+/// Since `FpNum` is `Copy`, this wipes only this copy: values passed by value, and the
+/// intermediate values of arithmetic, are not wiped.
+/// Keep secrets in a [`zeroize::Zeroizing`] wrapper, and pass them by reference.
+#[cfg(feature = "zeroize")]
+impl<const P: u128> zeroize::Zeroize for FpNum<P> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 impl<const P: u128> Add<FpNum<P>> for FpNum<P> {
     type Output = FpNum<P>;
     fn add(self, rhs: FpNum<P>) -> FpNum<P> {
-        let sum = self.0 + rhs.0;
-        if sum >= P {
-            FpNum(sum - P)
-        } else {
-            FpNum(sum)
-        }
+        self.const_add(&rhs)
     }
 }
 
 impl<const P: u128> Add<FpNum<P>> for &FpNum<P> {
     type Output = FpNum<P>;
     fn add(self, rhs: FpNum<P>) -> FpNum<P> {
-        let sum = self.0 + rhs.0;
-        if sum >= P {
-            FpNum(sum - P)
-        } else {
-            FpNum(sum)
-        }
+        self.const_add(&rhs)
     }
 }
 
 impl<const P: u128> Add<&FpNum<P>> for FpNum<P> {
     type Output = FpNum<P>;
     fn add(self, rhs: &FpNum<P>) -> FpNum<P> {
-        let sum = self.0 + rhs.0;
-        if sum >= P {
-            FpNum(sum - P)
-        } else {
-            FpNum(sum)
-        }
+        self.const_add(rhs)
     }
 }
 
 impl<const P: u128> Add<&FpNum<P>> for &FpNum<P> {
     type Output = FpNum<P>;
     fn add(self, rhs: &FpNum<P>) -> FpNum<P> {
-        let sum = self.0 + rhs.0;
-        if sum >= P {
-            FpNum(sum - P)
-        } else {
-            FpNum(sum)
-        }
+        self.const_add(rhs)
     }
 }
 
 impl<const P: u128> Sub<FpNum<P>> for FpNum<P> {
     type Output = FpNum<P>;
     fn sub(self, rhs: FpNum<P>) -> FpNum<P> {
-        if self.0 >= rhs.0 {
-            FpNum(self.0 - rhs.0)
-        } else {
-            FpNum(P + self.0 - rhs.0)
-        }
+        self.const_sub(&rhs)
     }
 }
 
 impl<const P: u128> Sub<FpNum<P>> for &FpNum<P> {
     type Output = FpNum<P>;
     fn sub(self, rhs: FpNum<P>) -> FpNum<P> {
-        if self.0 >= rhs.0 {
-            FpNum(self.0 - rhs.0)
-        } else {
-            FpNum(P + self.0 - rhs.0)
-        }
+        self.const_sub(&rhs)
     }
 }
 
 impl<const P: u128> Sub<&FpNum<P>> for FpNum<P> {
     type Output = FpNum<P>;
     fn sub(self, rhs: &FpNum<P>) -> FpNum<P> {
-        if self.0 >= rhs.0 {
-            FpNum(self.0 - rhs.0)
-        } else {
-            FpNum(P + self.0 - rhs.0)
-        }
+        self.const_sub(rhs)
     }
 }
 
 impl<const P: u128> Sub<&FpNum<P>> for &FpNum<P> {
     type Output = FpNum<P>;
     fn sub(self, rhs: &FpNum<P>) -> FpNum<P> {
-        if self.0 >= rhs.0 {
-            FpNum(self.0 - rhs.0)
-        } else {
-            FpNum(P + self.0 - rhs.0)
-        }
+        self.const_sub(rhs)
     }
 }
 
@@ -391,22 +773,14 @@ impl<const P: u128> MulAssign<FpNum<P>> for FpNum<P> {
 impl<const P: u128> Neg for FpNum<P> {
     type Output = FpNum<P>;
     fn neg(self) -> FpNum<P> {
-        if self.0 == 0 {
-            FpNum(0)
-        } else {
-            FpNum(P - self.0)
-        }
+        self.const_neg()
     }
 }
 
 impl<const P: u128> Neg for &FpNum<P> {
     type Output = FpNum<P>;
     fn neg(self) -> FpNum<P> {
-        if self.0 == 0 {
-            FpNum(0)
-        } else {
-            FpNum(P - self.0)
-        }
+        self.const_neg()
     }
 }
 
@@ -483,6 +857,26 @@ mod tests {
         assert!(x == FpNum::ONE);
     }
 
+    #[test]
+    fn powers_up_with_limbs() {
+        let x = FpNum::<BIG_P>::from(3);
+        assert_eq!(x.pow_big(&[]), FpNum::ONE);
+        assert_eq!(x.pow_big(&[12345]), x.pow(12345));
+        let n = BIG_P - 2;
+        assert_eq!(x.pow_big(&[n as u64, (n >> 64) as u64]), x.pow(n));
+        // x^(2^128) = (x^(2^64))^(2^64)
+        let y = x.pow(1 << 64).pow(1 << 64);
+        assert_eq!(x.pow_big(&[0, 0, 1]), y);
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn powers_up_with_biguint() {
+        let x = FpNum::<BIG_P>::from(3);
+        let n = num_bigint::BigUint::from(BIG_P - 1) * num_bigint::BigUint::from(BIG_P + 1);
+        assert_eq!(x.pow_biguint(&n), FpNum::ONE);
+    }
+
     #[test]
     fn powers_up_big() {
         let mut x = FpNum::<BIG_P>::from(3);
@@ -516,6 +910,63 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sylow_seeded() {
+        for seed in [Seed(1), Seed(2), Seed(0xC0FFEE)] {
+            let g = SylowDecomp::<Phantom, 7, FpNum<BIG_P>>::new_with_seed(seed);
+            assert!(g.verify());
+            let h = SylowDecomp::<Phantom, 7, FpNum<BIG_P>>::new_with_seed(seed);
+            assert!((0..7).all(|i| g.generator(i) == h.generator(i)));
+        }
+        type F = FpNum<BIG_P>;
+        for seed in [Seed::DEFAULT, Seed(5)] {
+            let gens = <F as SylowDecomposable<Phantom>>::find_sylow_generators_with::<7>(seed);
+            assert!((0..7).all(|i| gens[i]
+                == <F as SylowDecomposable<Phantom>>::find_sylow_generator_with(i, seed)));
+        }
+        let a = FpNum::<BIG_P>::find_nonresidue_with(Seed(3));
+        assert_eq!(a.pow((BIG_P - 1) / 2), FpNum::from(BIG_P - 1));
+    }
+
+    #[test]
+    fn sylow_verifies() {
+        let g = SylowDecomp::<Phantom, 2, FpNum<29>>::new();
+        assert!(g.verify());
+        assert_eq!(g.generator_order(0), 4);
+        assert_eq!(g.generator_order(1), 7);
+
+        let g = SylowDecomp::<Phantom, 7, FpNum<BIG_P>>::new();
+        assert!(g.verify());
+        for i in 0..7 {
+            assert_eq!(
+                g.generator_order(i),
+                SylowElem::<Phantom, 7, FpNum<BIG_P>>::FACTORS.factor(i)
+            );
+        }
+    }
+
+    #[test]
+    fn sylow_to_products() {
+        let g = SylowDecomp::<Phantom, 2, FpNum<13>>::new();
+        let elems = (0..4)
+            .flat_map(|a| (0..3).map(move |b| SylowElem::new([a, b])))
+            .chain([SylowElem::new([5, 7]), SylowElem::new([0, 0])])
+            .collect::<Vec<_>>();
+        let products = g.to_products(elems.clone()).collect::<Vec<_>>();
+        assert_eq!(products.len(), elems.len());
+        for (x, y) in elems.iter().zip(&products) {
+            assert_eq!(x.to_product(&g), *y);
+        }
+
+        let g = SylowDecomp::<Phantom, 7, FpNum<BIG_P>>::new();
+        let elems = (1..50)
+            .map(|i| SylowElem::new([i % 2, 1, i % 13, 5, i, 705737 - i, 215288719 / i]))
+            .collect::<Vec<_>>();
+        for (x, y) in elems.iter().zip(g.to_products(elems.clone())) {
+            assert_eq!(x.to_product(&g), y);
+        }
+    }
+
     #[test]
     fn sylow_order() {
         for i in 1..13 {
@@ -545,7 +996,7 @@ mod tests {
     #[test]
     fn calculates_square_roots() {
         let mut nonresidues = 0;
-        for x in (1..13).map(|i| FpNum::<13>::from(i)) {
+        for x in (1..13).map(FpNum::<13>::from) {
             match x.int_sqrt() {
                 None => {
                     nonresidues += 1;
@@ -563,7 +1014,7 @@ mod tests {
     fn inverses() {
         for i in 2..13 {
             let mut x = FpNum::<13>::from(i);
-            let y = x.clone();
+            let y = x;
             x = x.inverse();
             assert!(x != FpNum::ONE);
             x = x.multiply(&y);
@@ -571,6 +1022,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn const_inverses() {
+        assert_eq!(FpNum::<13>::ZERO.const_inverse(), None);
+        for i in 1..13 {
+            let x = FpNum::<13>::from(i);
+            assert_eq!(x.const_inverse(), Some(x.pow(11)));
+        }
+        for i in 1..1000 {
+            let x = FpNum::<BIG_P>::from(i * 0xABCDEF);
+            assert_eq!(x * x.const_inverse().unwrap(), FpNum::ONE);
+        }
+    }
+
+    #[test]
+    fn converts_slices() {
+        let xs = (0..1000).map(|i| i * 0xABCDEF1234567).collect::<Vec<_>>();
+        let ys = FpNum::<BIG_P>::from_slice(&xs);
+        assert!(xs.iter().zip(&ys).all(|(x, y)| FpNum::from(*x) == *y));
+        let zs = FpNum::to_u128s(&ys);
+        assert!(xs.iter().zip(&zs).all(|(x, z)| x % BIG_P == *z));
+    }
+
     #[test]
     fn finds_magic() {
         assert_eq!(FpNum::<13>::MAGIC, 11);
@@ -580,7 +1053,7 @@ mod tests {
     fn test_multiply() {
         let five = FpNum::<13>::from(5);
         let six = FpNum::<13>::from(6);
-        let thirty = &five * &six;
+        let thirty = five * six;
         assert_eq!(u128::from(thirty), 30 % 13);
     }
 
@@ -594,6 +1067,78 @@ mod tests {
         assert_eq!(u128::from(res), (a * b) % BIG_P);
     }
 
+    #[test]
+    fn const_ops_match_operators() {
+        const A: FpNum<13> = FpNum::from_u128(9);
+        const B: FpNum<13> = FpNum::from_u128(7);
+        const SUM: FpNum<13> = A.const_add(&B);
+        const DIFF: FpNum<13> = B.const_sub(&A);
+        const NEG: FpNum<13> = A.const_neg();
+        assert_eq!(SUM, A + B);
+        assert_eq!(u128::from(SUM), 3);
+        assert_eq!(DIFF, B - A);
+        assert_eq!(u128::from(DIFF), 11);
+        assert_eq!(NEG, -A);
+        assert_eq!(FpNum::<13>::ZERO.const_neg(), FpNum::ZERO);
+    }
+
+    #[test]
+    fn multi_pow_matches_pow() {
+        let bases = FpNum::<BIG_P>::from_slice(&[2, 3, 5, 7, BIG_P - 1]);
+        let naive = |exps: &[u128]| {
+            bases
+                .iter()
+                .zip(exps)
+                .fold(FpNum::ONE, |acc, (b, e)| acc * b.pow(*e))
+        };
+        for exps in [
+            [0, 0, 0, 0, 0],
+            [1, 0, 65535, 2, 3],
+            [0, 1 << 16, 3, 0, 1],
+            [BIG_P - 2, 12345678901234567, 0, u128::MAX, 1 << 100],
+        ] {
+            assert_eq!(FpNum::multi_pow(&bases, &exps), naive(&exps));
+        }
+        assert_eq!(FpNum::<BIG_P>::multi_pow(&[], &[]), FpNum::ONE);
+    }
+
+    #[test]
+    fn batch_inverses() {
+        let mut xs = FpNum::<BIG_P>::from_iter((1..100).map(|i| i * i * 1_000_000_007));
+        let expected = xs.iter().map(|x| x.inverse()).collect::<Vec<_>>();
+        FpNum::batch_inverse(&mut xs);
+        assert_eq!(xs, expected);
+
+        let mut one = [FpNum::<13>::from(5)];
+        FpNum::batch_inverse(&mut one);
+        assert_eq!(one, [FpNum::from(8)]);
+        FpNum::<13>::batch_inverse(&mut []);
+    }
+
+    #[test]
+    #[should_panic]
+    fn batch_inverse_of_zero() {
+        let mut xs = FpNum::<13>::from_slice(&[2, 0, 3]);
+        FpNum::batch_inverse(&mut xs);
+    }
+
+    #[test]
+    fn word_multiplication_matches_wide() {
+        fn check<const P: u128>(xs: &[u128]) {
+            assert!(FpNum::<P>::SMALL);
+            for x in FpNum::<P>::from_slice(xs) {
+                for y in FpNum::<P>::from_slice(xs) {
+                    assert_eq!(x * y, FpNum::redc2(carrying_mul(x.0, y.0)));
+                }
+            }
+        }
+        check::<4001>(&[0, 1, 2, 2000, 4000]);
+        // The largest prime below 2^63.
+        const Q: u128 = (1 << 63) - 25;
+        check::<Q>(&[0, 1, 2, 1 << 62, Q / 3, Q - 1]);
+        assert_eq!(FpNum::<Q>::from(3).pow(Q - 1), FpNum::ONE);
+    }
+
     #[test]
     fn test_pow() {
         let a = 0xABCDEF;
@@ -601,4 +1146,75 @@ mod tests {
         let res = FpNum::<BIG_P>::from(a).const_pow(n);
         assert_eq!(u128::from(res), intpow::<BIG_P>(a, n));
     }
+
+    #[test]
+    fn legendre_batch_matches_legendre() {
+        fn symbols<const P: u128>(xs: &[FpNum<P>]) -> Vec<i8> {
+            xs.iter()
+                .map(|x| match x.legendre() {
+                    y if y == FpNum::ZERO => 0,
+                    y if y == FpNum::ONE => 1,
+                    _ => -1,
+                })
+                .collect()
+        }
+
+        // Small enough to use the table of residues...
+        let xs = (0..200).map(FpNum::<29>::from).collect::<Vec<_>>();
+        assert_eq!(FpNum::legendre_batch(&xs), symbols(&xs));
+        // ... but not with only a few elements.
+        assert_eq!(FpNum::legendre_batch(&xs[..3]), symbols(&xs[..3]));
+
+        let xs = (0..200)
+            .map(|i| FpNum::<BIG_P>::from(i * 0x123456789ABCDEF + i))
+            .collect::<Vec<_>>();
+        assert_eq!(FpNum::legendre_batch(&xs), symbols(&xs));
+    }
+
+    #[test]
+    fn dlog_matches_powers() {
+        for b in 1..29 {
+            let base = FpNum::<29>::from(b);
+            assert_eq!(base.order_unfactored(), base.order::<Phantom>());
+            for t in 1..29 {
+                let target = FpNum::from(t);
+                let expected = (0..28).find(|k| base.pow(*k) == target);
+                for memory in [1, 3, 6, 100] {
+                    assert_eq!(FpNum::dlog_with_memory(base, target, memory), expected);
+                }
+            }
+        }
+        assert_eq!(FpNum::dlog(FpNum::<29>::ZERO, FpNum::ONE), None);
+
+        let base = FpNum::<1_000_003>::from(2);
+        let target = base.pow(123_456);
+        let k = FpNum::dlog(base, target).unwrap();
+        assert_eq!(base.pow(k), target);
+        assert!(k <= 123_456);
+    }
+
+    #[test]
+    fn nth_roots() {
+        // 1009 - 1 = 2^4 * 3^2 * 7
+        for n in [1, 2, 3, 4, 5, 7, 9, 12, 16, 63, 1008, 2016, 3 * 1009] {
+            let g = gcd(n, 1008);
+            for x in (0..1009).map(FpNum::<1009>::from) {
+                let is_power = x == FpNum::ZERO || x.pow(1008 / g) == FpNum::ONE;
+                match x.nth_root(n) {
+                    Some(y) => assert_eq!(y.pow(n), x, "{x:?}^(1/{n})"),
+                    None => assert!(!is_power, "{x:?}^(1/{n})"),
+                }
+            }
+        }
+
+        // Roots of order 29^2 need the digits of a discrete logarithm.
+        for n in [3, 14, 29 * 29, 2 * 29 * 29 * 43, 215288719] {
+            let x = FpNum::<BIG_P>::from(5).pow(n);
+            let y = x.nth_root_factored::<Phantom>(n).unwrap();
+            assert_eq!(y.pow(n), x);
+            assert_eq!(x.nth_root(n).map(|y| y.pow(n)), Some(x));
+        }
+        let x = FpNum::<BIG_P>::from(5);
+        assert_eq!(x.nth_root_factored::<Phantom>(29), None);
+    }
 }
@@ -0,0 +1,317 @@
+use libbgs_util::U256;
+
+use crate::numbers::{pow_by_bits, RuntimeFpElem};
+
+/// An odd prime modulus below $2^{255}$, together with the constants needed for Montgomery
+/// multiplication modulo it.
+///
+/// Elements of $\mathbb{Z} / p\mathbb{Z}$ for such a prime are represented by [`Fp256`], which
+/// borrows its modulus.
+/// This covers primes of cryptographic size, too large for [`FpNum`](crate::numbers::FpNum),
+/// with fixed-size arithmetic; see [`BigModulus`](crate::numbers::BigModulus) for primes of
+/// any size.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Modulus256 {
+    p: U256,
+    r2: U256,
+    p_inv_neg: u64,
+}
+
+/// An integer modulo a [`Modulus256`], kept internally in Montgomery form, with $R = 2^{256}$.
+///
+/// Like [`FpBig`](crate::numbers::FpBig), it implements [`RuntimeFpElem`] in place of
+/// [`GroupElem`](crate::numbers::GroupElem), since its modulus is only known at runtime and the
+/// size of its group need not fit into a `u128`; so it cannot be used with
+/// [`SylowDecomp`](crate::numbers::SylowDecomp), nor in searches.
+///
+/// # Example
+/// ```
+/// use libbgs::numbers::*;
+/// use libbgs::util::U256;
+///
+/// // 2^192 - 2^64 - 1
+/// let p: U256 = "6277101735386680763835789423207666416083908700390324961279"
+///     .parse()
+///     .unwrap();
+/// let modulus = Modulus256::new(p);
+/// let x = modulus.elem(&U256::from(3));
+/// assert!(x.pow(&p.checked_sub(&U256::ONE).unwrap()).is_one());
+/// assert!(x.multiply(&x.inverse()).is_one());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Fp256<'a> {
+    modulus: &'a Modulus256,
+    value: U256,
+}
+
+impl Modulus256 {
+    /// Creates a modulus for the prime `p`.
+    /// This method will panic if `p` is even, less than 3, or at least $2^{255}$; `p` is assumed
+    /// to be prime.
+    pub const fn new(p: U256) -> Modulus256 {
+        assert!(
+            p.bit(0) && p.bits() > 1 && !p.bit(255),
+            "modulus must be an odd prime below 2^255"
+        );
+        // R mod p, doubled 256 times, is R^2 mod p.
+        let mut r2 = U256::MAX.rem(&p).overflowing_add(&U256::ONE).0.rem(&p);
+        let mut i = 0;
+        while i < 256 {
+            r2 = add_mod(&r2, &r2, &p);
+            i += 1;
+        }
+
+        // Newton's iteration for the inverse of p modulo 2^64; each step doubles the number of
+        // correct bits.
+        let p0 = p.limbs()[0];
+        let mut inv: u64 = 1;
+        let mut i = 0;
+        while i < 6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(p0.wrapping_mul(inv)));
+            i += 1;
+        }
+
+        Modulus256 {
+            p,
+            r2,
+            p_inv_neg: inv.wrapping_neg(),
+        }
+    }
+
+    /// Returns the prime `p`.
+    pub const fn modulus(&self) -> U256 {
+        self.p
+    }
+
+    /// Returns the residue of `n` modulo `p`.
+    pub const fn elem(&self, n: &U256) -> Fp256<'_> {
+        Fp256 {
+            modulus: self,
+            value: self.mont_mul(&n.rem(&self.p), &self.r2),
+        }
+    }
+
+    /// Returns the multiplicative identity modulo `p`.
+    pub const fn one(&self) -> Fp256<'_> {
+        self.elem(&U256::ONE)
+    }
+
+    // Returns abR^{-1} mod p, for a, b < p, by the coarsely integrated operand scanning method.
+    const fn mont_mul(&self, a: &U256, b: &U256) -> U256 {
+        let (a, b, p) = (a.limbs(), b.limbs(), self.p.limbs());
+        let mut t = [0u64; 6];
+        let mut i = 0;
+        while i < 4 {
+            let mut carry = 0;
+            let mut j = 0;
+            while j < 4 {
+                let s = t[j] as u128 + a[j] as u128 * b[i] as u128 + carry;
+                t[j] = s as u64;
+                carry = s >> 64;
+                j += 1;
+            }
+            let s = t[4] as u128 + carry;
+            t[4] = s as u64;
+            t[5] = (s >> 64) as u64;
+
+            let m = t[0].wrapping_mul(self.p_inv_neg);
+            let mut carry = (t[0] as u128 + m as u128 * p[0] as u128) >> 64;
+            let mut j = 1;
+            while j < 4 {
+                let s = t[j] as u128 + m as u128 * p[j] as u128 + carry;
+                t[j - 1] = s as u64;
+                carry = s >> 64;
+                j += 1;
+            }
+            let s = t[4] as u128 + carry;
+            t[3] = s as u64;
+            t[4] = t[5] + (s >> 64) as u64;
+            i += 1;
+        }
+        // Since p < 2^255, the result is less than 2p < 2^256, so t[4] is zero.
+        let res = U256::from_limbs([t[0], t[1], t[2], t[3]]);
+        match res.checked_sub(&self.p) {
+            Some(r) => r,
+            None => res,
+        }
+    }
+}
+
+// Returns a + b mod p, for a, b < p < 2^255.
+const fn add_mod(a: &U256, b: &U256, p: &U256) -> U256 {
+    let sum = a.overflowing_add(b).0;
+    match sum.checked_sub(p) {
+        Some(r) => r,
+        None => sum,
+    }
+}
+
+impl<'a> Fp256<'a> {
+    /// Returns the modulus of this element.
+    pub const fn modulus(&self) -> &'a Modulus256 {
+        self.modulus
+    }
+
+    /// Returns the order of this element, that is, the smallest positive power `n` for which
+    /// `self.pow(n).is_one()` returns True.
+    /// The factors are the prime powers of $p - 1$, or of a multiple of the order of this
+    /// element, as pairs of a prime and its power.
+    pub fn order(&self, factors: &[(U256, usize)]) -> U256 {
+        let prime_power = |p: &U256, t: usize| {
+            (0..t).fold(U256::ONE, |acc, _| {
+                acc.checked_mul(p).expect("factor overflows a U256")
+            })
+        };
+        let mut res = U256::ONE;
+        for (i, (p, _)) in factors.iter().enumerate() {
+            let mut x = *self;
+            for (j, (q, t)) in factors.iter().enumerate() {
+                if j != i {
+                    x = x.pow(&prime_power(q, *t));
+                }
+            }
+            while !x.is_one() {
+                x = x.pow(p);
+                res = res.checked_mul(p).expect("order overflows a U256");
+            }
+        }
+        res
+    }
+}
+
+impl<'a> RuntimeFpElem for Fp256<'a> {
+    type Int = U256;
+
+    fn one(&self) -> Fp256<'a> {
+        self.modulus.one()
+    }
+
+    fn is_one(&self) -> bool {
+        self.modulus.mont_mul(&self.value, &U256::ONE) == U256::ONE
+    }
+
+    fn multiply(&self, other: &Fp256<'a>) -> Fp256<'a> {
+        assert_eq!(self.modulus.p, other.modulus.p, "moduli must match");
+        Fp256 {
+            modulus: self.modulus,
+            value: self.modulus.mont_mul(&self.value, &other.value),
+        }
+    }
+
+    fn pow(&self, n: &U256) -> Fp256<'a> {
+        pow_by_bits(self, n.bits().into(), |i| n.bit(i as u32))
+    }
+
+    fn inverse(&self) -> Fp256<'a> {
+        assert!(
+            !self.value.is_zero(),
+            "Attempted to take the multiplicative inverse of zero."
+        );
+        let p = self.modulus.p;
+        self.pow(&p.overflowing_sub(&U256::from_u128(2)).0)
+    }
+}
+
+impl From<Fp256<'_>> for U256 {
+    fn from(src: Fp256<'_>) -> U256 {
+        src.modulus.mont_mul(&src.value, &U256::ONE)
+    }
+}
+
+impl std::fmt::Debug for Fp256<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Fp256").field(&U256::from(*self)).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers::*;
+
+    const BIG_P: u128 = 1_000_000_000_000_000_124_399;
+
+    struct Phantom {}
+
+    impl Factor<Phantom> for FpNum<BIG_P> {
+        const FACTORS: Factorization = Factorization::new(&[
+            (2, 1),
+            (7, 1),
+            (13, 1),
+            (29, 2),
+            (43, 1),
+            (705737, 1),
+            (215288719, 1),
+        ]);
+    }
+
+    #[test]
+    fn matches_fp_num() {
+        let modulus = Modulus256::new(U256::from(BIG_P));
+        let factors = <FpNum<BIG_P> as Factor<Phantom>>::FACTORS
+            .factors()
+            .iter()
+            .map(|(p, t)| (U256::from(*p), *t))
+            .collect::<Vec<_>>();
+        for x in [2, 3, 5, 1 << 64, BIG_P - 1] {
+            let small = FpNum::<BIG_P>::from(x);
+            let big = modulus.elem(&U256::from(x));
+            assert_eq!(
+                U256::from(big.multiply(&big)),
+                U256::from(u128::from(small * small))
+            );
+            assert_eq!(
+                U256::from(big.inverse()),
+                U256::from(u128::from(small.inverse()))
+            );
+            assert_eq!(big.order(&factors), U256::from(small.order::<Phantom>()));
+        }
+    }
+
+    #[test]
+    fn cryptographic_primes() {
+        // The NIST P-256 prime, 2^256 - 2^224 + 2^192 + 2^96 - 1, is too large, so take the
+        // P-224 prime, 2^224 - 2^96 + 1.
+        let p = U256::ONE
+            .shl(224)
+            .checked_sub(&U256::ONE.shl(96))
+            .unwrap()
+            .checked_add(&U256::ONE)
+            .unwrap();
+        let modulus = Modulus256::new(p);
+        let p_minus_1 = p.checked_sub(&U256::ONE).unwrap();
+        let x = modulus.elem(&U256::MAX);
+        assert_eq!(U256::from(x), U256::MAX.rem(&p));
+        assert!(x.pow(&p_minus_1).is_one());
+        assert!(x.multiply(&x.inverse()).is_one());
+        assert!(!modulus.elem(&p_minus_1).is_one());
+        assert!(modulus.elem(&p.checked_add(&U256::ONE).unwrap()).is_one());
+        // -1 has order 2; the order is found correctly even though the odd part of p - 1 is
+        // given as a single factor, since it is coprime to the order.
+        let factors = [(U256::from(2), 96), (p_minus_1.shr(96), 1)];
+        assert_eq!(modulus.elem(&p_minus_1).order(&factors), U256::from(2));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn matches_fp_big() {
+        use num_bigint::BigUint;
+
+        let to_big = |x: U256| BigUint::from_bytes_le(&x.limbs().map(u64::to_le_bytes).concat());
+        // 2^192 - 2^64 - 1
+        let p = U256::ONE
+            .shl(192)
+            .overflowing_sub(&U256::ONE.shl(64))
+            .0
+            .overflowing_sub(&U256::ONE)
+            .0;
+        let small = Modulus256::new(p);
+        let big = BigModulus::new(to_big(p));
+        for x in [U256::from(3), U256::MAX, p.shr(1)] {
+            let (a, b) = (small.elem(&x), big.elem(&to_big(x)));
+            let e = x.shr(7);
+            assert_eq!(to_big(a.pow(&e).into()), BigUint::from(b.pow(&to_big(e))));
+            assert_eq!(to_big(a.inverse().into()), BigUint::from(b.inverse()));
+        }
+    }
+}
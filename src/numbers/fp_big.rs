@@ -0,0 +1,379 @@
+use num_bigint::BigUint;
+
+use crate::numbers::{pow_by_bits, Factorization, RuntimeFpElem};
+
+/// An odd prime modulus of arbitrary size, together with the constants needed for Montgomery
+/// multiplication modulo it.
+///
+/// Elements of $\mathbb{Z} / p\mathbb{Z}$ for such a prime are represented by [`FpBig`], which
+/// borrows its modulus.
+///
+/// Requires the `bigint` feature.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BigModulus {
+    p: BigUint,
+    limbs: usize,
+    r2: BigUint,
+    p_inv_neg: u64,
+}
+
+/// An integer modulo a [`BigModulus`], for primes which do not fit into a `u128`.
+///
+/// This is the arbitrary precision counterpart of [`FpNum`](crate::numbers::FpNum), and is kept
+/// internally in Montgomery form over 64-bit limbs.
+/// It implements [`RuntimeFpElem`] in place of [`GroupElem`](crate::numbers::GroupElem), which
+/// it cannot implement: the identity and the size of the group are only known at runtime, and
+/// the size need not fit into a `u128`.
+///
+/// Requires the `bigint` feature.
+///
+/// # Example
+/// ```
+/// use libbgs::numbers::*;
+/// use num_bigint::BigUint;
+///
+/// // 2^130 - 5
+/// let p = (BigUint::from(1u8) << 130u32) - 5u8;
+/// let modulus = BigModulus::new(p.clone());
+/// let x = modulus.elem(&BigUint::from(3u8));
+/// assert!(x.pow(&(p - 1u8)).is_one());
+/// assert!(x.multiply(&x.inverse()).is_one());
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct FpBig<'a> {
+    modulus: &'a BigModulus,
+    value: BigUint,
+}
+
+/// A prime power decomposition of a positive integer of arbitrary size, known at runtime.
+/// This is the arbitrary precision counterpart of [`Factorization`].
+///
+/// Requires the `bigint` feature.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BigFactorization {
+    value: BigUint,
+    factors: Vec<(BigUint, usize)>,
+}
+
+impl BigModulus {
+    /// Creates a modulus for the prime `p`.
+    /// This method will panic if `p` is even or less than 3; `p` is assumed to be prime.
+    pub fn new(p: BigUint) -> BigModulus {
+        assert!(
+            p.bit(0) && p > BigUint::from(1u8),
+            "modulus must be an odd prime"
+        );
+        let limbs = p.iter_u64_digits().len();
+        let r2 = (BigUint::from(1u8) << (128 * limbs)) % &p;
+
+        // Newton's iteration for the inverse of p modulo 2^64; each step doubles the number of
+        // correct bits.
+        let p0 = p.iter_u64_digits().next().unwrap();
+        let mut inv: u64 = 1;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(p0.wrapping_mul(inv)));
+        }
+
+        BigModulus {
+            p,
+            limbs,
+            r2,
+            p_inv_neg: inv.wrapping_neg(),
+        }
+    }
+
+    /// Returns the prime `p`.
+    pub fn modulus(&self) -> &BigUint {
+        &self.p
+    }
+
+    /// Returns the residue of `n` modulo `p`.
+    pub fn elem(&self, n: &BigUint) -> FpBig<'_> {
+        FpBig {
+            modulus: self,
+            value: self.redc((n % &self.p) * &self.r2),
+        }
+    }
+
+    /// Returns the multiplicative identity modulo `p`.
+    pub fn one(&self) -> FpBig<'_> {
+        self.elem(&BigUint::from(1u8))
+    }
+
+    fn redc(&self, mut t: BigUint) -> BigUint {
+        for _ in 0..self.limbs {
+            let m = t
+                .iter_u64_digits()
+                .next()
+                .unwrap_or(0)
+                .wrapping_mul(self.p_inv_neg);
+            t = (t + &self.p * m) >> 64;
+        }
+        if t >= self.p {
+            t -= &self.p;
+        }
+        t
+    }
+}
+
+impl<'a> FpBig<'a> {
+    /// Returns the modulus of this element.
+    pub fn modulus(&self) -> &'a BigModulus {
+        self.modulus
+    }
+
+    /// Returns the order of this element, that is, the smallest positive power `n` for which
+    /// `self.pow(n).is_one()` returns True.
+    /// The factorization must be of $p - 1$, or of a multiple of the order of this element.
+    pub fn order(&self, factors: &BigFactorization) -> BigUint {
+        let mut res = BigUint::from(1u8);
+        for (i, (p, _)) in factors.factors.iter().enumerate() {
+            let mut x = self.clone();
+            for j in 0..factors.len() {
+                if j != i {
+                    x = x.pow(&factors.factor(j));
+                }
+            }
+            while !x.is_one() {
+                x = x.pow(p);
+                res *= p;
+            }
+        }
+        res
+    }
+}
+
+impl<'a> RuntimeFpElem for FpBig<'a> {
+    type Int = BigUint;
+
+    fn one(&self) -> FpBig<'a> {
+        self.modulus.one()
+    }
+
+    fn is_one(&self) -> bool {
+        self.modulus.redc(self.value.clone()) == BigUint::from(1u8)
+    }
+
+    fn multiply(&self, other: &FpBig<'a>) -> FpBig<'a> {
+        assert_eq!(self.modulus.p, other.modulus.p, "moduli must match");
+        FpBig {
+            modulus: self.modulus,
+            value: self.modulus.redc(&self.value * &other.value),
+        }
+    }
+
+    fn pow(&self, n: &BigUint) -> FpBig<'a> {
+        pow_by_bits(self, n.bits(), |i| n.bit(i))
+    }
+
+    fn inverse(&self) -> FpBig<'a> {
+        assert!(
+            self.value != BigUint::ZERO,
+            "Attempted to take the multiplicative inverse of zero."
+        );
+        self.pow(&(&self.modulus.p - 2u8))
+    }
+}
+
+impl From<FpBig<'_>> for BigUint {
+    fn from(src: FpBig<'_>) -> BigUint {
+        src.modulus.redc(src.value)
+    }
+}
+
+impl std::fmt::Debug for FpBig<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("FpBig")
+            .field(&self.modulus.redc(self.value.clone()))
+            .finish()
+    }
+}
+
+impl BigFactorization {
+    /// Creates a new factorization from the given prime powers.
+    pub fn new(factors: Vec<(BigUint, usize)>) -> BigFactorization {
+        let value = factors.iter().map(|(p, t)| p.pow(*t as u32)).product();
+        BigFactorization { value, factors }
+    }
+
+    /// Returns the prime power factor represented by prime number `i`, $p_i^{t_i}$.
+    /// This method will `panic` if `i` is out of bounds.
+    pub fn factor(&self, i: usize) -> BigUint {
+        let (p, t) = &self.factors[i];
+        p.pow(*t as u32)
+    }
+
+    /// Gets the prime powers as a slice.
+    /// The first element of each entry is the prime, and the second is the power.
+    pub fn factors(&self) -> &[(BigUint, usize)] {
+        &self.factors
+    }
+
+    /// Returns the positive integer represented by this `BigFactorization`.
+    pub fn value(&self) -> &BigUint {
+        &self.value
+    }
+
+    /// Returns the number of prime factors in the factorization.
+    pub fn len(&self) -> usize {
+        self.factors.len()
+    }
+
+    /// True if there the factorization represents 1.
+    /// False otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.factors.is_empty()
+    }
+
+    /// Returns the factorization of the product of two integers, given their factorizations.
+    pub fn product(&self, other: &BigFactorization) -> BigFactorization {
+        let mut factors = self.factors.clone();
+        for (p, t) in &other.factors {
+            match factors.iter_mut().find(|(q, _)| q == p) {
+                Some((_, s)) => *s += t,
+                None => factors.push((p.clone(), *t)),
+            }
+        }
+        factors.sort();
+        BigFactorization::new(factors)
+    }
+}
+
+impl From<&Factorization> for BigFactorization {
+    fn from(src: &Factorization) -> BigFactorization {
+        BigFactorization::new(
+            src.factors()
+                .iter()
+                .map(|(p, t)| (BigUint::from(*p), *t))
+                .collect(),
+        )
+    }
+}
+
+/// Writes the factorization as a product of prime powers, such as `2^2 * 3 * 5`, or `1` if it is
+/// empty.
+impl std::fmt::Display for BigFactorization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.factors.is_empty() {
+            return write!(f, "1");
+        }
+        for (i, (p, t)) in self.factors.iter().enumerate() {
+            if i > 0 {
+                write!(f, " * ")?;
+            }
+            match t {
+                1 => write!(f, "{p}")?,
+                t => write!(f, "{p}^{t}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses a product of prime powers, in the format written by `Display`.
+/// A prime may appear more than once, as in `2 * 3 * 2`; its powers are added together.
+/// Primality of the factors is not checked.
+impl std::str::FromStr for BigFactorization {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<BigFactorization, String> {
+        if s.trim() == "1" {
+            return Ok(BigFactorization::new(Vec::new()));
+        }
+        let mut factors: Vec<(BigUint, usize)> = s
+            .split('*')
+            .map(|term| {
+                let (p, t) = term.split_once('^').unwrap_or((term, "1"));
+                let p = p
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("invalid prime {:?}", p.trim()))?;
+                let t = t
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("invalid exponent {:?}", t.trim()))?;
+                Ok((p, t))
+            })
+            .collect::<Result<_, String>>()?;
+        factors.sort();
+        factors.dedup_by(|(p, t), (q, s)| {
+            let same = p == q;
+            if same {
+                *s += *t;
+            }
+            same
+        });
+        Ok(BigFactorization::new(factors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers::*;
+
+    const BIG_P: u128 = 1_000_000_000_000_000_124_399;
+
+    struct Phantom {}
+
+    impl Factor<Phantom> for FpNum<BIG_P> {
+        const FACTORS: Factorization = Factorization::new(&[
+            (2, 1),
+            (7, 1),
+            (13, 1),
+            (29, 2),
+            (43, 1),
+            (705737, 1),
+            (215288719, 1),
+        ]);
+    }
+
+    #[test]
+    fn matches_fp_num() {
+        let modulus = BigModulus::new(BigUint::from(BIG_P));
+        let factors = BigFactorization::from(&<FpNum<BIG_P> as Factor<Phantom>>::FACTORS);
+        assert_eq!(*factors.value(), BigUint::from(BIG_P - 1));
+        for x in [2, 3, 5, 1 << 64, BIG_P - 1] {
+            let small = FpNum::<BIG_P>::from(x);
+            let big = modulus.elem(&BigUint::from(x));
+            assert_eq!(
+                BigUint::from(big.multiply(&big)),
+                BigUint::from(u128::from(small * small))
+            );
+            assert_eq!(
+                BigUint::from(big.inverse()),
+                BigUint::from(u128::from(small.inverse()))
+            );
+            assert_eq!(big.order(&factors), BigUint::from(small.order::<Phantom>()));
+        }
+    }
+
+    #[test]
+    fn beyond_u128() {
+        // 2^130 - 5
+        let p = (BigUint::from(1u8) << 130u32) - 5u8;
+        let modulus = BigModulus::new(p.clone());
+        let x = modulus.elem(&(BigUint::from(u128::MAX) * 7u8));
+        assert!(x.pow(&(&p - 1u8)).is_one());
+        assert!(x.multiply(&x.inverse()).is_one());
+        assert_eq!(BigUint::from(x), (BigUint::from(u128::MAX) * 7u8) % &p);
+        assert!(!modulus.elem(&(&p - 1u8)).is_one());
+        assert!(modulus.elem(&(&p + 1u8)).is_one());
+    }
+
+    #[test]
+    fn products_of_factorizations() {
+        let a = BigFactorization::new(vec![(BigUint::from(2u8), 1), (BigUint::from(5u8), 1)]);
+        let b = BigFactorization::new(vec![(BigUint::from(2u8), 2), (BigUint::from(3u8), 1)]);
+        let c = a.product(&b);
+        assert_eq!(*c.value(), BigUint::from(120u8));
+        assert_eq!(c.factors()[0], (BigUint::from(2u8), 3));
+        assert_eq!(c.len(), 3);
+        assert_eq!(c.to_string(), "2^3 * 3 * 5");
+        assert_eq!("5 * 2^3*3".parse::<BigFactorization>(), Ok(c.clone()));
+        assert!("2^ * 3".parse::<BigFactorization>().is_err());
+        let d = "2 * 5 * 2^2 * 3".parse::<BigFactorization>().unwrap();
+        assert_eq!(d, c);
+        assert_eq!(d.to_string(), "2^3 * 3 * 5");
+    }
+}
@@ -1,6 +1,9 @@
 use crate::numbers::Factor;
 use libbgs_util::*;
 
+// Exponents of at most this many bits are scanned one bit at a time by `GroupElem::multi_pow`.
+const MULTI_POW_SHORT: u32 = 16;
+
 /// Types that represent the elements of a group.
 /// In order for a type to represent the elements of the group, the type must satisfy these axioms:
 /// * The type has a binary operator (`multiply`).
@@ -10,7 +13,7 @@ pub trait GroupElem: Clone + Eq {
     /// The unique identity element of this group.
     const ONE: Self;
 
-    ///! 256 copies of `Self::ONE` in an array.
+    /// 256 copies of `Self::ONE` in an array.
     #[deprecated(note = "To be replaced by inline `const` expressions once stabilized.")]
     const ONE_256: [Self; 256] = [Self::ONE; 256];
 
@@ -41,6 +44,93 @@ pub trait GroupElem: Clone + Eq {
         res.multiply(&y)
     }
 
+    /// Returns the product of `bases[i]` raised to the power of `exps[i]`, over every `i`.
+    ///
+    /// Rather than raising each base to its power separately, this shares the squarings between
+    /// them, by Straus's method: the exponents are scanned together from the most significant
+    /// bit, 4 bits at a time, multiplying in powers of each base from a precomputed table.
+    /// For short exponents, the table is not worth building, and the exponents are scanned one
+    /// bit at a time instead.
+    /// This method will panic if `bases` and `exps` have different lengths.
+    /// ```
+    /// use libbgs::numbers::{FpNum, GroupElem};
+    /// let bases = [FpNum::<13>::from(2), FpNum::from(3)];
+    /// assert_eq!(
+    ///     FpNum::multi_pow(&bases, &[5, 7]),
+    ///     bases[0].pow(5).multiply(&bases[1].pow(7))
+    /// );
+    /// ```
+    fn multi_pow(bases: &[Self], exps: &[u128]) -> Self {
+        assert_eq!(
+            bases.len(),
+            exps.len(),
+            "There must be one exponent for each base."
+        );
+        let bits = exps
+            .iter()
+            .map(|e| 128 - e.leading_zeros())
+            .max()
+            .unwrap_or(0);
+        let mut res = Self::ONE;
+        if bits <= MULTI_POW_SHORT {
+            for i in (0..bits).rev() {
+                res = res.multiply(&res);
+                for (b, e) in bases.iter().zip(exps) {
+                    if (e >> i) & 1 == 1 {
+                        res = res.multiply(b);
+                    }
+                }
+            }
+            return res;
+        }
+
+        let (bases, exps): (Vec<&Self>, Vec<u128>) =
+            bases.iter().zip(exps).filter(|(_, e)| **e > 0).unzip();
+        // The powers 0 through 15 of each base, one base after another.
+        let mut table = Vec::with_capacity(16 * exps.len());
+        for b in bases {
+            let mut x = Self::ONE;
+            for _ in 0..16 {
+                table.push(x.clone());
+                x = x.multiply(b);
+            }
+        }
+        for w in (0..bits.div_ceil(4)).rev() {
+            for _ in 0..4 {
+                res = res.multiply(&res);
+            }
+            for (k, e) in exps.iter().enumerate() {
+                let d = ((e >> (4 * w)) & 0xF) as usize;
+                if d > 0 {
+                    res = res.multiply(&table[16 * k + d]);
+                }
+            }
+        }
+        res
+    }
+
+    /// Raises this element to the power of `n`, where `n` is given as a slice of 64-bit limbs,
+    /// least significant limb first.
+    /// This is intended for exponents which do not fit into a `u128`.
+    fn pow_big(&self, n: &[u64]) -> Self {
+        let mut res = Self::ONE;
+        for limb in n.iter().rev() {
+            for i in (0..64).rev() {
+                res = res.multiply(&res);
+                if (limb >> i) & 1 == 1 {
+                    res = res.multiply(self);
+                }
+            }
+        }
+        res
+    }
+
+    /// Raises this element to the power of `n`, for an arbitrary precision integer `n`.
+    #[cfg(feature = "bigint")]
+    fn pow_biguint(&self, n: &num_bigint::BigUint) -> Self {
+        self.pow_big(&n.to_u64_digits())
+    }
+
     /// Returns the multiplicative inverse of this element.
     /// If you implement this trait, you must guarantee `x.inverse().multiply(x)` and
     /// `x.multiply(x.inverse())` both evaluate to `ONE`.
@@ -74,3 +164,60 @@ pub trait GroupElem: Clone + Eq {
         res
     }
 }
+
+/// An element of $\mathbb{Z} / p\mathbb{Z}$ for an odd prime $p$ known only at runtime, such as
+/// [`Fp256`](crate::numbers::Fp256) or [`FpBig`](crate::numbers::FpBig).
+///
+/// Such types cannot implement [`GroupElem`]: the identity depends on the modulus, so it cannot
+/// be a constant, and the size of the group need not fit into a `u128`.
+/// In particular, they cannot be used with [`SylowDecomp`](crate::numbers::SylowDecomp), nor in
+/// searches.
+/// This trait gives them the basic operations of a `GroupElem` instead, with exponents of type
+/// `Int`, so that arithmetic can be written once for every size of prime.
+///
+/// # Example
+/// ```
+/// use libbgs::numbers::*;
+/// use libbgs::util::U256;
+///
+/// fn fermat<T: RuntimeFpElem>(x: &T, p_minus_1: &T::Int) -> bool {
+///     x.pow(p_minus_1).is_one()
+/// }
+///
+/// let modulus = Modulus256::new(U256::from(1_000_000_007));
+/// assert!(fermat(&modulus.elem(&U256::from(2)), &U256::from(1_000_000_006)));
+/// ```
+pub trait RuntimeFpElem: Clone + Eq {
+    /// The unsigned integer type of the modulus and of exponents.
+    type Int;
+
+    /// Returns the multiplicative identity, modulo the same prime as this element.
+    fn one(&self) -> Self;
+
+    /// True if this element is the multiplicative identity.
+    fn is_one(&self) -> bool;
+
+    /// Returns the product of two elements.
+    /// This method will panic if the elements have different moduli.
+    fn multiply(&self, other: &Self) -> Self;
+
+    /// Raises this element to the power of `n`.
+    fn pow(&self, n: &Self::Int) -> Self;
+
+    /// Returns the multiplicative inverse of this element.
+    /// This method will panic if this element is zero.
+    fn inverse(&self) -> Self;
+}
+
+// Raises `x` to the power of the `bits`-bit exponent whose `i`th bit is `bit(i)`, by squaring and
+// multiplying from the most significant bit.
+pub(crate) fn pow_by_bits<T: RuntimeFpElem>(x: &T, bits: u64, bit: impl Fn(u64) -> bool) -> T {
+    let mut res = x.one();
+    for i in (0..bits).rev() {
+        res = res.multiply(&res);
+        if bit(i) {
+            res = res.multiply(x);
+        }
+    }
+    res
+}
@@ -0,0 +1,246 @@
+use std::marker::PhantomData;
+
+use crate::numbers::*;
+
+/// Maps from the group of `A` to the group of `B` which preserve `multiply`.
+/// If you implement this trait, you must guarantee that
+/// `h.apply(&x.multiply(&y)) == h.apply(&x).multiply(&h.apply(&y))` for every `x` and `y`; the
+/// helpers here, and [`SylowPushforward`], rely on it to transfer orders and decompositions from
+/// `A` to `B` without recomputing them in `B`.
+///
+/// Closures `Fn(&A) -> B` implement this trait, so any map known to be a homomorphism may be
+/// used directly.
+pub trait Homomorphism<A: GroupElem, B: GroupElem> {
+    /// Returns the image of `x`.
+    fn apply(&self, x: &A) -> B;
+
+    /// Returns the order of the image of `x`.
+    /// The order divides the order of `x`, so only the factorization of the order of the group of
+    /// `A` is needed; the group of `B` need not implement [`Factor`] at all.
+    /// The result is meaningless if `x` is not in the group of `A`, as when a [`QuadNum`] does not
+    /// have norm 1; debug builds panic in that case.
+    fn image_order<S>(&self, x: &A) -> u128
+    where
+        A: Factor<S>,
+    {
+        let mut res = 1;
+        for i in 0..A::FACTORS.len() {
+            let (p, t) = A::FACTORS[i];
+            let mut y = self.apply(&x.pow(A::SIZE / A::FACTORS.factor(i)));
+            for _ in 0..t {
+                if y == B::ONE {
+                    break;
+                }
+                y = y.pow(p);
+                res *= p;
+            }
+            debug_assert!(
+                y == B::ONE,
+                "The order of the image is not a divisor of the order of the group."
+            );
+        }
+        res
+    }
+
+    /// True if the image of `x` is the identity.
+    fn in_kernel(&self, x: &A) -> bool {
+        self.apply(x) == B::ONE
+    }
+}
+
+impl<A: GroupElem, B: GroupElem, F: Fn(&A) -> B> Homomorphism<A, B> for F {
+    fn apply(&self, x: &A) -> B {
+        self(x)
+    }
+}
+
+/// The reduction map $\mathbb{Z} / N\mathbb{Z} \to \mathbb{Z} / M\mathbb{Z}$, for $M$ dividing
+/// $N$.
+/// Applying it panics if $M$ does not divide $N$, since the map is then not a homomorphism.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reduction;
+
+impl<const N: u128, const M: u128> Homomorphism<CyclicNum<N>, CyclicNum<M>> for Reduction {
+    fn apply(&self, x: &CyclicNum<N>) -> CyclicNum<M> {
+        assert!(N.is_multiple_of(M), "Reduction requires {M} to divide {N}.");
+        CyclicNum::from(x.value())
+    }
+}
+
+/// The map $x \mapsto x^k$, which is a homomorphism from every abelian group to itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Power(pub u128);
+
+impl<C: GroupElem> Homomorphism<C, C> for Power {
+    fn apply(&self, x: &C) -> C {
+        x.pow(self.0)
+    }
+}
+
+impl<S, const L: usize, C: SylowDecomposable<S>> Homomorphism<SylowElem<S, L, C>, C>
+    for SylowDecomp<S, L, C>
+{
+    fn apply(&self, x: &SylowElem<S, L, C>) -> C {
+        x.to_product(self)
+    }
+}
+
+/// A homomorphism carried over to the Sylow decompositions of its domain and codomain.
+/// The images of the generators of the domain's decomposition are found once, as coordinates in
+/// the codomain's decomposition; thereafter, elements are mapped by arithmetic on their
+/// coordinates alone, and the orders of the image and kernel follow from the orders of those
+/// images.
+///
+/// # Example
+/// ```
+/// use libbgs::numbers::*;
+///
+/// struct Ph;
+/// impl_cyclic_factors!(Ph, 60, 12);
+///
+/// let from = SylowDecomp::<Ph, 3, CyclicNum<60>>::new();
+/// let to = SylowDecomp::<Ph, 2, CyclicNum<12>>::new();
+/// let push = SylowPushforward::new(&Reduction, &from, &to).unwrap();
+/// assert_eq!(push.image_order(), 12);
+/// assert_eq!(push.kernel_order(), 5);
+///
+/// let x = SylowElem::from_product(&CyclicNum::from(35), &from).unwrap();
+/// assert_eq!(push.apply(&x).to_product(&to), CyclicNum::from(11));
+/// ```
+pub struct SylowPushforward<S, const LA: usize, const LB: usize, A, B>
+where
+    A: SylowDecomposable<S>,
+    B: SylowDecomposable<S>,
+{
+    images: [SylowElem<S, LB, B>; LA],
+    _phantom: PhantomData<A>,
+}
+
+impl<S, const LA: usize, const LB: usize, A, B> SylowPushforward<S, LA, LB, A, B>
+where
+    A: SylowDecomposable<S>,
+    B: SylowDecomposable<S>,
+{
+    /// Returns the homomorphism `h` carried over to the decompositions `from` and `to`, or `None`
+    /// if the image of some generator of `from` is not in the group decomposed by `to`.
+    pub fn new(
+        h: &impl Homomorphism<A, B>,
+        from: &SylowDecomp<S, LA, A>,
+        to: &SylowDecomp<S, LB, B>,
+    ) -> Option<SylowPushforward<S, LA, LB, A, B>> {
+        let mut images = [SylowElem::ONE; LA];
+        for (i, image) in images.iter_mut().enumerate() {
+            *image = SylowElem::from_product(&h.apply(from.generator(i)), to)?;
+        }
+        Some(SylowPushforward {
+            images,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Returns the image of the generator of the Sylow subgroup of prime power index `i`, as
+    /// coordinates in the codomain's decomposition.
+    pub fn image_of_generator(&self, i: usize) -> &SylowElem<S, LB, B> {
+        &self.images[i]
+    }
+
+    /// Returns the image of `x`, as coordinates in the codomain's decomposition.
+    pub fn apply(&self, x: &SylowElem<S, LA, A>) -> SylowElem<S, LB, B> {
+        (0..LA)
+            .filter(|i| x.coords[*i] > 0)
+            .fold(SylowElem::ONE, |acc, i| {
+                acc.multiply(&self.images[i].pow(x.coords[i]))
+            })
+    }
+
+    /// Returns the order of the image of `x`.
+    pub fn image_order_of(&self, x: &SylowElem<S, LA, A>) -> u128 {
+        self.apply(x).order()
+    }
+
+    /// Returns the order of the image of the homomorphism.
+    /// The image of each Sylow subgroup of the domain is cyclic, and lies in the Sylow subgroup of
+    /// the codomain for the same prime, so this is the product of the orders of the images of the
+    /// generators.
+    pub fn image_order(&self) -> u128 {
+        self.images.iter().map(|y| y.order()).product()
+    }
+
+    /// Returns the order of the kernel of the homomorphism.
+    pub fn kernel_order(&self) -> u128 {
+        A::SIZE / self.image_order()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Eq, Clone, Copy, Debug)]
+    struct Phantom {}
+
+    impl_factors!(Phantom, 61);
+    impl_cyclic_factors!(Phantom, 360, 24);
+
+    #[test]
+    fn image_orders() {
+        for x in 1..61 {
+            let x = FpNum::<61>::from(x);
+            for k in [1, 2, 6, 15, 60] {
+                assert_eq!(
+                    Power(k).image_order::<Phantom>(&x),
+                    x.pow(k).order::<Phantom>()
+                );
+            }
+        }
+        let square = |x: &QuadNum<61>| *x * *x;
+        let x = *SylowDecomp::<Phantom, 2, QuadNum<61>>::new().generator(1);
+        assert_eq!(square.image_order::<Phantom>(&x), 31);
+    }
+
+    #[test]
+    fn pushes_forward_cyclic() {
+        let from = SylowDecomp::<Phantom, 3, CyclicNum<360>>::new();
+        let to = SylowDecomp::<Phantom, 2, CyclicNum<24>>::new();
+        let push = SylowPushforward::new(&Reduction, &from, &to).unwrap();
+        assert_eq!(push.image_order(), 24);
+        assert_eq!(push.kernel_order(), 15);
+        for n in 0..360 {
+            let x = CyclicNum::<360>::from(n);
+            let y = push.apply(&SylowElem::from_product(&x, &from).unwrap());
+            assert_eq!(y.to_product(&to), Reduction.apply(&x));
+            let order = <Reduction as Homomorphism<_, CyclicNum<24>>>::image_order::<Phantom>;
+            assert_eq!(y.order(), order(&Reduction, &x));
+        }
+
+        let triple = SylowPushforward::new(&Power(3), &from, &from).unwrap();
+        assert_eq!(triple.image_order(), 120);
+        assert_eq!(triple.kernel_order(), 3);
+    }
+
+    #[test]
+    fn pushforward_outside_codomain() {
+        // The embedding of the prime subfield misses the elements of norm 1, except for $\pm 1$.
+        let from = SylowDecomp::<Phantom, 3, FpNum<61>>::new();
+        let to = SylowDecomp::<Phantom, 2, QuadNum<61>>::new();
+        let embed = |x: &FpNum<61>| QuadNum::from(*x);
+        assert!(SylowPushforward::new(&embed, &from, &to).is_none());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn image_order_outside_group() {
+        // The norm is trivial on the norm 1 subgroup, but not on the rest of $\mathbb{F}\_{61^2}$.
+        let x = QuadNum::<61>::all()
+            .find(|x| !x.is_zero() && norm(*x).order::<Phantom>() == 60)
+            .unwrap();
+        (|x: &QuadNum<61>| norm(*x)).image_order::<Phantom>(&x);
+    }
+
+    #[test]
+    #[should_panic]
+    fn reduction_needs_divisor() {
+        Homomorphism::<CyclicNum<360>, CyclicNum<7>>::apply(&Reduction, &CyclicNum::from(5));
+    }
+}
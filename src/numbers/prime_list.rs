@@ -0,0 +1,208 @@
+use std::io::BufRead;
+use std::path::Path;
+
+use num_bigint::BigUint;
+
+use crate::numbers::factor_backend::is_probable_prime;
+use crate::numbers::{BigFactorization, BigModulus, FactorBackend};
+
+/// A prime read from a prime list, with the factorizations of the orders of its multiplicative
+/// groups.
+///
+/// Requires the `bigint` feature.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PrimeRecord {
+    /// The prime.
+    pub p: BigUint,
+    /// The factorization of $p - 1$.
+    pub minus: BigFactorization,
+    /// The factorization of $p + 1$.
+    pub plus: BigFactorization,
+}
+
+/// The reasons a prime list may fail to load.
+/// Lines are numbered from 1.
+#[derive(Debug)]
+pub enum PrimeListError {
+    /// The list could not be read.
+    Io(std::io::Error),
+    /// A line is not in the expected format.
+    Parse(usize, String),
+    /// A line lists a number which is not prime.
+    NotPrime(usize),
+    /// A line lists the prime 2; only odd primes are supported.
+    EvenPrime(usize),
+    /// A line gives a factorization which does not multiply to $p \pm 1$, or has a composite
+    /// factor.
+    BadFactorization(usize),
+    /// The factorization backend could not factor $p \pm 1$ for a line lacking a factorization.
+    Unfactored(usize),
+}
+
+impl std::fmt::Display for PrimeListError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrimeListError::Io(e) => write!(f, "could not read prime list: {e}"),
+            PrimeListError::Parse(line, msg) => write!(f, "line {line}: {msg}"),
+            PrimeListError::NotPrime(line) => write!(f, "line {line}: not a prime"),
+            PrimeListError::EvenPrime(line) => write!(f, "line {line}: 2 is not an odd prime"),
+            PrimeListError::BadFactorization(line) => {
+                write!(
+                    f,
+                    "line {line}: factorization does not match p - 1 or p + 1"
+                )
+            }
+            PrimeListError::Unfactored(line) => {
+                write!(f, "line {line}: could not factor p - 1 or p + 1")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PrimeListError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PrimeListError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PrimeListError {
+    fn from(e: std::io::Error) -> PrimeListError {
+        PrimeListError::Io(e)
+    }
+}
+
+impl PrimeRecord {
+    /// Returns the modulus for computing with elements of $\mathbb{Z} / p\mathbb{Z}$ as
+    /// [`FpBig`](crate::numbers::FpBig).
+    pub fn modulus(&self) -> BigModulus {
+        BigModulus::new(self.p.clone())
+    }
+}
+
+/// Reads a list of primes, one per line.
+/// Each line is either a prime alone, or comma separated values `p, p - 1, p + 1`, giving the
+/// factorizations of $p - 1$ and $p + 1$ in the format of [`BigFactorization`]'s `Display`, such
+/// as `2^2 * 3 * 5`.
+/// Either factorization may be left empty.
+/// Blank lines, and everything following a `#`, are ignored.
+///
+/// Every prime is checked for primality, and every given factorization is checked against $p \pm
+/// 1$; factorizations which are not given are found with `backend`.
+/// The prime 2 is rejected, since [`BigModulus`] requires an odd modulus.
+///
+/// # Example
+/// ```
+/// use libbgs::numbers::*;
+///
+/// let list = "# p, p - 1, p + 1\n61, 2^2 * 3 * 5, 2 * 31\n1000000007\n";
+/// let records = read_primes(list.as_bytes(), &PollardRho::default()).unwrap();
+/// assert_eq!(records[0].plus.to_string(), "2 * 31");
+/// assert_eq!(records[1].minus.factors().len(), 2);
+/// ```
+pub fn read_primes<R: BufRead>(
+    reader: R,
+    backend: &impl FactorBackend,
+) -> Result<Vec<PrimeRecord>, PrimeListError> {
+    let mut res = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        res.push(read_record(i + 1, line, backend)?);
+    }
+    Ok(res)
+}
+
+/// Reads a list of primes from the file at `path`.
+/// See [`read_primes`] for the format.
+pub fn load_primes(
+    path: impl AsRef<Path>,
+    backend: &impl FactorBackend,
+) -> Result<Vec<PrimeRecord>, PrimeListError> {
+    let file = std::fs::File::open(path)?;
+    read_primes(std::io::BufReader::new(file), backend)
+}
+
+fn read_record(
+    line: usize,
+    src: &str,
+    backend: &impl FactorBackend,
+) -> Result<PrimeRecord, PrimeListError> {
+    let fields = src.split(',').map(str::trim).collect::<Vec<_>>();
+    if fields.len() != 1 && fields.len() != 3 {
+        return Err(PrimeListError::Parse(
+            line,
+            format!("expected 1 or 3 fields, found {}", fields.len()),
+        ));
+    }
+    let p: BigUint = fields[0]
+        .parse()
+        .map_err(|_| PrimeListError::Parse(line, format!("invalid prime {:?}", fields[0])))?;
+    if !is_probable_prime(&p) {
+        return Err(PrimeListError::NotPrime(line));
+    }
+    if p == BigUint::from(2u8) {
+        return Err(PrimeListError::EvenPrime(line));
+    }
+
+    let one = BigUint::from(1u8);
+    let factor = |i: usize, n: BigUint| match fields.get(i).filter(|s| !s.is_empty()) {
+        Some(s) => {
+            let res = s
+                .parse::<BigFactorization>()
+                .map_err(|msg| PrimeListError::Parse(line, msg))?;
+            if *res.value() != n || !res.factors().iter().all(|(q, _)| is_probable_prime(q)) {
+                return Err(PrimeListError::BadFactorization(line));
+            }
+            Ok(res)
+        }
+        None => backend.factor(&n).ok_or(PrimeListError::Unfactored(line)),
+    };
+    Ok(PrimeRecord {
+        minus: factor(1, &p - &one)?,
+        plus: factor(2, &p + &one)?,
+        p,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers::PollardRho;
+
+    fn read(src: &str) -> Result<Vec<PrimeRecord>, PrimeListError> {
+        read_primes(src.as_bytes(), &PollardRho::default())
+    }
+
+    #[test]
+    fn reads_and_factors() {
+        let records = read("  13 # a comment\n\n61, 2^2 * 3 * 5,\n7, , 2^3\n").unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].minus.to_string(), "2^2 * 3");
+        assert_eq!(records[0].plus.to_string(), "2 * 7");
+        assert_eq!(records[1].plus.to_string(), "2 * 31");
+        assert_eq!(records[2].minus.to_string(), "2 * 3");
+        assert_eq!(*records[2].modulus().modulus(), BigUint::from(7u8));
+    }
+
+    #[test]
+    fn validates() {
+        assert!(matches!(read("15"), Err(PrimeListError::NotPrime(1))));
+        assert!(matches!(read("3\n2"), Err(PrimeListError::EvenPrime(2))));
+        assert!(matches!(read("13\nabc"), Err(PrimeListError::Parse(2, _))));
+        assert!(matches!(read("13, 12"), Err(PrimeListError::Parse(1, _))));
+        assert!(matches!(
+            read("13, 2 * 6, "),
+            Err(PrimeListError::BadFactorization(1))
+        ));
+        assert!(matches!(
+            read("13, 2^2 * 3, 2 * 3"),
+            Err(PrimeListError::BadFactorization(1))
+        ));
+    }
+}
@@ -22,6 +22,35 @@ pub struct QuadNum<const P: u128>(
     pub FpNum<P>,
 );
 
+/// The subgroup of $\mathbb{F}\_{p^2}^\times$ of elements with norm 1, of order $p + 1$.
+/// This is the group decomposed by the `SylowDecomposable` implementation of `QuadNum<P>`.
+/// A value of this type is an element of the subgroup, checked on construction by
+/// `Norm1::try_from`.
+/// See [`is_norm_one`].
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct Norm1<const P: u128>(QuadNum<P>);
+
+/// The error returned when converting a [`QuadNum`] to a [`Norm1`] fails, holding the element
+/// whose norm is not 1.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct NotNormOne<const P: u128>(pub QuadNum<P>);
+
+/// An iterator yielding every element of $\mathbb{F}\_{p^2}$ in order of Steinitz index.
+/// See [`QuadNum::all`].
+#[derive(Clone, Debug)]
+pub struct QuadNumIter<const P: u128> {
+    next: u128,
+    end: u128,
+}
+
+/// An iterator yielding every element of norm 1 in $\mathbb{F}\_{p^2}$.
+/// See [`Norm1::all`].
+#[derive(Clone, Debug)]
+pub struct Norm1Iter<const P: u128> {
+    next: u128,
+    end: u128,
+}
+
 impl<const P: u128> QuadNum<P> {
     /// The basis element for the numbers outside of the prime subfield.
     pub const R: FpNum<P> = FpNum::<P>::find_nonresidue();
@@ -35,23 +64,121 @@ impl<const P: u128> QuadNum<P> {
     }
 
     /// Returns the Steinitz element of $\mathbb{F}\_{p^2}$ with index `i`.
-    pub fn steinitz(i: u128) -> QuadNum<P> {
-        QuadNum::from((i % P, i / P))
+    pub const fn steinitz(i: u128) -> QuadNum<P> {
+        QuadNum(FpNum::from_u128(i % P), FpNum::from_u128(i / P))
+    }
+
+    /// Returns the Steinitz index of this element; that is, the inverse of
+    /// [`QuadNum::steinitz`].
+    /// This method will panic if the index does not fit into a `u128`, which is only possible
+    /// when $p \geq 2^{64}$.
+    pub fn steinitz_index(&self) -> u128 {
+        u128::from(self.1)
+            .checked_mul(P)
+            .and_then(|x| x.checked_add(u128::from(self.0)))
+            .expect("Steinitz index overflows a u128")
+    }
+
+    /// Returns an iterator yielding every element of $\mathbb{F}\_{p^2}$ exactly once, in order of
+    /// Steinitz index, beginning with zero.
+    /// This method will panic if $p^2$ does not fit into a `u128`, that is, if $p \geq 2^{64}$;
+    /// such fields are far too large to enumerate anyway.
+    /// ```
+    /// use libbgs::numbers::*;
+    /// let all = QuadNum::<5>::all();
+    /// assert_eq!(all.len(), 25);
+    /// assert_eq!(all.filter(|x| is_norm_one(*x)).count(), 6);
+    /// ```
+    pub fn all() -> QuadNumIter<P> {
+        let Some(end) = P.checked_mul(P) else {
+            panic!("The field of order {P}^2 has more than 2^128 elements.");
+        };
+        QuadNumIter { next: 0, end }
     }
 
     /// Calculates the square root of an integer modulo `P`, casting to an `FpNum<P>` if `x` is a
     /// quadratic residue.
     /// Returns a `Left` `QuadNum<P>` if `x` is a quadratic nonresidue, or a `Right` `FpNum<P>` if
     /// `x` is a quadratic residue (including 0).
-    pub fn int_sqrt_either(mut x: FpNum<P>) -> Either<QuadNum<P>, FpNum<P>> {
+    pub const fn int_sqrt_either(x: FpNum<P>) -> Either<QuadNum<P>, FpNum<P>> {
         if let Some(y) = x.int_sqrt() {
             return Right(y);
         }
 
-        let r = Self::R.inverse();
-        x = x.multiply(&r);
-        let a1 = x.int_sqrt().unwrap();
-        Left(QuadNum(FpNum::from(0), a1))
+        let Some(r) = Self::R.const_inverse() else {
+            unreachable!();
+        };
+        let Some(a1) = x.const_mul(&r).int_sqrt() else {
+            unreachable!();
+        };
+        Left(QuadNum(FpNum::ZERO, a1))
+    }
+
+    /// Returns the value $a_0$, when writing this number as $a_0 + a_1\sqrt{r}$.
+    pub const fn a0(&self) -> FpNum<P> {
+        self.0
+    }
+
+    /// Returns the value $a_1$, when writing this number as $a_0 + a_1\sqrt{r}$.
+    pub const fn a1(&self) -> FpNum<P> {
+        self.1
+    }
+
+    /// Returns the conjugate of this number, $\bar{x} = x^p$.
+    /// Writing $x = a_0 + a_1\sqrt{r}$, this is $a_0 - a_1\sqrt{r}$.
+    pub const fn conjugate(&self) -> QuadNum<P> {
+        QuadNum(self.0, self.1.const_neg())
+    }
+
+    /// Returns the norm of this number down to the prime subfield, $N(x) = x \bar{x} = x^{p + 1}$.
+    /// Writing $x = a_0 + a_1\sqrt{r}$, this is $a_0^2 - r a_1^2$.
+    pub const fn norm(&self) -> FpNum<P> {
+        let r_a1_sq = Self::R.const_mul(&self.1).const_mul(&self.1);
+        self.0.const_mul(&self.0).const_sub(&r_a1_sq)
+    }
+
+    /// Returns the image of this number under the Frobenius automorphism, $x \mapsto x^p$, which
+    /// is its conjugate; see [`QuadNum::conjugate`].
+    pub const fn frobenius(&self) -> QuadNum<P> {
+        self.conjugate()
+    }
+
+    /// Returns the trace of this number down to the prime subfield, $\text{Tr}(x) = x + \bar{x}$.
+    /// Writing $x = a_0 + a_1\sqrt{r}$, this is $2 a_0$.
+    pub const fn trace(&self) -> FpNum<P> {
+        self.0.const_add(&self.0)
+    }
+
+    /// Returns a square root of this number, or `None` if it is not a square in
+    /// $\mathbb{F}\_{p^2}$.
+    ///
+    /// Writing this number as $a_0 + a_1\sqrt{r}$ with $a_1 \neq 0$, it is a square exactly when its
+    /// norm $n^2$ is a square in the prime subfield, and then the root is $c + d\sqrt{r}$, where
+    /// $c^2 = (a_0 \pm n) / 2$ for whichever sign makes this a square, and $d = a_1 / 2c$.
+    /// Every element of the prime subfield is a square; see [`QuadNum::int_sqrt_either`].
+    /// ```
+    /// use libbgs::numbers::*;
+    /// let x = QuadNum::<13>::from((2, 5));
+    /// let y = (x * x).sqrt().unwrap();
+    /// assert!(y == x || y == x * QuadNum::from((12, 0)));
+    /// assert_eq!(QuadNum::<13>::R.int_sqrt(), None);
+    /// assert!(QuadNum::<13>::from((0, 1)).sqrt().is_none());
+    /// ```
+    pub fn sqrt(&self) -> Option<QuadNum<P>> {
+        if self.1 == FpNum::ZERO {
+            return Some(Self::int_sqrt(self.0));
+        }
+        let n = self.norm().int_sqrt()?;
+        let half = FpNum::from(2).inverse();
+        // The product of the two choices is $r a_1^2 / 4$, a nonresidue, so exactly one is a
+        // square.
+        let Some(c) = ((self.0 + n) * half)
+            .int_sqrt()
+            .or_else(|| ((self.0 - n) * half).int_sqrt())
+        else {
+            unreachable!();
+        };
+        Some(QuadNum(c, self.1 * (c + c).inverse()))
     }
 
     /// Calculates the square root af in integer modulo `P`.
@@ -60,6 +187,141 @@ impl<const P: u128> QuadNum<P> {
     }
 }
 
+impl<const P: u128> Norm1<P> {
+    /// Returns the element of $\mathbb{F}\_{p^2}$ this value wraps.
+    pub const fn get(&self) -> QuadNum<P> {
+        self.0
+    }
+
+    /// Returns an iterator yielding every element of norm 1 exactly once, beginning with 1.
+    /// The rest are the elements $(t + \sqrt{r}) / (t - \sqrt{r})$ for $t = 0, \ldots, p - 1$, in
+    /// that order.
+    /// ```
+    /// use libbgs::numbers::*;
+    /// let circle = Norm1::<7>::all();
+    /// assert_eq!(circle.len(), 8);
+    /// assert!(circle.clone().all(|x| is_norm_one(x.get())));
+    /// assert!(circle.map(|x| x.get().pow(8)).all(|x| x == QuadNum::ONE));
+    /// ```
+    pub fn all() -> Norm1Iter<P> {
+        Norm1Iter {
+            next: 0,
+            end: P + 1,
+        }
+    }
+
+    /// Returns the "angle" of `x` on the circle of elements of norm 1, that is, the integer $k$
+    /// in the range `0..P + 1` such that $x = g^k$, where $g$ is the product of the generators of
+    /// `decomp`.
+    /// The logarithm is found by the Pohlig-Hellman algorithm; see [`SylowElem::from_product`].
+    /// This method will panic if the generators of `decomp` do not generate the circle, which
+    /// cannot happen for a decomposition that passes [`SylowDecomp::verify`].
+    /// ```
+    /// use libbgs::numbers::*;
+    /// struct Ph;
+    /// impl_factors!(Ph, 13);
+    ///
+    /// let decomp = SylowDecomp::<Ph, 2, QuadNum<13>>::new();
+    /// let x = Norm1::from_log(5, &decomp);
+    /// assert_eq!(Norm1::log(&x, &decomp), 5);
+    /// // The circle has 14 elements, so 3 * 5 = 15 wraps around to 1.
+    /// let y = Norm1::try_from(x.get().pow(3)).unwrap();
+    /// assert_eq!(Norm1::log(&y, &decomp), 1);
+    /// ```
+    pub fn log<S, const L: usize>(x: &Norm1<P>, decomp: &SylowDecomp<S, L, QuadNum<P>>) -> u128
+    where
+        QuadNum<P>: Factor<S>,
+    {
+        let Some(k) = SylowElem::from_product(&x.0, decomp) else {
+            panic!("The decomposition does not generate the elements of norm 1.");
+        };
+        k.to_log()
+    }
+
+    /// Returns the element of norm 1 with angle `k`, that is, $g^k$, where $g$ is the product of
+    /// the generators of `decomp`; this is the inverse of [`Norm1::log`].
+    pub fn from_log<S, const L: usize>(k: u128, decomp: &SylowDecomp<S, L, QuadNum<P>>) -> Norm1<P>
+    where
+        QuadNum<P>: Factor<S>,
+    {
+        Norm1(SylowElem::from_log(k).to_product(decomp))
+    }
+}
+
+impl<const P: u128> Iterator for QuadNumIter<P> {
+    type Item = QuadNum<P>;
+
+    fn next(&mut self) -> Option<QuadNum<P>> {
+        if self.next == self.end {
+            return None;
+        }
+        self.next += 1;
+        Some(QuadNum::steinitz(self.next - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = usize::try_from(self.end - self.next).ok();
+        (len.unwrap_or(usize::MAX), len)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<QuadNum<P>> {
+        self.next = u128::min(self.next.saturating_add(n as u128), self.end);
+        self.next()
+    }
+}
+
+impl<const P: u128> ExactSizeIterator for QuadNumIter<P> {}
+
+impl<const P: u128> Iterator for Norm1Iter<P> {
+    type Item = Norm1<P>;
+
+    fn next(&mut self) -> Option<Norm1<P>> {
+        if self.next == self.end {
+            return None;
+        }
+        self.next += 1;
+        if self.next == 1 {
+            return Some(Norm1(QuadNum::ONE));
+        }
+        // (t + sqrt(r)) / (t - sqrt(r)) = (t^2 + r + 2t sqrt(r)) / (t^2 - r), where t^2 - r is
+        // nonzero because r is a nonresidue.
+        let t = FpNum::from(self.next - 2);
+        let r = QuadNum::<P>::R;
+        let Some(d) = (t * t - r).const_inverse() else {
+            unreachable!();
+        };
+        Some(Norm1(QuadNum((t * t + r) * d, (t + t) * d)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = usize::try_from(self.end - self.next).ok();
+        (len.unwrap_or(usize::MAX), len)
+    }
+}
+
+impl<const P: u128> ExactSizeIterator for Norm1Iter<P> {}
+
+/// Returns the norm of `x` down to the prime subfield, $N(x) = x \bar{x} = x^{p + 1}$, where
+/// $\bar{x} = x^p$ is the conjugate of $x$.
+/// Writing $x = a_0 + a_1\sqrt{r}$, this is $a_0^2 - r a_1^2$.
+/// See [`QuadNum::norm`].
+pub fn norm<const P: u128>(x: QuadNum<P>) -> FpNum<P> {
+    x.norm()
+}
+
+/// Returns the trace of `x` down to the prime subfield, $\text{Tr}(x) = x + \bar{x}$.
+/// Writing $x = a_0 + a_1\sqrt{r}$, this is $2 a_0$.
+/// See [`QuadNum::trace`].
+pub fn trace<const P: u128>(x: QuadNum<P>) -> FpNum<P> {
+    x.trace()
+}
+
+/// True if `x` has norm 1, that is, if it belongs to the subgroup of $\mathbb{F}\_{p^2}^\times$ of
+/// order $p + 1$ described by the `SylowDecomposable` implementation of `QuadNum<P>`.
+pub fn is_norm_one<const P: u128>(x: QuadNum<P>) -> bool {
+    norm(x) == FpNum::ONE
+}
+
 impl<const P: u128> GroupElem for QuadNum<P> {
     const ONE: Self = QuadNum(
         FpNum::ONE,
@@ -74,11 +336,14 @@ impl<const P: u128> GroupElem for QuadNum<P> {
         QuadNum(a0, a1)
     }
 
+    // Since $x \bar{x} = N(x)$, the inverse is $\bar{x} / N(x)$, taking a single inversion in
+    // the prime subfield.
     fn inverse(&self) -> QuadNum<P> {
-        if *self == QuadNum::ZERO {
-            panic!("Attempted to take the multiplicative inverse of zero."); 
-        }
-        self.pow(P * P - 2)
+        let Some(n_inv) = self.norm().const_inverse() else {
+            panic!("Attempted to take the multiplicative inverse of zero.");
+        };
+        let conj = self.conjugate();
+        QuadNum(conj.0 * n_inv, conj.1 * n_inv)
     }
 }
 
@@ -87,9 +352,13 @@ where
     QuadNum<P>: Factor<S>,
 {
     fn find_sylow_generator(i: usize) -> QuadNum<P> {
+        <QuadNum<P> as SylowDecomposable<S>>::find_sylow_generator_with(i, Seed::DEFAULT)
+    }
+
+    fn find_sylow_generator_with(i: usize, seed: Seed) -> QuadNum<P> {
         (1..P * 2)
             .map(|i| {
-                let j = standard_affine_shift(P * 2, i);
+                let j = seed.shift(P * 2, i);
                 let p = QuadNum::steinitz(j);
                 p.pow(P - 1)
             })
@@ -97,8 +366,56 @@ where
             .find_map(|c| QuadNum::is_sylow_generator(&c, Self::FACTORS[i]))
             .unwrap()
     }
+
+    fn find_sylow_generators_with<const L: usize>(seed: Seed) -> [QuadNum<P>; L] {
+        let candidates = (1..P * 2)
+            .map(|i| QuadNum::steinitz(seed.shift(P * 2, i)).pow(P - 1))
+            .filter(|c| *c != QuadNum::ZERO);
+        Self::first_sylow_generators(candidates, [None; L])
+    }
+}
+
+/// Overwrites both coordinates of this number with zero.
+/// As for `FpNum`, this wipes only this copy.
+#[cfg(feature = "zeroize")]
+impl<const P: u128> zeroize::Zeroize for QuadNum<P> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+        self.1.zeroize();
+    }
 }
 
+impl<const P: u128> TryFrom<QuadNum<P>> for Norm1<P> {
+    type Error = NotNormOne<P>;
+
+    fn try_from(x: QuadNum<P>) -> Result<Norm1<P>, NotNormOne<P>> {
+        if is_norm_one(x) {
+            Ok(Norm1(x))
+        } else {
+            Err(NotNormOne(x))
+        }
+    }
+}
+
+impl<const P: u128> From<Norm1<P>> for QuadNum<P> {
+    fn from(x: Norm1<P>) -> QuadNum<P> {
+        x.0
+    }
+}
+
+impl<const P: u128> std::fmt::Display for NotNormOne<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} has norm {}, not 1",
+            self.0,
+            u128::from(self.0.norm())
+        )
+    }
+}
+
+impl<const P: u128> std::error::Error for NotNormOne<P> {}
+
 impl<const P: u128> PartialEq<u128> for QuadNum<P> {
     fn eq(&self, other: &u128) -> bool {
         self.0 == FpNum::from(*other) && self.1 == FpNum::ZERO 
@@ -117,6 +434,30 @@ impl<const P: u128> From<(u128, u128)> for QuadNum<P> {
     }
 }
 
+impl<const P: u128> From<(FpNum<P>, FpNum<P>)> for QuadNum<P> {
+    fn from(value: (FpNum<P>, FpNum<P>)) -> QuadNum<P> {
+        QuadNum(value.0, value.1)
+    }
+}
+
+/// Writes the number as $a_0 + a_1\sqrt{r}$, with the value of the basis element $r$.
+/// ```
+/// use libbgs::numbers::*;
+/// assert_eq!(QuadNum::<7>::R, FpNum::from(6));
+/// assert_eq!(QuadNum::<7>::from((2, 5)).to_string(), "2 + 5√6");
+/// ```
+impl<const P: u128> std::fmt::Display for QuadNum<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} + {}√{}",
+            u128::from(self.0),
+            u128::from(self.1),
+            u128::from(Self::R)
+        )
+    }
+}
+
 impl<const P: u128> Add<Self> for QuadNum<P> {
     type Output = QuadNum<P>;
     fn add(self, other: Self) -> QuadNum<P> {
@@ -153,6 +494,7 @@ impl<const P: u128> Mul<Self> for QuadNum<P> {
 mod tests {
     use super::*;
     use crate::numbers::sylow::tests::*;
+    use std::collections::HashSet;
 
     const BIG_P: u128 = 1_000_000_000_000_000_124_399;
 
@@ -171,6 +513,123 @@ mod tests {
 
     impl_factors!(Phantom, 41);
 
+    #[test]
+    fn iterates_all() {
+        let all = QuadNum::<41>::all().collect::<HashSet<_>>();
+        assert_eq!(all.len(), 41 * 41);
+        assert_eq!(
+            QuadNum::<41>::all().nth(41 * 41 - 1),
+            Some(QuadNum::steinitz(41 * 41 - 1))
+        );
+
+        let circle = Norm1::<41>::all().map(|x| x.get()).collect::<HashSet<_>>();
+        assert_eq!(circle.len(), 42);
+        assert_eq!(
+            circle,
+            all.into_iter().filter(|x| is_norm_one(*x)).collect()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn iterates_all_needs_small_primes() {
+        QuadNum::<1_000_000_000_000_000_124_399>::all();
+    }
+
+    #[test]
+    #[should_panic]
+    fn steinitz_index_overflows() {
+        const BIG_P: u128 = 1_000_000_000_000_000_124_399;
+        QuadNum::<BIG_P>::from((0, BIG_P - 1)).steinitz_index();
+    }
+
+    #[test]
+    fn norm1_logs() {
+        let decomp = SylowDecomp::<Phantom, 3, QuadNum<41>>::new();
+        let circle = (0..42)
+            .map(|k| Norm1::from_log(k, &decomp))
+            .collect::<Vec<_>>();
+        assert_eq!(circle.iter().collect::<HashSet<_>>().len(), 42);
+        for (k, x) in circle.iter().enumerate() {
+            assert!(is_norm_one(x.get()));
+            assert_eq!(Norm1::log(x, &decomp), k as u128);
+            let y = Norm1::try_from(x.get() * circle[5].get()).unwrap();
+            assert_eq!(Norm1::log(&y, &decomp), (k as u128 + 5) % 42);
+        }
+    }
+
+    #[test]
+    fn no_coordinates_off_the_circle() {
+        let decomp = SylowDecomp::<Phantom, 3, QuadNum<41>>::new();
+        let x = QuadNum::<41>::from((1, 1));
+        assert_ne!(x.norm(), FpNum::ONE);
+        assert_eq!(SylowElem::from_product(&x, &decomp), None);
+        assert_eq!(SylowElem::from_product(&QuadNum::ZERO, &decomp), None);
+    }
+
+    #[test]
+    fn norm_and_trace() {
+        for x in QuadNum::<41>::all().filter(|x| !x.is_zero()) {
+            let n = x.pow(42);
+            assert_eq!(QuadNum::from(norm(x)), n);
+            assert_eq!(QuadNum::from(trace(x)), x + x.pow(41));
+            assert!(is_norm_one(x.pow(40)));
+            assert_eq!(is_norm_one(x), n == QuadNum::ONE);
+            assert_eq!(x.frobenius(), x.pow(41));
+            assert_eq!(x.trace(), trace(x));
+            match Norm1::try_from(x) {
+                Ok(y) => assert_eq!(QuadNum::from(y), x),
+                Err(e) => assert_eq!(e, NotNormOne(x)),
+            }
+            assert_eq!(Norm1::try_from(x.pow(40)).map(|y| y.get()), Ok(x.pow(40)));
+        }
+    }
+
+    #[test]
+    fn accessors_and_display() {
+        let x = QuadNum::<BIG_P>::from((FpNum::from(3), -FpNum::ONE));
+        assert_eq!((x.a0(), x.a1()), (FpNum::from(3), FpNum::from(BIG_P - 1)));
+        assert_eq!(
+            x.to_string(),
+            format!("3 + {}√{}", BIG_P - 1, u128::from(QuadNum::<BIG_P>::R))
+        );
+    }
+
+    #[test]
+    fn square_roots() {
+        let mut squares = 0;
+        for x in QuadNum::<41>::all() {
+            let y = (x * x).sqrt().unwrap();
+            assert_eq!(y * y, x * x);
+            match x.sqrt() {
+                Some(y) => {
+                    assert_eq!(y * y, x);
+                    squares += 1;
+                }
+                None => assert_ne!(x.pow((41 * 41 - 1) / 2), QuadNum::ONE),
+            }
+        }
+        // Zero, and half of the nonzero elements.
+        assert_eq!(squares, 1 + (41 * 41 - 1) / 2);
+
+        let x = QuadNum::<BIG_P>::from((3, 5));
+        let y = (x * x).sqrt().unwrap();
+        assert_eq!(y * y, x * x);
+    }
+
+    #[test]
+    fn inverts_by_conjugate() {
+        for x in QuadNum::<41>::all().filter(|x| !x.is_zero()) {
+            assert_eq!(x.conjugate(), x.pow(41));
+            assert_eq!(x * x.inverse(), QuadNum::ONE);
+            assert_eq!(x.inverse(), x.pow(41 * 41 - 2));
+        }
+        // The exponent $p^2 - 2$ does not fit into a `u128` for this prime.
+        let x = QuadNum::<BIG_P>::from((3, 5));
+        assert_eq!(x * x.inverse(), QuadNum::ONE);
+        assert_eq!(x.inverse().inverse(), x);
+    }
+
     #[test]
     fn calculates_r_as_nonresidue() {
         for i in 2..7 {
@@ -178,6 +637,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn steinitz_index_inverts_steinitz() {
+        for (i, x) in QuadNum::<7>::all().enumerate() {
+            assert_eq!(x, QuadNum::steinitz(i as u128));
+            assert_eq!(x.steinitz_index(), i as u128);
+        }
+        assert_eq!(QuadNum::<7>::all().count(), 49);
+    }
+
     #[test]
     fn powers_up() {
         let mut x = QuadNum::<7>::from((3, 4));
@@ -233,4 +701,35 @@ mod tests {
             test_is_generator_big::<Phantom, 11, QuadNum<BIG_P>>(gen, d);
         }
     }
+
+    #[test]
+    fn sylow_generators_at_once() {
+        type Q = QuadNum<BIG_P>;
+        for seed in [Seed::DEFAULT, Seed(5)] {
+            let gens = <Q as SylowDecomposable<Phantom>>::find_sylow_generators_with::<11>(seed);
+            for (i, gen) in gens.iter().enumerate() {
+                let expected =
+                    <Q as SylowDecomposable<Phantom>>::find_sylow_generator_with(i, seed);
+                assert_eq!(*gen, expected);
+            }
+        }
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroizes() {
+        use zeroize::{Zeroize, Zeroizing};
+
+        let g = SylowDecomp::<Phantom, 3, QuadNum<41>>::new();
+        let mut x = *g.generator(2);
+        let mut k = SylowElem::from_product(&x, &g).unwrap();
+        assert_eq!(k.coords, [0, 0, 1]);
+        x.zeroize();
+        k.zeroize();
+        assert_eq!(x, QuadNum::ZERO);
+        assert_eq!(k.coords, [0; 3]);
+
+        let secret = Zeroizing::new(FpNum::<BIG_P>::from(12345));
+        assert_eq!(*secret * FpNum::ONE, FpNum::from(12345));
+    }
 }
@@ -10,8 +10,7 @@ use libbgs_util::*;
 /// $$|G| = \prod_{i = 1}^n p_i^{t_i}$$
 /// and $G$ is a finite cyclic group.
 pub struct SylowDecomp<S, const L: usize, C: SylowDecomposable<S>> {
-    precomputed: [[C; 256]; L],
-    generators_powered: [C; L],
+    tables: [FixedBase<C>; L],
     _phantom: PhantomData<S>,
 }
 
@@ -32,6 +31,57 @@ pub trait SylowDecomposable<S>: Factor<S> + GroupElem + Eq {
     /// Finds a Sylow generator for the Sylow subgroup of prime power index `i`.
     fn find_sylow_generator(i: usize) -> Self;
 
+    /// Finds a Sylow generator for the Sylow subgroup of prime power index `i`, trying candidates
+    /// in the order given by `seed`.
+    /// The default implementation ignores the seed, which is correct for groups whose generators
+    /// are found without searching.
+    fn find_sylow_generator_with(i: usize, _seed: Seed) -> Self {
+        Self::find_sylow_generator(i)
+    }
+
+    /// Finds generators for the Sylow subgroups of every prime power index, the same generators
+    /// which [`SylowDecomposable::find_sylow_generator_with`] finds for each index.
+    /// The default implementation calls that method for each index; implementations which search
+    /// through candidates should instead test each candidate against every subgroup at once, with
+    /// [`SylowDecomposable::first_sylow_generators`].
+    fn find_sylow_generators_with<const L: usize>(seed: Seed) -> [Self; L] {
+        std::array::from_fn(|i| Self::find_sylow_generator_with(i, seed))
+    }
+
+    /// Returns, for each prime power index `i`, `known[i]` if it is not `None`, and otherwise the
+    /// generator of the Sylow subgroup of index `i` found by testing each of `candidates` in turn
+    /// with [`SylowDecomposable::is_sylow_generator`].
+    ///
+    /// Rather than being tested against one subgroup at a time, each candidate is raised to the
+    /// powers $|G| / p_i^{t_i}$ for every index still without a generator at once, sharing the
+    /// exponentiations by halving the set of indices recursively.
+    /// Testing a candidate against $L$ subgroups then takes $O(\log |G| \log L)$
+    /// multiplications, rather than $O(L \log |G|)$, which is the bulk of the time to build a
+    /// [`SylowDecomp`] when $|G|$ has many prime factors.
+    /// This method will panic if the candidates run out before every generator is found.
+    fn first_sylow_generators<const L: usize>(
+        candidates: impl IntoIterator<Item = Self>,
+        known: [Option<Self>; L],
+    ) -> [Self; L] {
+        let mut res = known;
+        let mut candidates = candidates.into_iter();
+        let mut pending = (0..L).filter(|i| res[*i].is_none()).collect::<Vec<_>>();
+        while !pending.is_empty() {
+            let c = candidates
+                .next()
+                .expect("Ran out of candidates for Sylow generators.");
+            let x = c.pow(Self::SIZE / cofactor::<S, Self>(&pending));
+            project::<S, Self>(x, &pending, &mut |i, y| {
+                let (p, t) = Self::FACTORS[i];
+                if y.pow(intpow::<0>(p, (t - 1) as u128)) != Self::ONE {
+                    res[i] = Some(y);
+                }
+            });
+            pending.retain(|i| res[*i].is_none());
+        }
+        res.map(Option::unwrap)
+    }
+
     /// True if the given element is a generator of the Sylow subgroup of the prime power
     /// represented by `d`.
     fn is_sylow_generator(candidate: &Self, d: (u128, usize)) -> Option<Self> {
@@ -60,30 +110,48 @@ pub trait SylowDecomposable<S>: Factor<S> + GroupElem + Eq {
     }
 }
 
+// Returns the product of the prime powers of the indices `idxs`.
+fn cofactor<S, C: SylowDecomposable<S>>(idxs: &[usize]) -> u128 {
+    idxs.iter().map(|i| C::FACTORS.factor(*i)).product()
+}
+
+// Calls `f(i, x^(n / q_i))` for each index `i` of `idxs`, where `q_i` is the prime power of index
+// `i`, and `n` is the product of the prime powers of `idxs`.
+fn project<S, C: SylowDecomposable<S>>(x: C, idxs: &[usize], f: &mut impl FnMut(usize, C)) {
+    match idxs {
+        [] => {}
+        [i] => f(*i, x),
+        _ => {
+            let (left, right) = idxs.split_at(idxs.len() / 2);
+            project::<S, C>(x.pow(cofactor::<S, C>(right)), left, f);
+            project::<S, C>(x.pow(cofactor::<S, C>(left)), right, f);
+        }
+    }
+}
+
 impl<S, const L: usize, C: SylowDecomposable<S>> SylowDecomp<S, L, C> {
     /// Returns a decomposition for the group.
-    /// This method may be expensive because it calls `find_sylow_generator` for each Sylow
-    /// subgroup.
+    /// This method may be expensive because it searches for a generator of each Sylow subgroup.
     pub fn new() -> SylowDecomp<S, L, C> {
-        let mut generators_powered = [C::ONE; L];
-        #[allow(deprecated)]
-        let mut precomputed = [C::ONE_256; L];
-        let mut i = 0;
-        while i < L {
-            let x = C::find_sylow_generator(i);
-            let mut g = C::ONE;
-            let mut j = 0;
-            while j < 256 {
-                precomputed[i][j] = g.clone();
-                g = g.multiply(&x);
-                j += 1;
-            }
-            generators_powered[i] = g;
-            i += 1;
-        }
+        Self::new_with_seed(Seed::DEFAULT)
+    }
+
+    /// Returns a decomposition for the group, searching for each generator in the order given by
+    /// `seed`.
+    /// See [`SylowDecomposable::find_sylow_generators_with`].
+    pub fn new_with_seed(seed: Seed) -> SylowDecomp<S, L, C> {
+        Self::from_generators(C::find_sylow_generators_with::<L>(seed))
+    }
+
+    /// Returns a decomposition with the given generators, such as those recorded from an earlier
+    /// run, without searching for any.
+    /// The generator at index `i` must generate the Sylow subgroup of the prime power at index `i`
+    /// of the factorization; this is not checked, but may be with [`SylowDecomp::verify`].
+    pub fn from_generators(generators: [C; L]) -> SylowDecomp<S, L, C> {
         SylowDecomp {
-            precomputed,
-            generators_powered,
+            tables: std::array::from_fn(|i| {
+                FixedBase::new(generators[i].clone(), C::FACTORS.factor(i) - 1)
+            }),
             _phantom: PhantomData,
         }
     }
@@ -94,7 +162,63 @@ impl<S, const L: usize, C: SylowDecomposable<S>> SylowDecomp<S, L, C> {
     /// then the generator at index `i` of the array returned by the `generators` method is a
     /// generator of the Sylow subgroup of order $p^t$.
     pub fn generator(&self, i: usize) -> &C {
-        &self.precomputed[i][1]
+        self.tables[i].base()
+    }
+
+    /// Returns the table of powers of the generator at index `i`, with which any element of its
+    /// Sylow subgroup can be found quickly.
+    pub fn table(&self, i: usize) -> &FixedBase<C> {
+        &self.tables[i]
+    }
+
+    /// Returns the order of the generator at index `i`.
+    /// For a valid decomposition, this is the prime power at index `i` of the factorization.
+    pub fn generator_order(&self, i: usize) -> u128 {
+        self.generator(i).order::<S>()
+    }
+
+    /// Returns the element of the original group for each element yielded by `elems`, as
+    /// [`SylowElem::to_product`] does.
+    ///
+    /// The product of the powers of the generators for the first coordinates of each element is
+    /// kept, so when consecutive elements share their first coordinates, as those yielded by a
+    /// [`SylowStream`](crate::streams::SylowStream) mostly do, only the powers for the remaining
+    /// coordinates are computed.
+    pub fn to_products<'a>(
+        &'a self,
+        elems: impl IntoIterator<Item = SylowElem<S, L, C>> + 'a,
+    ) -> impl Iterator<Item = C> + 'a {
+        // `prefix[i]` is the product of the powers for the first `i` coordinates of `last`, for
+        // `i` up to `valid`.
+        let mut prefix = vec![C::ONE; L + 1];
+        let mut last = [0; L];
+        let mut valid = 0;
+        elems.into_iter().map(move |x| {
+            let coords: [u128; L] =
+                std::array::from_fn(|i| x.coords[i] % (self.tables[i].max_exp() + 1));
+            let k = (0..valid).find(|i| coords[*i] != last[*i]).unwrap_or(valid);
+            for i in k..L {
+                prefix[i + 1] = match coords[i] {
+                    0 => prefix[i].clone(),
+                    r => prefix[i].multiply(&self.tables[i].pow(r)),
+                };
+            }
+            last = coords;
+            valid = L;
+            prefix[L].clone()
+        })
+    }
+
+    /// True if every stored generator generates the Sylow subgroup it is meant to, and the
+    /// precomputed tables of powers of the generators are consistent; false otherwise.
+    /// This is much cheaper than recomputing the decomposition, and is intended as an integrity
+    /// check before trusting a decomposition for a long computation.
+    pub fn verify(&self) -> bool {
+        (0..L).all(|i| {
+            self.generator_order(i) == C::FACTORS.factor(i)
+                && self.tables[i].max_exp() == C::FACTORS.factor(i) - 1
+                && self.tables[i].verify()
+        })
     }
 }
 
@@ -124,36 +248,100 @@ impl<S, const L: usize, C: SylowDecomposable<S>> SylowElem<S, L, C> {
 
     /// Returns the element of the original group with the given coordinates.
     pub fn to_product(&self, g: &SylowDecomp<S, L, C>) -> C {
-        (0..L).filter(|i| self.coords[*i] > 0).fold(C::ONE, |x, i| {
-            let mut y = g.precomputed[i][(self.coords[i] & 0xFF) as usize].clone();
-            if self.coords[i] > 0xFF {
-                y = y.multiply(&g.generators_powered[i].pow(self.coords[i] >> 8));
-            }
-            x.multiply(&y)
-        })
+        // Each generator's order is one more than the greatest power in its table.
+        let coords =
+            std::array::from_fn::<_, L, _>(|i| self.coords[i] % (g.tables[i].max_exp() + 1));
+        FixedBase::multi_pow(&g.tables, &coords)
     }
 
-    /// Returns the positive integer represented by this `Factorization`.
-    pub fn order(&self) -> u128 {
-        let mut res = 1;
-        for i in 0..L {
-            let mut x = *self;
-            for j in 0..L {
-                if j == i {
-                    continue;
-                }
-                x = x.pow(C::FACTORS.factor(j));
+    /// Returns the coordinates of an element of the original group, the inverse of
+    /// [`SylowElem::to_product`], or `None` if `x` is not in the group generated by the
+    /// decomposition.
+    /// Each coordinate is a discrete logarithm in a Sylow subgroup, found one base-$p$ digit at a
+    /// time, by the Pohlig-Hellman algorithm.
+    /// Each digit is found by exhaustive search, so this method takes time proportional to the
+    /// sum of $p t$ over the prime powers $p^t$ of the factorization; it is intended for groups
+    /// without large prime factors, or for occasional use.
+    pub fn from_product(x: &C, g: &SylowDecomp<S, L, C>) -> Option<SylowElem<S, L, C>> {
+        let mut coords = [0; L];
+        for (i, coord) in coords.iter_mut().enumerate() {
+            let (p, t) = C::FACTORS[i];
+            let q = C::FACTORS.factor(i);
+            // Project onto the Sylow subgroup, taking the logarithm to a base which is the
+            // generator projected the same way, so that the logarithm is the coordinate.
+            let base = g.generator(i).pow(C::SIZE / q);
+            let y = x.pow(C::SIZE / q);
+            let gamma = base.pow(q / p);
+            let mut p_j = 1;
+            for j in 0..t {
+                let h = y
+                    .multiply(&base.pow(*coord).inverse())
+                    .pow(intpow::<0>(p, (t - 1 - j) as u128));
+                let mut power = C::ONE;
+                let digit = (0..p).find(|_| {
+                    let found = power == h;
+                    power = power.multiply(&gamma);
+                    found
+                })?;
+                *coord += digit * p_j;
+                p_j *= p;
             }
+        }
+        // An element outside the group may still have a logarithm in each projection.
+        let res = SylowElem::new(coords);
+        (res.to_product(g) == *x).then_some(res)
+    }
 
-            let mut r = 0;
-            while x != Self::ONE {
-                x = x.pow(C::FACTORS[i].0);
-                r += 1;
-            }
-            res *= intpow::<0>(C::FACTORS[i].0, r);
+    /// Returns the integer $k$ in the range `0..C::SIZE` such that this element is $g^k$, where $g$
+    /// is the product of the generators of the decomposition, which generates the whole group.
+    /// The coordinates are the residues of $k$ modulo each prime power of the factorization, so
+    /// $k$ is found by the Chinese remainder theorem.
+    pub fn to_log(&self) -> u128 {
+        let mut res = 0;
+        let mut modulus = 1;
+        for i in 0..L {
+            let q = C::FACTORS.factor(i);
+            // Find $t$ with $res + modulus \cdot t \equiv c_i \mod q$.
+            let Some(inv) = mod_inverse(modulus % q, q) else {
+                unreachable!("The prime powers of a factorization are coprime.");
+            };
+            let diff = (self.coords[i] % q + q - res % q) % q;
+            res += modulus * mul_mod(diff, inv, q);
+            modulus *= q;
         }
         res
     }
+
+    /// Returns the element $g^k$, where $g$ is the product of the generators of the
+    /// decomposition; this is the inverse of [`SylowElem::to_log`].
+    pub fn from_log(k: u128) -> SylowElem<S, L, C> {
+        SylowElem::new(std::array::from_fn(|i| k % C::FACTORS.factor(i)))
+    }
+
+    /// Returns the order of this element.
+    pub fn order(&self) -> u128 {
+        C::order_from_powers(&self.order_powers())
+    }
+
+    /// Returns the order of this element, as the powers on the prime factors of the size of the
+    /// group.
+    /// The power for each prime is read from the valuation of the corresponding coordinate,
+    /// reduced modulo the prime power, without any group operations.
+    pub fn order_powers(&self) -> [usize; L] {
+        std::array::from_fn(|i| {
+            let (p, d) = C::FACTORS[i];
+            let mut c = self.coords[i] % C::FACTORS.factor(i);
+            if c == 0 {
+                return 0;
+            }
+            let mut e = d;
+            while c.is_multiple_of(p) {
+                c /= p;
+                e -= 1;
+            }
+            e
+        })
+    }
 }
 
 impl<S, const L: usize, C: Eq> GroupElem for SylowElem<S, L, C>
@@ -204,6 +392,15 @@ impl<S, const L: usize, C: SylowDecomposable<S>> Clone for SylowElem<S, L, C> {
 }
 impl<S, const L: usize, C: SylowDecomposable<S>> Copy for SylowElem<S, L, C> {}
 
+/// Overwrites the coordinates of this element, which are discrete logarithms, with zero.
+/// As for `FpNum`, this wipes only this copy.
+#[cfg(feature = "zeroize")]
+impl<S, const L: usize, C: SylowDecomposable<S>> zeroize::Zeroize for SylowElem<S, L, C> {
+    fn zeroize(&mut self) {
+        self.coords.zeroize();
+    }
+}
+
 impl<S, const L: usize, C: SylowDecomposable<S>> std::fmt::Debug for SylowElem<S, L, C> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.coords.fmt(f)
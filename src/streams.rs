@@ -1,8 +1,14 @@
 //! Tools for creating streams of objects constructed in this library.
+#[cfg(feature = "async")]
+mod async_stream;
 mod coord_stream;
 mod divisor_stream;
+mod stream_plan;
 mod sylow_stream;
 
+#[cfg(feature = "async")]
+pub use async_stream::*;
 pub use coord_stream::*;
 pub use divisor_stream::*;
+pub use stream_plan::*;
 pub use sylow_stream::*;
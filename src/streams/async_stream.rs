@@ -0,0 +1,138 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread;
+
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use futures::{SinkExt, Stream, StreamExt};
+
+use crate::markoff::Coord;
+use crate::numbers::*;
+use crate::streams::*;
+
+/// A [`futures::Stream`] yielding the items of an iterator in chunks.
+/// The iterator is driven on a dedicated worker thread, so polling this stream never blocks the
+/// executor.
+/// At most one chunk is buffered ahead of the consumer; dropping the stream stops the worker
+/// after its current chunk.
+///
+/// Requires the `async` feature.
+pub struct AsyncChunks<T> {
+    rx: mpsc::Receiver<Vec<T>>,
+}
+
+impl<T: Send + 'static> AsyncChunks<T> {
+    /// Returns a stream yielding the items of `iter` in chunks of `chunk_size`.
+    /// Every chunk has exactly `chunk_size` items, except possibly the last.
+    /// Panics if `chunk_size` is 0.
+    pub fn new<I>(iter: I, chunk_size: usize) -> AsyncChunks<T>
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: Send + 'static,
+    {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        let (mut tx, rx) = mpsc::channel(0);
+        let mut iter = iter.into_iter();
+        thread::spawn(move || loop {
+            let chunk = iter.by_ref().take(chunk_size).collect::<Vec<_>>();
+            if chunk.is_empty() || block_on(tx.send(chunk)).is_err() {
+                break;
+            }
+        });
+        AsyncChunks { rx }
+    }
+}
+
+impl<T> Stream for AsyncChunks<T> {
+    type Item = Vec<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Vec<T>>> {
+        self.rx.poll_next_unpin(cx)
+    }
+}
+
+impl<S, const L: usize, C, T> SylowStream<S, L, C, T>
+where
+    S: Send + Sync + 'static,
+    C: SylowDecomposable<S> + Send + Sync + 'static,
+    T: Clone + Send + Sync + 'static,
+{
+    /// Returns a [`futures::Stream`] yielding the elements of this stream in chunks of
+    /// `chunk_size`, generated on a worker thread.
+    /// See [`AsyncChunks`].
+    ///
+    /// Requires the `async` feature.
+    pub fn into_async_chunks(self, chunk_size: usize) -> AsyncChunks<(SylowElem<S, L, C>, T)> {
+        AsyncChunks::new(self, chunk_size)
+    }
+}
+
+impl<S, const L_HYPER: usize, const L_ELLIP: usize, const P: u128>
+    CoordStream<'static, S, L_HYPER, L_ELLIP, P>
+where
+    S: Send + Sync + 'static,
+    FpNum<P>: SylowDecomposable<S>,
+    QuadNum<P>: SylowDecomposable<S>,
+{
+    /// Returns a [`futures::Stream`] yielding the coordinates of this stream in chunks of
+    /// `chunk_size`, generated on a worker thread.
+    /// The Sylow decompositions must outlive the worker, so they must be `'static`; a
+    /// decomposition built at runtime can be leaked with [`Box::leak`] or stored in a
+    /// [`std::sync::OnceLock`].
+    /// See [`AsyncChunks`].
+    ///
+    /// Requires the `async` feature.
+    pub fn into_async_chunks(self, chunk_size: usize) -> AsyncChunks<Coord<P>> {
+        AsyncChunks::new(self, chunk_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Eq, Clone, Copy, Debug)]
+    struct Phantom {}
+
+    impl_factors!(Phantom, 61);
+
+    #[test]
+    fn sylow_chunks_match_iterator() {
+        let builder = SylowStreamBuilder::<Phantom, 3, FpNum<61>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_target(&[2, 1, 0]);
+        let expected = builder
+            .clone()
+            .into_iter()
+            .map(|(x, _)| x)
+            .collect::<Vec<_>>();
+        let chunks = block_on(builder.into_iter().into_async_chunks(3).collect::<Vec<_>>());
+        assert!(chunks.iter().rev().skip(1).all(|c| c.len() == 3));
+        let actual = chunks
+            .into_iter()
+            .flatten()
+            .map(|(x, _)| x)
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn coord_chunks_match_iterator() {
+        let hyper: &'static _ = Box::leak(Box::new(SylowDecomp::<Phantom, 3, FpNum<61>>::new()));
+        let ellip: &'static _ = Box::leak(Box::new(SylowDecomp::<Phantom, 2, QuadNum<61>>::new()));
+        let expected = CoordStream::new(hyper, ellip, 60, 62).collect::<Vec<_>>();
+        let actual = block_on(
+            CoordStream::new(hyper, ellip, 60, 62)
+                .into_async_chunks(4)
+                .concat(),
+        );
+        assert!(actual == expected);
+    }
+
+    #[test]
+    fn dropping_stops_worker() {
+        let mut chunks = AsyncChunks::new(0.., 10);
+        assert_eq!(block_on(chunks.next()), Some((0..10).collect()));
+        drop(chunks);
+    }
+}
@@ -16,6 +16,7 @@ where
     ellip_stream: Option<SylowStream<S, L_ELLIP, QuadNum<P>, ()>>,
     hyper_decomp: &'a SylowDecomp<S, L_HYPER, FpNum<P>>,
     ellip_decomp: &'a SylowDecomp<S, L_ELLIP, QuadNum<P>>,
+    dedup: bool,
 }
 
 impl<'a, S, const L_HYPER: usize, const L_ELLIP: usize, const P: u128>
@@ -31,31 +32,61 @@ where
         hyper_lim: u128,
         ellip_lim: u128,
     ) -> CoordStream<'a, S, L_HYPER, L_ELLIP, P> {
-        let hyper_stream = DivisorStream::new(FpNum::FACTORS.factors(), hyper_lim, true)
-            .map(|v| v.try_into().unwrap())
-            .fold(
-                SylowStreamBuilder::<S, L_HYPER, FpNum<P>, ()>::new()
-                    .add_flag(flags::NO_PARABOLIC)
-                    .add_flag(flags::NO_UPPER_HALF)
-                    .add_flag(flags::LEQ),
-                |b, x| b.add_target(&x),
-            )
+        Self::with_plans(
+            hyper_decomp,
+            ellip_decomp,
+            &StreamPlan::new(&FpNum::FACTORS, hyper_lim),
+            &StreamPlan::new(&QuadNum::FACTORS, ellip_lim),
+        )
+    }
+
+    /// Creates a new `CoordStream` with orders up to the limits of the given plans, which may be
+    /// shared between the streams of many primes.
+    /// See [`StreamPlans`].
+    pub fn with_plans(
+        hyper_decomp: &'a SylowDecomp<S, L_HYPER, FpNum<P>>,
+        ellip_decomp: &'a SylowDecomp<S, L_ELLIP, QuadNum<P>>,
+        hyper_plan: &StreamPlan<L_HYPER>,
+        ellip_plan: &StreamPlan<L_ELLIP>,
+    ) -> CoordStream<'a, S, L_HYPER, L_ELLIP, P> {
+        let hyper_stream = SylowStreamBuilder::<S, L_HYPER, FpNum<P>, ()>::new()
+            .for_unique_chi_with_plan(hyper_plan)
             .into_iter();
-        let ellip_stream = DivisorStream::new(QuadNum::FACTORS.factors(), ellip_lim, true)
-            .map(|v| v.try_into().unwrap())
-            .fold(
-                SylowStreamBuilder::<S, L_ELLIP, QuadNum<P>, ()>::new()
-                    .add_flag(flags::NO_PARABOLIC)
-                    .add_flag(flags::NO_UPPER_HALF)
-                    .add_flag(flags::LEQ),
-                |b, x| b.add_target(&x),
-            )
+        let ellip_stream = SylowStreamBuilder::<S, L_ELLIP, QuadNum<P>, ()>::new()
+            .for_unique_chi_with_plan(ellip_plan)
             .into_iter();
         CoordStream {
             hyper_stream: Some(hyper_stream),
             ellip_stream: Some(ellip_stream),
             hyper_decomp,
             ellip_decomp,
+            dedup: false,
+        }
+    }
+
+    /// Returns this stream, filtered to guarantee that each coordinate is yielded at most once.
+    /// Each coordinate $a = \chi + \chi^{-1}$ is kept only for the one of $\chi$ and
+    /// $\chi^{-1}$ with the lesser [`SylowElem::coords`], and only if $\chi$ has order larger
+    /// than 2.
+    ///
+    /// The filter looks at each $\chi$ alone, so it only works for streams which, from each pair
+    /// $\{\chi, \chi^{-1}\}$ they touch, yield the member with the lesser coordinates; a stream
+    /// yielding only the greater member of a pair would lose that coordinate entirely.
+    /// The streams from [`CoordStream::new`] and [`CoordStream::with_plans`] meet this
+    /// precondition, since they skip the upper half of each Sylow subgroup.
+    /// It needs no memory, so it is also safe to use in parallel.
+    pub fn dedup(mut self) -> Self {
+        self.dedup = true;
+        self
+    }
+
+    /// Returns an iterator yielding each coordinate together with its Legendre symbol, as given by
+    /// [`FpNum::legendre_batch`].
+    /// The symbols are computed in batches of 256 coordinates.
+    pub fn legendre(self) -> LegendreStream<Self, P> {
+        LegendreStream {
+            stream: self,
+            buffer: Vec::new(),
         }
     }
 
@@ -70,9 +101,7 @@ where
             type Item = (I, I::Item);
             fn next(&mut self) -> Option<Self::Item> {
                 let iter = self.0.clone();
-                let Some(a) = self.0.next() else {
-                    return None;
-                };
+                let a = self.0.next()?;
                 Some((iter, a))
             }
         }
@@ -82,6 +111,48 @@ where
     }
 }
 
+/// An iterator yielding coordinates together with their Legendre symbols.
+/// See [`CoordStream::legendre`].
+#[derive(Clone)]
+pub struct LegendreStream<I, const P: u128> {
+    stream: I,
+    // The rest of the current batch, in reverse order.
+    buffer: Vec<(Coord<P>, i8)>,
+}
+
+const LEGENDRE_BATCH_SIZE: usize = 256;
+
+impl<I: Iterator<Item = Coord<P>>, const P: u128> Iterator for LegendreStream<I, P> {
+    type Item = (Coord<P>, i8);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.is_empty() {
+            let batch = self
+                .stream
+                .by_ref()
+                .take(LEGENDRE_BATCH_SIZE)
+                .collect::<Vec<_>>();
+            let symbols = FpNum::legendre_batch(&batch.iter().map(|c| c.0).collect::<Vec<_>>());
+            self.buffer = batch.into_iter().zip(symbols).rev().collect();
+        }
+        self.buffer.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lo, hi) = self.stream.size_hint();
+        let n = self.buffer.len();
+        (lo.saturating_add(n), hi.and_then(|hi| hi.checked_add(n)))
+    }
+}
+
+// True if `chi` should be kept by a stream with the given deduplication mode.
+fn is_canonical<S, const L: usize, C: SylowDecomposable<S>>(
+    dedup: bool,
+    chi: &SylowElem<S, L, C>,
+) -> bool {
+    !dedup || (chi.order() > 2 && chi.coords <= chi.inverse().coords)
+}
+
 impl<'a, S, const L_HYPER: usize, const L_ELLIP: usize, const P: u128> Iterator
     for CoordStream<'a, S, L_HYPER, L_ELLIP, P>
 where
@@ -91,14 +162,15 @@ where
     type Item = Coord<P>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        let dedup = self.dedup;
         if let Some(stream) = self.hyper_stream.as_mut() {
-            if let Some((a, _)) = stream.next() {
+            if let Some((a, _)) = stream.find(|(a, _)| is_canonical(dedup, a)) {
                 return Some(Coord(FpNum::from_chi(&a, self.hyper_decomp)));
             }
             self.hyper_stream = None;
         }
         if let Some(stream) = self.ellip_stream.as_mut() {
-            if let Some((a, _)) = stream.next() {
+            if let Some((a, _)) = stream.find(|(a, _)| is_canonical(dedup, a)) {
                 return Some(Coord(QuadNum::from_chi(&a, self.ellip_decomp)));
             }
             self.ellip_stream = None;
@@ -120,15 +192,18 @@ where
     where
         C: UnindexedConsumer<Self::Item>,
     {
+        let dedup = self.dedup;
         let left = self.hyper_stream.map(|stream| {
             stream
                 .parallelize()
+                .filter(|(x, _)| is_canonical(dedup, x))
                 .map(|(x, _)| Coord(FpNum::from_chi(&x, self.hyper_decomp)))
                 .drive_unindexed(consumer.split_off_left())
         });
         let right = self.ellip_stream.map(|stream| {
             stream
                 .parallelize()
+                .filter(|(x, _)| is_canonical(dedup, x))
                 .map(|(x, _)| Coord(QuadNum::from_chi(&x, self.ellip_decomp)))
                 .drive_unindexed(consumer.split_off_left())
         });
@@ -140,3 +215,56 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, PartialEq, Eq)]
+    struct Ph {}
+
+    impl_factors!(Ph, 3001);
+
+    #[test]
+    fn legendre_matches_coords() {
+        let hyper = SylowDecomp::<Ph, 3, FpNum<3001>>::new();
+        let ellip = SylowDecomp::<Ph, 3, QuadNum<3001>>::new();
+        let coords = Iterator::collect::<Vec<_>>(CoordStream::new(&hyper, &ellip, 3000, 3002));
+        let symbols = CoordStream::new(&hyper, &ellip, 3000, 3002)
+            .legendre()
+            .collect::<Vec<_>>();
+        assert!(coords.len() > 256);
+        assert_eq!(symbols.len(), coords.len());
+        for (c, (d, sym)) in coords.iter().zip(symbols) {
+            assert!(*c == d);
+            assert_eq!(sym, FpNum::legendre_batch(&[c.0])[0]);
+        }
+    }
+
+    #[test]
+    fn dedup_matches_brute_force() {
+        let hyper = SylowDecomp::<Ph, 3, FpNum<3001>>::new();
+        let ellip = SylowDecomp::<Ph, 3, QuadNum<3001>>::new();
+        for (hyper_lim, ellip_lim) in [(3000, 3002), (100, 200), (3, 3)] {
+            let mut expected = (0..3001)
+                .filter(|a| match Coord::<3001>::from(*a).rot_order::<Ph, Ph>() {
+                    RotOrder::Hyperbola(d) => d <= hyper_lim,
+                    RotOrder::Ellipse(d) => d <= ellip_lim,
+                    RotOrder::Parabola(_) => false,
+                })
+                .collect::<Vec<_>>();
+            expected.sort();
+            for stream in [
+                CoordStream::new(&hyper, &ellip, hyper_lim, ellip_lim),
+                CoordStream::new(&hyper, &ellip, hyper_lim, ellip_lim).dedup(),
+            ] {
+                let mut seq = Iterator::map(stream.clone(), u128::from).collect::<Vec<_>>();
+                seq.sort();
+                assert_eq!(seq, expected);
+                let mut par = ParallelIterator::map(stream, u128::from).collect::<Vec<_>>();
+                par.sort();
+                assert_eq!(par, expected);
+            }
+        }
+    }
+}
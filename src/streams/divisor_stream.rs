@@ -15,9 +15,9 @@ impl<'a> DivisorStream<'a> {
     /// In particular, it will return all values $d$ satisfying these properties:
     /// * $d | n$
     /// * $d < limit$
-    /// * (if and only if `maximal_only` is True) There does not exist a $k$, $d | k | n$, with $k <
-    /// limit$
-    pub fn new(source: &'a [(u128, usize)], limit: u128, maximal_only: bool) -> DivisorStream {
+    /// * (if and only if `maximal_only` is True) There does not exist a $k$, $d | k | n$, with
+    ///   $k < limit$
+    pub fn new(source: &'a [(u128, usize)], limit: u128, maximal_only: bool) -> DivisorStream<'a> {
         DivisorStream {
             source,
             limit,
@@ -31,9 +31,7 @@ impl<'a> Iterator for DivisorStream<'a> {
     type Item = Vec<usize>;
 
     fn next(&mut self) -> Option<Vec<usize>> {
-        let Some((i, state)) = self.stack.pop() else {
-            return None;
-        };
+        let (i, state) = self.stack.pop()?;
         // println!("{state:?}");
         let prod: u128 = state
             .iter()
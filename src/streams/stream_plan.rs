@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::numbers::*;
+use crate::streams::*;
+
+/// The targets of a stream yielding every element of order at most a limit, planned once from a
+/// factorization so that they can be reused by every stream over a group of that order.
+/// A plan depends only on the factorization and the limit, and not on the group, so the plan for
+/// $p - 1$ of one prime $p$ also serves $q + 1$ for the prime $q = p - 2$, and one plan serves
+/// every phantom type `S`.
+///
+/// See [`SylowStreamBuilder::with_plan`] and [`StreamPlans`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamPlan<const L: usize> {
+    factors: &'static [(u128, usize)],
+    limit: u128,
+    targets: Vec<[usize; L]>,
+}
+
+/// A cache of [`StreamPlan`]s, keyed by factorization and limit.
+/// Campaigns over many primes can share one cache, so that each plan is only computed once.
+#[derive(Clone, Debug, Default)]
+pub struct StreamPlans<const L: usize> {
+    plans: HashMap<PlanKey, Arc<StreamPlan<L>>>,
+}
+
+type PlanKey = (&'static [(u128, usize)], u128);
+
+impl<const L: usize> StreamPlan<L> {
+    /// Plans the targets of a stream yielding every element of order at most `limit`, in a group
+    /// whose order has the factorization `factors`.
+    /// This method will panic if `factors` does not have exactly `L` prime factors.
+    pub fn new(factors: &Factorization, limit: u128) -> StreamPlan<L> {
+        assert_eq!(
+            factors.len(),
+            L,
+            "Factorization does not have L prime factors."
+        );
+        StreamPlan {
+            factors: factors.factors(),
+            limit,
+            targets: DivisorStream::new(factors.factors(), limit, true)
+                .map(|v| v.try_into().unwrap())
+                .collect(),
+        }
+    }
+
+    /// Returns the exponents of the maximal orders at most the limit.
+    pub fn targets(&self) -> &[[usize; L]] {
+        &self.targets
+    }
+
+    /// Returns the limit on the orders of the elements yielded.
+    pub fn limit(&self) -> u128 {
+        self.limit
+    }
+
+    /// True if this plan was made for the factorization `factors`.
+    pub fn is_for(&self, factors: &Factorization) -> bool {
+        self.factors == factors.factors()
+    }
+}
+
+impl<const L: usize> StreamPlans<L> {
+    /// Returns a new, empty cache.
+    pub fn new() -> StreamPlans<L> {
+        StreamPlans::default()
+    }
+
+    /// Returns the plan for `factors` and `limit`, computing it only if it is not yet cached.
+    pub fn get(&mut self, factors: &Factorization, limit: u128) -> Arc<StreamPlan<L>> {
+        self.plans
+            .entry((factors.factors(), limit))
+            .or_insert_with(|| Arc::new(StreamPlan::new(factors, limit)))
+            .clone()
+    }
+
+    /// Returns the number of plans in the cache.
+    pub fn len(&self) -> usize {
+        self.plans.len()
+    }
+
+    /// True if the cache holds no plans.
+    pub fn is_empty(&self) -> bool {
+        self.plans.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Eq)]
+    struct Ph {}
+
+    impl_factors!(Ph, 59, 61);
+
+    #[test]
+    fn plan_is_shared() {
+        // 61 - 1 = 59 + 1 = 60
+        let mut plans = StreamPlans::<3>::new();
+        let plan = plans.get(&<FpNum<61> as Factor<Ph>>::FACTORS, 20);
+        assert!(Arc::ptr_eq(
+            &plan,
+            &plans.get(&<QuadNum<59> as Factor<Ph>>::FACTORS, 20)
+        ));
+        assert_eq!(plans.len(), 1);
+        assert!(plan.is_for(&<QuadNum<59> as Factor<Ph>>::FACTORS));
+        assert!(!plan.is_for(&<QuadNum<61> as Factor<Ph>>::FACTORS));
+
+        let hyper = SylowStreamBuilder::<Ph, 3, FpNum<61>, ()>::new()
+            .with_plan(&plan)
+            .into_iter()
+            .map(|(x, _)| x.order())
+            .collect::<Vec<_>>();
+        let ellip = SylowStreamBuilder::<Ph, 3, QuadNum<59>, ()>::new()
+            .with_plan(&plan)
+            .into_iter()
+            .map(|(x, _)| x.order())
+            .collect::<Vec<_>>();
+        assert!(hyper.iter().all(|d| *d <= 20));
+        assert_eq!(hyper.len(), ellip.len());
+        // One element for each unit modulo each divisor of 60 which is at most 20.
+        let expected = (1..=60u128)
+            .filter(|d| 60 % d == 0 && *d <= 20)
+            .map(|d| (1..=d).filter(|x| libbgs_util::gcd(*x, d) == 1).count())
+            .sum::<usize>();
+        assert_eq!(hyper.len(), expected);
+    }
+}
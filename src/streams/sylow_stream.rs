@@ -3,14 +3,21 @@ use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
 use std::cell::RefCell;
 use std::marker::PhantomData;
+use std::mem::MaybeUninit;
 use std::sync::Arc;
 
 use crate::numbers::*;
-use crate::streams::DivisorStream;
+use crate::streams::{DivisorStream, StreamPlan};
 use libbgs_util::*;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 const STACK_ADDITION_LIMIT: u8 = 127;
 
+// Streams with fewer elements than this are not split further by `par_collect_vec`.
+const SEQUENTIAL_COLLECT_LIMIT: usize = 4096;
+
 /// Bitwise flags for configuring a SylowStreamBuilder.
 /// May be combined with the bitwise integer OR operator, `|`.
 pub mod flags {
@@ -41,6 +48,9 @@ pub struct SylowStreamBuilder<S, const L: usize, C: SylowDecomposable<S>, T> {
     mode: u8,
     tree: Box<FactorTrie<S, L, C, (Consume, T)>>,
     quotient: Option<[usize; L]>,
+    block_size: usize,
+    filter: Option<Filter<L>>,
+    budget: Option<usize>,
     _phantom: PhantomData<(S, C)>,
 }
 
@@ -50,14 +60,52 @@ pub struct SylowParStream<S: Send + Sync, const L: usize, C: SylowDecomposable<S
 {
     stream: SylowStream<S, L, C, T>,
     splits: usize,
+    block_size: usize,
+    on_split: Option<SplitCallback>,
+}
+
+/// A report on one stretch of work by a [`SylowParStream`], from when a piece of the stream
+/// begins running on a thread until it either splits off work for another thread or is
+/// exhausted.
+/// See [`SylowParStream::on_split`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SplitEvent {
+    /// The index of the `rayon` thread which did the work, or `None` if it was done outside of a
+    /// `rayon` thread pool.
+    pub thread: Option<usize>,
+    /// True if the piece was stolen by another thread before this stretch began.
+    pub migrated: bool,
+    /// The number of subtrees of the trie, that is, partially generated elements, held by the
+    /// piece when this stretch began.
+    pub subtrees: usize,
+    /// The number of elements produced during this stretch.
+    pub elements: usize,
+    /// True if this stretch ended by splitting the piece in two; false if the piece was
+    /// exhausted.
+    pub split: bool,
+}
+
+/// The data of one node of a [`FactorTrie`] shared through an `Arc`, as yielded by the streams of
+/// [`SylowStreamBuilder::new_sharing_trie`].
+/// Dereferences to the data of the node.
+pub struct SharedData<S, const L: usize, C, T> {
+    trie: Arc<FactorTrie<S, L, C, T>>,
+    ds: [usize; L],
 }
 
+/// A stream yielding each element of a [`SylowStream`] or [`SylowParStream`] together with its
+/// inverse.
+/// See [`SylowStream::with_inverses`].
+#[derive(Clone)]
+pub struct WithInverses<I>(I);
+
 /// A stream yielding elements of particular orders, as their Sylow decompositions.
 /// Generates the elements sequentially on a single thread.
 pub struct SylowStream<S, const L: usize, C: SylowDecomposable<S>, T> {
     stack: Vec<Seed<S, L, C, T>>,
     buffer: Vec<Output<S, L, C, T>>,
     tree: Arc<FactorTrie<S, L, C, (GenData, T)>>,
+    filter: Option<Filter<L>>,
 }
 
 #[derive(Debug)]
@@ -72,9 +120,82 @@ struct GenData {
     consume: Consume,
     step: u128,
     lim: u128,
+    // The number of elements yielded for each coordinate generated at this node, counting this
+    // node and all descendants along larger primes.
+    weight: u128,
 }
 
 type Output<S, const L: usize, C, T> = (SylowElem<S, L, C>, T);
+type Filter<const L: usize> = Arc<dyn Fn(&[u128; L]) -> bool + Send + Sync>;
+type SplitCallback = Arc<dyn Fn(&SplitEvent) + Send + Sync>;
+/// A serializable description of part of the work of a `SylowStream`.
+/// A `ShardDescriptor` is produced by [`SylowStreamBuilder::shards`], and can be turned back into
+/// a stream with [`SylowStreamBuilder::resume_shard`], possibly in another process or on another
+/// machine.
+/// Nodes of the `FactorTrie` are identified by their exponents, which locate them uniquely.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ShardDescriptor {
+    /// The partially generated elements which remain to be extended.
+    pub seeds: Vec<SeedDescriptor>,
+    /// The coordinates of the elements which have been generated, but not yet yielded.
+    pub elements: Vec<Vec<u128>>,
+}
+
+/// A partially generated element of a [`ShardDescriptor`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SeedDescriptor {
+    /// The exponents of the `FactorTrie` node at which to continue generating.
+    pub node: Vec<usize>,
+    /// The coordinates generated so far.
+    pub coords: Vec<u128>,
+    /// The first multiple of the node's step to add to the coordinate of the node's prime.
+    pub start: u128,
+}
+
+/// A prediction of the work and memory of a `SylowStream`, made by building the stream without
+/// running it.
+/// See [`SylowStreamBuilder::estimate`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StreamEstimate {
+    /// The number of elements the stream yields, or an upper bound on it if `exact` is false.
+    pub elements: u128,
+    /// False if a filter is set, so that the number of elements is only bounded.
+    pub exact: bool,
+    /// The number of nodes of the stream's `FactorTrie`.
+    pub nodes: usize,
+    /// A bound on the number of partially generated elements held at once by a sequential stream.
+    pub peak_stack: usize,
+    /// A bound on the number of generated elements buffered at once by a sequential stream.
+    pub peak_buffer: usize,
+    /// A rough bound on the memory used by a sequential stream, in bytes, including its trie.
+    pub memory: usize,
+}
+
+/// The error returned by [`SylowStream::propagate_into`] when the stream's stack has too little
+/// spare capacity to propagate the next seed without allocating.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StackFull {
+    /// The number of free slots of the stack needed to propagate the next seed.
+    pub needed: usize,
+    /// The number of free slots of the stack.
+    pub available: usize,
+}
+
+impl std::fmt::Display for StackFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "stream stack has {} free slots, but propagating needs {}",
+            self.available, self.needed
+        )
+    }
+}
+
+impl std::error::Error for StackFull {}
+
 #[derive(Clone, Debug, Default)]
 struct Consume {
     this: bool,
@@ -88,6 +209,9 @@ impl<S, const L: usize, C: SylowDecomposable<S>> SylowStreamBuilder<S, L, C, ()>
             mode: flags::NONE,
             tree: Box::new(FactorTrie::new().map(&|_: (), _, _| (Consume::default(), ()))),
             quotient: None,
+            block_size: 0,
+            filter: None,
+            budget: None,
             _phantom: PhantomData,
         }
     }
@@ -100,6 +224,9 @@ impl<S, const L: usize, C: SylowDecomposable<S>> SylowStreamBuilder<S, L, C, [u1
             mode: flags::NONE,
             tree: Box::new(FactorTrie::<S, L, C, ()>::new().map(&|_, ds, _| (Consume::default(), *ds))),
             quotient: None,
+            block_size: 0,
+            filter: None,
+            budget: None,
             _phantom: PhantomData,
         }
     }
@@ -112,6 +239,41 @@ impl<'a, S, const L: usize, C: SylowDecomposable<S>, T> SylowStreamBuilder<S, L,
             mode: flags::NONE,
             tree: Box::new(trie.as_ref().map(&|t, _, _| (Consume::default(), t))),
             quotient: None,
+            block_size: 0,
+            filter: None,
+            budget: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<S, const L: usize, C: SylowDecomposable<S>, T>
+    SylowStreamBuilder<S, L, C, SharedData<S, L, C, T>>
+{
+    /// Creates a new `SylowStreamBuilder` with a "parallel" trie to that given here, like
+    /// [`SylowStreamBuilder::new_with_trie`], but sharing ownership of the trie, so that the
+    /// builder and the data it yields may outlive the caller's handle.
+    /// Each element is yielded with a [`SharedData`] dereferencing to the data of its node.
+    ///
+    /// Cloning a `SharedData` touches the reference count of the trie, so when the data is not
+    /// needed, borrowing the trie with `new_with_trie` is cheaper.
+    pub fn new_sharing_trie(
+        trie: Arc<FactorTrie<S, L, C, T>>,
+    ) -> SylowStreamBuilder<S, L, C, SharedData<S, L, C, T>> {
+        let tree = FactorTrie::as_ref(&trie).map(&|_, ds, _| {
+            let data = SharedData {
+                trie: Arc::clone(&trie),
+                ds: *ds,
+            };
+            (Consume::default(), data)
+        });
+        SylowStreamBuilder {
+            mode: flags::NONE,
+            tree: Box::new(tree),
+            quotient: None,
+            block_size: 0,
+            filter: None,
+            budget: None,
             _phantom: PhantomData,
         }
     }
@@ -124,6 +286,35 @@ impl<S, const L: usize, C: SylowDecomposable<S>, T> SylowStreamBuilder<S, L, C,
         self
     }
 
+    /// Removes a flag from the `SylowStreamBuilder`.
+    /// Note that the `LEQ` flag only affects targets added while it is set, so removing it does
+    /// not change the targets which were already added.
+    pub fn remove_flag(mut self, mode: u8) -> SylowStreamBuilder<S, L, C, T> {
+        self.mode &= !mode;
+        self
+    }
+
+    /// Removes every target from this `SylowStreamBuilder`, keeping its flags and quotient.
+    /// The underlying trie is reused, rather than rebuilt.
+    pub fn clear_targets(mut self) -> SylowStreamBuilder<S, L, C, T> {
+        self.mode &= !flags::INCLUDE_ONE;
+        self.tree
+            .for_each_mut(&mut |(consume, _), _| *consume = Consume::default());
+        self
+    }
+
+    /// Replaces the flags and targets of this `SylowStreamBuilder`, reusing the underlying trie.
+    /// This is equivalent to building a new `SylowStreamBuilder` with the flags in `mode` and then
+    /// adding each target in `targets`, but does not reallocate the trie.
+    pub fn reconfigure<'b, I>(mut self, mode: u8, targets: I) -> SylowStreamBuilder<S, L, C, T>
+    where
+        I: IntoIterator<Item = &'b [usize; L]>,
+    {
+        self = self.clear_targets();
+        self.mode = mode;
+        targets.into_iter().fold(self, |b, t| b.add_target(t))
+    }
+
     /// Adds a target order to this `SylowStreamBuilder`.
     /// The `SylowStream` built from this builder will only yield elements of the orders of
     /// `target`s, or elements of order dividing `target` if `target
@@ -171,6 +362,50 @@ impl<S, const L: usize, C: SylowDecomposable<S>, T> SylowStreamBuilder<S, L, C,
             .add_flag(flags::LEQ)
     }
 
+    /// Sets the `LEQ` flag and adds every target of `plan`, so that the stream yields every
+    /// element of order at most the plan's limit.
+    /// This method will panic if `plan` was not made for `C::FACTORS`.
+    pub fn with_plan(self, plan: &StreamPlan<L>) -> Self {
+        assert!(
+            plan.is_for(&C::FACTORS),
+            "Stream plan was made for a different factorization."
+        );
+        plan.targets()
+            .iter()
+            .fold(self.add_flag(flags::LEQ), |b, t| b.add_target(t))
+    }
+
+    /// Configures this `SylowStreamBuilder` to yield exactly one of $\chi$ and $\chi^{-1}$ for
+    /// every $\chi$ of order at most `limit`, other than those of order 1 or 2.
+    /// Since $\chi$ and $\chi^{-1}$ give the same coordinate $\chi + \chi^{-1}$, this yields each
+    /// non-parabolic coordinate of small order once.
+    ///
+    /// This sets the flags `LEQ | NO_UPPER_HALF | NO_PARABOLIC` before adding the targets, as the
+    /// `LEQ` flag only affects targets added while it is set.
+    pub fn for_unique_chi(self, limit: u128) -> Self {
+        self.add_flag(flags::LEQ | flags::NO_UPPER_HALF | flags::NO_PARABOLIC)
+            .add_targets_leq(limit)
+    }
+
+    /// Configures this `SylowStreamBuilder` as [`SylowStreamBuilder::for_unique_chi`] does, with
+    /// the targets of `plan`.
+    /// This method will panic if `plan` was not made for `C::FACTORS`.
+    pub fn for_unique_chi_with_plan(self, plan: &StreamPlan<L>) -> Self {
+        self.add_flag(flags::NO_UPPER_HALF | flags::NO_PARABOLIC)
+            .with_plan(plan)
+    }
+
+    /// Configures this `SylowStreamBuilder` to sweep the cosets of the subgroup whose order has
+    /// the exponents `quotient`, as the coset phase of the search does for each $\chi$ with
+    /// those exponents: the stream yields one representative of each coset, up to inverses.
+    ///
+    /// This sets the `NO_UPPER_HALF` flag, targets the whole group, and sets the quotient.
+    pub fn for_coset_sweep(self, quotient: [usize; L]) -> Self {
+        self.add_flag(flags::NO_UPPER_HALF)
+            .add_targets_leq(C::SIZE)
+            .set_quotient(Some(quotient))
+    }
+
     /// Remove the target, so elements of that order will not be generated.
     pub fn remove_target(mut self, t: &[usize; L]) -> Self {
         if t.iter().all(|x| *x == 0) {
@@ -197,6 +432,91 @@ impl<S, const L: usize, C: SylowDecomposable<S>, T> SylowStreamBuilder<S, L, C,
         self
     }
 
+    /// Adds every target of `other` to this `SylowStreamBuilder`, so that the resulting stream
+    /// yields elements of the orders yielded by either builder.
+    /// The flags of `self` are kept.
+    pub fn union_targets<U>(self, other: &SylowStreamBuilder<S, L, C, U>) -> Self {
+        self.combine_targets(other, |a, b| a || b)
+    }
+
+    /// Removes every target of this `SylowStreamBuilder` which is not a target of `other`, so that
+    /// the resulting stream yields elements of the orders yielded by both builders.
+    /// The flags of `self` are kept.
+    pub fn intersect_targets<U>(self, other: &SylowStreamBuilder<S, L, C, U>) -> Self {
+        self.combine_targets(other, |a, b| a && b)
+    }
+
+    /// Restricts the stream to elements lying in the subgroup of order
+    /// $\prod_i p_i^{e_i}$, where `exps` gives the exponents $e_i$.
+    /// Only elements both in that subgroup and of one of the target orders are yielded.
+    /// Since the group is cyclic, this subgroup is the unique subgroup of its order, and consists
+    /// of exactly the elements whose orders divide its order.
+    /// The flags of `self` are kept.
+    pub fn restrict_to_subgroup(self, exps: &[usize; L]) -> Self {
+        let subgroup = SylowStreamBuilder::<S, L, C, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_target(exps);
+        self.intersect_targets(&subgroup)
+    }
+
+    /// Restricts the stream to elements whose orders are divisible only by the primes at the given
+    /// indices into `C::FACTORS`; that is, the coordinates of every other prime are zero.
+    /// For example, if the first prime is 2, then restricting to the indices `1..L` leaves only
+    /// the elements of odd order.
+    /// See [`SylowStreamBuilder::restrict_to_subgroup`].
+    pub fn restrict_primes(self, indices: &[usize]) -> Self {
+        let exps = std::array::from_fn(|i| {
+            if indices.contains(&i) {
+                C::FACTORS[i].1
+            } else {
+                0
+            }
+        });
+        self.restrict_to_subgroup(&exps)
+    }
+
+    /// Restricts the stream to elements lying in the subgroup generated by `g`.
+    /// See [`SylowStreamBuilder::restrict_to_subgroup`].
+    pub fn restrict_to_subgroup_of(self, g: &SylowElem<S, L, C>) -> Self {
+        self.restrict_to_subgroup(&g.order_powers())
+    }
+
+    fn combine_targets<U, F>(mut self, other: &SylowStreamBuilder<S, L, C, U>, f: F) -> Self
+    where
+        F: Fn(bool, bool) -> bool,
+    {
+        fn help<const L: usize, S, C, T, U, F>(
+            f: &F,
+            node: &mut FactorTrie<S, L, C, (Consume, T)>,
+            other: &FactorTrie<S, L, C, (Consume, U)>,
+        ) -> usize
+        where
+            F: Fn(bool, bool) -> bool,
+        {
+            node.data.0.this = f(node.data.0.this, other.data.0.this);
+            node.data.0.descendants = 0;
+            for j in node.index()..L {
+                let (Some(child), Some(other)) = (node.child_mut(j), other.child(j)) else {
+                    continue;
+                };
+                let descendants = help(f, child, other);
+                node.data.0.descendants += descendants;
+            }
+            node.data.0.descendants + if node.data.0.this { 1 } else { 0 }
+        }
+        help(&f, &mut self.tree, &other.tree);
+
+        let one = f(
+            self.mode & flags::INCLUDE_ONE != 0,
+            other.mode & flags::INCLUDE_ONE != 0,
+        );
+        self.mode &= !flags::INCLUDE_ONE;
+        if one {
+            self.mode |= flags::INCLUDE_ONE;
+        }
+        self
+    }
+
     /// Guarantees that this stream will only ever yield one representative of the cosets of the
     /// quotient.
     pub fn set_quotient(mut self, q: Option<[usize; L]>) -> Self {
@@ -204,6 +524,59 @@ impl<S, const L: usize, C: SylowDecomposable<S>, T> SylowStreamBuilder<S, L, C,
         self
     }
 
+    /// Sets the number of elements the parallel stream hands to each consumer at once.
+    /// When `n > 0`, elements are generated into blocks of `n` elements, and the parallel stream
+    /// only considers splitting its work between blocks, which reduces per-element overhead.
+    /// When `n == 0` (the default), elements are consumed one at a time.
+    /// This setting has no effect on the sequential stream.
+    pub fn set_block_size(mut self, n: usize) -> Self {
+        self.block_size = n;
+        self
+    }
+
+    /// Restricts the stream to elements whose coordinates satisfy the predicate `f`.
+    /// The predicate is evaluated on partially generated elements while the stream runs, and
+    /// every element generated from a rejected partial element is pruned without being
+    /// generated.
+    /// Therefore, `f` must be monotone: if `f` rejects some coordinates, it must also reject every
+    /// coordinates obtained from them by changing later coordinates, or by adding to the same
+    /// coordinate a multiple of a higher power of its prime.
+    /// For example, "the coordinate for the prime 2 is zero" is monotone.
+    /// Calling this method more than once keeps only elements satisfying every predicate.
+    ///
+    /// When a filter is set, the stream can no longer count its elements ahead of time, so
+    /// `size_hint` only gives bounds and `nth` generates every element it skips.
+    pub fn filter_coords<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&[u128; L]) -> bool + Send + Sync + 'static,
+    {
+        self.filter = Some(match self.filter.take() {
+            Some(g) => Arc::new(move |x: &[u128; L]| g(x) && f(x)),
+            None => Arc::new(f),
+        });
+        self
+    }
+
+    /// Limits the stream to yield at most `n` elements of each target order, giving a sample
+    /// stratified across the orders.
+    /// Rather than searching for the elements, the stream decodes them directly from their ranks
+    /// among the elements of their order, visiting the ranks with a stride of about $1/\varphi$
+    /// times their number, where $\varphi$ is the golden ratio, so that the sample is spread
+    /// evenly over each order, and costs time proportional to its size rather than to the size
+    /// of the orders.
+    /// Elements which the stream would not yield, such as those rejected by
+    /// [`SylowStreamBuilder::filter_coords`] or in the upper half of a Sylow subgroup, are
+    /// skipped; a filter which rejects most elements can make drawing the sample as slow as
+    /// enumerating the orders.
+    ///
+    /// The sample is drawn when the stream is built, and held in memory until it is yielded.
+    /// It is divided between the threads of a parallel stream, or between split streams, so that
+    /// each piece yields its own part of it; clones of the stream each yield the whole sample.
+    pub fn with_budget(mut self, n: usize) -> Self {
+        self.budget = Some(n);
+        self
+    }
+
     /// Add all the targets yielded by this `DivisorStream`.
     pub fn add_targets_from_factors(self, stream: DivisorStream) -> Self {
         stream
@@ -212,7 +585,216 @@ impl<S, const L: usize, C: SylowDecomposable<S>, T> SylowStreamBuilder<S, L, C,
     }
 }
 
+// Writes every element of `stream` into `out`, splitting the work between threads.
+// Panics unless the stream yields exactly as many elements as `out` has room for.
+fn par_fill<S, const L: usize, C, T>(
+    stream: SylowStream<S, L, C, T>,
+    out: &mut [MaybeUninit<Output<S, L, C, T>>],
+) where
+    S: Send + Sync,
+    C: SylowDecomposable<S> + Send + Sync,
+    T: Clone + Send + Sync,
+{
+    let (mut stream, other) = if out.len() < SEQUENTIAL_COLLECT_LIMIT {
+        (stream, None)
+    } else {
+        stream.split()
+    };
+    match other {
+        Some(other) => {
+            let (left, right) = out.split_at_mut(stream.size_hint().0);
+            rayon::join(|| par_fill(stream, left), || par_fill(other, right));
+        }
+        None => {
+            let mut count = 0;
+            for (slot, x) in out.iter_mut().zip(&mut stream) {
+                slot.write(x);
+                count += 1;
+            }
+            assert_eq!(
+                count,
+                out.len(),
+                "Stream yielded fewer elements than counted."
+            );
+            assert!(
+                stream.next().is_none(),
+                "Stream yielded more elements than counted."
+            );
+        }
+    }
+}
+
+impl<S, const L: usize, C, T> SylowStreamBuilder<S, L, C, T>
+where
+    C: SylowDecomposable<S>,
+    T: Clone,
+{
+    /// Collects every element of the stream into a `Vec`.
+    /// The number of elements is computed before generating any of them, so the `Vec` is
+    /// allocated exactly once.
+    pub fn collect_vec(self) -> Vec<Output<S, L, C, T>> {
+        let stream = self.into_iter();
+        let mut res = Vec::with_capacity(stream.size_hint().0);
+        res.extend(stream);
+        res
+    }
+
+    /// Collects every element of the stream into a `Vec`, generating the elements in parallel.
+    /// The number of elements is computed before generating any of them, so the `Vec` is
+    /// allocated exactly once, and each thread writes directly into its own part of the `Vec`.
+    /// If a filter is set with [`SylowStreamBuilder::filter_coords`], the number of elements is
+    /// not known ahead of time, and the elements are collected normally instead.
+    /// The order of the elements is unspecified.
+    pub fn par_collect_vec(self) -> Vec<Output<S, L, C, T>>
+    where
+        S: Send + Sync,
+        C: Send + Sync,
+        T: Send + Sync,
+    {
+        if self.filter.is_some() {
+            return self.into_par_iter().collect();
+        }
+        let stream = self.into_iter();
+        let len = stream.size_hint().0;
+        let mut res = Vec::with_capacity(len);
+        par_fill(stream, &mut res.spare_capacity_mut()[..len]);
+        // Safety: `par_fill` has initialized all of the first `len` elements, or else panicked.
+        unsafe {
+            res.set_len(len);
+        }
+        res
+    }
+}
+
+impl<S, const L: usize, C, T> SylowStreamBuilder<S, L, C, T>
+where
+    C: SylowDecomposable<S>,
+    T: Clone,
+{
+    /// Partitions the work of the stream into at most `n` shards, which together yield exactly
+    /// the elements the stream would have yielded.
+    /// Fewer than `n` shards are returned if there is too little work to divide.
+    /// The largest shard, by its bound on the number of elements, is split first.
+    ///
+    /// Each shard is turned back into a stream by calling
+    /// [`SylowStreamBuilder::resume_shard`] on a builder configured identically to this one, so
+    /// that shards can be handed to other processes or machines.
+    pub fn shards(self, n: usize) -> Vec<ShardDescriptor> {
+        let mut open = vec![self.into_iter()];
+        let mut closed = Vec::new();
+        while !open.is_empty() && open.len() + closed.len() < n {
+            let (i, _) = open
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, s)| s.size_hint().1.unwrap_or(usize::MAX))
+                .unwrap();
+            match open.swap_remove(i).split() {
+                (a, Some(b)) => {
+                    open.push(a);
+                    open.push(b);
+                }
+                (a, None) => closed.push(a),
+            }
+        }
+        open.iter()
+            .chain(closed.iter())
+            .map(SylowStream::describe)
+            .collect()
+    }
+
+    /// Returns a stream yielding the elements of `shard`, which must have been produced by
+    /// [`SylowStreamBuilder::shards`] on a builder configured identically to this one.
+    /// Panics if the shard refers to nodes or coordinates which this builder does not have.
+    pub fn resume_shard(self, shard: &ShardDescriptor) -> SylowStream<S, L, C, T> {
+        let mut stream = self.into_iter();
+        stream.stack.clear();
+        stream.buffer = shard
+            .elements
+            .iter()
+            .map(|coords| {
+                let part = SylowElem::new(to_array(coords));
+                let node = stream.node_at(&part.order_powers());
+                (part, node.data.1.clone())
+            })
+            .collect();
+        stream.stack = shard
+            .seeds
+            .iter()
+            .map(|seed| Seed {
+                part: SylowElem::new(to_array(&seed.coords)),
+                start: seed.start,
+                node: stream.node_at(&to_array(&seed.node)),
+            })
+            .collect();
+        stream
+    }
+}
+
+impl<S, const L: usize, C, T> SylowStreamBuilder<S, L, C, T>
+where
+    C: SylowDecomposable<S>,
+    T: Clone,
+{
+    /// Predicts the number of elements this stream yields, and the memory it needs, by building
+    /// the stream and counting the elements below its initial seeds, without running it.
+    /// This is usually cheap compared to running the stream, costing about as much as building
+    /// the stream's `FactorTrie`.
+    /// Building the stream does generate some elements, however: with the flag
+    /// [`flags::NO_PARABOLIC`], the seed of the prime 2 is propagated at once, and with
+    /// [`SylowStreamBuilder::with_budget`], the whole sample is drawn, so the estimate costs as
+    /// much as drawing it.
+    /// A parallel stream holds at most one sequential stream's stack and buffer per thread.
+    pub fn estimate(&self) -> StreamEstimate {
+        let stream = self.clone().into_iter();
+        let elements = stream
+            .stack
+            .iter()
+            .map(SylowStream::seed_count)
+            .fold(stream.buffer.len() as u128, u128::saturating_add);
+
+        let mut nodes = 0;
+        let mut depth = 0;
+        stream.tree.for_each(&mut |_, ds| {
+            nodes += 1;
+            depth = depth.max(ds.iter().sum::<usize>());
+        });
+
+        // Propagating a seed handles at most `STACK_ADDITION_LIMIT` coordinates of its prime,
+        // pushing a seed for each later prime for every coordinate, and one more seed for the
+        // remaining coordinates. Seeds are propagated depth first, so at most `depth`
+        // propagations are pending at once.
+        let coords = |i: usize| u128::min(C::FACTORS[i].0, STACK_ADDITION_LIMIT as u128) as usize;
+        let width = (0..L).map(|i| coords(i) * (L - i) + 1).max().unwrap_or(0);
+        let peak_stack = stream.stack.len() + depth * width;
+        let peak_buffer = (0..L).map(coords).fold(stream.buffer.len(), usize::max);
+
+        StreamEstimate {
+            elements,
+            exact: stream.is_exact(),
+            nodes,
+            peak_stack,
+            peak_buffer,
+            memory: nodes * std::mem::size_of::<FactorTrie<S, L, C, (GenData, T)>>()
+                + peak_stack * std::mem::size_of::<Seed<S, L, C, T>>()
+                + peak_buffer * std::mem::size_of::<Output<S, L, C, T>>(),
+        }
+    }
+}
+
+fn to_array<X: Copy, const L: usize>(v: &[X]) -> [X; L] {
+    v.try_into()
+        .expect("Shard descriptor does not match the length of the decomposition.")
+}
+
 impl<S, const L: usize, C: SylowDecomposable<S>, T> SylowStream<S, L, C, T> {
+    /// Returns a stream yielding each element $\chi$ of this stream as the pair
+    /// $(\chi, \chi^{-1})$.
+    /// The inverse is found by negating the coordinates, so this is cheaper than computing it
+    /// from the product, as in $\chi + \chi^{-1}$.
+    pub fn with_inverses(self) -> WithInverses<Self> {
+        WithInverses(self)
+    }
+
     /// Converts a sequential Sylow stream into a parallel one.
     pub fn parallelize(self) -> SylowParStream<S, L, C, T>
     where
@@ -221,6 +803,254 @@ impl<S, const L: usize, C: SylowDecomposable<S>, T> SylowStream<S, L, C, T> {
         SylowParStream {
             stream: self,
             splits: rayon::current_num_threads(),
+            block_size: 0,
+            on_split: None,
+        }
+    }
+
+    /// Splits this stream into two streams which together yield exactly the elements this stream
+    /// would have yielded.
+    /// The second stream is `None` if there is too little work left to split.
+    /// This allows the work to be partitioned between threads or processes without using
+    /// `rayon`.
+    pub fn split(mut self) -> (Self, Option<Self>)
+    where
+        T: Clone,
+    {
+        while self.stack.len() == 1 {
+            let top = self.stack.pop().unwrap();
+            self.propagate(top, |slf, e| slf.buffer.push(e));
+        }
+        let other = self.split_off();
+        (self, other)
+    }
+
+    /// Adds `additional` free slots to this stream's stack of partially generated elements, on
+    /// top of those already free, so that [`SylowStream::propagate_into`] can run without
+    /// allocating.
+    /// After a [`StackFull`] error, reserving `needed - available` slots is exactly enough to
+    /// retry, and reserving [`StreamEstimate::peak_stack`] slots, as given by
+    /// [`SylowStreamBuilder::estimate`], is always enough to exhaust the stream.
+    pub fn reserve_stack(&mut self, additional: usize) {
+        let free = self.stack.capacity() - self.stack.len();
+        self.stack.reserve_exact(free + additional);
+    }
+
+    /// Propagates the partially generated element on top of this stream's stack by one step,
+    /// passing every element this generates to `consume` and pushing the partially generated
+    /// elements it leaves onto the stack.
+    /// Elements which were generated but not yet yielded, for example by
+    /// [`SylowStream::split`], are passed to `consume` first.
+    /// Returns `Ok(false)` once the stream is exhausted, and `Ok(true)` otherwise.
+    ///
+    /// Unlike [`Iterator::next`], this never allocates: the stack is never grown beyond its
+    /// capacity, which can be set with [`SylowStream::reserve_stack`]. If the stack has too little
+    /// spare capacity, the stream is left unchanged and [`StackFull`] is returned.
+    /// This lets the stream be driven by custom schedulers, or in environments without an
+    /// allocator once the stack is reserved, provided that cloning `T` does not allocate.
+    pub fn propagate_into(
+        &mut self,
+        consume: &mut dyn FnMut((SylowElem<S, L, C>, T)),
+    ) -> Result<bool, StackFull>
+    where
+        T: Clone,
+    {
+        while let Some(e) = self.buffer.pop() {
+            consume(e);
+        }
+        let Some(top) = self.stack.last() else {
+            return Ok(false);
+        };
+        let i = unsafe { &*top.node }.index();
+        // As in `SylowStreamBuilder::estimate`, propagating a seed pushes at most one seed for
+        // each later prime per coordinate, and one more seed for the remaining coordinates, after
+        // popping the seed itself.
+        let coords = u128::min(C::FACTORS[i].0, STACK_ADDITION_LIMIT as u128) as usize;
+        let needed = coords * (L - i);
+        let available = self.stack.capacity() - self.stack.len();
+        if available < needed {
+            return Err(StackFull { needed, available });
+        }
+        let Some(top) = self.stack.pop() else {
+            unreachable!();
+        };
+        self.propagate(top, |_, e| consume(e));
+        Ok(true)
+    }
+
+    fn split_off(&mut self) -> Option<Self> {
+        let len = self.stack.len();
+        if len == 0 && self.buffer.len() > 1 {
+            // A stream with no seeds, such as a sample drawn by `with_budget`, may still hold
+            // enough elements to be worth dividing.
+            let buffer = self.buffer.split_off(self.buffer.len() / 2);
+            return Some(SylowStream {
+                tree: Arc::clone(&self.tree),
+                stack: Vec::new(),
+                buffer,
+                filter: self.filter.clone(),
+            });
+        }
+        if len <= 1 {
+            return None;
+        }
+        let stack = self.stack.split_off(len / 2);
+        Some(SylowStream {
+            tree: Arc::clone(&self.tree),
+            stack,
+            buffer: Vec::new(),
+            filter: self.filter.clone(),
+        })
+    }
+
+    // Returns the node of the trie with exponents `ds`.
+    fn node_at(&self, ds: &[usize; L]) -> &FactorTrie<S, L, C, (GenData, T)> {
+        let mut node = &*self.tree;
+        for (i, d) in ds.iter().enumerate() {
+            for _ in 0..*d {
+                node = node
+                    .child(i)
+                    .expect("Shard descriptor refers to a node which does not exist in this trie.");
+            }
+        }
+        node
+    }
+
+    fn describe(&self) -> ShardDescriptor {
+        ShardDescriptor {
+            seeds: self
+                .stack
+                .iter()
+                .map(|seed| SeedDescriptor {
+                    node: unsafe { &*seed.node }.ds().to_vec(),
+                    coords: seed.part.coords.to_vec(),
+                    start: seed.start,
+                })
+                .collect(),
+            elements: self.buffer.iter().map(|(x, _)| x.coords.to_vec()).collect(),
+        }
+    }
+
+    // Whether the number of elements yielded is known before they are generated.
+    fn is_exact(&self) -> bool {
+        self.filter.is_none()
+    }
+
+    // Returns the number of elements that will eventually be yielded by propagating `seed`.
+    // This walks only the chain of nodes below `seed` with the same prime, so it runs in time
+    // proportional to the exponent of that prime.
+    fn seed_count(seed: &Seed<S, L, C, T>) -> u128 {
+        let node = unsafe { &*seed.node };
+        Self::chain_count(node, seed.part.coords[node.index()], seed.start)
+    }
+
+    fn chain_count(node: &FactorTrie<S, L, C, (GenData, T)>, c: u128, start: u128) -> u128 {
+        // Counts the multiples of `m` in the interval `[lo, hi]`.
+        fn multiples(lo: u128, hi: u128, m: u128) -> u128 {
+            if lo == 0 {
+                hi / m + 1
+            } else {
+                hi / m - (lo - 1) / m
+            }
+        }
+
+        let i = node.index();
+        let (p, d) = C::FACTORS[i];
+        let e0 = node.ds()[i];
+        // Every coordinate generated below this node lies in the interval `[lo, top]`.
+        let lo = c + start * intpow::<0>(p, (d - e0) as u128);
+        let top = c + intpow::<0>(p, (d - e0 + 1) as u128) - 1;
+
+        let mut total = 0;
+        let mut next = Some(node);
+        while let Some(n) = next {
+            let hi = u128::min(n.data.0.lim, top);
+            if hi >= lo {
+                // The coordinates generated at depth `e` are exactly those of valuation `d - e`.
+                let m = intpow::<0>(p, (d - n.ds()[i]) as u128);
+                let count = multiples(lo, hi, m) - multiples(lo, hi, m * p);
+                total += count * n.data.0.weight;
+            }
+            next = n.child(i);
+        }
+        total
+    }
+
+    // Pushes onto the buffer at most `n` of the elements which propagating seeds would yield at
+    // the node with exponents `ds`; see `SylowStreamBuilder::with_budget`.
+    // Every element of the node's order is ranked in mixed radix, with one digit for the
+    // coordinate of each prime, the last prime being the least significant. The digit of a
+    // coordinate generated over `e` steps is read from the multiples of the step added at each of
+    // them, the last of which is nonzero.
+    fn sample(&mut self, ds: &[usize; L], n: usize)
+    where
+        T: Clone,
+    {
+        let tree = Arc::clone(&self.tree);
+        let mut path = Vec::new();
+        let mut node = &*tree;
+        for (i, d) in ds.iter().enumerate() {
+            for _ in 0..*d {
+                node = node
+                    .child(i)
+                    .expect("Sampled a node which does not exist in this trie.");
+                path.push(node);
+            }
+        }
+
+        let radix = |i: usize| {
+            let (p, _) = C::FACTORS[i];
+            (p - 1) * intpow::<0>(p, (ds[i] - 1) as u128)
+        };
+        let count = (0..L).filter(|i| ds[*i] > 0).map(radix).product::<u128>();
+        // The largest stride below `count` over the golden ratio which is coprime to `count`, so
+        // that every rank is visited.
+        let mut stride = (count as f64 * 0.618_033_988_749_895) as u128;
+        while gcd(stride, count) != 1 {
+            stride -= 1;
+        }
+
+        let decode = |mut rank: u128| {
+            let mut digits = [0; L];
+            for i in (0..L).rev().filter(|i| ds[*i] > 0) {
+                digits[i] = rank % radix(i);
+                rank /= radix(i);
+            }
+            let mut part = SylowElem::<S, L, C>::ONE;
+            for n in &path {
+                let i = n.index();
+                let (p, _) = C::FACTORS[i];
+                let e = n.ds()[i];
+                let j = if e == ds[i] {
+                    1 + digits[i] % (p - 1)
+                } else {
+                    digits[i] / (p - 1) / intpow::<0>(p, (ds[i] - 1 - e) as u128) % p
+                };
+                part.coords[i] += j * n.data.0.step;
+                if part.coords[i] > n.data.0.lim
+                    || self.filter.as_ref().is_some_and(|f| !f(&part.coords))
+                {
+                    return None;
+                }
+            }
+            Some(part)
+        };
+
+        let mut rank = 0;
+        let mut taken = 0;
+        for _ in 0..count {
+            if taken == n {
+                break;
+            }
+            if let Some(x) = decode(rank) {
+                self.buffer.push((x, node.data.1.clone()));
+                taken += 1;
+            }
+            rank = if rank >= count - stride {
+                rank - (count - stride)
+            } else {
+                rank + stride
+            };
         }
     }
 
@@ -252,6 +1082,9 @@ impl<S, const L: usize, C: SylowDecomposable<S>, T> SylowStream<S, L, C, T> {
             }
             let mut part = seed.part;
             part.coords[node.index()] = tmp;
+            if self.filter.as_ref().is_some_and(|f| !f(&part.coords)) {
+                continue;
+            }
 
             if let Some(n) = node.child(node.index()) {
                 self.stack.push(Seed {
@@ -300,6 +1133,68 @@ where
             None
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self
+            .stack
+            .iter()
+            .map(Self::seed_count)
+            .fold(self.buffer.len() as u128, u128::saturating_add);
+        match (usize::try_from(count), self.is_exact()) {
+            (Ok(count), true) => (count, Some(count)),
+            (Ok(count), false) => (self.buffer.len(), Some(count)),
+            (Err(_), true) => (usize::MAX, None),
+            (Err(_), false) => (self.buffer.len(), None),
+        }
+    }
+
+    // Entire seeds are skipped at once, without generating their elements, whenever they yield
+    // no more elements than remain to be skipped.
+    fn nth(&mut self, n: usize) -> Option<(SylowElem<S, L, C>, T)> {
+        if !self.is_exact() {
+            for _ in 0..n {
+                self.next()?;
+            }
+            return self.next();
+        }
+        let mut n = n as u128;
+        loop {
+            let len = self.buffer.len() as u128;
+            if n < len {
+                self.buffer.truncate((len - n) as usize);
+                return self.buffer.pop();
+            }
+            n -= len;
+            self.buffer.clear();
+
+            let mut top = self.stack.pop()?;
+            let count = Self::seed_count(&top);
+            if count <= n {
+                n -= count;
+                continue;
+            }
+
+            // Propagating a seed handles `STACK_ADDITION_LIMIT` coordinates at a time before
+            // pushing a seed for the rest, so whole blocks of coordinates may also be skipped.
+            // Binary search for the number of blocks to skip.
+            let node = unsafe { &*top.node };
+            let c = top.part.coords[node.index()];
+            let (p, _) = C::FACTORS[node.index()];
+            let block = STACK_ADDITION_LIMIT as u128;
+            let (mut lo, mut hi) = (0, (p - top.start).div_ceil(block));
+            while hi - lo > 1 {
+                let mid = (lo + hi) / 2;
+                if count - Self::chain_count(node, c, top.start + mid * block) <= n {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            n -= count - Self::chain_count(node, c, top.start + lo * block);
+            top.start += lo * block;
+            self.propagate(top, |slf, e| slf.buffer.push(e));
+        }
+    }
 }
 
 impl<S, const L: usize, C, T> SylowParStream<S, L, C, T>
@@ -317,29 +1212,52 @@ where
             return None;
         }
 
-        let len = self.stream.stack.len();
-        if len <= 1 {
-            return None;
-        }
-        let stack = self.stream.stack.split_off(len / 2);
+        let stream = self.stream.split_off()?;
         self.splits /= 2;
         Some(SylowParStream {
-            stream: SylowStream {
-                tree: Arc::clone(&self.stream.tree),
-                stack,
-                buffer: Vec::new(),
-            },
+            stream,
             splits: self.splits,
+            block_size: self.block_size,
+            on_split: self.on_split.clone(),
         })
     }
 
+    /// Sets the number of elements handed to each consumer at once.
+    /// See [`SylowStreamBuilder::set_block_size`].
+    pub fn with_block_size(mut self, n: usize) -> Self {
+        self.block_size = n;
+        self
+    }
+
+    /// Sets a callback to be called with a [`SplitEvent`] for each stretch of work done by each
+    /// piece of this stream, so that load imbalance between threads can be diagnosed.
+    /// The callback is called from the worker threads, and should return quickly.
+    pub fn on_split(mut self, f: impl Fn(&SplitEvent) + Send + Sync + 'static) -> Self {
+        self.on_split = Some(Arc::new(f));
+        self
+    }
+
+    fn report(&self, stolen: bool, subtrees: usize, elements: usize, split: bool) {
+        if let Some(f) = &self.on_split {
+            f(&SplitEvent {
+                thread: rayon::current_thread_index(),
+                migrated: stolen,
+                subtrees,
+                elements,
+                split,
+            });
+        }
+    }
+
     fn work<Con>(&mut self, stolen: bool, consumer: Con) -> Con::Result
     where
         Con: UnindexedConsumer<Output<S, L, C, T>>,
     {
-        let mut folder = consumer.split_off_left().into_folder();
-        while let Some(buf) = self.stream.buffer.pop() {
-            folder = folder.consume(buf);
+        let subtrees = self.stream.stack.len();
+        let folder = consumer.split_off_left().into_folder();
+        let mut elements = 0;
+        if self.block_size > 0 {
+            return self.work_blocks(stolen, consumer, folder, subtrees, elements);
         }
         let folder = RefCell::new(Some(folder));
 
@@ -348,26 +1266,124 @@ where
             let mut f = folder.take().unwrap();
             f = f.consume(top);
             folder.replace(Some(f));
+            elements += 1;
 
             if count % 10_000 == 0 {
                 let Some(mut split) = self.maybe_split(stolen) else {
                     continue;
                 };
+                self.report(stolen, subtrees, elements, true);
                 let (r1, r2) = (consumer.to_reducer(), consumer.to_reducer());
                 let left_consumer = consumer.split_off_left();
 
-                let (left, right) = rayon::join_context(
-                    |ctx| self.work(ctx.migrated(), left_consumer),
-                    |ctx| split.work(ctx.migrated(), consumer),
-                );
-                return r1.reduce(
-                    folder.into_inner().unwrap().complete(),
-                    r2.reduce(left, right),
-                );
-            }
-            count += 1;
-        }
-        folder.into_inner().unwrap().complete()
+                let (left, right) = rayon::join_context(
+                    |ctx| self.work(ctx.migrated(), left_consumer),
+                    |ctx| split.work(ctx.migrated(), consumer),
+                );
+                return r1.reduce(
+                    folder.into_inner().unwrap().complete(),
+                    r2.reduce(left, right),
+                );
+            }
+            count += 1;
+        }
+        self.report(stolen, subtrees, elements, false);
+        folder.into_inner().unwrap().complete()
+    }
+
+    fn work_blocks<Con>(
+        &mut self,
+        stolen: bool,
+        consumer: Con,
+        mut folder: Con::Folder,
+        subtrees: usize,
+        mut elements: usize,
+    ) -> Con::Result
+    where
+        Con: UnindexedConsumer<Output<S, L, C, T>>,
+    {
+        let mut block = Vec::with_capacity(self.block_size);
+        loop {
+            while block.len() < self.block_size {
+                let Some(top) = self.stream.next() else {
+                    break;
+                };
+                block.push(top);
+            }
+            let exhausted = block.len() < self.block_size;
+            elements += block.len();
+            folder = folder.consume_iter(block.drain(..));
+            if exhausted || folder.full() {
+                break;
+            }
+
+            let Some(mut split) = self.maybe_split(stolen) else {
+                continue;
+            };
+            self.report(stolen, subtrees, elements, true);
+            let (r1, r2) = (consumer.to_reducer(), consumer.to_reducer());
+            let left_consumer = consumer.split_off_left();
+
+            let (left, right) = rayon::join_context(
+                |ctx| self.work(ctx.migrated(), left_consumer),
+                |ctx| split.work(ctx.migrated(), consumer),
+            );
+            return r1.reduce(folder.complete(), r2.reduce(left, right));
+        }
+        self.report(stolen, subtrees, elements, false);
+        folder.complete()
+    }
+}
+
+impl<S, const L: usize, C, T> SylowParStream<S, L, C, T>
+where
+    S: Send + Sync,
+    C: SylowDecomposable<S> + Send + Sync,
+    T: Clone + Send + Sync,
+{
+    /// Returns a stream yielding each element of this stream together with its inverse.
+    /// See [`SylowStream::with_inverses`].
+    pub fn with_inverses(self) -> WithInverses<Self> {
+        WithInverses(self)
+    }
+}
+
+impl<S, const L: usize, C, T> Iterator for WithInverses<SylowStream<S, L, C, T>>
+where
+    C: SylowDecomposable<S>,
+    T: Clone,
+{
+    type Item = ((SylowElem<S, L, C>, SylowElem<S, L, C>), T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (x, t) = self.0.next()?;
+        let x_inv = x.inverse();
+        Some(((x, x_inv), t))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<S, const L: usize, C, T> ParallelIterator for WithInverses<SylowParStream<S, L, C, T>>
+where
+    S: Send + Sync,
+    C: SylowDecomposable<S> + Send + Sync,
+    T: Clone + Send + Sync,
+{
+    type Item = ((SylowElem<S, L, C>, SylowElem<S, L, C>), T);
+
+    fn drive_unindexed<Con>(self, consumer: Con) -> Con::Result
+    where
+        Con: UnindexedConsumer<Self::Item>,
+    {
+        self.0
+            .map(|(x, t)| {
+                let x_inv = x.inverse();
+                ((x, x_inv), t)
+            })
+            .drive_unindexed(consumer)
     }
 }
 
@@ -402,6 +1418,7 @@ where
                 consume: consume.0,
                 step: intpow::<0>(p, (d - ds[i]) as u128),
                 lim: 0,
+                weight: 0,
             }, consume.1)
         });
 
@@ -439,18 +1456,63 @@ where
         };
         help(block, lims, &mut tree);
 
+        fn weigh<S, const L: usize, C, T>(node: &mut FactorTrie<S, L, C, (GenData, T)>)
+        where
+            C: SylowDecomposable<S>,
+        {
+            for j in node.index()..L {
+                if let Some(child) = node.child_mut(j) {
+                    weigh(child);
+                }
+            }
+            let mut weight = if node.data.0.consume.this { 1 } else { 0 };
+            for j in (node.index() + 1)..L {
+                let Some(child) = node.child(j) else {
+                    continue;
+                };
+                if child.data.0.consume.this || child.data.0.consume.descendants >= 1 {
+                    weight += SylowStream::chain_count(child, 0, 0);
+                }
+            }
+            node.data.0.weight = weight;
+        }
+        weigh(&mut tree);
+
         let mut stream = SylowStream {
             stack: Vec::new(),
-            buffer: if (self.mode & flags::INCLUDE_ONE != 0)
-                || (self.mode & flags::LEQ != 0 && self.mode & flags::NO_PARABOLIC == 0)
+            buffer: if ((self.mode & flags::INCLUDE_ONE != 0)
+                || (self.mode & flags::LEQ != 0 && self.mode & flags::NO_PARABOLIC == 0))
+                && self
+                    .filter
+                    .as_ref()
+                    .is_none_or(|f| f(&SylowElem::<S, L, C>::ONE.coords))
+                && self.budget != Some(0)
             {
                 vec![(SylowElem::ONE, tree.data.1.clone())]
             } else {
                 Vec::<(SylowElem<_, L, _>, _)>::new()
             },
             tree: Arc::from(tree),
+            filter: self.filter,
         };
 
+        if let Some(n) = self.budget {
+            let no_parabolic = self.mode & flags::NO_PARABOLIC != 0;
+            let mut targets = Vec::new();
+            stream.tree.for_each(&mut |(data, _), ds| {
+                let depth = ds.iter().sum::<usize>();
+                let parabolic =
+                    depth == 1 && C::FACTORS[ds.iter().position(|d| *d == 1).unwrap()].0 == 2;
+                if data.consume.this && depth > 0 && !(no_parabolic && parabolic) {
+                    targets.push(ds);
+                }
+            });
+            for ds in targets {
+                stream.sample(&ds, n);
+            }
+            return stream;
+        }
+
         for i in 0..L {
             let Some(n) = stream.tree.child(i) else {
                 continue;
@@ -487,9 +1549,12 @@ where
     type Iter = SylowParStream<S, L, C, T>;
 
     fn into_par_iter(self) -> Self::Iter {
+        let block_size = self.block_size;
         SylowParStream {
             stream: self.into_iter(),
             splits: rayon::current_num_threads(),
+            block_size,
+            on_split: None,
         }
     }
 }
@@ -501,21 +1566,80 @@ impl<S, const L: usize, C: SylowDecomposable<S>, T: Clone> Clone for Seed<S, L,
 }
 impl<S, const L: usize, C: SylowDecomposable<S>, T: Copy> Copy for Seed<S, L, C, T> {}
 
+/// Shows the flags and the trie of targets, including which nodes are consumed.
+impl<S, const L: usize, C, T> std::fmt::Debug for SylowStreamBuilder<S, L, C, T>
+where
+    C: SylowDecomposable<S>,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "SylowStreamBuilder {{ mode: {:#04x}, quotient: {:?}, filtered: {}, budget: {:?} }}",
+            self.mode,
+            self.quotient,
+            self.filter.is_some(),
+            self.budget,
+        )?;
+        write!(f, "{}", self.tree)
+    }
+}
+
 impl<S, const L: usize, C: SylowDecomposable<S>, T: Clone> Clone for SylowStreamBuilder<S, L, C, T> {
     fn clone(&self) -> Self {
         SylowStreamBuilder {
             tree: self.tree.clone(),
+            filter: self.filter.clone(),
             ..*self
         }
     }
 }
 
+impl<S, const L: usize, C, T> SharedData<S, L, C, T> {
+    /// Returns the trie this data belongs to.
+    pub fn trie(&self) -> &Arc<FactorTrie<S, L, C, T>> {
+        &self.trie
+    }
+
+    /// Returns the powers on the prime factorization of the node this data belongs to.
+    pub fn ds(&self) -> &[usize; L] {
+        &self.ds
+    }
+}
+
+impl<S, const L: usize, C, T> std::ops::Deref for SharedData<S, L, C, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        let Some(data) = self.trie.get(&self.ds) else {
+            unreachable!("Shared data only refers to nodes in its trie.");
+        };
+        data
+    }
+}
+
+impl<S, const L: usize, C, T> Clone for SharedData<S, L, C, T> {
+    fn clone(&self) -> SharedData<S, L, C, T> {
+        SharedData {
+            trie: Arc::clone(&self.trie),
+            ds: self.ds,
+        }
+    }
+}
+
+impl<S, const L: usize, C, T: std::fmt::Debug> std::fmt::Debug for SharedData<S, L, C, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SharedData").field(&**self).finish()
+    }
+}
+
 impl<S, const L: usize, C: SylowDecomposable<S>, T: Clone> Clone for SylowStream<S, L, C, T> {
     fn clone(&self) -> SylowStream<S, L, C, T> {
         SylowStream {
             stack: self.stack.clone(),
             buffer: self.buffer.clone(),
             tree: self.tree.clone(),
+            filter: self.filter.clone(),
         }
     }
 }
@@ -530,8 +1654,11 @@ where
                 stack: self.stream.stack.clone(),
                 buffer: self.stream.buffer.clone(),
                 tree: Arc::clone(&self.stream.tree),
+                filter: self.stream.filter.clone(),
             },
             splits: self.splits,
+            block_size: self.block_size,
+            on_split: self.on_split.clone(),
         }
     }
 }
@@ -598,7 +1725,7 @@ mod tests {
         let stream = SylowStreamBuilder::new().add_target(&[1, 0, 0]).into_iter();
         let coords: Vec<SylowElem<Phantom, 3, FpNum<61>>> = stream.map(|(a, _)| a).collect();
         assert_eq!(coords.len(), 1);
-        let mut x = coords[0].clone();
+        let mut x = coords[0];
         assert!(x != SylowElem::ONE);
         x = x.pow(2);
         assert!(x == SylowElem::ONE);
@@ -610,7 +1737,7 @@ mod tests {
             .for_each(|(mut x, _)| {
                 count += 1;
                 for _ in 1..3 {
-                    let y = x.clone();
+                    let y = x;
                     assert!(x != SylowElem::ONE);
                     x = x.multiply(&y);
                 }
@@ -734,7 +1861,7 @@ mod tests {
             .into_par_iter();
         let coords = stream.collect::<Vec<_>>();
         assert_eq!(coords.len(), 1);
-        let (mut x, _) = coords[0].clone();
+        let (mut x, _) = coords[0];
         assert!(x != SylowElem::ONE);
         x = x.pow(2);
         assert!(x == SylowElem::ONE);
@@ -746,7 +1873,7 @@ mod tests {
             .for_each(|(mut x, _)| {
                 count.fetch_add(1, Ordering::Relaxed);
                 for _ in 1..3 {
-                    let y = x.clone();
+                    let y = x;
                     assert!(x != SylowElem::ONE);
                     x = x.multiply(&y);
                 }
@@ -842,6 +1969,405 @@ mod tests {
         assert_eq!(count.into_inner(), 18);
     }
 
+    #[test]
+    pub fn test_blocks_par() {
+        for n in [1, 7, 100, 10_000] {
+            let count = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+                .add_flag(flags::LEQ)
+                .add_target(&[1, 3, 1])
+                .set_block_size(n)
+                .into_par_iter()
+                .count();
+            assert_eq!(count, 270);
+        }
+
+        let count = SylowStreamBuilder::<Phantom, 7, FpNum<BIG_P>, ()>::new()
+            .add_target(&[0, 0, 0, 2, 0, 0, 0])
+            .into_iter()
+            .parallelize()
+            .with_block_size(64)
+            .count();
+        assert_eq!(count, 29 * 29 - 29);
+    }
+
+    #[test]
+    pub fn test_split_events() {
+        for block_size in [0, 64] {
+            let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let log = Arc::clone(&events);
+            let count = SylowStreamBuilder::<Phantom, 7, FpNum<BIG_P>, ()>::new()
+                .add_target(&[0, 0, 0, 2, 0, 0, 0])
+                .into_iter()
+                .parallelize()
+                .with_block_size(block_size)
+                .on_split(move |e| log.lock().unwrap().push(e.clone()))
+                .count();
+            let events = events.lock().unwrap();
+            assert_eq!(count, 29 * 29 - 29);
+            assert_eq!(events.iter().map(|e| e.elements).sum::<usize>(), count);
+            assert!(events.iter().any(|e| !e.split));
+        }
+    }
+
+    #[test]
+    pub fn test_propagate_into() {
+        let builder = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_target(&[1, 3, 1]);
+        let mut stream = builder.clone().into_iter();
+        stream.stack.shrink_to_fit();
+        let (buffered, seeds) = (stream.buffer.len(), stream.stack.len());
+        let mut all = Vec::new();
+        let err = stream
+            .propagate_into(&mut |(x, _)| all.push(x.coords))
+            .unwrap_err();
+        let i = unsafe { &*stream.stack.last().unwrap().node }.index();
+        let free = stream.stack.capacity() - stream.stack.len();
+        let q = <FpNum<271> as Factor<Phantom>>::FACTORS[i].0;
+        assert_eq!(err.needed, q as usize * (3 - i));
+        assert_eq!(err.available, free);
+        assert_eq!((all.len(), stream.stack.len()), (buffered, seeds));
+
+        // One slot short of enough still fails, leaving the stream unchanged.
+        stream.reserve_stack(err.needed - err.available - 1);
+        let short = stream.propagate_into(&mut |(x, _)| all.push(x.coords));
+        assert_eq!(
+            short,
+            Err(StackFull {
+                needed: err.needed,
+                available: err.needed - 1
+            })
+        );
+        stream.reserve_stack(1);
+        assert_eq!(
+            stream.propagate_into(&mut |(x, _)| all.push(x.coords)),
+            Ok(true)
+        );
+
+        stream.reserve_stack(builder.estimate().peak_stack);
+        let capacity = stream.stack.capacity();
+        while stream
+            .propagate_into(&mut |(x, _)| all.push(x.coords))
+            .unwrap()
+        {}
+        assert_eq!(stream.stack.capacity(), capacity);
+        all.sort();
+        let mut expected = builder
+            .into_iter()
+            .map(|(x, _)| x.coords)
+            .collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(all, expected);
+    }
+
+    #[test]
+    pub fn test_collect_vec() {
+        let builder = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_target(&[1, 3, 1]);
+        let all = builder.clone().into_iter().collect::<Vec<_>>();
+        let res = builder.clone().collect_vec();
+        assert_eq!(res, all);
+        assert_eq!(res.capacity(), 270);
+
+        let mut all = all.into_iter().map(|(x, _)| x.coords).collect::<Vec<_>>();
+        all.sort();
+        let mut res = builder
+            .par_collect_vec()
+            .into_iter()
+            .map(|(x, _)| x.coords)
+            .collect::<Vec<_>>();
+        res.sort();
+        assert_eq!(res, all);
+
+        let builder = SylowStreamBuilder::<Phantom, 3, QuadNum<3001>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_targets_leq(3002);
+        let mut all = builder
+            .clone()
+            .into_iter()
+            .map(|(x, _)| x.coords)
+            .collect::<Vec<_>>();
+        all.sort();
+        let res = builder.par_collect_vec();
+        assert_eq!(res.capacity(), 3002);
+        let mut res = res.into_iter().map(|(x, _)| x.coords).collect::<Vec<_>>();
+        res.sort();
+        assert_eq!(res, all);
+    }
+
+    #[test]
+    #[should_panic(expected = "more elements than counted")]
+    pub fn test_par_fill_too_short() {
+        let stream = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_target(&[1, 3, 1])
+            .into_iter();
+        let mut out = Vec::with_capacity(270);
+        par_fill(stream, &mut out.spare_capacity_mut()[..100]);
+    }
+
+    #[test]
+    #[should_panic(expected = "fewer elements than counted")]
+    pub fn test_par_fill_too_long() {
+        let stream = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_target(&[1, 3, 1])
+            .into_iter();
+        let mut out = Vec::with_capacity(300);
+        par_fill(stream, &mut out.spare_capacity_mut()[..300]);
+    }
+
+    #[test]
+    pub fn test_filter_coords() {
+        let builder = SylowStreamBuilder::<Phantom, 3, QuadNum<3001>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_targets_leq(3002);
+        let pred = |x: &[u128; 3]| x[0] == 0 && x[2] % 7 != 3;
+        let mut expected = builder
+            .clone()
+            .into_iter()
+            .map(|(x, _)| x.coords)
+            .filter(pred)
+            .collect::<Vec<_>>();
+        expected.sort();
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let filtered = builder
+            .filter_coords(move |x| {
+                counter.fetch_add(1, Ordering::Relaxed);
+                x[0] == 0
+            })
+            .filter_coords(|x| x[2] % 7 != 3);
+        let mut res = filtered
+            .clone()
+            .into_iter()
+            .map(|(x, _)| x.coords)
+            .collect::<Vec<_>>();
+        res.sort();
+        assert_eq!(res, expected);
+        // The elements with a nonzero 2-adic coordinate are pruned without being generated.
+        assert!(calls.load(Ordering::Relaxed) < 3002);
+
+        let mut res = filtered
+            .clone()
+            .par_collect_vec()
+            .into_iter()
+            .map(|(x, _)| x.coords)
+            .collect::<Vec<_>>();
+        res.sort();
+        assert_eq!(res, expected);
+
+        assert_eq!(
+            filtered.clone().into_iter().nth(10).map(|(x, _)| x),
+            filtered.into_iter().nth(10).map(|(x, _)| x)
+        );
+    }
+
+    #[test]
+    pub fn test_restrict_to_subgroup() {
+        // 270 = 2 * 3^3 * 5
+        let builder = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_target(&[1, 3, 1]);
+        let mut res = builder
+            .clone()
+            .restrict_to_subgroup(&[1, 2, 0])
+            .into_iter()
+            .map(|(x, _)| x)
+            .collect::<Vec<_>>();
+        assert_eq!(res.len(), 18);
+        for x in &res {
+            assert_eq!(x.pow(18), SylowElem::ONE);
+        }
+
+        let g = SylowElem::<Phantom, 3, FpNum<271>>::new([1, 3, 0]);
+        let mut generated = builder
+            .clone()
+            .restrict_to_subgroup_of(&g)
+            .into_iter()
+            .map(|(x, _)| x)
+            .collect::<Vec<_>>();
+        res.sort_by_key(|x| x.coords);
+        generated.sort_by_key(|x| x.coords);
+        assert_eq!(generated, res);
+
+        // Only the targets which lie in the subgroup remain.
+        let count = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+            .add_target(&[1, 3, 1])
+            .add_target(&[0, 1, 0])
+            .restrict_to_subgroup(&[1, 2, 0])
+            .into_iter()
+            .count();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    pub fn test_restrict_primes() {
+        // 270 = 2 * 3^3 * 5
+        let odd = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_target(&[1, 3, 1])
+            .restrict_primes(&[1, 2])
+            .into_iter()
+            .map(|(x, _)| x)
+            .collect::<Vec<_>>();
+        assert_eq!(odd.len(), 135);
+        assert!(odd.iter().all(|x| x.coords[0] == 0));
+
+        let count = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_target(&[1, 3, 1])
+            .restrict_primes(&[0])
+            .into_iter()
+            .count();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    pub fn test_budget() {
+        fn check<C>(builder: SylowStreamBuilder<Phantom, 3, C, [usize; 3]>)
+        where
+            C: SylowDecomposable<Phantom> + Send + Sync,
+        {
+            use std::collections::{HashMap, HashSet};
+
+            let all = builder
+                .clone()
+                .into_iter()
+                .map(|(x, ds)| (x.coords, ds))
+                .collect::<Vec<_>>();
+            let mut counts = HashMap::new();
+            for (_, ds) in &all {
+                *counts.entry(*ds).or_insert(0) += 1;
+            }
+
+            // A budget of every element yields exactly the elements of the whole stream.
+            let everything = builder.clone().with_budget(all.len()).into_iter();
+            assert_eq!(
+                everything.map(|(x, _)| x.coords).collect::<HashSet<_>>(),
+                all.iter().map(|(x, _)| *x).collect::<HashSet<_>>()
+            );
+
+            let sample = builder.clone().with_budget(5).into_iter();
+            assert_eq!(sample.clone().count(), sample.size_hint().0);
+            let seq = sample
+                .clone()
+                .map(|(x, ds)| (x.coords, ds))
+                .collect::<Vec<_>>();
+            let par = builder
+                .clone()
+                .with_budget(5)
+                .into_par_iter()
+                .collect::<Vec<_>>();
+            assert_eq!(seq.len(), par.len());
+            assert_eq!(
+                seq.iter().copied().collect::<HashSet<_>>(),
+                par.iter()
+                    .map(|(x, ds)| (x.coords, *ds))
+                    .collect::<HashSet<_>>()
+            );
+            let mut sampled = HashMap::new();
+            for (x, ds) in &seq {
+                assert!(all.contains(&(*x, *ds)));
+                *sampled.entry(*ds).or_insert(0) += 1;
+            }
+            assert!(counts
+                .iter()
+                .all(|(ds, n)| sampled.get(ds) == Some(&usize::min(*n, 5))));
+
+            // The sample of the largest order is spread out, rather than the first elements the
+            // stream generates.
+            let (ds, _) = counts.iter().max_by_key(|(_, n)| **n).unwrap();
+            let first = all.iter().filter(|(_, e)| e == ds).take(5);
+            assert!(first.clone().any(|x| !seq.contains(x)));
+
+            assert_eq!(builder.with_budget(0).into_iter().count(), 0);
+        }
+
+        check(
+            SylowStreamBuilder::<Phantom, 3, QuadNum<3001>, [u128; 3]>::new_with_orders()
+                .add_flag(flags::LEQ)
+                .add_targets_leq(3002),
+        );
+        check(
+            SylowStreamBuilder::<Phantom, 3, FpNum<3001>, [u128; 3]>::new_with_orders()
+                .for_unique_chi(3000),
+        );
+        check(
+            SylowStreamBuilder::<Phantom, 3, FpNum<3001>, [u128; 3]>::new_with_orders()
+                .add_flag(flags::LEQ)
+                .add_targets_leq(3000)
+                .filter_coords(|c| c[1] == 0),
+        );
+    }
+
+    #[test]
+    pub fn test_estimate() {
+        let builder = SylowStreamBuilder::<Phantom, 3, QuadNum<3001>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_flag(flags::NO_UPPER_HALF)
+            .add_targets_leq(3002);
+        let est = builder.estimate();
+        assert!(est.exact);
+        assert!(est.nodes > 1);
+
+        let mut stream = builder.clone().into_iter();
+        let (mut count, mut stack, mut buffer) = (0, 0, 0);
+        loop {
+            stack = stack.max(stream.stack.len());
+            buffer = buffer.max(stream.buffer.len());
+            if stream.next().is_none() {
+                break;
+            }
+            count += 1;
+        }
+        assert_eq!(est.elements, count);
+        assert!(stack <= est.peak_stack);
+        assert!(buffer <= est.peak_buffer);
+
+        let est = builder.clone().with_budget(1).estimate();
+        assert!(est.exact);
+        assert_eq!(
+            est.elements,
+            builder.with_budget(1).into_iter().count() as u128
+        );
+    }
+
+    #[test]
+    pub fn test_with_inverses() {
+        let builder = SylowStreamBuilder::<Phantom, 3, FpNum<61>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_flag(flags::NO_UPPER_HALF)
+            .add_target(&[2, 1, 1]);
+        let seq = builder
+            .clone()
+            .into_iter()
+            .with_inverses()
+            .map(|((x, x_inv), _)| {
+                assert_eq!(x.multiply(&x_inv), SylowElem::ONE);
+                x
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            seq,
+            builder
+                .clone()
+                .into_iter()
+                .map(|(x, _)| x)
+                .collect::<Vec<_>>()
+        );
+
+        let count = builder
+            .into_iter()
+            .parallelize()
+            .with_inverses()
+            .map(|((x, x_inv), _)| assert_eq!(x_inv.multiply(&x), SylowElem::ONE))
+            .count();
+        assert_eq!(count, seq.len());
+    }
+
     #[test]
     pub fn test_no_parabolic_no_upper_half_seq() {
         let count = SylowStreamBuilder::<Phantom, 3, FpNum<61>, ()>::new()
@@ -952,6 +2478,298 @@ mod tests {
         assert_eq!(count, 4);
     }
 
+    #[test]
+    pub fn test_sharing_trie() {
+        let trie = FactorTrie::<Phantom, 3, FpNum<61>, u128>::new_with(|ds, _| {
+            <FpNum<61> as Factor<Phantom>>::FACTORS.from_powers(ds)
+        });
+        let borrowed = SylowStreamBuilder::new_with_trie(&trie)
+            .add_flag(flags::LEQ)
+            .add_targets_leq(60)
+            .into_iter()
+            .map(|(x, d)| (x.coords, *d))
+            .collect::<Vec<_>>();
+
+        let trie = Arc::new(trie);
+        let stream = SylowStreamBuilder::new_sharing_trie(Arc::clone(&trie))
+            .add_flag(flags::LEQ)
+            .add_targets_leq(60)
+            .into_iter();
+        drop(trie);
+        let shared = stream
+            .inspect(|(x, d)| assert_eq!(x.order(), **d))
+            .map(|(x, d)| (x.coords, *d))
+            .collect::<Vec<_>>();
+        assert_eq!(shared, borrowed);
+        assert_eq!(shared.len(), 60);
+    }
+
+    #[test]
+    pub fn test_presets() {
+        let coords = |b: SylowStreamBuilder<Phantom, 3, FpNum<61>, ()>| {
+            b.into_iter().map(|(x, _)| x.coords).collect::<Vec<_>>()
+        };
+        let unique = coords(SylowStreamBuilder::new().for_unique_chi(20));
+        // Half of the elements of each order 3, 4, 5, 6, 10, 12, 15, and 20.
+        assert_eq!(unique.len(), (2 + 2 + 4 + 2 + 4 + 4 + 8 + 8) / 2);
+        assert!(unique.iter().all(|c| {
+            let d = SylowElem::<Phantom, 3, FpNum<61>>::new(*c).order();
+            2 < d && d <= 20
+        }));
+        let plan = StreamPlan::new(&<FpNum<61> as Factor<Phantom>>::FACTORS, 20);
+        assert_eq!(
+            coords(SylowStreamBuilder::new().for_unique_chi_with_plan(&plan)),
+            unique
+        );
+
+        let sweep = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+            .for_coset_sweep([0, 2, 0])
+            .into_iter()
+            .map(|(x, _)| x.coords)
+            .collect::<Vec<_>>();
+        let manual = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+            .add_flag(flags::NO_UPPER_HALF)
+            .add_targets_leq(272)
+            .set_quotient(Some([0, 2, 0]))
+            .into_iter()
+            .map(|(x, _)| x.coords)
+            .collect::<Vec<_>>();
+        assert_eq!(sweep, manual);
+    }
+
+    fn check_skips<S, const L: usize, C>(builder: SylowStreamBuilder<S, L, C, ()>)
+    where
+        C: SylowDecomposable<S>,
+    {
+        let all = builder.clone().into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            builder.clone().into_iter().size_hint(),
+            (all.len(), Some(all.len()))
+        );
+        let stride = usize::max(1, all.len() / 64);
+        for k in (0..=all.len())
+            .step_by(stride)
+            .chain([all.len().saturating_sub(1)])
+        {
+            let mut stream = builder.clone().into_iter();
+            assert_eq!(stream.nth(k), all.get(k).cloned());
+            assert_eq!(stream.size_hint().0, all.len().saturating_sub(k + 1));
+            assert_eq!(
+                stream.collect::<Vec<_>>(),
+                all[usize::min(k + 1, all.len())..]
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_nth_matches_iteration() {
+        check_skips(SylowStreamBuilder::<Phantom, 3, FpNum<61>, ()>::new().add_target(&[2, 0, 0]));
+        check_skips(
+            SylowStreamBuilder::<Phantom, 3, FpNum<61>, ()>::new()
+                .add_flag(flags::LEQ)
+                .add_flag(flags::NO_PARABOLIC)
+                .add_flag(flags::NO_UPPER_HALF)
+                .add_target(&[2, 0, 1]),
+        );
+        check_skips(
+            SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+                .add_flag(flags::LEQ)
+                .add_target(&[1, 3, 1]),
+        );
+        check_skips(
+            SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+                .add_flag(flags::LEQ)
+                .add_flag(flags::NO_UPPER_HALF)
+                .add_target(&[1, 3, 1]),
+        );
+        check_skips(
+            SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+                .add_flag(flags::LEQ)
+                .add_flag(flags::NO_PARABOLIC)
+                .add_flag(flags::NO_UPPER_HALF)
+                .add_target(&[0, 3, 0])
+                .set_quotient(Some([0, 1, 0])),
+        );
+        check_skips(
+            SylowStreamBuilder::<Phantom, 2, FpNum<41>, ()>::new()
+                .add_flag(flags::NO_UPPER_HALF)
+                .add_target(&[3, 1]),
+        );
+        check_skips(
+            SylowStreamBuilder::<Phantom, 3, FpNum<61>, ()>::new()
+                .add_target(&[0, 1, 0])
+                .add_target(&[0, 1, 1]),
+        );
+        check_skips(
+            SylowStreamBuilder::<Phantom, 3, QuadNum<3001>, ()>::new()
+                .add_flag(flags::LEQ)
+                .add_targets_leq(3002)
+                .set_quotient(Some([0, 0, 1])),
+        );
+        check_skips(
+            SylowStreamBuilder::<Phantom, 4, FpNum<13928643>, ()>::new()
+                .add_flag(flags::LEQ)
+                .add_target(&[0, 1, 1, 0]),
+        );
+    }
+
+    #[test]
+    pub fn test_nth_skips_deep() {
+        // 3000 = 2^3 * 3 * 5^3; skip far into a stream of every element.
+        let builder = SylowStreamBuilder::<Phantom, 3, FpNum<3001>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_targets_leq(3001);
+        let all = builder.clone().into_iter().collect::<Vec<_>>();
+        assert_eq!(all.len(), 3000);
+        for k in [0, 1, 127, 128, 1000, 2500, 2999, 3000] {
+            assert_eq!(builder.clone().into_iter().nth(k), all.get(k).cloned());
+        }
+
+        let count = SylowStreamBuilder::<Phantom, 7, FpNum<BIG_P>, ()>::new()
+            .add_target(&[0, 0, 0, 0, 0, 1, 1])
+            .into_iter()
+            .size_hint();
+        let expected = (705737 - 1) * (215288719 - 1);
+        assert_eq!(count, (expected, Some(expected)));
+        let mut stream = SylowStreamBuilder::<Phantom, 7, FpNum<BIG_P>, ()>::new()
+            .add_target(&[0, 0, 0, 0, 0, 1, 1])
+            .into_iter();
+        let (mut x, _) = stream.nth(expected - 1).unwrap();
+        assert!(stream.next().is_none());
+        assert!(x != SylowElem::ONE);
+        x = x.pow(705737 * 215288719);
+        assert!(x == SylowElem::ONE);
+    }
+
+    #[test]
+    pub fn test_reconfigure() {
+        let builder = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_flag(flags::NO_UPPER_HALF)
+            .add_target(&[1, 3, 1]);
+        assert_eq!(builder.clone().into_iter().count(), 136);
+
+        let builder = builder.remove_flag(flags::NO_UPPER_HALF);
+        assert_eq!(builder.clone().into_iter().count(), 270);
+
+        // With the `LEQ` flag still set, only the identity remains.
+        let builder = builder.clear_targets();
+        assert_eq!(builder.clone().into_iter().count(), 1);
+        let builder = builder.remove_flag(flags::LEQ);
+        assert_eq!(builder.clone().into_iter().count(), 0);
+
+        let builder = builder.reconfigure(flags::NO_UPPER_HALF, &[[0, 2, 1]]);
+        assert_eq!(builder.clone().into_iter().count(), 12);
+
+        let builder = builder.reconfigure(flags::NONE, &[[1, 0, 0], [0, 1, 0]]);
+        assert_eq!(builder.into_iter().count(), 3);
+    }
+
+    #[test]
+    pub fn test_target_set_operations() {
+        let a = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_target(&[1, 1, 0]);
+        let b = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_target(&[0, 2, 0]);
+
+        // The divisors of 6 and of 9 are 1, 2, 3, 6 and 1, 3, 9 respectively.
+        let union = a.clone().union_targets(&b);
+        assert_eq!(union.into_iter().count(), 1 + 1 + 2 + 2 + 6);
+        let intersection = a.clone().intersect_targets(&b);
+        assert_eq!(intersection.into_iter().count(), 1 + 2);
+
+        let c = SylowStreamBuilder::<Phantom, 3, FpNum<271>, [u128; 3]>::new_with_orders()
+            .add_target(&[0, 0, 1]);
+        let union = b.clone().union_targets(&c);
+        assert_eq!(union.clone().into_iter().count(), 1 + 2 + 6 + 4);
+        let disjoint = union.intersect_targets(&a).remove_flag(flags::LEQ);
+        assert_eq!(disjoint.into_iter().count(), 2);
+    }
+
+    #[test]
+    pub fn test_split() {
+        let builder = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
+            .add_flag(flags::LEQ)
+            .add_target(&[1, 3, 1]);
+        let mut all = builder
+            .clone()
+            .into_iter()
+            .map(|(x, _)| x.coords)
+            .collect::<Vec<_>>();
+        all.sort();
+
+        let mut streams = vec![builder.into_iter()];
+        let mut done = Vec::new();
+        while let Some(stream) = streams.pop() {
+            match stream.split() {
+                (a, Some(b)) if done.len() + streams.len() < 16 => {
+                    streams.push(a);
+                    streams.push(b);
+                }
+                (a, b) => {
+                    done.push(a);
+                    done.extend(b);
+                }
+            }
+        }
+        assert!(done.len() > 1);
+        let mut res = done
+            .into_iter()
+            .flatten()
+            .map(|(x, _)| x.coords)
+            .collect::<Vec<_>>();
+        res.sort();
+        assert_eq!(res, all);
+    }
+
+    #[test]
+    pub fn test_shards() {
+        let builder = SylowStreamBuilder::<Phantom, 3, FpNum<271>, [u128; 3]>::new_with_orders()
+            .add_flag(flags::LEQ)
+            .add_target(&[1, 3, 1]);
+        let mut all = builder
+            .clone()
+            .into_iter()
+            .map(|(x, ds)| (x.coords, ds))
+            .collect::<Vec<_>>();
+        all.sort();
+
+        let shards = builder.clone().shards(8);
+        assert!(shards.len() > 1 && shards.len() <= 8);
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&shards).unwrap();
+            assert_eq!(
+                serde_json::from_str::<Vec<ShardDescriptor>>(&json).unwrap(),
+                shards
+            );
+        }
+        let mut res = shards
+            .iter()
+            .flat_map(|shard| builder.clone().resume_shard(shard))
+            .map(|(x, ds)| (x.coords, ds))
+            .collect::<Vec<_>>();
+        res.sort();
+        assert_eq!(res, all);
+        assert!(res
+            .iter()
+            .all(|(x, ds)| SylowElem::<Phantom, 3, FpNum<271>>::new(*x).order_powers() == *ds));
+
+        assert_eq!(builder.clone().shards(1).len(), 1);
+        let one = builder.clone().shards(1).pop().unwrap();
+        assert_eq!(builder.resume_shard(&one).count(), 270);
+    }
+
+    #[test]
+    pub fn test_builder_debug() {
+        let builder = SylowStreamBuilder::<Phantom, 2, FpNum<7>, ()>::new().add_target(&[1, 0]);
+        let debug = format!("{builder:?}");
+        assert!(debug.contains("2: (Consume { this: true, descendants: 0 }, ())"));
+        assert!(debug.contains("3: (Consume { this: false, descendants: 0 }, ())"));
+    }
+
     #[test]
     pub fn test_generate_everything() {
         let count = SylowStreamBuilder::<Phantom, 3, FpNum<271>, ()>::new()
@@ -1,4 +1,6 @@
 //! Extra generally useful methods.
 extern crate libbgs_util;
 
+pub mod stats;
+
 pub use libbgs_util::*;
@@ -0,0 +1,267 @@
+//! Histograms of `u128` samples, such as coset walk lengths, component sizes, and search depths.
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How a [`Histogram`] groups its samples into buckets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Buckets {
+    /// Buckets of the given number of consecutive values, beginning at 0.
+    /// A width of 1 counts each value exactly.
+    Linear(u128),
+    /// A bucket for 0, and a bucket for each range $[2^k, 2^{k + 1})$.
+    Log2,
+}
+
+/// A histogram of `u128` samples.
+/// Only the nonempty buckets are stored, so that samples may be spread over the whole range of
+/// `u128` without overflow or large allocations.
+///
+/// Histograms with the same [`Buckets`] may be merged, so that samples may be counted on many
+/// threads and combined in a `rayon` reduction:
+/// ```
+/// use rayon::prelude::*;
+/// use libbgs::util::stats::*;
+///
+/// let hist = (0..1000u128)
+///     .into_par_iter()
+///     .fold(Histogram::log2, |mut h, x| {
+///         h.add(x * x);
+///         h
+///     })
+///     .reduce(Histogram::log2, Histogram::merged);
+/// assert_eq!(hist.count(), 1000);
+/// assert_eq!(hist.max(), Some(999 * 999));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Histogram {
+    buckets: Buckets,
+    counts: BTreeMap<u128, u128>,
+    count: u128,
+    min: Option<u128>,
+    max: Option<u128>,
+}
+
+impl Histogram {
+    /// Returns an empty histogram grouping its samples into `buckets`.
+    /// Panics if `buckets` is `Buckets::Linear(0)`.
+    pub fn new(buckets: Buckets) -> Histogram {
+        assert!(
+            buckets != Buckets::Linear(0),
+            "Linear buckets must have positive width."
+        );
+        Histogram {
+            buckets,
+            counts: BTreeMap::new(),
+            count: 0,
+            min: None,
+            max: None,
+        }
+    }
+
+    /// Returns an empty histogram counting each value exactly.
+    pub fn exact() -> Histogram {
+        Histogram::new(Buckets::Linear(1))
+    }
+
+    /// Returns an empty histogram with a bucket for each power of two.
+    pub fn log2() -> Histogram {
+        Histogram::new(Buckets::Log2)
+    }
+
+    /// Returns how this histogram groups its samples into buckets.
+    pub fn bucketing(&self) -> Buckets {
+        self.buckets
+    }
+
+    /// Adds the sample `x`.
+    pub fn add(&mut self, x: u128) {
+        self.add_n(x, 1);
+    }
+
+    /// Adds `n` samples of the value `x`.
+    pub fn add_n(&mut self, x: u128, n: u128) {
+        if n == 0 {
+            return;
+        }
+        *self.counts.entry(self.index(x)).or_insert(0) += n;
+        self.count += n;
+        self.min = Some(self.min.map_or(x, |m| u128::min(m, x)));
+        self.max = Some(self.max.map_or(x, |m| u128::max(m, x)));
+    }
+
+    /// Adds every sample of `other` to this histogram.
+    /// Panics if the histograms do not have the same [`Buckets`].
+    pub fn merge(&mut self, other: &Histogram) {
+        assert_eq!(
+            self.buckets, other.buckets,
+            "Cannot merge histograms with different buckets."
+        );
+        for (i, n) in &other.counts {
+            *self.counts.entry(*i).or_insert(0) += n;
+        }
+        self.count += other.count;
+        self.min = match (self.min, other.min) {
+            (Some(a), Some(b)) => Some(u128::min(a, b)),
+            (a, b) => a.or(b),
+        };
+        self.max = Option::max(self.max, other.max);
+    }
+
+    /// Returns the histogram of the samples of both `self` and `other`, as
+    /// [`Histogram::merge`] does, for use in reductions.
+    pub fn merged(mut self, other: Histogram) -> Histogram {
+        self.merge(&other);
+        self
+    }
+
+    /// Returns the number of samples.
+    pub fn count(&self) -> u128 {
+        self.count
+    }
+
+    /// True if there are no samples.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns the least sample, or `None` if there are no samples.
+    pub fn min(&self) -> Option<u128> {
+        self.min
+    }
+
+    /// Returns the greatest sample, or `None` if there are no samples.
+    pub fn max(&self) -> Option<u128> {
+        self.max
+    }
+
+    /// Returns the range of values and the number of samples of each nonempty bucket, in
+    /// increasing order.
+    pub fn buckets(&self) -> impl Iterator<Item = (RangeInclusive<u128>, u128)> + '_ {
+        self.counts.iter().map(|(i, n)| (self.range(*i), *n))
+    }
+
+    /// Returns an upper bound on the `q`-th quantile of the samples, for `q` between 0 and 1:
+    /// at least a `q` fraction of the samples are at most the value returned.
+    /// The bound is the greatest value of the bucket holding the quantile, or the greatest
+    /// sample if that is smaller, so it is exact when counting each value exactly.
+    /// Returns `None` if there are no samples.
+    pub fn percentile(&self, q: f64) -> Option<u128> {
+        let max = self.max?;
+        let rank = ((q * self.count as f64).ceil() as u128).clamp(1, self.count);
+        let mut seen = 0;
+        self.counts.iter().find_map(|(i, n)| {
+            seen += n;
+            (seen >= rank).then(|| u128::min(*self.range(*i).end(), max))
+        })
+    }
+
+    /// Returns an upper bound on the median of the samples; see [`Histogram::percentile`].
+    pub fn median(&self) -> Option<u128> {
+        self.percentile(0.5)
+    }
+
+    fn index(&self, x: u128) -> u128 {
+        match self.buckets {
+            Buckets::Linear(width) => x / width,
+            Buckets::Log2 => (u128::BITS - x.leading_zeros()) as u128,
+        }
+    }
+
+    fn range(&self, i: u128) -> RangeInclusive<u128> {
+        match self.buckets {
+            Buckets::Linear(width) => {
+                let lo = i * width;
+                lo..=lo.saturating_add(width - 1)
+            }
+            Buckets::Log2 if i == 0 => 0..=0,
+            Buckets::Log2 => {
+                let lo = 1 << (i - 1);
+                lo..=lo + (lo - 1)
+            }
+        }
+    }
+}
+
+/// The default histogram counts each value exactly.
+impl Default for Histogram {
+    fn default() -> Histogram {
+        Histogram::exact()
+    }
+}
+
+impl Extend<u128> for Histogram {
+    fn extend<I: IntoIterator<Item = u128>>(&mut self, iter: I) {
+        for x in iter {
+            self.add(x);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_percentiles() {
+        let mut hist = Histogram::exact();
+        assert_eq!(hist.median(), None);
+        hist.extend(1..=100);
+        assert_eq!(hist.count(), 100);
+        assert_eq!((hist.min(), hist.max()), (Some(1), Some(100)));
+        assert_eq!(hist.percentile(0.0), Some(1));
+        assert_eq!(hist.median(), Some(50));
+        assert_eq!(hist.percentile(0.9), Some(90));
+        assert_eq!(hist.percentile(1.0), Some(100));
+    }
+
+    #[test]
+    fn log_buckets_span_u128() {
+        let mut hist = Histogram::log2();
+        hist.extend([0, 1, 2, 3, 4, u128::MAX, 1 << 127]);
+        let buckets = hist.buckets().collect::<Vec<_>>();
+        assert_eq!(
+            buckets,
+            vec![
+                (0..=0, 1),
+                (1..=1, 1),
+                (2..=3, 2),
+                (4..=7, 1),
+                ((1 << 127)..=u128::MAX, 2),
+            ]
+        );
+        assert_eq!(hist.median(), Some(3));
+        assert_eq!(hist.percentile(1.0), Some(u128::MAX));
+
+        let mut wide = Histogram::new(Buckets::Linear(u128::MAX / 2));
+        wide.add_n(u128::MAX, 3);
+        assert_eq!(wide.buckets().last(), Some((u128::MAX - 1..=u128::MAX, 3)));
+    }
+
+    #[test]
+    fn merges() {
+        let mut a = Histogram::new(Buckets::Linear(10));
+        let mut b = a.clone();
+        a.extend(0..50);
+        b.extend(25..75);
+        let mut all = Histogram::new(Buckets::Linear(10));
+        all.extend((0..50).chain(25..75));
+        assert_eq!(a.clone().merged(b.clone()), all);
+        b.merge(&a);
+        assert_eq!(b, all);
+        assert_eq!(
+            all.buckets().map(|(_, n)| n).collect::<Vec<_>>(),
+            [10, 10, 15, 20, 20, 10, 10, 5]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn merge_requires_same_buckets() {
+        Histogram::log2().merge(&Histogram::exact());
+    }
+}
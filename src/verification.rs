@@ -0,0 +1,428 @@
+//! Brute-force checks of this library's results modulo small primes.
+//! Each check recomputes its ground truth directly from the definitions, without factoring
+//! $p \pm 1$ or using any Sylow decomposition, and compares it against what the library computes.
+//! The checks take time polynomial in $p$, up to $O(p^3)$, so are only feasible for primes up to
+//! a few hundred.
+//!
+//! The results of [`crate::bgs`] are not checked: the search bounds the number of small
+//! coordinates met along cosets, and has no ground truth short of the connectivity of the Markoff
+//! graph itself, which [`census::component_census`] is checked against instead.
+//!
+//! These checks are intended for test suites: downstream changes, and user-provided [`Factor`]
+//! implementations, can be validated by running them on a handful of small primes.
+//! ```
+//! use libbgs::numbers::*;
+//! use libbgs::verification::*;
+//!
+//! struct Ph;
+//! impl_factors!(Ph, 11, 13);
+//!
+//! verify_primes!(Ph, 11, 13).unwrap();
+//! ```
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::markoff::*;
+use crate::numbers::*;
+use crate::streams::*;
+
+/// Runs [`verify_prime`] for each of a list of primes, returning the first mismatch found.
+/// The first argument is the marker type of the [`Factor`] implementations to check, and the
+/// number of prime factors of $p \pm 1$ is filled in from those implementations.
+#[macro_export]
+macro_rules! verify_primes {
+    ($mrk:ty, $($p:literal),+ $(,)?) => {
+        (|| -> Result<(), $crate::verification::Mismatch> {
+            $(
+                $crate::verification::verify_prime::<
+                    $mrk,
+                    $p,
+                    { <$crate::numbers::FpNum<$p> as $crate::numbers::Factor<$mrk>>::FACTORS.len() },
+                    { <$crate::numbers::QuadNum<$p> as $crate::numbers::Factor<$mrk>>::FACTORS.len() },
+                >()?;
+            )+
+            Ok(())
+        })()
+    };
+}
+pub use verify_primes;
+
+/// One of the two groups in which the library computes: $\mathbb{F}\_p^\times$, of order
+/// $p - 1$, and the norm one subgroup of $\mathbb{F}\_{p^2}^\times$, of order $p + 1$.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Group {
+    /// The multiplicative group $\mathbb{F}\_p^\times$, of [`FpNum`]s.
+    Hyperbolic,
+    /// The norm one subgroup of $\mathbb{F}\_{p^2}^\times$, of [`QuadNum`]s.
+    Elliptic,
+}
+
+/// Which check failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Check {
+    /// The [`Factor`] implementation is not the prime factorization of the order of the group.
+    Factorization(Group),
+    /// [`GroupElem::order`] disagrees with the least power of an element equal to one.
+    Orders(Group),
+    /// A [`SylowDecomp`] failed [`SylowDecomp::verify`], or did not round trip an element.
+    Decomposition(Group),
+    /// A [`SylowStreamBuilder`] yielded the wrong elements of some order.
+    Streams(Group),
+    /// [`census::triples`] disagrees with the solutions of the Markoff equation.
+    Triples,
+    /// [`Coord::rot_order`] disagrees with the order of the matrix of $\text{rot}\_a$.
+    RotOrders,
+    /// [`stats::order_histogram`] disagrees with the rotation orders of every coordinate.
+    OrderHistogram,
+    /// [`census::component_census`] disagrees with the components found by union-find.
+    Components,
+}
+
+/// A disagreement between the library and the brute-force ground truth.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The prime modulo which the check failed.
+    pub prime: u128,
+    /// The check which failed.
+    pub check: Check,
+    /// A description of the disagreement.
+    pub detail: String,
+}
+
+impl std::fmt::Display for Group {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Group::Hyperbolic => write!(f, "hyperbolic"),
+            Group::Elliptic => write!(f, "elliptic"),
+        }
+    }
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Check::Factorization(g) => write!(f, "{g} factorization"),
+            Check::Orders(g) => write!(f, "{g} orders"),
+            Check::Decomposition(g) => write!(f, "{g} Sylow decomposition"),
+            Check::Streams(g) => write!(f, "{g} streams"),
+            Check::Triples => write!(f, "triples"),
+            Check::RotOrders => write!(f, "rotation orders"),
+            Check::OrderHistogram => write!(f, "rotation order histogram"),
+            Check::Components => write!(f, "components"),
+        }
+    }
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} modulo {}: {}", self.check, self.prime, self.detail)
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+/// Checks every result of the library modulo the small prime `P` against ground truth computed
+/// by brute force, returning the first disagreement found.
+/// `L_HYPER` and `L_ELLIP` must be the number of distinct prime factors of $p - 1$ and $p + 1$,
+/// respectively; see [`verify_primes`] to fill them in automatically.
+///
+/// The following are checked, in each of the two [`Group`]s where applicable:
+/// * the [`Factor`] implementations are the prime factorizations of $p \pm 1$;
+/// * the order of every element;
+/// * that a [`SylowDecomp`] verifies, and round trips every element;
+/// * that a [`SylowStreamBuilder`] targeting each order yields exactly the elements of that
+///   order, each once;
+/// * the nonzero Markoff triples, and the connected components of the Markoff graph;
+/// * the rotation order of every coordinate, and [`stats::order_histogram`].
+///
+/// The search in [`crate::bgs`] is not checked.
+pub fn verify_prime<S, const P: u128, const L_HYPER: usize, const L_ELLIP: usize>(
+) -> Result<(), Mismatch>
+where
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    verify_group::<S, P, L_HYPER, FpNum<P>>(Group::Hyperbolic, P - 1, (1..P).map(FpNum::from))?;
+    verify_group::<S, P, L_ELLIP, QuadNum<P>>(
+        Group::Elliptic,
+        P + 1,
+        Norm1::<P>::all().map(QuadNum::from),
+    )?;
+    verify_triples::<P>()?;
+    verify_rot_orders::<S, P>()?;
+    Ok(())
+}
+
+fn mismatch<const P: u128>(check: Check, detail: String) -> Result<(), Mismatch> {
+    Err(Mismatch {
+        prime: P,
+        check,
+        detail,
+    })
+}
+
+fn verify_group<S, const P: u128, const L: usize, C>(
+    group: Group,
+    size: u128,
+    elements: impl Iterator<Item = C>,
+) -> Result<(), Mismatch>
+where
+    C: SylowDecomposable<S> + Hash + Debug,
+{
+    let factors = C::FACTORS.factors();
+    if factors.len() != L {
+        return mismatch::<P>(
+            Check::Factorization(group),
+            format!("{} prime factors, expected {L}", factors.len()),
+        );
+    }
+    let product = factors.iter().try_fold(1u128, |acc, (p, t)| {
+        (0..*t).try_fold(acc, |acc, _| acc.checked_mul(*p))
+    });
+    if product != Some(size)
+        || factors.iter().any(|(p, t)| *t == 0 || !is_prime(*p))
+        || factors.windows(2).any(|w| w[0].0 >= w[1].0)
+    {
+        return mismatch::<P>(
+            Check::Factorization(group),
+            format!("{factors:?} is not the factorization of {size}"),
+        );
+    }
+
+    let divisors = (1..=size)
+        .filter(|d| size.is_multiple_of(*d))
+        .collect::<Vec<_>>();
+    let mut by_order: HashMap<u128, HashSet<C>> = HashMap::new();
+    for x in elements {
+        let ord = *divisors.iter().find(|d| x.pow(**d) == C::ONE).unwrap();
+        if x.order::<S>() != ord {
+            return mismatch::<P>(
+                Check::Orders(group),
+                format!("{x:?} has order {ord}, not {}", x.order::<S>()),
+            );
+        }
+        by_order.entry(ord).or_default().insert(x);
+    }
+    let count = by_order.values().map(|xs| xs.len() as u128).sum::<u128>();
+    if count != size {
+        return mismatch::<P>(
+            Check::Orders(group),
+            format!("found {count} elements, expected {size}"),
+        );
+    }
+
+    let decomp = SylowDecomp::<S, L, C>::new();
+    if !decomp.verify() {
+        return mismatch::<P>(Check::Decomposition(group), "failed to verify".to_string());
+    }
+    for (ord, xs) in &by_order {
+        for x in xs {
+            let Some(y) = SylowElem::from_product(x, &decomp) else {
+                return mismatch::<P>(
+                    Check::Decomposition(group),
+                    format!("{x:?} has no coordinates"),
+                );
+            };
+            if y.order() != *ord || y.to_product(&decomp) != *x {
+                return mismatch::<P>(
+                    Check::Decomposition(group),
+                    format!("{x:?} has coordinates {:?}", y.coords),
+                );
+            }
+        }
+    }
+
+    for ds in DivisorStream::new(factors, size, false) {
+        let ds: [usize; L] = std::array::from_fn(|i| ds[i]);
+        let ord = C::FACTORS.from_powers(&ds);
+        let expected = by_order.get(&ord).cloned().unwrap_or_default();
+        let mut found = HashSet::new();
+        for (y, _) in SylowStreamBuilder::<S, L, C, ()>::new().add_target(&ds) {
+            let x = y.to_product(&decomp);
+            if !expected.contains(&x) || !found.insert(x.clone()) {
+                return mismatch::<P>(
+                    Check::Streams(group),
+                    format!("stream for order {ord} yielded {x:?}"),
+                );
+            }
+        }
+        if found.len() != expected.len() || C::count_elements_of_order(&ds) != found.len() as u128 {
+            return mismatch::<P>(
+                Check::Streams(group),
+                format!(
+                    "stream for order {ord} yielded {} elements, expected {}",
+                    found.len(),
+                    expected.len()
+                ),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn verify_triples<const P: u128>() -> Result<(), Mismatch> {
+    let mut expected = Vec::new();
+    for a in 0..P {
+        for b in 0..P {
+            for c in 0..P {
+                if (a, b, c) != (0, 0, 0) && (a * a + b * b + c * c) % P == a * b % P * c % P {
+                    expected.push((a, b, c));
+                }
+            }
+        }
+    }
+    let mut found = census::triples::<P>()
+        .map(|t| (u128::from(t.a()), u128::from(t.b()), u128::from(t.c())))
+        .collect::<Vec<_>>();
+    found.sort();
+    if found != expected {
+        return mismatch::<P>(
+            Check::Triples,
+            format!("found {} triples, expected {}", found.len(), expected.len()),
+        );
+    }
+
+    // Union-find over the indices of `expected`, joining each triple to its Vieta images.
+    let index = expected
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (*t, i))
+        .collect::<HashMap<_, _>>();
+    let mut parent = (0..expected.len()).collect::<Vec<_>>();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for (i, (a, b, c)) in expected.iter().enumerate() {
+        let images = [
+            ((b * c + P - a) % P, *b, *c),
+            (*a, (a * c + P - b) % P, *c),
+            (*a, *b, (a * b + P - c) % P),
+        ];
+        for t in images {
+            let (x, y) = (root(&mut parent, i), root(&mut parent, index[&t]));
+            parent[x] = y;
+        }
+    }
+    let mut sizes = HashMap::new();
+    for i in 0..expected.len() {
+        *sizes.entry(root(&mut parent, i)).or_insert(0u128) += 1;
+    }
+    let mut expected = sizes.into_values().collect::<Vec<_>>();
+    expected.sort_by(|a, b| b.cmp(a));
+    let found = census::component_census::<P>()
+        .components
+        .iter()
+        .map(|c| c.size)
+        .collect::<Vec<_>>();
+    if found != expected {
+        return mismatch::<P>(
+            Check::Components,
+            format!("component sizes {found:?}, expected {expected:?}"),
+        );
+    }
+    Ok(())
+}
+
+fn verify_rot_orders<S, const P: u128>() -> Result<(), Mismatch>
+where
+    FpNum<P>: Factor<S>,
+    QuadNum<P>: Factor<S>,
+{
+    let mut hist = stats::OrderHistogram::default();
+    let (mut hyperbola, mut ellipse, mut parabola) =
+        (HashMap::new(), HashMap::new(), HashMap::new());
+    for a in 0..P {
+        // $\text{rot}\_a$ is $(b, c) \mapsto (c, ac - b)$; find the least power of its matrix
+        // which is the identity.
+        let step = |[[w, x], [y, z]]: [[u128; 2]; 2]| {
+            [[y, z], [(a * y + P * P - w) % P, (a * z + P * P - x) % P]]
+        };
+        let mut m = step([[1, 0], [0, 1]]);
+        let mut ord = 1;
+        while m != [[1, 0], [0, 1]] {
+            m = step(m);
+            ord += 1;
+        }
+        let disc = FpNum::<P>::from((a * a + 4 * P - 4) % P);
+        let expected = if ord <= 2 || disc == FpNum::ZERO {
+            RotOrder::Parabola(if a == 2 { 1 } else { 2 })
+        } else if disc.int_sqrt().is_some() {
+            RotOrder::Hyperbola(ord)
+        } else {
+            RotOrder::Ellipse(ord)
+        };
+        let found = Coord::<P>::from(a).rot_order::<S, S>();
+        if found != expected {
+            return mismatch::<P>(
+                Check::RotOrders,
+                format!("coordinate {a} has order {found:?}, expected {expected:?}"),
+            );
+        }
+        match expected {
+            RotOrder::Hyperbola(d) => *hyperbola.entry(d).or_insert(0) += 1,
+            RotOrder::Ellipse(d) => *ellipse.entry(d).or_insert(0) += 1,
+            RotOrder::Parabola(d) => *parabola.entry(d).or_insert(0) += 1,
+        }
+    }
+    hist.hyperbola = hyperbola.into_iter().collect();
+    hist.ellipse = ellipse.into_iter().collect();
+    hist.parabola = parabola.into_iter().collect();
+    hist.hyperbola.sort();
+    hist.ellipse.sort();
+    hist.parabola.sort();
+    let found = stats::order_histogram::<S, P>();
+    if found != hist {
+        return mismatch::<P>(
+            Check::OrderHistogram,
+            format!("{found:?}, expected {hist:?}"),
+        );
+    }
+    Ok(())
+}
+
+fn is_prime(n: u128) -> bool {
+    n >= 2
+        && (2..)
+            .take_while(|d| d * d <= n)
+            .all(|d| !n.is_multiple_of(d))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Ph;
+    impl_factors!(Ph, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43);
+
+    struct Bad;
+    impl Factor<Bad> for FpNum<13> {
+        const FACTORS: Factorization = Factorization::new(&[(2, 1), (3, 1)]);
+    }
+    impl Factor<Bad> for QuadNum<13> {
+        const FACTORS: Factorization = Factorization::new(&[(2, 1), (7, 1)]);
+    }
+
+    #[test]
+    fn verifies_small_primes() {
+        assert_eq!(
+            verify_primes!(Ph, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rejects_bad_factorization() {
+        let err = verify_primes!(Bad, 13).unwrap_err();
+        assert_eq!(err.prime, 13);
+        assert_eq!(err.check, Check::Factorization(Group::Hyperbolic));
+        assert_eq!(
+            err.to_string(),
+            "hyperbolic factorization modulo 13: [(2, 1), (3, 1)] is not the factorization of 12"
+        );
+    }
+}